@@ -14,13 +14,24 @@ use {
                 field_f32,
                 field_f64,
                 field_bytes,
+                field_tsvector,
                 Field,
+                Identity,
             },
+            schema::index::IndexMethod,
+            schema::partition::{
+                PartitionBound,
+                PartitionInterval,
+                PartitionMethod,
+            },
+            schema::policy::PolicyCommand,
+            schema::grant::Privilege,
             query::{
                 expr::{
                     Expr,
                     BinOp,
                     ComputeType,
+                    ExprValName,
                 },
                 select::{
                     Join,
@@ -28,8 +39,16 @@ use {
                     JoinSource,
                     JoinType,
                     Order,
+                    Lock,
+                    LockStrength,
+                    LockWait,
+                },
+                helpers::{
+                    eq_field,
+                    field_param,
+                    set_field,
                 },
-                helpers::set_field,
+                functions,
             },
             generate,
             new_insert,
@@ -39,6 +58,8 @@ use {
             new_delete,
             types::{
                 type_i64,
+                type_str,
+                type_tsquery,
                 SimpleSimpleType,
             },
         },
@@ -416,6 +437,48 @@ pub fn build(root: &Path) {
         }).return_field(&three).return_field(&two).build_query("get_it", QueryResCount::One)]).unwrap();
     }
 
+    // # Select lateral
+    {
+        let mut v = Version::default();
+        let users = v.table("zVN5VQ1CY", "users");
+        let user_id = users.field(&mut v, "z3VGFWOVL", "id", field_i32().build());
+        let posts = v.table("zG5FGSN5E", "posts");
+        let post_user = posts.field(&mut v, "zJ2NQO2GU", "user", field_i32().build());
+        let post_score = posts.field(&mut v, "zSMN0K6F1", "score", field_i32().build());
+        generate(&root.join("tests/pg_gen_select_lateral.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(&users, vec![set_field("id", &user_id)]).build_query("insert_user", QueryResCount::None),
+            new_insert(
+                &posts,
+                vec![set_field("user", &post_user), set_field("score", &post_score)],
+            ).build_query("insert_post", QueryResCount::None),
+            new_select(&users).join(Join {
+                source: Box::new(NamedSelectSource {
+                    source: JoinSource::Lateral(
+                        Box::new(
+                            new_select(&posts)
+                                .return_field(&post_score)
+                                .where_(Expr::BinOp {
+                                    left: Box::new(Expr::Field(post_user.clone())),
+                                    op: BinOp::Equals,
+                                    right: Box::new(Expr::Field(user_id.clone())),
+                                })
+                                .order(Expr::Field(post_score.clone()), Order::Desc)
+                                .limit(Expr::LitI64(1))
+                                .build_migration(),
+                        ),
+                    ),
+                    alias: Some("best".into()),
+                }),
+                type_: JoinType::Inner,
+                on: Expr::LitBool(true),
+            }).return_field(&user_id).return_named("best", Expr::Binding(ExprValName {
+                table_id: "best".into(),
+                id: "score".into(),
+            })).build_query("get_best", QueryResCount::Many)
+        ]).unwrap();
+    }
+
     // # Select limit
     {
         let mut v = Version::default();
@@ -452,6 +515,711 @@ pub fn build(root: &Path) {
         ]).unwrap();
     }
 
+    // # Select order choice, offset
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zEOIWAACJ", "bannanana");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        generate(&root.join("tests/pg_gen_select_order_choice.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(&bananna, vec![set_field("v", &hizat)]).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .order_choice(
+                    "BananOrder",
+                    vec![
+                        ("HizatAsc".into(), vec![(Expr::Field(hizat.clone()), Order::Asc)]),
+                        ("HizatDesc".into(), vec![(Expr::Field(hizat.clone()), Order::DescNullsLast)])
+                    ],
+                )
+                .limit(Expr::LitI64(2))
+                .offset(Expr::LitI64(1))
+                .build_query("get_banan", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Select paginated
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zEOIWAACJ", "bannanana");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let hizat2 = bananna.field(&mut v, "z3CRAVV3M", "hizat2", field_str().build());
+        generate(&root.join("tests/pg_gen_select_paginate.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("v", &hizat), set_field("v2", &hizat2)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .return_fields(&[&hizat, &hizat2])
+                .paginate_by(&[(Expr::Field(hizat2.clone()), Order::Asc), (Expr::Field(hizat.clone()), Order::Desc)])
+                .build_query("get_banan", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Operators
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zEOIWAACJ", "bannanana");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let name = bananna.field(&mut v, "z8RDVKDG3", "name", field_str().build());
+        generate(&root.join("tests/pg_gen_operators.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("hizat", &hizat), set_field("name", &name)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .return_named("shifted", Expr::BinOp {
+                    left: Box::new(Expr::Field(hizat.clone())),
+                    op: BinOp::ShiftLeft,
+                    right: Box::new(Expr::LitI32(1)),
+                })
+                .return_named("greeting", Expr::BinOp {
+                    left: Box::new(Expr::LitString("hi ".to_string())),
+                    op: BinOp::Concat,
+                    right: Box::new(Expr::Field(name.clone())),
+                })
+                .where_(Expr::BinOpChain {
+                    op: BinOp::Or,
+                    exprs: vec![Expr::BinOp {
+                        left: Box::new(Expr::Field(name.clone())),
+                        op: BinOp::ILike,
+                        right: Box::new(Expr::LitString("b%".to_string())),
+                    }, Expr::BinOp {
+                        left: Box::new(Expr::Field(name.clone())),
+                        op: BinOp::RegexMatch,
+                        right: Box::new(Expr::LitString("^[0-9]+$".to_string())),
+                    }],
+                })
+                .order(Expr::Field(hizat.clone()), Order::Asc)
+                .build_query("get_banan", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Null narrowing
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zEOIWAACJ", "bannanana");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let name = bananna.field(&mut v, "z8RDVKDG3", "name", field_str().opt().build());
+        generate(&root.join("tests/pg_gen_null_narrowing.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("hizat", &hizat), set_field("name", &name)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna).return_fields(&[&hizat, &name]).where_(Expr::BinOp {
+                left: Box::new(Expr::Field(name.clone())),
+                op: BinOp::IsNot,
+                right: Box::new(Expr::LitNull(name.type_.type_.type_.clone())),
+            }).order(Expr::Field(hizat.clone()), Order::Asc).build_query("get_banan", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Result count inference
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zQ7M2XK4P", "bannanana");
+        let hizat = bananna.field(&mut v, "zH5C8RW1T", "hizat", field_i32().build());
+        let code = bananna.field(&mut v, "zN3V6BJ9E", "code", field_str().build());
+        bananna.index("zF8K2TA5U", "bannanana_code", &[&code]).unique().build(&mut v);
+        generate(&root.join("tests/pg_gen_res_count.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(&bananna, vec![set_field("hizat", &hizat), set_field("code", &code)]).build_query(
+                "insert_banan",
+                QueryResCount::Auto,
+            ),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .where_(eq_field("code", &code))
+                .build_query("get_by_code", QueryResCount::Auto),
+            new_select(&bananna)
+                .return_named("count", functions::count_rows())
+                .build_query("count_banan", QueryResCount::Auto),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .order(Expr::Field(hizat.clone()), Order::Asc)
+                .build_query("list_banan", QueryResCount::Auto),
+            new_delete(&bananna)
+                .where_(eq_field("code", &code))
+                .return_field(&hizat)
+                .build_query("delete_banan", QueryResCount::Auto)
+        ]).unwrap();
+    }
+
+    // # Result count, limit and returning
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zW3H8PD5K", "bannanana");
+        let hizat = bananna.field(&mut v, "zB6T1QX9M", "hizat", field_i32().build());
+        let code = bananna.field(&mut v, "zE4N7RG2V", "code", field_str().build());
+        bananna.index("zL9C3YU6A", "bannanana_hizat", &[&hizat]).unique().build(&mut v);
+        generate(&root.join("tests/pg_gen_res_count_limit.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(&bananna, vec![set_field("hizat", &hizat), set_field("code", &code)]).build_query(
+                "insert_banan",
+                QueryResCount::None,
+            ),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .order(Expr::Field(hizat.clone()), Order::Desc)
+                .limit(Expr::LitI64(1))
+                .build_query("last_banan", QueryResCount::Auto),
+            new_select(&bananna)
+                .return_field(&code)
+                .where_(eq_field("code", &code))
+                .limit(Expr::LitI64(1))
+                .build_query("get_by_code", QueryResCount::MaybeOne),
+            new_update(&bananna, vec![set_field("code", &code)])
+                .where_(eq_field("hizat", &hizat))
+                .return_field(&code)
+                .build_query("update_banan", QueryResCount::Auto),
+            new_delete(&bananna)
+                .where_(eq_field("hizat", &hizat))
+                .return_field(&code)
+                .build_query("delete_banan", QueryResCount::One)
+        ]).unwrap();
+    }
+
+    // # Convert
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zW5G8NB2R", "bannanana");
+        let hizat = bananna.field(&mut v, "zP3L7XC1M", "hizat", field_i32().build());
+        let digits = bananna.field(&mut v, "zA9S4KE6Q", "digits", field_str().opt().build());
+        generate(&root.join("tests/pg_gen_convert.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(&bananna, vec![set_field("hizat", &hizat), set_field("digits", &digits)]).build_query(
+                "insert_banan",
+                QueryResCount::None,
+            ),
+            new_select(&bananna)
+                .return_named("text", Expr::Convert {
+                    expr: Box::new(Expr::Field(hizat.clone())),
+                    type_: SimpleSimpleType::String,
+                })
+                .return_named("real", Expr::Convert {
+                    expr: Box::new(Expr::Field(hizat.clone())),
+                    type_: SimpleSimpleType::F64,
+                })
+                .return_named("number", Expr::Convert {
+                    expr: Box::new(Expr::Field(digits.clone())),
+                    type_: SimpleSimpleType::I64,
+                })
+                .return_named("flag", Expr::Convert {
+                    expr: Box::new(Expr::Field(hizat.clone())),
+                    type_: SimpleSimpleType::Bool,
+                })
+                .order(Expr::Field(hizat.clone()), Order::Asc)
+                .build_query("get_banan", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Tuple list parameters
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zR4T9GM6B", "bannanana");
+        let hizat = bananna.field(&mut v, "zJ2W7QD5N", "hizat", field_i32().build());
+        let code = bananna.field(&mut v, "zX8E3LF1C", "code", field_str().build());
+        let color = bananna.field(&mut v, "zK6P1VS9H", "color", field_str().build());
+        bananna.index("zU5Y2NA8G", "bannanana_key", &[&hizat, &code]).unique().build(&mut v);
+        generate(&root.join("tests/pg_gen_tuples.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("hizat", &hizat), set_field("code", &code), set_field("color", &color)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .return_field(&color)
+                .where_(Expr::BinOp {
+                    left: Box::new(Expr::Tuple(vec![Expr::Field(hizat.clone()), Expr::Field(code.clone())])),
+                    op: BinOp::In,
+                    right: Box::new(Expr::ParamTuples {
+                        name: "keys".into(),
+                        types: vec![hizat.type_.type_.clone(), code.type_.type_.clone()],
+                    }),
+                })
+                .order(Expr::Field(color.clone()), Order::Asc)
+                .build_query("get_banans", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Values source
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zC5M2XR8T", "bannanana");
+        let hizat = bananna.field(&mut v, "zW9B4JE1L", "hizat", field_i32().build());
+        let label = |id: &str| Expr::Binding(ExprValName {
+            table_id: "labels".into(),
+            id: id.into(),
+        });
+        generate(&root.join("tests/pg_gen_values.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(&bananna, vec![set_field("hizat", &hizat)]).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .join(Join {
+                    source: Box::new(NamedSelectSource {
+                        source: JoinSource::Values {
+                            columns: vec!["hizat".into(), "name".into()],
+                            rows: vec![
+                                vec![Expr::LitI32(1), Expr::LitString("one".into())],
+                                vec![Expr::LitI32(2), Expr::LitNull(type_str().build().type_)],
+                                vec![Expr::LitI32(3), Expr::Param {
+                                    name: "three".into(),
+                                    type_: type_str().build(),
+                                }]
+                            ],
+                        },
+                        alias: Some("labels".into()),
+                    }),
+                    type_: JoinType::Inner,
+                    on: Expr::BinOp {
+                        left: Box::new(Expr::Field(hizat.clone())),
+                        op: BinOp::Equals,
+                        right: Box::new(label("hizat")),
+                    },
+                })
+                .return_field(&hizat)
+                .return_named("name", label("name"))
+                .order(Expr::Field(hizat.clone()), Order::Asc)
+                .build_query("get_labeled", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Row locking
+    {
+        let mut v = Version::default();
+        let jobs = v.table("zQ7V3HN5D", "jobs");
+        let id = jobs.field(&mut v, "zB1K8RT4M", "id", field_auto().build());
+        let state = jobs.field(&mut v, "zG6S2LW9E", "state", field_str().build());
+        jobs.index("zY4A9CU3X", "jobs_id", &[&id]).unique().build(&mut v);
+        generate(&root.join("tests/pg_gen_lock.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(&jobs, vec![set_field("state", &state)]).build_query("insert_job", QueryResCount::None),
+            new_update(&jobs, vec![(state.clone(), Expr::LitString("running".into()))])
+                .where_(Expr::BinOp {
+                    left: Box::new(Expr::Field(id.clone())),
+                    op: BinOp::Equals,
+                    right: Box::new(
+                        Expr::Select(
+                            Box::new(
+                                new_select(&jobs)
+                                    .return_field(&id)
+                                    .where_(Expr::BinOp {
+                                        left: Box::new(Expr::Field(state.clone())),
+                                        op: BinOp::Equals,
+                                        right: Box::new(Expr::LitString("queued".into())),
+                                    })
+                                    .order(Expr::Field(id.clone()), Order::Asc)
+                                    .limit(Expr::LitI64(1))
+                                    .lock(Lock {
+                                        strength: LockStrength::Update,
+                                        of: vec![],
+                                        wait: LockWait::SkipLocked,
+                                    })
+                                    .build_migration(),
+                            ),
+                        ),
+                    ),
+                })
+                .return_field(&id)
+                .build_query("take_job", QueryResCount::Many),
+            new_select(&jobs)
+                .return_field(&state)
+                .where_(Expr::BinOp {
+                    left: Box::new(Expr::Field(id.clone())),
+                    op: BinOp::Equals,
+                    right: Box::new(Expr::Param {
+                        name: "id".into(),
+                        type_: id.type_.type_.clone(),
+                    }),
+                })
+                .lock(Lock {
+                    strength: LockStrength::NoKeyUpdate,
+                    of: vec!["jobs".into()],
+                    wait: LockWait::NoWait,
+                })
+                .build_query("lock_job", QueryResCount::One)
+        ]).unwrap();
+    }
+
+    // # Distinct on
+    {
+        let mut v = Version::default();
+        let posts = v.table("zN6R1GT8K", "posts");
+        let user = posts.field(&mut v, "zD3X7MB2Q", "user", field_i32().build());
+        let at = posts.field(&mut v, "zH9C4WS6V", "at", field_i64().build());
+        let text = posts.field(&mut v, "zA5L8PE1Y", "text", field_str().build());
+        generate(&root.join("tests/pg_gen_distinct_on.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &posts,
+                vec![set_field("user", &user), set_field("at", &at), set_field("text", &text)],
+            ).build_query("insert_post", QueryResCount::None),
+            new_select(&posts)
+                .distinct_on(vec![Expr::Field(user.clone())])
+                .return_fields(&[&user, &text])
+                .order(Expr::Field(user.clone()), Order::Asc)
+                .order(Expr::Field(at.clone()), Order::Desc)
+                .build_query("get_latest", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Full-text search
+    {
+        let mut v = Version::default();
+        let docs = v.table("zE2T6NJ9W", "docs");
+        let title = docs.field(&mut v, "zR8F3KA1P", "title", field_str().build());
+        let search = docs.field(&mut v, "zM4Q7XC2H", "search", field_tsvector().build());
+        docs.index("zJ1V5BD8S", "docs_search", &[&search]).method(IndexMethod::Gin).build(&mut v);
+        let title_param = Expr::Param {
+            name: "title".into(),
+            type_: title.type_.type_.clone(),
+        };
+        let terms_param = Expr::Param {
+            name: "terms".into(),
+            type_: type_str().build(),
+        };
+        generate(&root.join("tests/pg_gen_text_search.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &docs,
+                vec![
+                    (title.clone(), title_param.clone()),
+                    (search.clone(), functions::to_tsvector("english", title_param.clone()))
+                ],
+            ).build_query("insert_doc", QueryResCount::None),
+            new_select(&docs)
+                .return_field(&title)
+                .where_(Expr::BinOp {
+                    left: Box::new(Expr::Field(search.clone())),
+                    op: BinOp::TextSearchMatch,
+                    right: Box::new(functions::websearch_to_tsquery("english", terms_param.clone())),
+                })
+                .order(
+                    functions::ts_rank(
+                        Expr::Field(search.clone()),
+                        functions::websearch_to_tsquery("english", terms_param.clone()),
+                    ),
+                    Order::Desc,
+                )
+                .order(Expr::Field(title.clone()), Order::Asc)
+                .build_query("search_docs", QueryResCount::Many),
+            new_select(&docs)
+                .return_field(&title)
+                .where_(Expr::BinOp {
+                    left: Box::new(Expr::Field(search.clone())),
+                    op: BinOp::TextSearchMatch,
+                    right: Box::new(Expr::Param {
+                        name: "terms".into(),
+                        type_: type_tsquery().build(),
+                    }),
+                })
+                .order(Expr::Field(title.clone()), Order::Asc)
+                .build_query("search_docs_parsed", QueryResCount::Many),
+            new_select(&docs)
+                .return_field(&search)
+                .where_(eq_field("title", &title))
+                .build_query("get_search", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Index methods
+    {
+        let mut v = Version::default();
+        let events = v.table("zX5K9PB3N", "events");
+        let name = events.field(&mut v, "zC1R6HT8M", "name", field_str().build());
+        let kind = events.field(&mut v, "zW7D2QJ4F", "kind", field_i32().build());
+        let at = events.field(&mut v, "zA3N8GV1Y", "at", field_i64().build());
+        let search = events.field(&mut v, "zF6L4SE9U", "search", field_tsvector().build());
+        events
+            .index("zP2H7CK5T", "events_name", &[&name])
+            .opclass(&name, "text_pattern_ops")
+            .include(&[&kind])
+            .concurrently()
+            .build(&mut v);
+        events.index("zK8B1MR6W", "events_kind", &[&kind]).method(IndexMethod::Hash).concurrently().build(&mut v);
+        events.index("zT4J9XA2D", "events_at", &[&at]).method(IndexMethod::Brin).build(&mut v);
+        events.index("zM6V3FN7Q", "events_search", &[&search]).method(IndexMethod::Gist).build(&mut v);
+        let tags = v.table("zQ9G5WD1H", "tags");
+        let tag = tags.field(&mut v, "zE4Y8TL3B", "tag", field_str().build());
+        tags.index("zU1S6KC9R", "tags_tag", &[&tag]).unique().concurrently().build(&mut v);
+        generate(&root.join("tests/pg_gen_index_methods.rs"), vec![
+            // Versions (previous)
+            (0usize, {
+                let mut v = Version::default();
+                let events = v.table("zX5K9PB3N", "events");
+                events.field(&mut v, "zC1R6HT8M", "name", field_str().build());
+                let kind = events.field(&mut v, "zW7D2QJ4F", "kind", field_i32().build());
+                events.field(&mut v, "zA3N8GV1Y", "at", field_i64().build());
+                events.field(&mut v, "zF6L4SE9U", "search", field_tsvector().build());
+                events.index("zK8B1MR6W", "events_kind_old", &[&kind]).concurrently().build(&mut v);
+                v
+            }),
+            (1usize, v)
+        ], vec![
+            // Queries
+            new_insert(
+                &events,
+                vec![
+                    set_field("name", &name),
+                    set_field("kind", &kind),
+                    set_field("at", &at),
+                    (search.clone(), functions::to_tsvector("english", field_param("name", &name)))
+                ],
+            ).build_query("insert_event", QueryResCount::None),
+            new_select(&events)
+                .return_field(&kind)
+                .where_(Expr::BinOp {
+                    left: Box::new(Expr::Field(name.clone())),
+                    op: BinOp::Like,
+                    right: Box::new(Expr::Param {
+                        name: "pattern".into(),
+                        type_: type_str().build(),
+                    }),
+                })
+                .order(Expr::Field(kind.clone()), Order::Asc)
+                .build_query("get_kinds_like", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Concurrent index on a table dropped later
+    {
+        let mut v = Version::default();
+        let notes = v.table("zN2F7KD4R", "notes");
+        let text = notes.field(&mut v, "zB8M3QW6J", "text", field_str().build());
+        generate(&root.join("tests/pg_gen_index_concurrent_dropped.rs"), vec![
+            // Versions (previous)
+            (0usize, {
+                let mut v = Version::default();
+                let drafts = v.table("zH5T1CX9V", "drafts");
+                drafts.field(&mut v, "zR4G6PE2L", "text", field_str().build());
+                v
+            }),
+            (1usize, {
+                let mut v = Version::default();
+                let drafts = v.table("zH5T1CX9V", "drafts");
+                let text = drafts.field(&mut v, "zR4G6PE2L", "text", field_str().build());
+                drafts.index("zY7A3LU8S", "drafts_text", &[&text]).concurrently().build(&mut v);
+                v
+            }),
+            (2usize, v)
+        ], vec![
+            // Queries
+            new_insert(&notes, vec![set_field("text", &text)]).build_query("insert_note", QueryResCount::None)
+        ]).unwrap();
+    }
+
+    // # Identity columns and sequences
+    {
+        let mut v0 = Version::default();
+        let v0_tickets = v0.table("zQ4M8VN2C", "tickets");
+        v0_tickets.field(&mut v0, "zH7T3PW5K", "id", field_auto().build());
+        let v0_title = v0_tickets.field(&mut v0, "zD2X9RB6J", "title", field_str().build());
+        let mut v = Version::default();
+        for t in ["first", "second"] {
+            v.pre_migration(new_insert(&v0_tickets, vec![(v0_title.clone(), Expr::LitString(t.into()))]).build_migration());
+        }
+        let tickets = v.table("zQ4M8VN2C", "tickets");
+        let ticket_id = tickets.field(&mut v, "zH7T3PW5K", "id", field_i64().identity(Identity::ByDefault).build());
+        let title = tickets.field(&mut v, "zD2X9RB6J", "title", field_str().build());
+        let number = tickets.field(&mut v, "zN5G1YF8L", "number", field_i32().identity(Identity::Always).build());
+        let codes = v.sequence("zB9S4KE7T", "order_codes").start(1000).increment(10).build(&mut v);
+        let orders = v.table("zR3W7DJ1P", "orders");
+        orders.field(&mut v, "zT8K2LX6E", "id", field_i64().identity(Identity::Always).build());
+        let code = orders.field(&mut v, "zM1F5QA9H", "code", field_i64().build());
+        generate(&root.join("tests/pg_gen_identity.rs"), vec![
+            // Versions (previous)
+            (0usize, v0),
+            (1usize, v)
+        ], vec![
+            // Queries
+            new_insert(&tickets, vec![set_field("title", &title)])
+                .return_field(&ticket_id)
+                .build_query("insert_ticket", QueryResCount::One),
+            new_select(&tickets)
+                .return_fields(&[&ticket_id, &number, &title])
+                .order(Expr::Field(ticket_id.clone()), Order::Asc)
+                .build_query("list_tickets", QueryResCount::Many),
+            new_insert(&orders, vec![(code.clone(), functions::nextval(&codes))])
+                .return_field(&code)
+                .build_query("insert_order", QueryResCount::One)
+        ]).unwrap();
+    }
+
+    // # Schemas (namespaces) and extensions
+    {
+        let mut v0 = Version::default();
+        let v0_staging = v0.namespace("zP2H7WN4D", "staging");
+        let v0_items = v0.table("zS8C3KQ6V", "items");
+        let v0_name = v0_items.field(&mut v0, "zJ5Y1TB9R", "name", field_str().build());
+        v0_items.index("zE4M7XG2L", "items_name", &[&v0_name]).build(&mut v0);
+        let v0_logs = v0_staging.table(&mut v0, "zW9F6DA3U", "logs");
+        v0_logs.field(&mut v0, "zL3Q8HC5N", "message", field_str().build());
+        v0.sequence("zV4T1MS7E", "log_seq").build(&mut v0);
+        let mut v = Version::default();
+        v.pre_migration(new_insert(&v0_items, vec![(v0_name.clone(), Expr::LitString("red chair".into()))]).build_migration());
+        let archive = v.namespace("zP2H7WN4D", "archive");
+        let store = v.namespace("zK6R1VE8T", "store");
+        let trgm = v.extension("pg_trgm");
+        let products = store.table(&mut v, "zS8C3KQ6V", "products");
+        let name = products.field(&mut v, "zJ5Y1TB9R", "name", field_str().build());
+        products.index("zE4M7XG2L", "products_name", &[&name]).build(&mut v);
+        products
+            .index("zU2N9BF4Y", "products_name_trgm", &[&name])
+            .method(IndexMethod::Gin)
+            .opclass(&name, "gin_trgm_ops")
+            .requires(&trgm)
+            .build(&mut v);
+        let logs = archive.table(&mut v, "zW9F6DA3U", "logs");
+        let message = logs.field(&mut v, "zL3Q8HC5N", "message", field_str().build());
+        let number = logs.field(&mut v, "zN6G3PX8C", "number", field_i64().migrate_fill(Expr::LitI64(0)).build());
+        let log_ids = store.sequence("zV4T1MS7E", "log_ids").build(&mut v);
+        generate(&root.join("tests/pg_gen_namespaces.rs"), vec![
+            // Versions (previous)
+            (0usize, v0),
+            (1usize, v)
+        ], vec![
+            // Queries
+            new_insert(&products, vec![set_field("name", &name)]).build_query("insert_product", QueryResCount::None),
+            new_select(&products)
+                .return_field(&name)
+                .where_(Expr::BinOp {
+                    left: Box::new(Expr::Field(name.clone())),
+                    op: BinOp::Like,
+                    right: Box::new(Expr::Param {
+                        name: "pattern".into(),
+                        type_: type_str().build(),
+                    }),
+                })
+                .order(Expr::Field(name.clone()), Order::Asc)
+                .build_query("get_products_like", QueryResCount::Many),
+            new_insert(
+                &logs,
+                vec![set_field("message", &message), (number.clone(), functions::nextval(&log_ids))],
+            ).build_query("insert_log", QueryResCount::None),
+            new_select(&logs).return_fields(&[&number, &message]).build_query("get_logs", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Partitioned tables
+    {
+        let time = |y: i32| chrono::TimeZone::with_ymd_and_hms(&chrono::Utc, y, 1, 1, 0, 0, 0).unwrap();
+        let mut v0 = Version::default();
+        let v0_events = v0.table("zF3V8NC1K", "events");
+        let v0_at = v0_events.field(&mut v0, "zX6H2RD9M", "at", field_utctime_chrono().build());
+        let v0_name = v0_events.field(&mut v0, "zQ1B7TW4S", "name", field_str().build());
+        v0_events.partition_by(PartitionMethod::Range, &[&v0_at]).build(&mut v0);
+        v0_events.partition(&mut v0, "zD9K4YP6A", "events_early", PartitionBound::Range {
+            from: vec![Expr::LitUtcTimeChrono(time(2000))],
+            to: vec![Expr::LitUtcTimeChrono(time(2020))],
+        });
+        let mut v = Version::default();
+        v.pre_migration(
+            new_insert(
+                &v0_events,
+                vec![
+                    (v0_at.clone(), Expr::LitUtcTimeChrono(time(2010))),
+                    (v0_name.clone(), Expr::LitString("launch".into()))
+                ],
+            ).build_migration(),
+        );
+        let events = v.table("zF3V8NC1K", "events");
+        let at = events.field(&mut v, "zX6H2RD9M", "at", field_utctime_chrono().build());
+        let name = events.field(&mut v, "zQ1B7TW4S", "name", field_str().build());
+        events
+            .partition_by(PartitionMethod::Range, &[&at])
+            .time_partitions(PartitionInterval::Month, "create_event_partition_for")
+            .build(&mut v);
+        events.partition(&mut v, "zD9K4YP6A", "events_before_2024", PartitionBound::Range {
+            from: vec![Expr::LitUtcTimeChrono(time(2000))],
+            to: vec![Expr::LitUtcTimeChrono(time(2024))],
+        });
+        events.partition(&mut v, "zM5E1GJ8W", "events_default", PartitionBound::Default);
+        let sessions = v.table("zH7N3QF5B", "sessions");
+        let user = sessions.field(&mut v, "zR2T9VK4C", "user", field_i64().build());
+        sessions.partition_by(PartitionMethod::Hash, &[&user]).build(&mut v);
+        for i in 0 .. 2 {
+            sessions.partition(&mut v, format!("zS4W{}", i), format!("sessions_{}", i), PartitionBound::Hash {
+                modulus: 2,
+                remainder: i,
+            });
+        }
+        generate(&root.join("tests/pg_gen_partitions.rs"), vec![
+            // Versions (previous)
+            (0usize, v0),
+            (1usize, v)
+        ], vec![
+            // Queries
+            new_insert(&events, vec![set_field("at", &at), set_field("name", &name)]).build_query(
+                "insert_event",
+                QueryResCount::None,
+            ),
+            new_select(&events)
+                .return_field(&name)
+                .order(Expr::Field(at.clone()), Order::Asc)
+                .build_query("get_events", QueryResCount::Many),
+            new_insert(&sessions, vec![set_field("user", &user)]).build_query("insert_session", QueryResCount::None)
+        ]).unwrap();
+    }
+
+    // # Row-level security
+    {
+        let mut v0 = Version::default();
+        let v0_docs = v0.table("zW8C3LN5T", "docs");
+        let v0_tenant = v0_docs.field(&mut v0, "zB6Y1QH7P", "tenant_id", field_i64().build());
+        v0_docs.field(&mut v0, "zK2M9FS4D", "body", field_str().build());
+        v0_docs
+            .policy("zJ5R8XA2E", "tenant_only")
+            .command(PolicyCommand::Select)
+            .using(Expr::BinOp {
+                left: Box::new(Expr::Field(v0_tenant.clone())),
+                op: BinOp::Equals,
+                right: Box::new(Expr::LitI64(1)),
+            })
+            .build(&mut v0);
+        v0_docs.grant(&mut v0, "app", &[Privilege::Select]);
+        let mut v = Version::default();
+        let tenants = v.namespace("zH3X7PC1V", "tenants");
+        tenants.grant_usage(&mut v, "app");
+        let docs = tenants.table(&mut v, "zW8C3LN5T", "docs");
+        let tenant = docs.field(&mut v, "zB6Y1QH7P", "tenant_id", field_i64().build());
+        let body = docs.field(&mut v, "zK2M9FS4D", "body", field_str().build());
+        docs.row_level_security(&mut v, true);
+        let current_tenant = || Expr::BinOp {
+            left: Box::new(Expr::Field(tenant.clone())),
+            op: BinOp::Equals,
+            right: Box::new(Expr::Convert {
+                expr: Box::new(functions::current_setting_opt("app.tenant")),
+                type_: SimpleSimpleType::I64,
+            }),
+        };
+        docs
+            .policy("zJ5R8XA2E", "tenant_isolation")
+            .to("app")
+            .using(current_tenant())
+            .with_check(current_tenant())
+            .build(&mut v);
+        docs.grant(&mut v, "app", &[Privilege::Select, Privilege::Insert]);
+        generate(&root.join("tests/pg_gen_rls.rs"), vec![
+            // Versions (previous)
+            (0usize, v0),
+            (1usize, v)
+        ], vec![
+            // Queries
+            new_insert(&docs, vec![set_field("tenant_id", &tenant), set_field("body", &body)]).build_query(
+                "insert_doc",
+                QueryResCount::None,
+            ),
+            new_select(&docs)
+                .return_field(&body)
+                .order(Expr::Field(body.clone()), Order::Asc)
+                .build_query("get_docs", QueryResCount::Many)
+        ]).unwrap();
+    }
+
     // # Select group
     {
         let mut v = Version::default();
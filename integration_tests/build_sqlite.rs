@@ -6,6 +6,7 @@ use {
             new_delete,
             new_insert,
             new_select,
+            new_select_from,
             new_select_body,
             new_update,
             query::{
@@ -14,8 +15,25 @@ use {
                     Binding,
                     ComputeType,
                     Expr,
+                    PostfixOp,
+                    PrefixOp,
+                    WindowFrame,
+                    WindowFrameBound,
+                    WindowFrameUnits,
+                    WindowSpec,
                 },
+                functions,
                 helpers::{
+                    expr_and,
+                    expr_cte_depth_below,
+                    expr_cte_depth_next,
+                    expr_cte_path_append,
+                    expr_cte_path_excludes,
+                    expr_cte_path_start,
+                    expr_field_eq,
+                    expr_field_gte,
+                    expr_field_lte,
+                    fn_count,
                     fn_max,
                     set_field,
                 },
@@ -27,6 +45,7 @@ use {
                     NamedSelectSource,
                     Order,
                     SelectJunction,
+                    SelectJunctionOperator,
                 },
                 utils::{
                     CteBuilder,
@@ -54,7 +73,12 @@ use {
                     Field,
                 },
             },
-            types::type_i32,
+            types::{
+                type_i32,
+                type_i64,
+                type_str,
+                SimpleSimpleType,
+            },
             QueryResCount,
             Version,
         },
@@ -831,8 +855,8 @@ pub fn build(root: &Path) {
             ).build_query("insert_banan", QueryResCount::None),
             new_select(&bananna).return_field(&hizat).return_field(&hizat2).return_named("zombo", Expr::Window {
                 expr: Box::new(fn_max(Expr::Binding(Binding::field(&hizat2)))),
-                partition_by: vec![],
-                order_by: vec![],
+                filter: None,
+                over: WindowSpec::default(),
             }).build_query("get_banan", QueryResCount::Many)
         ]).unwrap();
     }
@@ -855,4 +879,926 @@ pub fn build(root: &Path) {
             }).build_query("get_banan", QueryResCount::Many)
         ]).unwrap();
     }
+
+    // # Table-valued function
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zEOIWAACJ", "bannanana");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let tags = bananna.field(&mut v, "z3CRAVV3M", "tags", field_str().build());
+        generate(&root.join("tests/sqlite_gen_select_table_function.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("v", &hizat), set_field("tags", &tags)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna).join(Join {
+                source: Box::new(NamedSelectSource {
+                    source: JoinSource::Function {
+                        func: "json_each".into(),
+                        args: vec![Expr::Binding(Binding::field(&tags))],
+                        columns: vec![("value".into(), type_i64().build())],
+                    },
+                    alias: Some("j".into()),
+                }),
+                type_: JoinType::Inner,
+                on: Expr::LitBool(true),
+            }).return_field(&hizat).return_named("tag", Expr::Binding(Binding {
+                table_id: "j".into(),
+                id: "value".into(),
+            })).build_query("get_banan", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Select having
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zEOIWAACJ", "bannanana");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let hizat2 = bananna.field(&mut v, "z3CRAVV3M", "hizat2", field_i32().build());
+        generate(&root.join("tests/sqlite_gen_select_having.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("v", &hizat), set_field("v2", &hizat2)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .return_named("count", fn_count(Expr::Binding(Binding::field(&hizat2))))
+                .group(vec![Expr::Binding(Binding::field(&hizat))])
+                .having(Expr::BinOp {
+                    left: Box::new(fn_count(Expr::Binding(Binding::field(&hizat2)))),
+                    op: BinOp::GreaterThan,
+                    right: Box::new(Expr::LitI64(1)),
+                })
+                .build_query("get_banan", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Select order choice, offset, nulls
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zEOIWAACJ", "bannanana");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let hizat2 = bananna.field(&mut v, "z3CRAVV3M", "hizat2", field_i32().opt().build());
+        generate(&root.join("tests/sqlite_gen_select_order_choice.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("v", &hizat), set_field("v2", &hizat2)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .order_choice(
+                    "BananOrder",
+                    vec![
+                        ("HizatAsc".into(), vec![(Expr::Binding(Binding::field(&hizat)), Order::Asc)]),
+                        ("HizatDesc".into(), vec![(Expr::Binding(Binding::field(&hizat)), Order::Desc)])
+                    ],
+                )
+                .limit(Expr::LitI64(2))
+                .offset(Expr::LitI64(1))
+                .build_query("get_banan", QueryResCount::Many),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .order(Expr::Binding(Binding::field(&hizat2)), Order::AscNullsLast)
+                .build_query("get_banan_nulls_last", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Select paginated
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zEOIWAACJ", "bannanana");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let hizat2 = bananna.field(&mut v, "z3CRAVV3M", "hizat2", field_str().build());
+        generate(&root.join("tests/sqlite_gen_select_paginate.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("v", &hizat), set_field("v2", &hizat2)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .paginate_by(&[(Expr::Binding(Binding::field(&hizat)), Order::Asc)])
+                .build_query("get_banan", QueryResCount::Many),
+            new_select(&bananna)
+                .return_fields(&[&hizat, &hizat2])
+                .paginate_by(
+                    &[
+                        (Expr::Binding(Binding::field(&hizat2)), Order::Asc),
+                        (Expr::Binding(Binding::field(&hizat)), Order::Desc),
+                    ],
+                )
+                .build_query("get_banan_mixed", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Case
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zEOIWAACJ", "bannanana");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        generate(&root.join("tests/sqlite_gen_case.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(&bananna, vec![set_field("v", &hizat)]).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .return_named("size", Expr::Case {
+                    operand: None,
+                    whens: vec![(Expr::BinOp {
+                        left: Box::new(Expr::Binding(Binding::field(&hizat))),
+                        op: BinOp::GreaterThan,
+                        right: Box::new(Expr::LitI32(10)),
+                    }, Expr::LitString("big".into()))],
+                    else_: Some(Box::new(Expr::LitString("small".into()))),
+                })
+                .return_named("name", Expr::Case {
+                    operand: Some(Box::new(Expr::Binding(Binding::field(&hizat)))),
+                    whens: vec![(Expr::LitI32(1), Expr::LitString("one".into()))],
+                    else_: None,
+                })
+                .order(Expr::Binding(Binding::field(&hizat)), Order::Asc)
+                .build_query("get_banan", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Functions
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zEOIWAACJ", "bannanana");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let name = bananna.field(&mut v, "z8RDVKDG3", "name", field_str().opt().build());
+        generate(&root.join("tests/sqlite_gen_functions.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("hizat", &hizat), set_field("name", &name)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .return_named("count", functions::count_rows())
+                .return_named("sum", functions::sum(Expr::Binding(Binding::field(&hizat))))
+                .return_named("max", functions::max(Expr::Binding(Binding::field(&hizat))))
+                .return_named("names", functions::count(Expr::Binding(Binding::field(&name))))
+                .build_query("get_aggregates", QueryResCount::One),
+            new_select(&bananna)
+                .return_named("abs", functions::abs(Expr::Binding(Binding::field(&hizat))))
+                .return_named(
+                    "name",
+                    functions::lower(
+                        functions::coalesce(
+                            vec![Expr::Binding(Binding::field(&name)), Expr::LitString("ANON".to_string())],
+                        ),
+                    ),
+                )
+                .return_named("len", functions::length(Expr::Binding(Binding::field(&name))))
+                .order(Expr::Binding(Binding::field(&hizat)), Order::Asc)
+                .build_query("get_scalars", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Window frames, filters, named windows
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zEOIWAACJ", "bannanana");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let hizat2 = bananna.field(&mut v, "z3CRAVV3M", "hizat2", field_i64().build());
+        generate(&root.join("tests/sqlite_gen_window_frame.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("v", &hizat), set_field("v2", &hizat2)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .return_field(&hizat2)
+                .window("by_hizat", WindowSpec {
+                    partition_by: vec![Expr::Binding(Binding::field(&hizat))],
+                    ..Default::default()
+                })
+                .window("by_hizat_ordered", WindowSpec {
+                    base: Some("by_hizat".to_string()),
+                    order_by: vec![(Expr::Binding(Binding::field(&hizat2)), Order::Asc)],
+                    ..Default::default()
+                })
+                .return_named("row", Expr::Window {
+                    expr: Box::new(functions::row_number()),
+                    filter: None,
+                    over: WindowSpec {
+                        base: Some("by_hizat_ordered".to_string()),
+                        ..Default::default()
+                    },
+                })
+                .return_named("prev", Expr::Window {
+                    expr: Box::new(functions::lag(Expr::Binding(Binding::field(&hizat2)), 1)),
+                    filter: None,
+                    over: WindowSpec {
+                        base: Some("by_hizat_ordered".to_string()),
+                        ..Default::default()
+                    },
+                })
+                .return_named("running", Expr::Window {
+                    expr: Box::new(functions::sum(Expr::Binding(Binding::field(&hizat2)))),
+                    filter: None,
+                    over: WindowSpec {
+                        base: Some("by_hizat_ordered".to_string()),
+                        frame: Some(WindowFrame {
+                            units: WindowFrameUnits::Rows,
+                            start: WindowFrameBound::UnboundedPreceding,
+                            end: Some(WindowFrameBound::CurrentRow),
+                        }),
+                        ..Default::default()
+                    },
+                })
+                .return_named("moving", Expr::Window {
+                    expr: Box::new(functions::sum(Expr::Binding(Binding::field(&hizat2)))),
+                    filter: None,
+                    over: WindowSpec {
+                        order_by: vec![(Expr::Binding(Binding::field(&hizat2)), Order::Asc)],
+                        frame: Some(WindowFrame {
+                            units: WindowFrameUnits::Rows,
+                            start: WindowFrameBound::Preceding(Box::new(Expr::LitI64(1))),
+                            end: Some(WindowFrameBound::CurrentRow),
+                        }),
+                        ..Default::default()
+                    },
+                })
+                .return_named("big", Expr::Window {
+                    expr: Box::new(functions::count_rows()),
+                    filter: Some(Box::new(Expr::BinOp {
+                        left: Box::new(Expr::Binding(Binding::field(&hizat2))),
+                        op: BinOp::GreaterThan,
+                        right: Box::new(Expr::LitI64(5)),
+                    })),
+                    over: WindowSpec {
+                        base: Some("by_hizat".to_string()),
+                        ..Default::default()
+                    },
+                })
+                .order(Expr::Binding(Binding::field(&hizat2)), Order::Asc)
+                .build_query("get_banan", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Operators
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zEOIWAACJ", "bannanana");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let name = bananna.field(&mut v, "z8RDVKDG3", "name", field_str().opt().build());
+        let b = |e: Expr| Box::new(e);
+        let hizat_e = || Expr::Binding(Binding::field(&hizat));
+        let name_e = || Expr::Binding(Binding::field(&name));
+        generate(&root.join("tests/sqlite_gen_operators.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("hizat", &hizat), set_field("name", &name)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .return_named("modded", Expr::BinOp {
+                    left: b(hizat_e()),
+                    op: BinOp::Mod,
+                    right: b(Expr::LitI32(3)),
+                })
+                .return_named("bits", Expr::BinOpChain {
+                    op: BinOp::BitOr,
+                    exprs: vec![Expr::BinOp {
+                        left: b(hizat_e()),
+                        op: BinOp::BitAnd,
+                        right: b(Expr::LitI32(6)),
+                    }, Expr::BinOp {
+                        left: b(Expr::LitI32(1)),
+                        op: BinOp::ShiftLeft,
+                        right: b(Expr::LitI32(4)),
+                    }],
+                })
+                .return_named("neg", Expr::PrefixOp {
+                    op: PrefixOp::Negate,
+                    right: b(hizat_e()),
+                })
+                .return_named("inv", Expr::PrefixOp {
+                    op: PrefixOp::BitNot,
+                    right: b(hizat_e()),
+                })
+                .return_named("greeting", Expr::BinOp {
+                    left: b(Expr::LitString("hi ".to_string())),
+                    op: BinOp::Concat,
+                    right: b(name_e()),
+                })
+                .return_named("unnamed", Expr::PostfixOp {
+                    op: PostfixOp::IsNull,
+                    left: b(name_e()),
+                })
+                .where_(Expr::BinOpChain {
+                    op: BinOp::Between,
+                    exprs: vec![hizat_e(), Expr::LitI32(2), Expr::LitI32(20)],
+                })
+                .order(hizat_e(), Order::Asc)
+                .build_query("get_banan", QueryResCount::Many),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .where_(expr_and(vec![Expr::BinOpChain {
+                    op: BinOp::NotLike,
+                    exprs: vec![name_e(), Expr::LitString("%!%%".to_string()), Expr::LitString("!".to_string())],
+                }, Expr::BinOp {
+                    left: b(name_e()),
+                    op: BinOp::Glob,
+                    right: b(Expr::LitString("[a-z]*".to_string())),
+                }]))
+                .order(hizat_e(), Order::Asc)
+                .build_query("get_banan_matching", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Null narrowing
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zEOIWAACJ", "bannanana");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let name = bananna.field(&mut v, "z8RDVKDG3", "name", field_str().opt().build());
+        let other = bananna.field(&mut v, "z2W8FJ6QX", "other", field_i32().opt().build());
+        let names = v.table("z1K5RN3CV", "names");
+        let names_name = names.field(&mut v, "zT4X0E7AH", "name", field_str().build());
+        let names_nick = names.field(&mut v, "zB9P2L6UD", "nick", field_str().opt().build());
+        generate(&root.join("tests/sqlite_gen_null_narrowing.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("hizat", &hizat), set_field("name", &name), set_field("other", &other)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_insert(
+                &names,
+                vec![set_field("name", &names_name), set_field("nick", &names_nick)],
+            ).build_query("insert_name", QueryResCount::None),
+            new_select(&bananna)
+                .return_fields(&[&hizat, &name, &other])
+                .where_(expr_and(vec![Expr::PostfixOp {
+                    op: PostfixOp::IsNotNull,
+                    left: Box::new(Expr::Binding(Binding::field(&name))),
+                }, Expr::BinOp {
+                    left: Box::new(Expr::BinOp {
+                        left: Box::new(Expr::Binding(Binding::field(&other))),
+                        op: BinOp::Plus,
+                        right: Box::new(Expr::LitI32(1)),
+                    }),
+                    op: BinOp::GreaterThan,
+                    right: Box::new(Expr::LitI32(0)),
+                }]))
+                .order(Expr::Binding(Binding::field(&hizat)), Order::Asc)
+                .build_query("get_banan", QueryResCount::Many),
+            new_select(&bananna)
+                .join(Join {
+                    source: Box::new(NamedSelectSource {
+                        source: JoinSource::Table(names.clone()),
+                        alias: None,
+                    }),
+                    type_: JoinType::Inner,
+                    on: Expr::BinOp {
+                        left: Box::new(Expr::Binding(Binding::field(&name))),
+                        op: BinOp::Equals,
+                        right: Box::new(Expr::Binding(Binding::field(&names_name))),
+                    },
+                })
+                .return_fields(&[&hizat, &name, &names_nick])
+                .order(Expr::Binding(Binding::field(&hizat)), Order::Asc)
+                .build_query("get_banan_joined", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Result count inference
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zQ7M2XK4P", "bannanana");
+        let hizat = bananna.field(&mut v, "zH5C8RW1T", "hizat", field_i32().build());
+        let code = bananna.field(&mut v, "zN3V6BJ9E", "code", field_str().build());
+        bananna.constraint(
+            &mut v,
+            "zD1Y4GS7L",
+            "bannanana_pk",
+            ConstraintType::PrimaryKey(PrimaryKeyDef { fields: vec![hizat.clone()] }),
+        );
+        bananna.index("zF8K2TA5U", "bannanana_code", &[&code]).unique().build(&mut v);
+        let peel = v.table("zR6W9LC3X", "peel");
+        let peel_banan = peel.field(&mut v, "zJ4B7MQ2N", "banan", field_i32().build());
+        let peel_idx = peel.field(&mut v, "zV2E5HD8S", "idx", field_i32().build());
+        let peel_color = peel.field(&mut v, "zC9U3PF6Y", "color", field_str().build());
+        peel.constraint(
+            &mut v,
+            "zK1T8NW4G",
+            "peel_pk",
+            ConstraintType::PrimaryKey(PrimaryKeyDef { fields: vec![peel_banan.clone(), peel_idx.clone()] }),
+        );
+        generate(&root.join("tests/sqlite_gen_res_count.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(&bananna, vec![set_field("hizat", &hizat), set_field("code", &code)]).build_query(
+                "insert_banan",
+                QueryResCount::Auto,
+            ),
+            new_insert(
+                &peel,
+                vec![set_field("banan", &peel_banan), set_field("idx", &peel_idx), set_field("color", &peel_color)],
+            )
+                .return_field(&peel_color)
+                .build_query("insert_peel", QueryResCount::Auto),
+            new_select(&bananna)
+                .return_field(&code)
+                .where_(expr_field_eq("hizat", &hizat))
+                .build_query("get_by_pk", QueryResCount::Auto),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .where_(expr_field_eq("code", &code))
+                .build_query("get_by_code", QueryResCount::Auto),
+            new_select(&bananna)
+                .return_named("count", functions::count_rows())
+                .build_query("count_banan", QueryResCount::Auto),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .order(Expr::Binding(Binding::field(&hizat)), Order::Asc)
+                .build_query("list_banan", QueryResCount::Auto),
+            new_select(&bananna)
+                .join(Join {
+                    source: Box::new(NamedSelectSource {
+                        source: JoinSource::Table(peel.clone()),
+                        alias: None,
+                    }),
+                    type_: JoinType::Inner,
+                    on: expr_and(vec![Expr::BinOp {
+                        left: Box::new(Expr::Binding(Binding::field(&peel_banan))),
+                        op: BinOp::Equals,
+                        right: Box::new(Expr::Binding(Binding::field(&hizat))),
+                    }, expr_field_eq("idx", &peel_idx)]),
+                })
+                .return_field(&peel_color)
+                .where_(expr_field_eq("code", &code))
+                .build_query("get_peel", QueryResCount::Auto),
+            new_select(&peel)
+                .return_field(&peel_color)
+                .where_(expr_field_eq("banan", &peel_banan))
+                .order(Expr::Binding(Binding::field(&peel_idx)), Order::Asc)
+                .build_query("list_peel", QueryResCount::Auto),
+            new_delete(&bananna)
+                .where_(expr_field_eq("hizat", &hizat))
+                .return_field(&code)
+                .build_query("delete_banan", QueryResCount::Auto)
+        ]).unwrap();
+    }
+
+    // # Result count, limit and returning
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zW3H8PD5K", "bannanana");
+        let hizat = bananna.field(&mut v, "zB6T1QX9M", "hizat", field_i32().build());
+        let code = bananna.field(&mut v, "zE4N7RG2V", "code", field_str().build());
+        bananna.constraint(
+            &mut v,
+            "zL9C3YU6A",
+            "bannanana_pk",
+            ConstraintType::PrimaryKey(PrimaryKeyDef { fields: vec![hizat.clone()] }),
+        );
+        generate(&root.join("tests/sqlite_gen_res_count_limit.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(&bananna, vec![set_field("hizat", &hizat), set_field("code", &code)]).build_query(
+                "insert_banan",
+                QueryResCount::None,
+            ),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .order(Expr::Binding(Binding::field(&hizat)), Order::Desc)
+                .limit(Expr::LitI64(1))
+                .build_query("last_banan", QueryResCount::Auto),
+            new_select(&bananna)
+                .return_field(&code)
+                .where_(expr_field_eq("code", &code))
+                .limit(Expr::LitI64(1))
+                .build_query("get_by_code", QueryResCount::MaybeOne),
+            new_update(&bananna, vec![set_field("code", &code)])
+                .where_(expr_field_eq("hizat", &hizat))
+                .return_field(&code)
+                .build_query("update_banan", QueryResCount::Auto),
+            new_delete(&bananna)
+                .where_(expr_field_eq("hizat", &hizat))
+                .return_field(&code)
+                .build_query("delete_banan", QueryResCount::One)
+        ]).unwrap();
+    }
+
+    // # Convert
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zW5G8NB2R", "bannanana");
+        let hizat = bananna.field(&mut v, "zP3L7XC1M", "hizat", field_i32().build());
+        let digits = bananna.field(&mut v, "zA9S4KE6Q", "digits", field_str().opt().build());
+        generate(&root.join("tests/sqlite_gen_convert.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(&bananna, vec![set_field("hizat", &hizat), set_field("digits", &digits)]).build_query(
+                "insert_banan",
+                QueryResCount::None,
+            ),
+            new_select(&bananna)
+                .return_named("text", Expr::Convert {
+                    expr: Box::new(Expr::Binding(Binding::field(&hizat))),
+                    type_: SimpleSimpleType::String,
+                })
+                .return_named("real", Expr::Convert {
+                    expr: Box::new(Expr::Binding(Binding::field(&hizat))),
+                    type_: SimpleSimpleType::F64,
+                })
+                .return_named("number", Expr::Convert {
+                    expr: Box::new(Expr::Binding(Binding::field(&digits))),
+                    type_: SimpleSimpleType::I64,
+                })
+                .return_named("flag", Expr::Convert {
+                    expr: Box::new(Expr::Binding(Binding::field(&hizat))),
+                    type_: SimpleSimpleType::Bool,
+                })
+                .order(Expr::Binding(Binding::field(&hizat)), Order::Asc)
+                .build_query("get_banan", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Tuple list parameters
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zR4T9GM6B", "bannanana");
+        let hizat = bananna.field(&mut v, "zJ2W7QD5N", "hizat", field_i32().build());
+        let code = bananna.field(&mut v, "zX8E3LF1C", "code", field_str().build());
+        let color = bananna.field(&mut v, "zK6P1VS9H", "color", field_str().build());
+        bananna.constraint(
+            &mut v,
+            "zU5Y2NA8G",
+            "bannanana_pk",
+            ConstraintType::PrimaryKey(PrimaryKeyDef { fields: vec![hizat.clone(), code.clone()] }),
+        );
+        generate(&root.join("tests/sqlite_gen_tuples.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("hizat", &hizat), set_field("code", &code), set_field("color", &color)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .return_field(&color)
+                .where_(Expr::BinOp {
+                    left: Box::new(
+                        Expr::Tuple(vec![Expr::Binding(Binding::field(&hizat)), Expr::Binding(Binding::field(&code))]),
+                    ),
+                    op: BinOp::In,
+                    right: Box::new(Expr::ParamTuples {
+                        name: "keys".into(),
+                        types: vec![hizat.type_.type_.clone(), code.type_.type_.clone()],
+                    }),
+                })
+                .order(Expr::Binding(Binding::field(&color)), Order::Asc)
+                .build_query("get_banans", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Recursive CTE
+    {
+        let mut v = Version::default();
+        let category = v.table("zC3F8WQ2D", "category");
+        let cat_id = category.field(&mut v, "zI6N1TB4K", "id", field_i64().build());
+        let cat_parent = category.field(&mut v, "zP9G5XR7A", "parent", field_i64().opt().build());
+        let cat_name = category.field(&mut v, "zN2L7HE3M", "name", field_str().build());
+        let mut tree =
+            CteBuilder::new(
+                "tree",
+                new_select_body(&category)
+                    .return_field(&cat_id)
+                    .return_field(&cat_name)
+                    .return_(Expr::LitI64(0))
+                    .return_(expr_cte_path_start(Expr::Binding(Binding::field(&cat_id))))
+                    .where_(expr_field_eq("root", &cat_id))
+                    .build(),
+            );
+        let tree_id = tree.field("id", cat_id.type_.type_.clone());
+        let tree_name = tree.field("name", cat_name.type_.type_.clone());
+        let tree_depth = tree.field("depth", type_i64().build());
+        let tree_path = tree.field("path", type_str().build());
+        tree.body_junction(SelectJunction {
+            op: SelectJunctionOperator::UnionAll,
+            body: new_select_body(&category)
+                .join(Join {
+                    source: Box::new(NamedSelectSource {
+                        source: JoinSource::Table(tree.table()),
+                        alias: None,
+                    }),
+                    type_: JoinType::Inner,
+                    on: Expr::BinOp {
+                        left: Box::new(Expr::Binding(Binding::field(&cat_parent))),
+                        op: BinOp::Equals,
+                        right: Box::new(Expr::Binding(Binding::field(&tree_id))),
+                    },
+                })
+                .return_field(&cat_id)
+                .return_field(&cat_name)
+                .return_(expr_cte_depth_next(Expr::Binding(Binding::field(&tree_depth))))
+                .return_(
+                    expr_cte_path_append(
+                        Expr::Binding(Binding::field(&tree_path)),
+                        Expr::Binding(Binding::field(&cat_id)),
+                    ),
+                )
+                .where_(
+                    expr_and(
+                        vec![
+                            expr_cte_depth_below(Expr::Binding(Binding::field(&tree_depth)), Expr::Param {
+                                name: "max_depth".into(),
+                                type_: type_i64().build(),
+                            }),
+                            expr_cte_path_excludes(
+                                Expr::Binding(Binding::field(&tree_path)),
+                                Expr::Binding(Binding::field(&cat_id)),
+                            )
+                        ],
+                    ),
+                )
+                .build(),
+        });
+        let (tree, tree_cte) = tree.build();
+        generate(&root.join("tests/sqlite_gen_recursive_cte.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &category,
+                vec![set_field("id", &cat_id), set_field("parent", &cat_parent), set_field("name", &cat_name)],
+            ).build_query("insert_category", QueryResCount::None),
+            new_select(&tree)
+                .with(With {
+                    recursive: true,
+                    ctes: vec![tree_cte],
+                })
+                .return_field(&tree_name)
+                .return_field(&tree_depth)
+                .order(Expr::Binding(Binding::field(&tree_depth)), Order::Asc)
+                .order(Expr::Binding(Binding::field(&tree_name)), Order::Asc)
+                .build_query("get_subtree", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # Full subqueries
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zV8K2PD6W", "bannanana");
+        let hizat = bananna.field(&mut v, "zE4R9MT1Y", "hizat", field_i32().build());
+        let kind = bananna.field(&mut v, "zL7C3BX5Q", "kind", field_str().build());
+        let mut big =
+            CteBuilder::new(
+                "big",
+                new_select_body(&bananna)
+                    .return_field(&hizat)
+                    .where_(Expr::BinOp {
+                        left: Box::new(Expr::Binding(Binding::field(&hizat))),
+                        op: BinOp::GreaterThan,
+                        right: Box::new(Expr::Param {
+                            name: "min".into(),
+                            type_: hizat.type_.type_.clone(),
+                        }),
+                    })
+                    .build(),
+            );
+        let big_hizat = big.field("hizat", hizat.type_.type_.clone());
+        let (big, big_cte) = big.build();
+        let picked =
+            new_select(&big)
+                .with(With {
+                    recursive: false,
+                    ctes: vec![big_cte],
+                })
+                .return_field(&big_hizat)
+                .junction(SelectJunction {
+                    op: SelectJunctionOperator::Union,
+                    body: new_select_body(&bananna).return_field(&hizat).where_(expr_field_eq("kind", &kind)).build(),
+                })
+                .order(Expr::Binding(Binding::field(&big_hizat)), Order::Asc)
+                .limit(Expr::LitI64(2))
+                .build_subquery();
+        let doubled =
+            new_select(&bananna)
+                .return_field(&hizat)
+                .junction(SelectJunction {
+                    op: SelectJunctionOperator::UnionAll,
+                    body: new_select_body(&bananna).return_field(&hizat).build(),
+                })
+                .build_subquery();
+        generate(&root.join("tests/sqlite_gen_full_subquery.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("hizat", &hizat), set_field("kind", &kind)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .return_field(&hizat)
+                .where_(Expr::BinOp {
+                    left: Box::new(Expr::Binding(Binding::field(&hizat))),
+                    op: BinOp::In,
+                    right: Box::new(Expr::Select(Box::new(picked))),
+                })
+                .order(Expr::Binding(Binding::field(&hizat)), Order::Asc)
+                .build_query("get_picked", QueryResCount::Many),
+            new_select_from(NamedSelectSource {
+                source: JoinSource::Subsel(Box::new(doubled)),
+                alias: Some("doubled".into()),
+            })
+                .return_named("count", functions::count_rows())
+                .build_query("count_doubled", QueryResCount::One)
+        ]).unwrap();
+    }
+
+    // # Values source
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zF3N8QW2J", "bannanana");
+        let hizat = bananna.field(&mut v, "zP6H1KC7R", "hizat", field_i32().build());
+        let label = |id: &str| Expr::Binding(Binding {
+            table_id: "labels".into(),
+            id: id.into(),
+        });
+        generate(&root.join("tests/sqlite_gen_values.rs"), vec![(0usize, v)], vec![
+            // Queries
+            new_insert(&bananna, vec![set_field("hizat", &hizat)]).build_query("insert_banan", QueryResCount::None),
+            new_select(&bananna)
+                .join(Join {
+                    source: Box::new(NamedSelectSource {
+                        source: JoinSource::Values {
+                            columns: vec!["hizat".into(), "name".into()],
+                            rows: vec![
+                                vec![Expr::LitI32(1), Expr::LitString("one".into())],
+                                vec![Expr::LitI32(2), Expr::LitNull(type_str().build().type_)],
+                                vec![Expr::LitI32(3), Expr::Param {
+                                    name: "three".into(),
+                                    type_: type_str().build(),
+                                }]
+                            ],
+                        },
+                        alias: Some("labels".into()),
+                    }),
+                    type_: JoinType::Inner,
+                    on: Expr::BinOp {
+                        left: Box::new(Expr::Binding(Binding::field(&hizat))),
+                        op: BinOp::Equals,
+                        right: Box::new(label("hizat")),
+                    },
+                })
+                .return_field(&hizat)
+                .return_named("name", label("name"))
+                .order(Expr::Binding(Binding::field(&hizat)), Order::Asc)
+                .build_query("get_labeled", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # FTS5
+    {
+        let mut v = Version::default();
+        let bananna = v.table("zK2M7XQ4F", "bannanana");
+        let banan_id = bananna.rowid_field(&mut v, None);
+        let hizat = bananna.field(&mut v, "zH8R3PW1T", "hizat", field_str().build());
+        let note = bananna.field(&mut v, "zN5C9LD2V", "note", field_str().opt().build());
+        let mut search = v.fts5_table("zS4B6TJ8Y", "banan_search");
+        search.external_content(&banan_id);
+        let search_hizat = search.content_column("zQ1W7EK3U", &hizat);
+        let search_note = search.content_column("zA9F2GM6R", &note);
+        search.tokenize("porter unicode61");
+        let search = search.build(&mut v);
+        let mut memo = v.fts5_table("zM3D8NV5X", "memo");
+        let memo_body = memo.column("zB7J4HS1Q", "body", field_str().build());
+        let memo_tag = memo.unindexed_column("zT2L9PC6W", "tag", field_str().opt().build());
+        let memo = memo.build(&mut v);
+        let terms = || Expr::Param {
+            name: "terms".into(),
+            type_: type_str().build(),
+        };
+        generate(&root.join("tests/sqlite_gen_fts5.rs"), vec![
+            // Versions (previous)
+            (0usize, {
+                let mut v = Version::default();
+                let bananna = v.table("zK2M7XQ4F", "bannanana");
+                let banan_id = bananna.rowid_field(&mut v, None);
+                let hizat = bananna.field(&mut v, "zH8R3PW1T", "hizat", field_str().build());
+                let note = bananna.field(&mut v, "zN5C9LD2V", "note", field_str().opt().build());
+                let mut search = v.fts5_table("zS4B6TJ8Y", "banan_search");
+                search.external_content(&banan_id);
+                search.content_column("zQ1W7EK3U", &hizat);
+                search.content_column("zA9F2GM6R", &note);
+                search.build(&mut v);
+                let mut memo = v.fts5_table("zM3D8NV5X", "memo");
+                let memo_body = memo.column("zB7J4HS1Q", "body", field_str().build());
+                let memo = memo.build(&mut v);
+                v.post_migration(
+                    new_insert(&bananna, vec![(hizat.clone(), Expr::LitString("bananas running fast".into()))])
+                        .build_migration(),
+                );
+                v.post_migration(
+                    new_insert(&memo, vec![(memo_body.clone(), Expr::LitString("yellow fruit".into()))])
+                        .build_migration(),
+                );
+                v
+            }),
+            (1usize, v)
+        ], vec![
+            // Queries
+            new_insert(
+                &bananna,
+                vec![set_field("hizat", &hizat), set_field("note", &note)],
+            ).build_query("insert_banan", QueryResCount::None),
+            new_update(&bananna, vec![set_field("hizat", &hizat)])
+                .where_(expr_field_eq("id", &banan_id))
+                .build_query("update_banan", QueryResCount::None),
+            new_select(&search)
+                .return_field(&search.rowid)
+                .return_named(
+                    "hizat",
+                    functions::highlight(
+                        &search,
+                        &search_hizat,
+                        Expr::LitString("[".into()),
+                        Expr::LitString("]".into()),
+                    ),
+                )
+                .return_named(
+                    "snip",
+                    functions::snippet(
+                        &search,
+                        None,
+                        Expr::LitString("[".into()),
+                        Expr::LitString("]".into()),
+                        Expr::LitString("...".into()),
+                        4,
+                    ),
+                )
+                .where_(functions::fts5_match(&search, terms()))
+                .order(functions::bm25(&search), Order::Asc)
+                .build_query("search", QueryResCount::Many),
+            new_select(&search)
+                .return_field(&search.rowid)
+                .where_(Expr::BinOp {
+                    left: Box::new(Expr::field(&search_note)),
+                    op: BinOp::Match,
+                    right: Box::new(terms()),
+                })
+                .build_query("search_note", QueryResCount::Many),
+            new_insert(
+                &memo,
+                vec![set_field("body", &memo_body), set_field("tag", &memo_tag)],
+            ).build_query("insert_memo", QueryResCount::None),
+            new_select(&memo)
+                .return_fields(&[&memo_body, &memo_tag])
+                .where_(functions::fts5_match(&memo, terms()))
+                .build_query("search_memo", QueryResCount::Many)
+        ]).unwrap();
+    }
+
+    // # R*Tree
+    {
+        let mut v = Version::default();
+        let mut places = v.rtree_table("zR6G2HV9B", "places");
+        let place_id = places.id_field("zD1X5QF8K", "id", None);
+        let (min_lon, max_lon) = places.dimension("zL3C7WN2J", "min_lon", "zU8P4TA6E", "max_lon");
+        let (min_lat, max_lat) = places.dimension("zY9M1KR5S", "min_lat", "zG6V3BZ7H", "max_lat");
+        let label = places.aux_column("zO4E8JD2N", "label", field_str().build());
+        let places = places.build(&mut v);
+        generate(&root.join("tests/sqlite_gen_rtree.rs"), vec![
+            // Versions (previous)
+            (0usize, {
+                let mut v = Version::default();
+                let mut places = v.rtree_table("zR6G2HV9B", "places");
+                places.id_field("zD1X5QF8K", "id", None);
+                places.dimension("zL3C7WN2J", "min_lon", "zU8P4TA6E", "max_lon");
+                places.dimension("zY9M1KR5S", "min_lat", "zG6V3BZ7H", "max_lat");
+                places.build(&mut v);
+                v
+            }),
+            (1usize, v)
+        ], vec![
+            // Queries
+            new_insert(
+                &places,
+                vec![
+                    set_field("id", &place_id),
+                    set_field("min_lon", &min_lon),
+                    set_field("max_lon", &max_lon),
+                    set_field("min_lat", &min_lat),
+                    set_field("max_lat", &max_lat),
+                    set_field("label", &label)
+                ],
+            ).build_query("insert_place", QueryResCount::None),
+            new_select(&places)
+                .return_fields(&[&place_id, &label])
+                .where_(
+                    expr_and(
+                        vec![
+                            expr_field_gte("west", &max_lon),
+                            expr_field_lte("east", &min_lon),
+                            expr_field_gte("south", &max_lat),
+                            expr_field_lte("north", &min_lat)
+                        ],
+                    ),
+                )
+                .order(Expr::field(&place_id), Order::Asc)
+                .build_query("get_places_in", QueryResCount::Many),
+            new_select(&places)
+                .return_fields(&[&min_lon, &max_lon])
+                .where_(expr_field_eq("id", &place_id))
+                .build_query("get_place_lon", QueryResCount::MaybeOne)
+        ]).unwrap();
+    }
 }
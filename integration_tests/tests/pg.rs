@@ -3,6 +3,13 @@ use {
         TimeZone,
         Utc,
     },
+    good_ormning_runtime::pg::{
+        TsLexeme,
+        TsPosition,
+        TsQuery,
+        TsVector,
+        TsWeight,
+    },
     integration_tests::MyString,
     std::time::Duration,
     testcontainers::{
@@ -34,6 +41,25 @@ pub mod pg_gen_select_join;
 pub mod pg_gen_select_group_by;
 pub mod pg_gen_select_order;
 pub mod pg_gen_select_limit;
+pub mod pg_gen_select_lateral;
+pub mod pg_gen_select_order_choice;
+pub mod pg_gen_select_paginate;
+pub mod pg_gen_operators;
+pub mod pg_gen_null_narrowing;
+pub mod pg_gen_res_count;
+pub mod pg_gen_res_count_limit;
+pub mod pg_gen_convert;
+pub mod pg_gen_tuples;
+pub mod pg_gen_values;
+pub mod pg_gen_lock;
+pub mod pg_gen_distinct_on;
+pub mod pg_gen_text_search;
+pub mod pg_gen_index_methods;
+pub mod pg_gen_index_concurrent_dropped;
+pub mod pg_gen_identity;
+pub mod pg_gen_namespaces;
+pub mod pg_gen_partitions;
+pub mod pg_gen_rls;
 pub mod pg_gen_migrate_add_field;
 pub mod pg_gen_migrate_rename_field;
 pub mod pg_gen_migrate_remove_field;
@@ -260,6 +286,413 @@ async fn test_select_join() -> Result<(), loga::Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_select_lateral() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_select_lateral::migrate(&mut db).await?;
+    pg_gen_select_lateral::insert_user(&mut db, 1).await?;
+    pg_gen_select_lateral::insert_user(&mut db, 2).await?;
+    pg_gen_select_lateral::insert_post(&mut db, 1, 4).await?;
+    pg_gen_select_lateral::insert_post(&mut db, 1, 9).await?;
+    pg_gen_select_lateral::insert_post(&mut db, 2, 3).await?;
+    let mut res =
+        pg_gen_select_lateral::get_best(&mut db)
+            .await?
+            .into_iter()
+            .map(|x| (x.id, x.best))
+            .collect::<Vec<_>>();
+    res.sort();
+    assert_eq!(res, vec![(1, 9), (2, 3)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_select_order_choice() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_select_order_choice::migrate(&mut db).await?;
+    for v in [1, 2, 3, 4] {
+        pg_gen_select_order_choice::insert_banan(&mut db, v).await?;
+    }
+    assert_eq!(
+        pg_gen_select_order_choice::get_banan(&mut db, pg_gen_select_order_choice::BananOrder::HizatAsc).await?,
+        vec![2, 3]
+    );
+    assert_eq!(
+        pg_gen_select_order_choice::get_banan(&mut db, pg_gen_select_order_choice::BananOrder::HizatDesc).await?,
+        vec![3, 2]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_select_paginate() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_select_paginate::migrate(&mut db).await?;
+    for (v, v2) in [(1, "a"), (2, "b"), (3, "a"), (4, "b"), (5, "a")] {
+        pg_gen_select_paginate::insert_banan(&mut db, v, v2).await?;
+    }
+    let mut all = vec![];
+    let mut cursor = None;
+    loop {
+        let (page, next) = pg_gen_select_paginate::get_banan(&mut db, 2, cursor).await?;
+        all.extend(page.into_iter().map(|x| x.hizat));
+        let Some(next) = next else {
+            break;
+        };
+        cursor = Some(next);
+    }
+    assert_eq!(all, vec![5, 3, 1, 4, 2]);
+
+    // Exactly full last page
+    let (page, next) = pg_gen_select_paginate::get_banan(&mut db, 5, None).await?;
+    assert_eq!(page.len(), 5);
+    assert!(next.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_operators() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_operators::migrate(&mut db).await?;
+    pg_gen_operators::insert_banan(&mut db, 1, "Bob").await?;
+    pg_gen_operators::insert_banan(&mut db, 2, "carl").await?;
+    pg_gen_operators::insert_banan(&mut db, 3, "42").await?;
+    let res =
+        pg_gen_operators::get_banan(&mut db)
+            .await?
+            .into_iter()
+            .map(|x| (x.hizat, x.shifted, x.greeting))
+            .collect::<Vec<_>>();
+    assert_eq!(res, vec![(1, 2, "hi Bob".to_string()), (3, 6, "hi 42".to_string())]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_null_narrowing() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_null_narrowing::migrate(&mut db).await?;
+    pg_gen_null_narrowing::insert_banan(&mut db, 1, Some("a")).await?;
+    pg_gen_null_narrowing::insert_banan(&mut db, 2, None).await?;
+    let res =
+        pg_gen_null_narrowing::get_banan(&mut db)
+            .await?
+            .into_iter()
+            .map(|x| -> (i32, String) {
+                (x.hizat, x.name)
+            })
+            .collect::<Vec<_>>();
+    assert_eq!(res, vec![(1, "a".to_string())]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_res_count_inference() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_res_count::migrate(&mut db).await?;
+    let () = pg_gen_res_count::insert_banan(&mut db, 1, "one").await?;
+    pg_gen_res_count::insert_banan(&mut db, 2, "two").await?;
+    assert_eq!(pg_gen_res_count::get_by_code(&mut db, "two").await?, Some(2));
+    assert_eq!(pg_gen_res_count::get_by_code(&mut db, "three").await?, None);
+    let count: i64 = pg_gen_res_count::count_banan(&mut db).await?;
+    assert_eq!(count, 2);
+    assert_eq!(pg_gen_res_count::list_banan(&mut db).await?, vec![1, 2]);
+    assert_eq!(pg_gen_res_count::delete_banan(&mut db, "one").await?, Some(1));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_res_count_limit() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_res_count_limit::migrate(&mut db).await?;
+    assert_eq!(pg_gen_res_count_limit::last_banan(&mut db).await?, None);
+    pg_gen_res_count_limit::insert_banan(&mut db, 1, "one").await?;
+    pg_gen_res_count_limit::insert_banan(&mut db, 2, "two").await?;
+    assert_eq!(pg_gen_res_count_limit::last_banan(&mut db).await?, Some(2));
+    assert_eq!(pg_gen_res_count_limit::get_by_code(&mut db, "two").await?, Some("two".to_string()));
+    assert_eq!(pg_gen_res_count_limit::update_banan(&mut db, "uno", 1).await?, Some("uno".to_string()));
+    assert_eq!(pg_gen_res_count_limit::update_banan(&mut db, "tres", 3).await?, None);
+    assert_eq!(pg_gen_res_count_limit::delete_banan(&mut db, 1).await?, "uno");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_convert() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_convert::migrate(&mut db).await?;
+    pg_gen_convert::insert_banan(&mut db, 0, None).await?;
+    pg_gen_convert::insert_banan(&mut db, 7, Some("42")).await?;
+    let res =
+        pg_gen_convert::get_banan(&mut db)
+            .await?
+            .into_iter()
+            .map(|x| -> (String, f64, Option<i64>, bool) {
+                (x.text, x.real, x.number, x.flag)
+            })
+            .collect::<Vec<_>>();
+    assert_eq!(res, vec![("0".to_string(), 0., None, false), ("7".to_string(), 7., Some(42), true)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tuple_list_param() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_tuples::migrate(&mut db).await?;
+    pg_gen_tuples::insert_banan(&mut db, 1, "a", "yellow").await?;
+    pg_gen_tuples::insert_banan(&mut db, 1, "b", "green").await?;
+    pg_gen_tuples::insert_banan(&mut db, 2, "a", "brown").await?;
+    assert_eq!(
+        pg_gen_tuples::get_banans(&mut db, vec![(1, "b"), (2, "a"), (2, "b")]).await?,
+        vec!["brown".to_string(), "green".to_string()]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_values_source() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_values::migrate(&mut db).await?;
+    for hizat in [3, 1, 2, 4] {
+        pg_gen_values::insert_banan(&mut db, hizat).await?;
+    }
+    let got =
+        pg_gen_values::get_labeled(&mut db, "three")
+            .await?
+            .into_iter()
+            .map(|r| (r.hizat, r.name))
+            .collect::<Vec<_>>();
+    assert_eq!(got, vec![(1, Some("one".to_string())), (2, None), (3, Some("three".to_string()))]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_row_lock() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_lock::migrate(&mut db).await?;
+    pg_gen_lock::insert_job(&mut db, "queued").await?;
+    pg_gen_lock::insert_job(&mut db, "queued").await?;
+    let first = pg_gen_lock::take_job(&mut db).await?;
+    let second = pg_gen_lock::take_job(&mut db).await?;
+    assert_eq!(first.len(), 1);
+    assert_eq!(second.len(), 1);
+    assert!(first[0] < second[0]);
+    assert!(pg_gen_lock::take_job(&mut db).await?.is_empty());
+    assert_eq!(pg_gen_lock::lock_job(&mut db, first[0]).await?, "running");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_distinct_on() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_distinct_on::migrate(&mut db).await?;
+    pg_gen_distinct_on::insert_post(&mut db, 1, 10, "a").await?;
+    pg_gen_distinct_on::insert_post(&mut db, 1, 30, "b").await?;
+    pg_gen_distinct_on::insert_post(&mut db, 1, 20, "c").await?;
+    pg_gen_distinct_on::insert_post(&mut db, 2, 5, "d").await?;
+    let got =
+        pg_gen_distinct_on::get_latest(&mut db)
+            .await?
+            .into_iter()
+            .map(|r| (r.user, r.text))
+            .collect::<Vec<_>>();
+    assert_eq!(got, vec![(1, "b".to_string()), (2, "d".to_string())]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_text_search() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_text_search::migrate(&mut db).await?;
+    pg_gen_text_search::insert_doc(&mut db, "Cats and dogs").await?;
+    pg_gen_text_search::insert_doc(&mut db, "A dog chasing dogs").await?;
+    pg_gen_text_search::insert_doc(&mut db, "Birds").await?;
+    assert_eq!(
+        pg_gen_text_search::search_docs(&mut db, "dog").await?,
+        vec!["A dog chasing dogs".to_string(), "Cats and dogs".to_string()]
+    );
+    assert_eq!(pg_gen_text_search::search_docs(&mut db, "dog -cat").await?, vec!["A dog chasing dogs".to_string()]);
+    let query = TsQuery::Lexeme {
+        lexeme: "bird".into(),
+        weights: vec![],
+        prefix: false,
+    };
+    assert_eq!(pg_gen_text_search::search_docs_parsed(&mut db, &query).await?, vec!["Birds".to_string()]);
+    let search = pg_gen_text_search::get_search(&mut db, "Birds").await?;
+    assert_eq!(search, vec![TsVector(vec![TsLexeme {
+        lexeme: "bird".into(),
+        positions: vec![TsPosition {
+            position: 1,
+            weight: TsWeight::D,
+        }],
+    }])]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index_methods() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_index_methods::migrate(&mut db).await?;
+    pg_gen_index_methods::migrate(&mut db).await?;
+    let mut indexes =
+        db
+            .query("select indexname, indexdef from pg_indexes where schemaname = 'public' and tablename != '__good_version'", &[])
+            .await?
+            .into_iter()
+            .map(|r| (r.get::<_, String>(0), r.get::<_, String>(1)))
+            .collect::<Vec<_>>();
+    indexes.sort();
+    let defs = indexes.iter().map(|(_, d)| d.split(" USING ").nth(1).unwrap_or("").to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        indexes.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>(),
+        vec!["events_at", "events_kind", "events_name", "events_search", "tags_tag"]
+    );
+    assert_eq!(defs, vec![
+        "brin (at)".to_string(),
+        "hash (kind)".to_string(),
+        "btree (name text_pattern_ops) INCLUDE (kind)".to_string(),
+        "gist (search)".to_string(),
+        "btree (tag)".to_string()
+    ]);
+    pg_gen_index_methods::insert_event(&mut db, "deploy api", 2, 100).await?;
+    pg_gen_index_methods::insert_event(&mut db, "deploy web", 1, 200).await?;
+    pg_gen_index_methods::insert_event(&mut db, "rollback", 3, 300).await?;
+    assert_eq!(pg_gen_index_methods::get_kinds_like(&mut db, "deploy%").await?, vec![1, 2]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index_concurrent_dropped() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_index_concurrent_dropped::migrate(&mut db).await?;
+    pg_gen_index_concurrent_dropped::migrate(&mut db).await?;
+    let dropped: bool = db.query_one("select to_regclass('drafts') is null", &[]).await?.get(0);
+    assert!(dropped);
+    let advisory_locks: i64 = db.query_one("select count(*) from pg_locks where locktype = 'advisory'", &[]).await?.get(0);
+    assert_eq!(advisory_locks, 0);
+    pg_gen_index_concurrent_dropped::insert_note(&mut db, "hello").await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_identity() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_identity::migrate(&mut db).await?;
+    pg_gen_identity::migrate(&mut db).await?;
+    assert_eq!(pg_gen_identity::insert_ticket(&mut db, "third").await?, 3);
+    let tickets =
+        pg_gen_identity::list_tickets(&mut db)
+            .await?
+            .into_iter()
+            .map(|r| (r.id, r.number, r.title))
+            .collect::<Vec<_>>();
+    assert_eq!(
+        tickets,
+        vec![(1, 1, "first".to_string()), (2, 2, "second".to_string()), (3, 3, "third".to_string())]
+    );
+    assert_eq!(pg_gen_identity::insert_order(&mut db).await?, 1000);
+    assert_eq!(pg_gen_identity::insert_order(&mut db).await?, 1010);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_namespaces() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_namespaces::migrate(&mut db).await?;
+    pg_gen_namespaces::migrate(&mut db).await?;
+    let mut tables =
+        db
+            .query(
+                "select table_schema, table_name from information_schema.tables where table_schema in ('archive', 'store', 'staging')",
+                &[],
+            )
+            .await?
+            .into_iter()
+            .map(|r| (r.get::<_, String>(0), r.get::<_, String>(1)))
+            .collect::<Vec<_>>();
+    tables.sort();
+    assert_eq!(
+        tables,
+        vec![("archive".to_string(), "logs".to_string()), ("store".to_string(), "products".to_string())]
+    );
+    pg_gen_namespaces::insert_product(&mut db, "red table").await?;
+    pg_gen_namespaces::insert_product(&mut db, "blue chair").await?;
+    assert_eq!(
+        pg_gen_namespaces::get_products_like(&mut db, "red%").await?,
+        vec!["red chair".to_string(), "red table".to_string()]
+    );
+    pg_gen_namespaces::insert_log(&mut db, "hello").await?;
+    let logs =
+        pg_gen_namespaces::get_logs(&mut db)
+            .await?
+            .into_iter()
+            .map(|r| (r.number, r.message))
+            .collect::<Vec<_>>();
+    assert_eq!(logs, vec![(1, "hello".to_string())]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_partitions() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    pg_gen_partitions::migrate(&mut db).await?;
+    pg_gen_partitions::migrate(&mut db).await?;
+    let march = Utc.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap();
+    pg_gen_partitions::create_event_partition_for(&mut db, march).await?;
+    pg_gen_partitions::create_event_partition_for(&mut db, march).await?;
+    pg_gen_partitions::insert_event(&mut db, march, "release").await?;
+    pg_gen_partitions::insert_event(&mut db, Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap(), "future").await?;
+    assert_eq!(pg_gen_partitions::get_events(&mut db).await?, vec!["launch", "release", "future"]);
+    let placement =
+        db
+            .query("select tableoid::regclass::text, name from events order by at", &[])
+            .await?
+            .into_iter()
+            .map(|r| (r.get::<_, String>(0), r.get::<_, String>(1)))
+            .collect::<Vec<_>>();
+    assert_eq!(
+        placement,
+        vec![
+            ("events_before_2024".to_string(), "launch".to_string()),
+            ("events_p2024_03".to_string(), "release".to_string()),
+            ("events_default".to_string(), "future".to_string())
+        ]
+    );
+    for user in 0 .. 10 {
+        pg_gen_partitions::insert_session(&mut db, user).await?;
+    }
+    let count: i64 = db.query_one("select count(*) from sessions_0", &[]).await?.get(0);
+    assert!(count > 0 && count < 10);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rls() -> Result<(), loga::Error> {
+    let (mut db, _cont) = db().await?;
+    db.execute("create role app", &[]).await?;
+    pg_gen_rls::migrate(&mut db).await?;
+    pg_gen_rls::migrate(&mut db).await?;
+
+    // Superusers bypass row-level security
+    pg_gen_rls::insert_doc(&mut db, 1, "apple").await?;
+    pg_gen_rls::insert_doc(&mut db, 2, "banana").await?;
+    db.batch_execute("set role app; set app.tenant = '1'").await?;
+    assert_eq!(pg_gen_rls::get_docs(&mut db).await?, vec!["apple".to_string()]);
+    pg_gen_rls::insert_doc(&mut db, 1, "cherry").await?;
+    assert!(pg_gen_rls::insert_doc(&mut db, 2, "durian").await.is_err());
+    assert_eq!(pg_gen_rls::get_docs(&mut db).await?, vec!["apple".to_string(), "cherry".to_string()]);
+    db.batch_execute("set app.tenant = '2'").await?;
+    assert_eq!(pg_gen_rls::get_docs(&mut db).await?, vec!["banana".to_string()]);
+
+    // A reset setting is empty, which matches nothing
+    db.batch_execute("reset app.tenant").await?;
+    assert!(pg_gen_rls::get_docs(&mut db).await?.is_empty());
+    db.batch_execute("reset role").await?;
+    assert_eq!(
+        pg_gen_rls::get_docs(&mut db).await?,
+        vec!["apple".to_string(), "banana".to_string(), "cherry".to_string()]
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_select_group_by() -> Result<(), loga::Error> {
     let (mut db, _cont) = db().await?;
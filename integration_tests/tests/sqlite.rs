@@ -39,6 +39,24 @@ pub mod sqlite_gen_migrate_remove_table;
 pub mod sqlite_gen_select_cte;
 pub mod sqlite_gen_select_window;
 pub mod sqlite_gen_select_junction;
+pub mod sqlite_gen_select_table_function;
+pub mod sqlite_gen_select_having;
+pub mod sqlite_gen_select_order_choice;
+pub mod sqlite_gen_select_paginate;
+pub mod sqlite_gen_case;
+pub mod sqlite_gen_functions;
+pub mod sqlite_gen_window_frame;
+pub mod sqlite_gen_operators;
+pub mod sqlite_gen_null_narrowing;
+pub mod sqlite_gen_res_count;
+pub mod sqlite_gen_res_count_limit;
+pub mod sqlite_gen_convert;
+pub mod sqlite_gen_tuples;
+pub mod sqlite_gen_recursive_cte;
+pub mod sqlite_gen_full_subquery;
+pub mod sqlite_gen_values;
+pub mod sqlite_gen_fts5;
+pub mod sqlite_gen_rtree;
 pub mod sqlite_gen_hello_world;
 
 #[test]
@@ -466,3 +484,392 @@ fn test_select_junction() -> Result<(), loga::Error> {
     assert_eq!(res, vec![1, 2, 3, 7]);
     Ok(())
 }
+
+#[test]
+fn test_select_table_function() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_select_table_function::migrate(&mut db)?;
+    sqlite_gen_select_table_function::insert_banan(&mut db, 1, "[7, 99]")?;
+    sqlite_gen_select_table_function::insert_banan(&mut db, 2, "[3]")?;
+    let mut res =
+        sqlite_gen_select_table_function::get_banan(&mut db)?
+            .into_iter()
+            .map(|x| (x.hizat, x.tag))
+            .collect::<Vec<_>>();
+    res.sort();
+    assert_eq!(res, vec![(1, 7), (1, 99), (2, 3)]);
+    Ok(())
+}
+
+#[test]
+fn test_select_having() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_select_having::migrate(&mut db)?;
+    sqlite_gen_select_having::insert_banan(&mut db, 1, 7)?;
+    sqlite_gen_select_having::insert_banan(&mut db, 1, 99)?;
+    sqlite_gen_select_having::insert_banan(&mut db, 2, 3)?;
+    let res =
+        sqlite_gen_select_having::get_banan(&mut db)?
+            .into_iter()
+            .map(|x| (x.hizat, x.count))
+            .collect::<Vec<_>>();
+    assert_eq!(res, vec![(1, 2)]);
+    Ok(())
+}
+
+#[test]
+fn test_select_order_choice() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_select_order_choice::migrate(&mut db)?;
+    sqlite_gen_select_order_choice::insert_banan(&mut db, 1, Some(7))?;
+    sqlite_gen_select_order_choice::insert_banan(&mut db, 2, None)?;
+    sqlite_gen_select_order_choice::insert_banan(&mut db, 3, Some(3))?;
+    sqlite_gen_select_order_choice::insert_banan(&mut db, 4, Some(5))?;
+    assert_eq!(
+        sqlite_gen_select_order_choice::get_banan(&mut db, sqlite_gen_select_order_choice::BananOrder::HizatAsc)?,
+        vec![2, 3]
+    );
+    assert_eq!(
+        sqlite_gen_select_order_choice::get_banan(&mut db, sqlite_gen_select_order_choice::BananOrder::HizatDesc)?,
+        vec![3, 2]
+    );
+    assert_eq!(sqlite_gen_select_order_choice::get_banan_nulls_last(&mut db)?, vec![3, 4, 1, 2]);
+    Ok(())
+}
+
+#[test]
+fn test_select_paginate() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_select_paginate::migrate(&mut db)?;
+    sqlite_gen_select_paginate::insert_banan(&mut db, 1, "a")?;
+    sqlite_gen_select_paginate::insert_banan(&mut db, 2, "b")?;
+    sqlite_gen_select_paginate::insert_banan(&mut db, 3, "a")?;
+    sqlite_gen_select_paginate::insert_banan(&mut db, 4, "b")?;
+    sqlite_gen_select_paginate::insert_banan(&mut db, 5, "a")?;
+    let (page, cursor) = sqlite_gen_select_paginate::get_banan(&mut db, 2, None)?;
+    assert_eq!(page, vec![1, 2]);
+    let (page, cursor) = sqlite_gen_select_paginate::get_banan(&mut db, 2, cursor)?;
+    assert_eq!(page, vec![3, 4]);
+    let (page, cursor) = sqlite_gen_select_paginate::get_banan(&mut db, 2, cursor)?;
+    assert_eq!(page, vec![5]);
+    assert!(cursor.is_none());
+    let (page, cursor) = sqlite_gen_select_paginate::get_banan(&mut db, 5, None)?;
+    assert_eq!(page, vec![1, 2, 3, 4, 5]);
+    assert!(cursor.is_none());
+    let mut all = vec![];
+    let mut cursor = None;
+    loop {
+        let (page, next) = sqlite_gen_select_paginate::get_banan_mixed(&mut db, 2, cursor)?;
+        all.extend(page.into_iter().map(|x| (x.hizat, x.hizat2)));
+        let Some(next) = next else {
+            break;
+        };
+        cursor = Some(next);
+    }
+    assert_eq!(
+        all,
+        vec![(5, "a".to_string()), (3, "a".to_string()), (1, "a".to_string()), (4, "b".to_string()), (2, "b".to_string())]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_case() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_case::migrate(&mut db)?;
+    sqlite_gen_case::insert_banan(&mut db, 1)?;
+    sqlite_gen_case::insert_banan(&mut db, 20)?;
+    let res =
+        sqlite_gen_case::get_banan(&mut db)?.into_iter().map(|x| (x.hizat, x.size, x.name)).collect::<Vec<_>>();
+    assert_eq!(res, vec![(1, "small".to_string(), Some("one".to_string())), (20, "big".to_string(), None)]);
+    Ok(())
+}
+
+#[test]
+fn test_functions() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_functions::migrate(&mut db)?;
+    let res = sqlite_gen_functions::get_aggregates(&mut db)?;
+    assert_eq!((res.count, res.sum, res.max, res.names), (0, None, None, 0));
+    sqlite_gen_functions::insert_banan(&mut db, -4, Some("Ohno"))?;
+    sqlite_gen_functions::insert_banan(&mut db, 7, None)?;
+    let res = sqlite_gen_functions::get_aggregates(&mut db)?;
+    assert_eq!((res.count, res.sum, res.max, res.names), (2, Some(3), Some(7), 1));
+    let res =
+        sqlite_gen_functions::get_scalars(&mut db)?.into_iter().map(|x| (x.abs, x.name, x.len)).collect::<Vec<_>>();
+    assert_eq!(res, vec![(4, "ohno".to_string(), Some(4)), (7, "anon".to_string(), None)]);
+    Ok(())
+}
+
+#[test]
+fn test_window_frame() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_window_frame::migrate(&mut db)?;
+    sqlite_gen_window_frame::insert_banan(&mut db, 1, 3)?;
+    sqlite_gen_window_frame::insert_banan(&mut db, 1, 7)?;
+    sqlite_gen_window_frame::insert_banan(&mut db, 2, 10)?;
+    sqlite_gen_window_frame::insert_banan(&mut db, 1, 20)?;
+    let res =
+        sqlite_gen_window_frame::get_banan(&mut db)?
+            .into_iter()
+            .map(|x| (x.hizat2, x.row, x.prev, x.running, x.moving, x.big))
+            .collect::<Vec<_>>();
+    assert_eq!(
+        res,
+        vec![
+            (3, 1, None, Some(3), Some(3), 2),
+            (7, 2, Some(3), Some(10), Some(10), 2),
+            (10, 1, None, Some(10), Some(17), 1),
+            (20, 3, Some(7), Some(30), Some(30), 2)
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_operators() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_operators::migrate(&mut db)?;
+    sqlite_gen_operators::insert_banan(&mut db, 1, Some("x"))?;
+    sqlite_gen_operators::insert_banan(&mut db, 7, None)?;
+    sqlite_gen_operators::insert_banan(&mut db, 11, Some("bob"))?;
+    sqlite_gen_operators::insert_banan(&mut db, 12, Some("50%"))?;
+    sqlite_gen_operators::insert_banan(&mut db, 13, Some("Carl"))?;
+    let res =
+        sqlite_gen_operators::get_banan(&mut db)?
+            .into_iter()
+            .map(|x| (x.hizat, x.modded, x.bits, x.neg, x.inv, x.greeting, x.unnamed))
+            .collect::<Vec<_>>();
+    assert_eq!(
+        res,
+        vec![
+            (7, 1, 22, -7, -8, None, true),
+            (11, 2, 18, -11, -12, Some("hi bob".to_string()), false),
+            (12, 0, 20, -12, -13, Some("hi 50%".to_string()), false),
+            (13, 1, 20, -13, -14, Some("hi Carl".to_string()), false)
+        ]
+    );
+    assert_eq!(sqlite_gen_operators::get_banan_matching(&mut db)?, vec![1, 11]);
+    Ok(())
+}
+
+#[test]
+fn test_null_narrowing() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_null_narrowing::migrate(&mut db)?;
+    sqlite_gen_null_narrowing::insert_banan(&mut db, 1, Some("a"), Some(4))?;
+    sqlite_gen_null_narrowing::insert_banan(&mut db, 2, None, Some(5))?;
+    sqlite_gen_null_narrowing::insert_banan(&mut db, 3, Some("b"), None)?;
+    sqlite_gen_null_narrowing::insert_name(&mut db, "a", None)?;
+
+    // Narrowed types - these would fail to compile if they were `Option`
+    let res =
+        sqlite_gen_null_narrowing::get_banan(&mut db)?
+            .into_iter()
+            .map(|x| -> (i32, String, i32) {
+                (x.hizat, x.name, x.other)
+            })
+            .collect::<Vec<_>>();
+    assert_eq!(res, vec![(1, "a".to_string(), 4)]);
+    let res =
+        sqlite_gen_null_narrowing::get_banan_joined(&mut db)?
+            .into_iter()
+            .map(|x| -> (i32, String, Option<String>) {
+                (x.hizat, x.name, x.nick)
+            })
+            .collect::<Vec<_>>();
+    assert_eq!(res, vec![(1, "a".to_string(), None)]);
+    Ok(())
+}
+
+#[test]
+fn test_res_count_inference() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_res_count::migrate(&mut db)?;
+    let () = sqlite_gen_res_count::insert_banan(&mut db, 1, "one")?;
+    sqlite_gen_res_count::insert_banan(&mut db, 2, "two")?;
+    let color: String = sqlite_gen_res_count::insert_peel(&mut db, 1, 0, "yellow")?;
+    assert_eq!(color, "yellow");
+    sqlite_gen_res_count::insert_peel(&mut db, 1, 1, "green")?;
+    assert_eq!(sqlite_gen_res_count::get_by_pk(&mut db, 1)?, Some("one".to_string()));
+    assert_eq!(sqlite_gen_res_count::get_by_pk(&mut db, 3)?, None);
+    assert_eq!(sqlite_gen_res_count::get_by_code(&mut db, "two")?, Some(2));
+    let count: i64 = sqlite_gen_res_count::count_banan(&mut db)?;
+    assert_eq!(count, 2);
+    assert_eq!(sqlite_gen_res_count::list_banan(&mut db)?, vec![1, 2]);
+    assert_eq!(sqlite_gen_res_count::get_peel(&mut db, 1, "one")?, Some("green".to_string()));
+    assert_eq!(sqlite_gen_res_count::list_peel(&mut db, 1)?, vec!["yellow".to_string(), "green".to_string()]);
+    assert_eq!(sqlite_gen_res_count::delete_banan(&mut db, 2)?, Some("two".to_string()));
+    Ok(())
+}
+
+#[test]
+fn test_res_count_limit() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_res_count_limit::migrate(&mut db)?;
+    assert_eq!(sqlite_gen_res_count_limit::last_banan(&mut db)?, None);
+    sqlite_gen_res_count_limit::insert_banan(&mut db, 1, "one")?;
+    sqlite_gen_res_count_limit::insert_banan(&mut db, 2, "two")?;
+    assert_eq!(sqlite_gen_res_count_limit::last_banan(&mut db)?, Some(2));
+    assert_eq!(sqlite_gen_res_count_limit::get_by_code(&mut db, "two")?, Some("two".to_string()));
+    assert_eq!(sqlite_gen_res_count_limit::update_banan(&mut db, "uno", 1)?, Some("uno".to_string()));
+    assert_eq!(sqlite_gen_res_count_limit::update_banan(&mut db, "tres", 3)?, None);
+    assert_eq!(sqlite_gen_res_count_limit::delete_banan(&mut db, 1)?, "uno");
+    Ok(())
+}
+
+#[test]
+fn test_convert() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_convert::migrate(&mut db)?;
+    sqlite_gen_convert::insert_banan(&mut db, 0, None)?;
+    sqlite_gen_convert::insert_banan(&mut db, 7, Some("42"))?;
+    let res =
+        sqlite_gen_convert::get_banan(&mut db)?
+            .into_iter()
+            .map(|x| -> (String, f64, Option<i64>, bool) {
+                (x.text, x.real, x.number, x.flag)
+            })
+            .collect::<Vec<_>>();
+    assert_eq!(res, vec![("0".to_string(), 0., None, false), ("7".to_string(), 7., Some(42), true)]);
+    Ok(())
+}
+
+#[test]
+fn test_tuple_list_param() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_tuples::migrate(&mut db)?;
+    sqlite_gen_tuples::insert_banan(&mut db, 1, "a", "yellow")?;
+    sqlite_gen_tuples::insert_banan(&mut db, 1, "b", "green")?;
+    sqlite_gen_tuples::insert_banan(&mut db, 2, "a \"quoted\"", "brown")?;
+    assert_eq!(
+        sqlite_gen_tuples::get_banans(&mut db, vec![(1, "b"), (2, "a \"quoted\""), (2, "b")])?,
+        vec!["brown".to_string(), "green".to_string()]
+    );
+    assert_eq!(sqlite_gen_tuples::get_banans(&mut db, vec![])?, Vec::<String>::new());
+    Ok(())
+}
+
+#[test]
+fn test_recursive_cte() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_recursive_cte::migrate(&mut db)?;
+    sqlite_gen_recursive_cte::insert_category(&mut db, 1, None, "food")?;
+    sqlite_gen_recursive_cte::insert_category(&mut db, 2, Some(1), "fruit")?;
+    sqlite_gen_recursive_cte::insert_category(&mut db, 3, Some(2), "banana")?;
+    sqlite_gen_recursive_cte::insert_category(&mut db, 4, Some(2), "apple")?;
+    sqlite_gen_recursive_cte::insert_category(&mut db, 5, Some(1), "bread")?;
+    sqlite_gen_recursive_cte::insert_category(&mut db, 6, Some(7), "loop a")?;
+    sqlite_gen_recursive_cte::insert_category(&mut db, 7, Some(6), "loop b")?;
+    let get_subtree = |db: &mut rusqlite::Connection, root: i64, max_depth: i64| -> Result<Vec<(String, i64)>, loga::Error> {
+        return Ok(
+            sqlite_gen_recursive_cte::get_subtree(db, root, max_depth)?
+                .into_iter()
+                .map(|x| (x.name, x.depth))
+                .collect(),
+        );
+    };
+    assert_eq!(
+        get_subtree(&mut db, 1, 10)?,
+        vec![
+            ("food".to_string(), 0),
+            ("bread".to_string(), 1),
+            ("fruit".to_string(), 1),
+            ("apple".to_string(), 2),
+            ("banana".to_string(), 2)
+        ]
+    );
+    assert_eq!(
+        get_subtree(&mut db, 1, 1)?,
+        vec![("food".to_string(), 0), ("bread".to_string(), 1), ("fruit".to_string(), 1)]
+    );
+    assert_eq!(get_subtree(&mut db, 6, 10)?, vec![("loop a".to_string(), 0), ("loop b".to_string(), 1)]);
+    Ok(())
+}
+
+#[test]
+fn test_full_subquery() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_full_subquery::migrate(&mut db)?;
+    for (hizat, kind) in [(1, "plain"), (3, "special"), (6, "plain"), (8, "plain"), (9, "plain")] {
+        sqlite_gen_full_subquery::insert_banan(&mut db, hizat, kind)?;
+    }
+    assert_eq!(sqlite_gen_full_subquery::get_picked(&mut db, 5, "special")?, vec![3, 6]);
+    assert_eq!(sqlite_gen_full_subquery::count_doubled(&mut db)?, 10);
+    Ok(())
+}
+
+#[test]
+fn test_values_source() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_values::migrate(&mut db)?;
+    for hizat in [3, 1, 2, 4] {
+        sqlite_gen_values::insert_banan(&mut db, hizat)?;
+    }
+    let got =
+        sqlite_gen_values::get_labeled(&mut db, "three")?
+            .into_iter()
+            .map(|r| (r.hizat, r.name))
+            .collect::<Vec<_>>();
+    assert_eq!(got, vec![(1, Some("one".to_string())), (2, None), (3, Some("three".to_string()))]);
+    Ok(())
+}
+
+#[test]
+fn test_fts5() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_fts5::migrate(&mut db)?;
+
+    // Content from before the rebuild is indexed, with the new tokenizer
+    let got = sqlite_gen_fts5::search(&mut db, "run")?;
+    assert_eq!(got.len(), 1);
+    assert_eq!(got[0].hizat, "bananas [running] fast");
+    assert_eq!(got[0].snip.as_deref(), Some("bananas [running] fast"));
+    let first = got[0].rowid;
+
+    // Triggers keep the index in sync
+    sqlite_gen_fts5::insert_banan(&mut db, "running late", Some("slow walk"))?;
+    let got = sqlite_gen_fts5::search(&mut db, "running")?.into_iter().map(|r| r.rowid).collect::<Vec<_>>();
+    assert_eq!(got.len(), 2);
+    let second = *got.iter().find(|r| **r != first).unwrap();
+    assert_eq!(sqlite_gen_fts5::search_note(&mut db, "walk")?, vec![second]);
+    assert!(sqlite_gen_fts5::search_note(&mut db, "late")?.is_empty());
+    sqlite_gen_fts5::update_banan(&mut db, "apple", second)?;
+    assert!(sqlite_gen_fts5::search(&mut db, "late")?.is_empty());
+    assert_eq!(sqlite_gen_fts5::search(&mut db, "apple")?.into_iter().map(|r| r.rowid).collect::<Vec<_>>(), vec![second]);
+
+    // Self-contained table data survives the rebuild
+    sqlite_gen_fts5::insert_memo(&mut db, "green fruit", Some("fruit"))?;
+    let got =
+        sqlite_gen_fts5::search_memo(&mut db, "fruit")?.into_iter().map(|r| (r.body, r.tag)).collect::<Vec<_>>();
+    assert_eq!(got.len(), 2);
+    assert!(got.contains(&("yellow fruit".to_string(), None)));
+    assert!(sqlite_gen_fts5::search_memo(&mut db, "yellow")?.len() == 1);
+    Ok(())
+}
+
+#[test]
+fn test_rtree() -> Result<(), loga::Error> {
+    let mut db = rusqlite::Connection::open_in_memory()?;
+    sqlite_gen_rtree::migrate(&mut db)?;
+    sqlite_gen_rtree::insert_place(&mut db, 1, 139.5, 139.9, 35.5, 35.8, "tokyo")?;
+    sqlite_gen_rtree::insert_place(&mut db, 2, 135.3, 135.7, 34.5, 34.8, "osaka")?;
+    sqlite_gen_rtree::insert_place(&mut db, 3, -0.5, 0.3, 51.3, 51.7, "london")?;
+    let get = |db: &mut rusqlite::Connection, west: f64, east: f64, south: f64, north: f64| {
+        return Ok::<_, loga::Error>(
+            sqlite_gen_rtree::get_places_in(db, west, east, south, north)?
+                .into_iter()
+                .map(|r| (r.id, r.label))
+                .collect::<Vec<_>>(),
+        );
+    };
+    assert_eq!(get(&mut db, 135.0, 140.0, 34.0, 36.0)?, vec![(1, "tokyo".to_string()), (2, "osaka".to_string())]);
+    assert_eq!(get(&mut db, -1.0, 0.0, 51.0, 52.0)?, vec![(3, "london".to_string())]);
+    assert!(get(&mut db, 10.0, 20.0, 10.0, 20.0)?.is_empty());
+
+    // Coordinates are stored as 32 bit floats, widened to contain the original box
+    let lon = sqlite_gen_rtree::get_place_lon(&mut db, 1)?.unwrap();
+    assert!(lon.min_lon <= 139.5 && lon.min_lon > 139.49);
+    assert!(lon.max_lon >= 139.9 && lon.max_lon < 139.91);
+    Ok(())
+}
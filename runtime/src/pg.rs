@@ -1,5 +1,19 @@
 use {
-    std::borrow::Cow,
+    std::{
+        borrow::Cow,
+        error::Error,
+    },
+    bytes::{
+        BufMut,
+        BytesMut,
+    },
+    tokio_postgres::types::{
+        to_sql_checked,
+        FromSql,
+        IsNull,
+        ToSql,
+        Type,
+    },
 };
 #[cfg(feature = "chrono")]
 use chrono::{
@@ -53,6 +67,16 @@ pub trait GoodOrmningCustomBytes<T> {
     fn from_sql(value: Vec<u8>) -> Result<T, String>;
 }
 
+pub trait GoodOrmningCustomTsVector<T> {
+    fn to_sql(value: &T) -> TsVector;
+    fn from_sql(value: TsVector) -> Result<T, String>;
+}
+
+pub trait GoodOrmningCustomTsQuery<T> {
+    fn to_sql(value: &T) -> TsQuery;
+    fn from_sql(value: TsQuery) -> Result<T, String>;
+}
+
 #[cfg(feature = "chrono")]
 pub trait GoodOrmningCustomUtcTimeChrono<T> {
     fn to_sql(value: &T) -> DateTime<Utc>;
@@ -76,3 +100,435 @@ pub trait GoodOrmningCustomFixedOffsetTimeJiff<T> {
     fn to_sql(value: &T) -> Zoned;
     fn from_sql(value: Zoned) -> Result<T, String>;
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TsWeight {
+    A,
+    B,
+    C,
+    D,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TsPosition {
+    /// 1-based, at most 16383.
+    pub position: u16,
+    pub weight: TsWeight,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TsLexeme {
+    pub lexeme: String,
+    pub positions: Vec<TsPosition>,
+}
+
+/// A Postgres `tsvector`, the normalized lexemes of a document. Usually produced
+/// in SQL with `to_tsvector`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct TsVector(pub Vec<TsLexeme>);
+
+/// A Postgres `tsquery`. Usually produced in SQL from user input with
+/// `plainto_tsquery` or `websearch_to_tsquery`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TsQuery {
+    /// A query with no lexemes (for example if all the words were stop words), which
+    /// matches nothing. This can be read from but not sent to Postgres.
+    Empty,
+    Lexeme {
+        lexeme: String,
+        /// Only match lexemes with these weights, or any weight if empty.
+        weights: Vec<TsWeight>,
+        /// Match lexemes starting with `lexeme`.
+        prefix: bool,
+    },
+    Not(Box<TsQuery>),
+    And(Box<TsQuery>, Box<TsQuery>),
+    Or(Box<TsQuery>, Box<TsQuery>),
+    /// `left <distance> right`, `left` followed by `right` `distance` positions later.
+    Phrase {
+        left: Box<TsQuery>,
+        right: Box<TsQuery>,
+        distance: u16,
+    },
+}
+
+// Wire format constants from Postgres' `ts_type.h`
+const TS_QUERY_VAL: u8 = 1;
+const TS_QUERY_OPR: u8 = 2;
+const TS_QUERY_NOT: u8 = 1;
+const TS_QUERY_AND: u8 = 2;
+const TS_QUERY_OR: u8 = 3;
+const TS_QUERY_PHRASE: u8 = 4;
+
+struct TsReader<'a>(&'a [u8]);
+
+impl<'a> TsReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Box<dyn Error + Sync + Send>> {
+        if self.0.len() < n {
+            return Err("Unexpected end of text search value".into());
+        }
+        let (out, rest) = self.0.split_at(n);
+        self.0 = rest;
+        return Ok(out);
+    }
+
+    fn u8(&mut self) -> Result<u8, Box<dyn Error + Sync + Send>> {
+        return Ok(self.take(1)?[0]);
+    }
+
+    fn u16(&mut self) -> Result<u16, Box<dyn Error + Sync + Send>> {
+        let b = self.take(2)?;
+        return Ok(u16::from_be_bytes([b[0], b[1]]));
+    }
+
+    fn u32(&mut self) -> Result<u32, Box<dyn Error + Sync + Send>> {
+        let b = self.take(4)?;
+        return Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    }
+
+    fn string(&mut self) -> Result<String, Box<dyn Error + Sync + Send>> {
+        let Some(len) = self.0.iter().position(|b| *b == 0) else {
+            return Err("Unterminated string in text search value".into());
+        };
+        let out = String::from_utf8(self.take(len)?.to_vec())?;
+        self.take(1)?;
+        return Ok(out);
+    }
+}
+
+fn put_string(out: &mut BytesMut, s: &str) -> Result<(), Box<dyn Error + Sync + Send>> {
+    if s.contains('\0') {
+        return Err("Text search strings can't contain nul characters".into());
+    }
+    out.put_slice(s.as_bytes());
+    out.put_u8(0);
+    return Ok(());
+}
+
+impl<'a> FromSql<'a> for TsVector {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let mut r = TsReader(raw);
+        let mut out = vec![];
+        for _ in 0 .. r.u32()? {
+            let lexeme = r.string()?;
+            let mut positions = vec![];
+            for _ in 0 .. r.u16()? {
+                let wep = r.u16()?;
+                positions.push(TsPosition {
+                    position: wep & 0x3fff,
+                    weight: match wep >> 14 {
+                        3 => TsWeight::A,
+                        2 => TsWeight::B,
+                        1 => TsWeight::C,
+                        _ => TsWeight::D,
+                    },
+                });
+            }
+            out.push(TsLexeme {
+                lexeme: lexeme,
+                positions: positions,
+            });
+        }
+        return Ok(TsVector(out));
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        return *ty == Type::TS_VECTOR;
+    }
+}
+
+impl ToSql for TsVector {
+    fn to_sql(&self, _ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        out.put_u32(self.0.len() as u32);
+        for lexeme in &self.0 {
+            put_string(out, &lexeme.lexeme)?;
+
+            // Postgres requires positions in increasing order
+            let mut positions = lexeme.positions.clone();
+            positions.sort_by_key(|p| p.position);
+            positions.dedup_by_key(|p| p.position);
+            out.put_u16(positions.len() as u16);
+            for p in positions {
+                if p.position == 0 || p.position > 0x3fff {
+                    return Err(format!("Text search position {} is out of range", p.position).into());
+                }
+                let weight = match p.weight {
+                    TsWeight::A => 3,
+                    TsWeight::B => 2,
+                    TsWeight::C => 1,
+                    TsWeight::D => 0,
+                };
+                out.put_u16(weight << 14 | p.position);
+            }
+        }
+        return Ok(IsNull::No);
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        return *ty == Type::TS_VECTOR;
+    }
+
+    to_sql_checked!();
+}
+
+impl TsQuery {
+    // Items are in prefix order, with the right operand of binary operators before
+    // the left operand.
+    fn read(r: &mut TsReader) -> Result<TsQuery, Box<dyn Error + Sync + Send>> {
+        match r.u8()? {
+            TS_QUERY_VAL => {
+                let weight = r.u8()?;
+                let prefix = r.u8()? != 0;
+                let lexeme = r.string()?;
+                let weights =
+                    [TsWeight::A, TsWeight::B, TsWeight::C, TsWeight::D]
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(i, _)| weight & (1 << (3 - i)) != 0)
+                        .map(|(_, w)| w)
+                        .collect();
+                return Ok(TsQuery::Lexeme {
+                    lexeme: lexeme,
+                    weights: weights,
+                    prefix: prefix,
+                });
+            },
+            TS_QUERY_OPR => {
+                let oper = r.u8()?;
+                if ![TS_QUERY_NOT, TS_QUERY_AND, TS_QUERY_OR, TS_QUERY_PHRASE].contains(&oper) {
+                    return Err(format!("Unknown text search query operator {}", oper).into());
+                }
+                let distance = if oper == TS_QUERY_PHRASE {
+                    r.u16()?
+                } else {
+                    0
+                };
+                if oper == TS_QUERY_NOT {
+                    return Ok(TsQuery::Not(Box::new(TsQuery::read(r)?)));
+                }
+                let right = Box::new(TsQuery::read(r)?);
+                let left = Box::new(TsQuery::read(r)?);
+                match oper {
+                    TS_QUERY_AND => return Ok(TsQuery::And(left, right)),
+                    TS_QUERY_OR => return Ok(TsQuery::Or(left, right)),
+                    TS_QUERY_PHRASE => return Ok(TsQuery::Phrase {
+                        left: left,
+                        right: right,
+                        distance: distance,
+                    }),
+                    _ => unreachable!(),
+                }
+            },
+            t => return Err(format!("Unknown text search query item type {}", t).into()),
+        }
+    }
+
+    fn count(&self) -> u32 {
+        match self {
+            TsQuery::Empty => return 0,
+            TsQuery::Lexeme { .. } => return 1,
+            TsQuery::Not(e) => return 1 + e.count(),
+            TsQuery::And(left, right) | TsQuery::Or(left, right) | TsQuery::Phrase { left, right, .. } => {
+                return 1 + left.count() + right.count();
+            },
+        }
+    }
+
+    fn write(&self, out: &mut BytesMut) -> Result<(), Box<dyn Error + Sync + Send>> {
+        match self {
+            TsQuery::Empty => return Err("Empty text search queries can't be nested in other queries".into()),
+            TsQuery::Lexeme { lexeme, weights, prefix } => {
+                out.put_u8(TS_QUERY_VAL);
+                let mut weight = 0u8;
+                for w in weights {
+                    weight |= match w {
+                        TsWeight::A => 1 << 3,
+                        TsWeight::B => 1 << 2,
+                        TsWeight::C => 1 << 1,
+                        TsWeight::D => 1,
+                    };
+                }
+                out.put_u8(weight);
+                out.put_u8(*prefix as u8);
+                put_string(out, lexeme)?;
+            },
+            TsQuery::Not(e) => {
+                out.put_u8(TS_QUERY_OPR);
+                out.put_u8(TS_QUERY_NOT);
+                e.write(out)?;
+            },
+            TsQuery::And(left, right) | TsQuery::Or(left, right) | TsQuery::Phrase { left, right, .. } => {
+                out.put_u8(TS_QUERY_OPR);
+                match self {
+                    TsQuery::And(..) => out.put_u8(TS_QUERY_AND),
+                    TsQuery::Or(..) => out.put_u8(TS_QUERY_OR),
+                    TsQuery::Phrase { distance, .. } => {
+                        out.put_u8(TS_QUERY_PHRASE);
+                        out.put_u16(*distance);
+                    },
+                    _ => unreachable!(),
+                }
+                right.write(out)?;
+                left.write(out)?;
+            },
+        }
+        return Ok(());
+    }
+}
+
+impl<'a> FromSql<'a> for TsQuery {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let mut r = TsReader(raw);
+        if r.u32()? == 0 {
+            return Ok(TsQuery::Empty);
+        }
+        return TsQuery::read(&mut r);
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        return *ty == Type::TSQUERY;
+    }
+}
+
+impl ToSql for TsQuery {
+    fn to_sql(&self, _ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        if let TsQuery::Empty = self {
+            return Err("Postgres doesn't accept empty text search queries as parameters".into());
+        }
+        out.put_u32(self.count());
+        self.write(out)?;
+        return Ok(IsNull::No);
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        return *ty == Type::TSQUERY;
+    }
+
+    to_sql_checked!();
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        bytes::BytesMut,
+        tokio_postgres::types::{
+            FromSql,
+            ToSql,
+            Type,
+        },
+        super::{
+            TsLexeme,
+            TsPosition,
+            TsQuery,
+            TsVector,
+            TsWeight,
+        },
+    };
+
+    fn hex(s: &str) -> Vec<u8> {
+        return (0 .. s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i .. i + 2], 16).unwrap()).collect();
+    }
+
+    fn lexeme(lexeme: &str, weights: Vec<TsWeight>, prefix: bool) -> Box<TsQuery> {
+        return Box::new(TsQuery::Lexeme {
+            lexeme: lexeme.into(),
+            weights: weights,
+            prefix: prefix,
+        });
+    }
+
+    // `tsvectorsend('cat:3A,1B fat:2 rat'::tsvector)`
+    const TS_VECTOR_SEND: &str = "000000036361740000028001c0036661740000010002726174000000";
+
+    // `tsquerysend('(cat:AB & !fat) | (rat:* <2> bat)'::tsquery)`
+    const TS_QUERY_SEND: &str = "0000000802030204000201000062617400010001726174000202020101000066617400010c0063617400";
+
+    fn ts_vector() -> TsVector {
+        return TsVector(vec![TsLexeme {
+            lexeme: "cat".into(),
+            positions: vec![TsPosition {
+                position: 1,
+                weight: TsWeight::B,
+            }, TsPosition {
+                position: 3,
+                weight: TsWeight::A,
+            }],
+        }, TsLexeme {
+            lexeme: "fat".into(),
+            positions: vec![TsPosition {
+                position: 2,
+                weight: TsWeight::D,
+            }],
+        }, TsLexeme {
+            lexeme: "rat".into(),
+            positions: vec![],
+        }]);
+    }
+
+    fn ts_query() -> TsQuery {
+        return TsQuery::Or(
+            Box::new(
+                TsQuery::And(
+                    lexeme("cat", vec![TsWeight::A, TsWeight::B], false),
+                    Box::new(TsQuery::Not(lexeme("fat", vec![], false))),
+                ),
+            ),
+            Box::new(TsQuery::Phrase {
+                left: lexeme("rat", vec![], true),
+                right: lexeme("bat", vec![], false),
+                distance: 2,
+            }),
+        );
+    }
+
+    #[test]
+    fn test_ts_vector_from_sql() {
+        assert_eq!(TsVector::from_sql(&Type::TS_VECTOR, &hex(TS_VECTOR_SEND)).unwrap(), ts_vector());
+    }
+
+    #[test]
+    fn test_ts_vector_to_sql() {
+        let mut out = BytesMut::new();
+        ts_vector().to_sql(&Type::TS_VECTOR, &mut out).unwrap();
+        assert_eq!(out.to_vec(), hex(TS_VECTOR_SEND));
+
+        // Positions are sorted before sending
+        let mut unsorted = ts_vector();
+        unsorted.0[0].positions.reverse();
+        let mut out = BytesMut::new();
+        unsorted.to_sql(&Type::TS_VECTOR, &mut out).unwrap();
+        assert_eq!(out.to_vec(), hex(TS_VECTOR_SEND));
+    }
+
+    #[test]
+    fn test_ts_query_from_sql() {
+        assert_eq!(TsQuery::from_sql(&Type::TSQUERY, &hex(TS_QUERY_SEND)).unwrap(), ts_query());
+        assert_eq!(TsQuery::from_sql(&Type::TSQUERY, &hex("00000000")).unwrap(), TsQuery::Empty);
+    }
+
+    #[test]
+    fn test_ts_query_to_sql() {
+        let mut out = BytesMut::new();
+        ts_query().to_sql(&Type::TSQUERY, &mut out).unwrap();
+        assert_eq!(out.to_vec(), hex(TS_QUERY_SEND));
+        assert!(TsQuery::Empty.to_sql(&Type::TSQUERY, &mut BytesMut::new()).is_err());
+    }
+
+    #[test]
+    fn test_ts_truncated_bad() {
+        let vector = hex(TS_VECTOR_SEND);
+        for i in 0 .. vector.len() {
+            assert!(TsVector::from_sql(&Type::TS_VECTOR, &vector[.. i]).is_err(), "length {}", i);
+        }
+        let query = hex(TS_QUERY_SEND);
+        for i in 0 .. query.len() {
+            assert!(TsQuery::from_sql(&Type::TSQUERY, &query[.. i]).is_err(), "length {}", i);
+        }
+    }
+
+    #[test]
+    fn test_ts_query_unknown_operator_bad() {
+        let err = TsQuery::from_sql(&Type::TSQUERY, &hex("0000000302090100006100010000620000")).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown text search query operator 9");
+    }
+}
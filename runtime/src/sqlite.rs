@@ -73,3 +73,98 @@ pub trait GoodOrmningCustomFixedOffsetTimeJiff<T> {
     fn to_sql(value: &T) -> Zoned;
     fn from_sql(value: Zoned) -> Result<T, String>;
 }
+
+/// Writes a value as JSON, used to pass lists of tuples to queries as a single
+/// parameter (expanded with `json_each`).
+pub trait ToJson {
+    fn to_json(&self, out: &mut String);
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self, out: &mut String) {
+        (**self).to_json(out);
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self, out: &mut String) {
+        match self {
+            Some(x) => x.to_json(out),
+            None => out.push_str("null"),
+        }
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self, out: &mut String) {
+        out.push_str(if *self {
+            "true"
+        } else {
+            "false"
+        });
+    }
+}
+
+impl ToJson for i32 {
+    fn to_json(&self, out: &mut String) {
+        out.push_str(&self.to_string());
+    }
+}
+
+impl ToJson for i64 {
+    fn to_json(&self, out: &mut String) {
+        out.push_str(&self.to_string());
+    }
+}
+
+impl ToJson for u32 {
+    fn to_json(&self, out: &mut String) {
+        out.push_str(&self.to_string());
+    }
+}
+
+impl ToJson for f32 {
+    fn to_json(&self, out: &mut String) {
+        // Stored as doubles, so must match the widened value
+        f64::from(*self).to_json(out);
+    }
+}
+
+impl ToJson for f64 {
+    fn to_json(&self, out: &mut String) {
+        if self.is_nan() {
+            // SQLite stores NaN as null
+            out.push_str("null");
+        } else if self.is_infinite() {
+            // SQLite's representation of infinity
+            out.push_str(if *self > 0. {
+                "9e999"
+            } else {
+                "-9e999"
+            });
+        } else {
+            out.push_str(&format!("{:?}", self));
+        }
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self, out: &mut String) {
+        out.push('"');
+        for c in self.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self, out: &mut String) {
+        self.as_str().to_json(out);
+    }
+}
@@ -29,7 +29,7 @@ pub trait NodeData: Clone {
 }
 
 pub struct Node<T: NodeData> {
-    deps: Vec<T::I>,
+    pub(crate) deps: Vec<T::I>,
     pub(crate) body: T,
 }
 
@@ -43,7 +43,7 @@ impl NodeDataDispatch for NodeConstraint_ {
 
     fn create(&self, ctx: &mut PgMigrateCtx) {
         let mut stmt = Tokens::new();
-        stmt.s("alter table").id(&self.def.table.id).s("add constraint").id(&self.def.id);
+        stmt.s("alter table").s(&self.def.table.sql_id()).s("add constraint").id(&self.def.id);
         match &self.def.type_ {
             ConstraintType::PrimaryKey(x) => {
                 stmt.s("primary key (").f(|t| {
@@ -66,7 +66,7 @@ impl NodeDataDispatch for NodeConstraint_ {
                 }).s(") references ").f(|t| {
                     for (i, pair) in x.fields.iter().enumerate() {
                         if i == 0 {
-                            t.id(&pair.1.table.id).s("(");
+                            t.s(&pair.1.table.sql_id()).s("(");
                         } else {
                             t.s(",");
                         }
@@ -88,7 +88,7 @@ impl NodeDataDispatch for NodeConstraint_ {
             .push(
                 Tokens::new()
                     .s("alter table")
-                    .id(&self.def.table.id)
+                    .s(&self.def.table.sql_id())
                     .s("drop constraint")
                     .id(&self.def.id)
                     .to_string(),
@@ -102,7 +102,7 @@ impl NodeData for NodeConstraint_ {
             let mut stmt = Tokens::new();
             stmt
                 .s("alter table")
-                .id(&self.def.table.0.id)
+                .s(&self.def.table.sql_id())
                 .s("rename constraint")
                 .id(&old.def.id)
                 .s("to")
@@ -0,0 +1,53 @@
+use std::collections::HashSet;
+use crate::{
+    pg::schema::extension::Extension,
+    graphmigrate::Comparison,
+    utils::Tokens,
+};
+use super::{
+    utils::{
+        NodeData,
+        PgMigrateCtx,
+        NodeDataDispatch,
+    },
+    Node,
+    GraphId,
+};
+
+#[derive(Clone)]
+pub(crate) struct NodeExtension_ {
+    pub def: Extension,
+}
+
+impl NodeExtension_ {
+    pub fn compare(&self, _old: &Self, _created: &HashSet<GraphId>) -> Comparison {
+        Comparison::DoNothing
+    }
+}
+
+impl NodeData for NodeExtension_ {
+    fn update(&self, _ctx: &mut PgMigrateCtx, _old: &Self) {
+        unreachable!();
+    }
+}
+
+impl NodeDataDispatch for NodeExtension_ {
+    fn create_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn create(&self, ctx: &mut PgMigrateCtx) {
+        // The extension may have been installed already, by an administrator or
+        // another application
+        ctx.statements.push(Tokens::new().s("create extension if not exists").id(&self.def.0).to_string());
+    }
+
+    fn delete_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn delete(&self, _ctx: &mut PgMigrateCtx) {
+        // Left installed, since it may have been installed by someone else or be used
+        // by other schemas or applications
+    }
+}
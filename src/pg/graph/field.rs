@@ -44,7 +44,8 @@ impl NodeField_ {
         }
         let t = &self.def.type_.type_;
         let old_t = &old.def.type_.type_;
-        if self.def.id != old.def.id || t.opt != old_t.opt || t.type_.type_ != old_t.type_.type_ {
+        if self.def.id != old.def.id || t.opt != old_t.opt || t.type_.type_ != old_t.type_.type_ ||
+            self.def.type_.identity != old.def.type_.identity {
             Comparison::Update
         } else {
             Comparison::DoNothing
@@ -54,6 +55,42 @@ impl NodeField_ {
     fn display_path(&self) -> rpds::Vector<String> {
         rpds::vector![self.def.to_string()]
     }
+
+    fn alter_column(&self) -> Tokens {
+        let mut out = Tokens::new();
+        out.s("alter table").s(&self.def.table.sql_id()).s("alter column").id(&self.def.id);
+        out
+    }
+
+    /// An expression for the name of the sequence backing this serial or identity
+    /// column.
+    fn sequence_name(&self) -> String {
+        format!(
+            "pg_get_serial_sequence({}, {})",
+            sql_str(&self.def.table.sql_id()),
+            sql_str(&self.def.id)
+        )
+    }
+
+    /// Continue the sequence after the largest existing value.
+    fn sync_sequence(&self, ctx: &mut PgMigrateCtx) {
+        ctx
+            .statements
+            .push(
+                Tokens::new()
+                    .s("select setval (")
+                    .s(&self.sequence_name())
+                    .s(", coalesce ( max (")
+                    .id(&self.def.id)
+                    .s(") , 0 ) + 1 , false ) from")
+                    .s(&self.def.table.sql_id())
+                    .to_string(),
+            );
+    }
+}
+
+fn sql_str(s: &str) -> String {
+    return format!("'{}'", s.replace("'", "''"));
 }
 
 impl NodeData for NodeField_ {
@@ -62,7 +99,7 @@ impl NodeData for NodeField_ {
             let mut stmt = Tokens::new();
             stmt
                 .s("alter table")
-                .id(&self.def.table.0.id)
+                .s(&self.def.table.sql_id())
                 .s("rename column")
                 .id(&old.def.id)
                 .s("to")
@@ -77,7 +114,7 @@ impl NodeData for NodeField_ {
                 .push(
                     Tokens::new()
                         .s("alter table")
-                        .id(&self.def.table.id)
+                        .s(&self.def.table.sql_id())
                         .s("alter column")
                         .id(&self.def.id)
                         .s("drop not null")
@@ -89,27 +126,44 @@ impl NodeData for NodeField_ {
                 .push(
                     Tokens::new()
                         .s("alter table")
-                        .id(&self.def.table.id)
+                        .s(&self.def.table.sql_id())
                         .s("alter column")
                         .id(&self.def.id)
                         .s("set not null")
                         .to_string(),
                 );
         }
-        if t.type_.type_ != old_t.type_.type_ {
+        let was_serial = matches!(old_t.type_.type_, SimpleSimpleType::Auto);
+        if t.type_.type_ != old_t.type_.type_ &&
+            !(was_serial && matches!(t.type_.type_, SimpleSimpleType::I64)) {
+            ctx.statements.push(self.alter_column().s("set type").s(to_sql_type(&t.type_.type_)).to_string());
+        }
+        let identity = &self.def.type_.identity;
+        let old_identity = &old.def.type_.identity;
+        if was_serial && !matches!(t.type_.type_, SimpleSimpleType::Auto) {
+            // Detach and drop the serial sequence (an identity column gets its own)
+            ctx.statements.push(self.alter_column().s("drop default").to_string());
             ctx
                 .statements
                 .push(
-                    Tokens::new()
-                        .s("alter table")
-                        .id(&self.def.table.id)
-                        .s("alter column")
-                        .id(&self.def.id)
-                        .s("set type")
-                        .s(to_sql_type(&t.type_.type_))
-                        .to_string(),
+                    format!("do $$ begin execute format('drop sequence %s', {}); end $$", self.sequence_name()),
                 );
         }
+        match (identity, old_identity) {
+            (Some(i), None) => {
+                ctx
+                    .statements
+                    .push(self.alter_column().s("add generated").s(i.to_sql()).s("as identity").to_string());
+                self.sync_sequence(ctx);
+            },
+            (None, Some(_)) => {
+                ctx.statements.push(self.alter_column().s("drop identity").to_string());
+            },
+            (Some(i), Some(old_i)) if i != old_i => {
+                ctx.statements.push(self.alter_column().s("set generated").s(i.to_sql()).to_string());
+            },
+            _ => { },
+        }
     }
 }
 
@@ -117,20 +171,30 @@ impl NodeDataDispatch for NodeField_ {
     fn create(&self, ctx: &mut PgMigrateCtx) {
         let path = self.display_path();
         if matches!(self.def.type_.type_.type_.type_, SimpleSimpleType::Auto) {
-            ctx.errs.err(&path, format!("Auto (serial) fields can't be added after table creation"));
+            ctx
+                .errs
+                .err(
+                    &path,
+                    format!("Auto (serial) fields can't be added after table creation, use an identity field instead"),
+                );
         }
         let mut stmt = Tokens::new();
         stmt
             .s("alter table")
-            .id(&self.def.table.0.id)
+            .s(&self.def.table.sql_id())
             .s("add column")
             .id(&self.def.id)
             .s(to_sql_type(&self.def.type_.type_.type_.type_));
-        if !self.def.type_.type_.opt {
+        if let Some(i) = &self.def.type_.identity {
+            // Existing rows are numbered from the new sequence
+            stmt.s("generated").s(i.to_sql()).s("as identity not null");
+        } else if !self.def.type_.type_.opt {
             if let Some(d) = &self.def.type_.migration_default {
                 stmt.s("not null default");
                 let qctx_fields = HashMap::new();
-                let mut qctx = PgQueryCtx::new(ctx.errs.clone(), &qctx_fields);
+                let qctx_unique_keys = HashMap::new();
+                let qctx_primary_keys = HashMap::new();
+                let mut qctx = PgQueryCtx::new(ctx.errs.clone(), &qctx_fields, &qctx_unique_keys, &qctx_primary_keys);
                 let e_res = d.build(&mut qctx, &path, &HashMap::new());
                 check_same(&mut qctx.errs, &path, &ExprType(vec![(ExprValName::empty(), Type {
                     type_: self.def.type_.type_.type_.clone(),
@@ -159,7 +223,7 @@ impl NodeDataDispatch for NodeField_ {
                 .push(
                     Tokens::new()
                         .s("alter table")
-                        .id(&self.def.table.id)
+                        .s(&self.def.table.sql_id())
                         .s("alter column")
                         .id(&self.def.id)
                         .s("drop default")
@@ -172,7 +236,12 @@ impl NodeDataDispatch for NodeField_ {
         ctx
             .statements
             .push(
-                Tokens::new().s("alter table").id(&self.def.table.id).s("drop column").id(&self.def.id).to_string(),
+                Tokens::new()
+                    .s("alter table")
+                    .s(&self.def.table.sql_id())
+                    .s("drop column")
+                    .id(&self.def.id)
+                    .to_string(),
             );
     }
 
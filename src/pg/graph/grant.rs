@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+use crate::{
+    pg::schema::grant::{
+        Grant,
+        NamespaceGrant,
+        Privilege,
+        role_sql,
+    },
+    graphmigrate::Comparison,
+    utils::Tokens,
+};
+use super::{
+    utils::{
+        NodeData,
+        PgMigrateCtx,
+        NodeDataDispatch,
+    },
+    Node,
+    GraphId,
+};
+
+#[derive(Clone)]
+pub(crate) struct NodeGrant_ {
+    pub def: Grant,
+}
+
+impl NodeGrant_ {
+    pub fn compare(&self, old: &Self, _created: &HashSet<GraphId>) -> Comparison {
+        if self.def.privileges.iter().collect::<HashSet<_>>() != old.def.privileges.iter().collect::<HashSet<_>>() {
+            Comparison::Update
+        } else {
+            Comparison::DoNothing
+        }
+    }
+
+    fn statement(&self, action: &str, privileges: &[&Privilege], direction: &str) -> String {
+        let mut out = Tokens::new();
+        out.s(action);
+        for (i, p) in privileges.iter().enumerate() {
+            if i > 0 {
+                out.s(",");
+            }
+            out.s(p.to_sql());
+        }
+        out.s("on").s(&self.def.table.sql_id()).s(direction).s(&role_sql(&self.def.role));
+        out.to_string()
+    }
+}
+
+impl NodeData for NodeGrant_ {
+    fn update(&self, ctx: &mut PgMigrateCtx, old: &Self) {
+        let removed =
+            old.def.privileges.iter().filter(|p| !self.def.privileges.contains(p)).collect::<Vec<_>>();
+        if !removed.is_empty() {
+            ctx.statements.push(self.statement("revoke", &removed, "from"));
+        }
+        let added = self.def.privileges.iter().filter(|p| !old.def.privileges.contains(p)).collect::<Vec<_>>();
+        if !added.is_empty() {
+            ctx.statements.push(self.statement("grant", &added, "to"));
+        }
+    }
+}
+
+impl NodeDataDispatch for NodeGrant_ {
+    fn create_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn create(&self, ctx: &mut PgMigrateCtx) {
+        ctx.statements.push(self.statement("grant", &self.def.privileges.iter().collect::<Vec<_>>(), "to"));
+    }
+
+    fn delete_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn delete(&self, ctx: &mut PgMigrateCtx) {
+        ctx.statements.push(self.statement("revoke", &self.def.privileges.iter().collect::<Vec<_>>(), "from"));
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct NodeNamespaceGrant_ {
+    pub def: NamespaceGrant,
+}
+
+impl NodeNamespaceGrant_ {
+    pub fn compare(&self, _old: &Self, _created: &HashSet<GraphId>) -> Comparison {
+        // The grant follows the schema if it's renamed
+        Comparison::DoNothing
+    }
+
+    fn statement(&self, action: &str, direction: &str) -> String {
+        Tokens::new()
+            .s(action)
+            .s("usage on schema")
+            .id(&self.def.namespace.id)
+            .s(direction)
+            .s(&role_sql(&self.def.role))
+            .to_string()
+    }
+}
+
+impl NodeData for NodeNamespaceGrant_ {
+    fn update(&self, _ctx: &mut PgMigrateCtx, _old: &Self) {
+        unreachable!();
+    }
+}
+
+impl NodeDataDispatch for NodeNamespaceGrant_ {
+    fn create_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn create(&self, ctx: &mut PgMigrateCtx) {
+        ctx.statements.push(self.statement("grant", "to"));
+    }
+
+    fn delete_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn delete(&self, ctx: &mut PgMigrateCtx) {
+        ctx.statements.push(self.statement("revoke", "from"));
+    }
+}
@@ -2,10 +2,17 @@ use std::collections::HashSet;
 use crate::{
     graphmigrate::Comparison,
     utils::Tokens,
-    pg::schema::index::Index,
+    pg::schema::{
+        index::{
+            Index,
+            IndexMethod,
+        },
+        table::qualified_id,
+    },
 };
 use super::{
     utils::{
+        ConcurrentStatement,
         NodeDataDispatch,
         PgMigrateCtx,
         NodeData,
@@ -21,7 +28,10 @@ pub(crate) struct NodeIndex_ {
 
 impl NodeIndex_ {
     pub fn compare(&self, old: &Self, created: &HashSet<GraphId>) -> Comparison {
-        if created.contains(&GraphId::Table(self.def.table.schema_id.clone())) || self.def.fields != old.def.fields {
+        if created.contains(&GraphId::Table(self.def.table.schema_id.clone())) || self.def.fields != old.def.fields ||
+            self.def.method != old.def.method ||
+            self.def.opclasses != old.def.opclasses ||
+            self.def.include != old.def.include {
             Comparison::Recreate
         } else if self.def.id != old.def.id {
             Comparison::Update
@@ -37,18 +47,61 @@ impl NodeDataDispatch for NodeIndex_ {
     }
 
     fn create(&self, ctx: &mut PgMigrateCtx) {
-        ctx.statements.push(Tokens::new().s("create").f(|t| {
+        // A new table has no rows, and the index must be created with it in case
+        // anything later in the migration needs it
+        let concurrently = self.def.concurrently && !ctx.created_tables.contains(&self.def.table.schema_id);
+        let deferred = concurrently || ctx.deferred_indexes.contains(&self.def.sql_id());
+        let stmt = Tokens::new().s("create").f(|t| {
             if self.def.unique {
                 t.s("unique");
             }
-        }).s("index").id(&self.def.id).s("on").id(&self.def.table.id).s("(").f(|t| {
-            for (i, field) in self.def.fields.iter().enumerate() {
+        }).s("index").f(|t| {
+            if concurrently {
+                t.s("concurrently");
+            }
+            if deferred {
+                t.s("if not exists");
+            }
+        }).id(&self.def.id).s("on").s(&self.def.table.sql_id()).f(|t| {
+            if self.def.method != IndexMethod::Btree {
+                t.s("using").s(self.def.method.to_sql());
+            }
+        }).s("(").f(|t| {
+            for (i, (field, opclass)) in self.def.fields.iter().zip(self.def.opclasses.iter()).enumerate() {
                 if i > 0 {
                     t.s(",");
                 }
                 t.id(&field.id);
+                if let Some(opclass) = opclass {
+                    t.s(opclass);
+                }
+            }
+        }).s(")").f(|t| {
+            if !self.def.include.is_empty() {
+                t.s("include (");
+                for (i, field) in self.def.include.iter().enumerate() {
+                    if i > 0 {
+                        t.s(",");
+                    }
+                    t.id(&field.id);
+                }
+                t.s(")");
             }
-        }).s(")").to_string());
+        }).to_string();
+        if deferred {
+            ctx.deferred_indexes.insert(self.def.sql_id());
+            ctx.concurrent_statements.push(ConcurrentStatement {
+                statement: stmt,
+                creates_index: if concurrently {
+                    Some(self.def.sql_id())
+                } else {
+                    None
+                },
+                index_table: Some(self.def.table.sql_id()),
+            });
+        } else {
+            ctx.statements.push(stmt);
+        }
     }
 
     fn delete_coalesce(&mut self, other: Node) -> Option<Node> {
@@ -56,16 +109,45 @@ impl NodeDataDispatch for NodeIndex_ {
     }
 
     fn delete(&self, ctx: &mut PgMigrateCtx) {
-        ctx.statements.push(Tokens::new().s("drop index").id(&self.def.id).to_string());
+        if self.def.concurrently || ctx.deferred_indexes.contains(&self.def.sql_id()) {
+            ctx.deferred_indexes.insert(self.def.sql_id());
+            ctx.concurrent_statements.push(ConcurrentStatement {
+                statement: Tokens::new().s("drop index").f(|t| {
+                    if self.def.concurrently {
+                        t.s("concurrently");
+                    }
+                }).s("if exists").s(&self.def.sql_id()).to_string(),
+                creates_index: None,
+                index_table: None,
+            });
+        } else {
+            ctx.statements.push(Tokens::new().s("drop index").s(&self.def.sql_id()).to_string());
+        }
     }
 }
 
 impl NodeData for NodeIndex_ {
     fn update(&self, ctx: &mut PgMigrateCtx, old: &Self) {
         if self.def.id != old.def.id {
+            // Indexes move with their table, which has already been updated
+            let old_sql_id = qualified_id(self.def.table.namespace.as_ref().map(|n| n.id.as_str()), &old.def.id);
             let mut stmt = Tokens::new();
-            stmt.s("alter index").id(&old.def.id).s("rename to").id(&self.def.id);
-            ctx.statements.push(stmt.to_string());
+            if old.def.concurrently || self.def.concurrently || ctx.deferred_indexes.contains(&old_sql_id) ||
+                ctx.deferred_indexes.contains(&self.def.sql_id()) {
+                // Keep the order relative to a concurrent create, which may not have run
+                // yet
+                ctx.deferred_indexes.insert(old_sql_id.clone());
+                ctx.deferred_indexes.insert(self.def.sql_id());
+                stmt.s("alter index if exists").s(&old_sql_id).s("rename to").id(&self.def.id);
+                ctx.concurrent_statements.push(ConcurrentStatement {
+                    statement: stmt.to_string(),
+                    creates_index: None,
+                    index_table: None,
+                });
+            } else {
+                stmt.s("alter index").s(&old_sql_id).s("rename to").id(&self.def.id);
+                ctx.statements.push(stmt.to_string());
+            }
         }
     }
 }
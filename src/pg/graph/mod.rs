@@ -7,6 +7,15 @@ use self::{
     field::NodeField_,
     constraint::NodeConstraint_,
     index::NodeIndex_,
+    sequence::NodeSequence_,
+    namespace::NodeNamespace_,
+    extension::NodeExtension_,
+    partition::NodePartition_,
+    policy::NodePolicy_,
+    grant::{
+        NodeGrant_,
+        NodeNamespaceGrant_,
+    },
     utils::{
         PgMigrateCtx,
         NodeDataDispatch,
@@ -18,12 +27,23 @@ use super::schema::{
     field::SchemaFieldId,
     constraint::SchemaConstraintId,
     index::SchemaIndexId,
+    sequence::SchemaSequenceId,
+    namespace::SchemaNamespaceId,
+    extension::Extension,
+    partition::SchemaPartitionId,
+    policy::SchemaPolicyId,
 };
 
 pub mod table;
 pub mod field;
 pub mod constraint;
 pub mod index;
+pub mod sequence;
+pub mod namespace;
+pub mod extension;
+pub mod partition;
+pub mod policy;
+pub mod grant;
 pub mod utils;
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug, PartialOrd, Ord)]
@@ -32,6 +52,13 @@ pub enum GraphId {
     Field(SchemaTableId, SchemaFieldId),
     Constraint(SchemaTableId, SchemaConstraintId),
     Index(SchemaTableId, SchemaIndexId),
+    Sequence(SchemaSequenceId),
+    Namespace(SchemaNamespaceId),
+    Extension(Extension),
+    Partition(SchemaTableId, SchemaPartitionId),
+    Policy(SchemaTableId, SchemaPolicyId),
+    Grant(SchemaTableId, String),
+    NamespaceGrant(SchemaNamespaceId, String),
 }
 
 #[derive(Clone)]
@@ -42,6 +69,13 @@ pub(crate) enum Node {
     Field(NodeField_),
     Constraint(NodeConstraint_),
     Index(NodeIndex_),
+    Sequence(NodeSequence_),
+    Namespace(NodeNamespace_),
+    Extension(NodeExtension_),
+    Partition(NodePartition_),
+    Policy(NodePolicy_),
+    Grant(NodeGrant_),
+    NamespaceGrant(NodeNamespaceGrant_),
 }
 
 impl Node {
@@ -60,6 +94,34 @@ impl Node {
     pub(crate) fn table_index(t: NodeIndex_) -> Self {
         Node::Index(t)
     }
+
+    pub(crate) fn sequence(t: NodeSequence_) -> Self {
+        Node::Sequence(t)
+    }
+
+    pub(crate) fn namespace(t: NodeNamespace_) -> Self {
+        Node::Namespace(t)
+    }
+
+    pub(crate) fn extension(t: NodeExtension_) -> Self {
+        Node::Extension(t)
+    }
+
+    pub(crate) fn partition(t: NodePartition_) -> Self {
+        Node::Partition(t)
+    }
+
+    pub(crate) fn policy(t: NodePolicy_) -> Self {
+        Node::Policy(t)
+    }
+
+    pub(crate) fn grant(t: NodeGrant_) -> Self {
+        Node::Grant(t)
+    }
+
+    pub(crate) fn namespace_grant(t: NodeNamespaceGrant_) -> Self {
+        Node::NamespaceGrant(t)
+    }
 }
 
 impl<'a> crate::graphmigrate::NodeData for Node {
@@ -72,6 +134,13 @@ impl<'a> crate::graphmigrate::NodeData for Node {
             PairwiseNode::Field(current, old) => current.compare(old, created),
             PairwiseNode::Constraint(current, old) => current.compare(old, created),
             PairwiseNode::Index(current, old) => current.compare(old, created),
+            PairwiseNode::Sequence(current, old) => current.compare(old, created),
+            PairwiseNode::Namespace(current, old) => current.compare(old, created),
+            PairwiseNode::Extension(current, old) => current.compare(old, created),
+            PairwiseNode::Partition(current, old) => current.compare(old, created),
+            PairwiseNode::Policy(current, old) => current.compare(old, created),
+            PairwiseNode::Grant(current, old) => current.compare(old, created),
+            PairwiseNode::NamespaceGrant(current, old) => current.compare(old, created),
             PairwiseNode::Nonmatching(_, _) => unreachable!(),
         }
     }
@@ -90,6 +159,13 @@ impl<'a> crate::graphmigrate::NodeData for Node {
             PairwiseNode::Field(current, old) => current.update(ctx, &old),
             PairwiseNode::Constraint(current, old) => current.update(ctx, &old),
             PairwiseNode::Index(current, old) => current.update(ctx, &old),
+            PairwiseNode::Sequence(current, old) => current.update(ctx, &old),
+            PairwiseNode::Namespace(current, old) => current.update(ctx, &old),
+            PairwiseNode::Extension(current, old) => current.update(ctx, &old),
+            PairwiseNode::Partition(current, old) => current.update(ctx, &old),
+            PairwiseNode::Policy(current, old) => current.update(ctx, &old),
+            PairwiseNode::Grant(current, old) => current.update(ctx, &old),
+            PairwiseNode::NamespaceGrant(current, old) => current.update(ctx, &old),
             PairwiseNode::Nonmatching(_, _) => unreachable!(),
         }
     }
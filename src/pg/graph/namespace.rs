@@ -0,0 +1,55 @@
+use std::collections::HashSet;
+use crate::{
+    pg::schema::namespace::Namespace,
+    graphmigrate::Comparison,
+    utils::Tokens,
+};
+use super::{
+    utils::{
+        NodeData,
+        PgMigrateCtx,
+        NodeDataDispatch,
+    },
+    Node,
+    GraphId,
+};
+
+#[derive(Clone)]
+pub(crate) struct NodeNamespace_ {
+    pub def: Namespace,
+}
+
+impl NodeNamespace_ {
+    pub fn compare(&self, old: &Self, _created: &HashSet<GraphId>) -> Comparison {
+        if self.def.id != old.def.id {
+            Comparison::Update
+        } else {
+            Comparison::DoNothing
+        }
+    }
+}
+
+impl NodeData for NodeNamespace_ {
+    fn update(&self, ctx: &mut PgMigrateCtx, old: &Self) {
+        ctx.statements.push(Tokens::new().s("alter schema").id(&old.def.id).s("rename to").id(&self.def.id).to_string());
+    }
+}
+
+impl NodeDataDispatch for NodeNamespace_ {
+    fn create_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn create(&self, ctx: &mut PgMigrateCtx) {
+        ctx.statements.push(Tokens::new().s("create schema").id(&self.def.id).to_string());
+    }
+
+    fn delete_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn delete(&self, ctx: &mut PgMigrateCtx) {
+        // Deletes run before updates, so wait for tables to be moved out
+        ctx.final_statements.push(Tokens::new().s("drop schema").id(&self.def.id).to_string());
+    }
+}
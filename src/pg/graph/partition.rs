@@ -0,0 +1,179 @@
+use std::collections::{
+    HashSet,
+    HashMap,
+};
+use crate::{
+    pg::{
+        schema::{
+            partition::{
+                Partition,
+                PartitionBound,
+            },
+            table::qualified_id,
+        },
+        types::Type,
+        query::{
+            utils::PgQueryCtx,
+            expr::{
+                Expr,
+                ExprType,
+                ExprValName,
+                check_same,
+            },
+        },
+    },
+    graphmigrate::Comparison,
+    utils::{
+        Tokens,
+        Errs,
+    },
+};
+use super::{
+    utils::{
+        NodeData,
+        PgMigrateCtx,
+        NodeDataDispatch,
+    },
+    Node,
+    GraphId,
+};
+
+#[derive(Clone)]
+pub(crate) struct NodePartition_ {
+    pub def: Partition,
+}
+
+impl NodePartition_ {
+    pub fn compare(&self, old: &Self, _created: &HashSet<GraphId>) -> Comparison {
+        if self.def.id != old.def.id || self.bound_sql(&Errs::new()) != old.bound_sql(&Errs::new()) {
+            Comparison::Update
+        } else {
+            Comparison::DoNothing
+        }
+    }
+
+    fn display_path(&self) -> rpds::Vector<String> {
+        rpds::vector![self.def.to_string()]
+    }
+
+    /// The `for values ...` clause, or `default`.
+    fn bound_sql(&self, errs: &Errs) -> String {
+        let path = self.display_path();
+        let key_fields = &self.def.partition_by.fields;
+        let mut out = Tokens::new();
+        let build_values = |out: &mut Tokens, values: &[Expr], fields: &mut dyn Iterator<Item = &Type>| {
+            out.s("(");
+            for (i, (value, type_)) in values.iter().zip(fields).enumerate() {
+                if i > 0 {
+                    out.s(",");
+                }
+                let qctx_fields = HashMap::new();
+                let qctx_unique_keys = HashMap::new();
+                let qctx_primary_keys = HashMap::new();
+                let mut qctx = PgQueryCtx::new(errs.clone(), &qctx_fields, &qctx_unique_keys, &qctx_primary_keys);
+                let path = path.push_back(format!("Bound value {}", i));
+                let e_res = value.build(&mut qctx, &path, &HashMap::new());
+                check_same(&mut qctx.errs, &path, &ExprType(vec![(ExprValName::empty(), Type {
+                    type_: type_.type_.clone(),
+                    opt: false,
+                })]), &e_res.0);
+                if !qctx.rust_args.is_empty() {
+                    qctx.errs.err(&path, format!("Partition bounds must be constants, but this has parameters"));
+                }
+                out.s(&e_res.1.to_string());
+            }
+            out.s(")");
+        };
+        match &self.def.bound {
+            PartitionBound::Range { from, to } => {
+                out.s("for values from");
+                build_values(&mut out, from, &mut key_fields.iter().map(|f| &f.type_.type_));
+                out.s("to");
+                build_values(&mut out, to, &mut key_fields.iter().map(|f| &f.type_.type_));
+            },
+            PartitionBound::List(values) => {
+                out.s("for values in");
+                build_values(&mut out, values, &mut std::iter::repeat(&key_fields[0].type_.type_));
+            },
+            PartitionBound::Hash { modulus, remainder } => {
+                out.s(&format!("for values with ( modulus {} , remainder {} )", modulus, remainder));
+            },
+            PartitionBound::Default => {
+                out.s("default");
+            },
+        }
+        out.to_string()
+    }
+}
+
+impl NodeData for NodePartition_ {
+    fn update(&self, ctx: &mut PgMigrateCtx, old: &Self) {
+        // The table may have been moved, and partitions are in the table's schema
+        let namespace = self.def.table.namespace.as_ref().map(|n| n.id.as_str());
+        if self.def.id != old.def.id {
+            ctx
+                .statements
+                .push(
+                    Tokens::new()
+                        .s("alter table")
+                        .s(&qualified_id(namespace, &old.def.id))
+                        .s("rename to")
+                        .id(&self.def.id)
+                        .to_string(),
+                );
+        }
+        let bound = self.bound_sql(&ctx.errs);
+        if bound != old.bound_sql(&Errs::new()) {
+            // Keep the rows by detaching and reattaching with the new bounds
+            ctx
+                .statements
+                .push(
+                    Tokens::new()
+                        .s("alter table")
+                        .s(&self.def.table.sql_id())
+                        .s("detach partition")
+                        .s(&self.def.sql_id())
+                        .to_string(),
+                );
+            ctx
+                .statements
+                .push(
+                    Tokens::new()
+                        .s("alter table")
+                        .s(&self.def.table.sql_id())
+                        .s("attach partition")
+                        .s(&self.def.sql_id())
+                        .s(&bound)
+                        .to_string(),
+                );
+        }
+    }
+}
+
+impl NodeDataDispatch for NodePartition_ {
+    fn create_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn create(&self, ctx: &mut PgMigrateCtx) {
+        ctx
+            .statements
+            .push(
+                Tokens::new()
+                    .s("create table")
+                    .s(&self.def.sql_id())
+                    .s("partition of")
+                    .s(&self.def.table.sql_id())
+                    .s(&self.bound_sql(&ctx.errs))
+                    .to_string(),
+            );
+    }
+
+    fn delete_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn delete(&self, ctx: &mut PgMigrateCtx) {
+        ctx.statements.push(Tokens::new().s("drop table").s(&self.def.sql_id()).to_string());
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::{
+    HashSet,
+    HashMap,
+};
+use crate::{
+    pg::{
+        schema::{
+            field::Field,
+            grant::role_sql,
+            policy::{
+                Policy,
+                PolicyCommand,
+            },
+        },
+        types::SimpleSimpleType,
+        query::{
+            utils::PgQueryCtx,
+            expr::{
+                Expr,
+                ExprValName,
+            },
+        },
+    },
+    graphmigrate::Comparison,
+    utils::{
+        Tokens,
+        Errs,
+    },
+};
+use super::{
+    utils::{
+        NodeData,
+        PgMigrateCtx,
+        NodeDataDispatch,
+    },
+    Node,
+    GraphId,
+};
+
+/// The fields referenced by a policy expression.
+pub(crate) fn policy_fields(e: &Expr, out: &mut Vec<Field>) {
+    if let Expr::Field(f) = e {
+        if !out.contains(f) {
+            out.push(f.clone());
+        }
+    }
+    for child in e.row_children() {
+        policy_fields(child, out);
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct NodePolicy_ {
+    pub def: Policy,
+}
+
+impl NodePolicy_ {
+    pub fn compare(&self, old: &Self, created: &HashSet<GraphId>) -> Comparison {
+        let mut fields = vec![];
+        for e in self.def.using.iter().chain(self.def.with_check.iter()) {
+            policy_fields(e, &mut fields);
+        }
+        if self.body_sql(&Errs::new()) != old.body_sql(&Errs::new()) ||
+            fields
+                .iter()
+                .any(|f| created.contains(&GraphId::Field(f.table.schema_id.clone(), f.schema_id.clone()))) {
+            Comparison::Recreate
+        } else if self.def.id != old.def.id {
+            Comparison::Update
+        } else {
+            Comparison::DoNothing
+        }
+    }
+
+    fn display_path(&self) -> rpds::Vector<String> {
+        rpds::vector![self.def.to_string()]
+    }
+
+    /// Everything after `on table`.
+    fn body_sql(&self, errs: &Errs) -> String {
+        let path = self.display_path();
+        let mut out = Tokens::new();
+        if self.def.restrictive {
+            out.s("as restrictive");
+        }
+        if self.def.command != PolicyCommand::All {
+            out.s("for").s(self.def.command.to_sql());
+        }
+        if !self.def.roles.is_empty() {
+            out.s("to");
+            for (i, role) in self.def.roles.iter().enumerate() {
+                if i > 0 {
+                    out.s(",");
+                }
+                out.s(&role_sql(role));
+            }
+        }
+        let build_condition = |out: &mut Tokens, clause: &str, e: &Expr| {
+            let path = path.push_back(format!("Policy {}", clause));
+            let qctx_fields = HashMap::new();
+            let qctx_unique_keys = HashMap::new();
+            let qctx_primary_keys = HashMap::new();
+            let mut qctx = PgQueryCtx::new(errs.clone(), &qctx_fields, &qctx_unique_keys, &qctx_primary_keys);
+            let mut fields = vec![];
+            policy_fields(e, &mut fields);
+            let mut scope = HashMap::new();
+            for f in fields {
+                if f.table != self.def.table {
+                    qctx
+                        .errs
+                        .err(
+                            &path,
+                            format!(
+                                "Policy {} references field {} which isn't part of the policy's table",
+                                clause,
+                                f
+                            ),
+                        );
+                    continue;
+                }
+                scope.insert(ExprValName::field(&f), f.type_.type_.clone());
+            }
+            let e_res = e.build(&mut qctx, &path, &scope);
+            if let Some((_, t)) = e_res.0.assert_scalar(&mut qctx.errs, &path) {
+                if t.type_.type_ != SimpleSimpleType::Bool {
+                    qctx.errs.err(&path, format!("Policy {} must be a bool but is {:?}", clause, t.type_.type_));
+                }
+            }
+            if !qctx.rust_args.is_empty() {
+                qctx
+                    .errs
+                    .err(
+                        &path,
+                        format!(
+                            "Policy expressions must not have any parameters, but this has {} parameters",
+                            qctx.rust_args.len()
+                        ),
+                    );
+            }
+            out.s(clause).s("(").s(&e_res.1.to_string()).s(")");
+        };
+        if let Some(e) = &self.def.using {
+            build_condition(&mut out, "using", e);
+        }
+        if let Some(e) = &self.def.with_check {
+            build_condition(&mut out, "with check", e);
+        }
+        out.to_string()
+    }
+}
+
+impl NodeData for NodePolicy_ {
+    fn update(&self, ctx: &mut PgMigrateCtx, old: &Self) {
+        ctx
+            .statements
+            .push(
+                Tokens::new()
+                    .s("alter policy")
+                    .id(&old.def.id)
+                    .s("on")
+                    .s(&self.def.table.sql_id())
+                    .s("rename to")
+                    .id(&self.def.id)
+                    .to_string(),
+            );
+    }
+}
+
+impl NodeDataDispatch for NodePolicy_ {
+    fn create_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn create(&self, ctx: &mut PgMigrateCtx) {
+        ctx
+            .statements
+            .push(
+                Tokens::new()
+                    .s("create policy")
+                    .id(&self.def.id)
+                    .s("on")
+                    .s(&self.def.table.sql_id())
+                    .s(&self.body_sql(&ctx.errs))
+                    .to_string(),
+            );
+    }
+
+    fn delete_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn delete(&self, ctx: &mut PgMigrateCtx) {
+        ctx
+            .statements
+            .push(Tokens::new().s("drop policy").id(&self.def.id).s("on").s(&self.def.table.sql_id()).to_string());
+    }
+}
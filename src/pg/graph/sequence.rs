@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+use crate::{
+    pg::schema::{
+        namespace::SchemaNamespaceId,
+        sequence::Sequence,
+        table::qualified_id,
+    },
+    graphmigrate::Comparison,
+    utils::Tokens,
+};
+use super::{
+    utils::{
+        NodeData,
+        PgMigrateCtx,
+        NodeDataDispatch,
+    },
+    Node,
+    GraphId,
+};
+
+#[derive(Clone)]
+pub(crate) struct NodeSequence_ {
+    pub def: Sequence,
+}
+
+impl NodeSequence_ {
+    pub fn compare(&self, old: &Self, _created: &HashSet<GraphId>) -> Comparison {
+        if self.def.id != old.def.id || namespace_schema_id(&old.def) != namespace_schema_id(&self.def) ||
+            self.def.start != old.def.start ||
+            self.def.increment != old.def.increment {
+            Comparison::Update
+        } else {
+            Comparison::DoNothing
+        }
+    }
+}
+
+fn namespace_schema_id(s: &Sequence) -> Option<&SchemaNamespaceId> {
+    s.namespace.as_ref().map(|n| &n.schema_id)
+}
+
+impl NodeData for NodeSequence_ {
+    fn update(&self, ctx: &mut PgMigrateCtx, old: &Self) {
+        // Like tables, if the sequence stays in the same schema the schema may have been
+        // renamed already
+        let moved = namespace_schema_id(&old.def) != namespace_schema_id(&self.def);
+        let old_namespace = if moved {
+            &old.def.namespace
+        } else {
+            &self.def.namespace
+        }.as_ref().map(|n| n.id.as_str());
+        if self.def.id != old.def.id {
+            let mut stmt = Tokens::new();
+            stmt.s("alter sequence").s(&qualified_id(old_namespace, &old.def.id)).s("rename to").id(&self.def.id);
+            ctx.statements.push(stmt.to_string());
+        }
+        if moved {
+            let mut stmt = Tokens::new();
+            stmt.s("alter sequence").s(&qualified_id(old_namespace, &self.def.id)).s("set schema");
+            match &self.def.namespace {
+                Some(n) => stmt.id(&n.id),
+                None => stmt.s("public"),
+            };
+            ctx.statements.push(stmt.to_string());
+        }
+        if self.def.start != old.def.start || self.def.increment != old.def.increment {
+            // Only affects future values (and `restart`), existing values are kept
+            let mut stmt = Tokens::new();
+            stmt.s("alter sequence").s(&self.def.sql_id());
+            if self.def.start != old.def.start {
+                stmt.s("start with").s(&self.def.start.unwrap_or(1).to_string());
+            }
+            if self.def.increment != old.def.increment {
+                stmt.s("increment by").s(&self.def.increment.unwrap_or(1).to_string());
+            }
+            ctx.statements.push(stmt.to_string());
+        }
+    }
+}
+
+impl NodeDataDispatch for NodeSequence_ {
+    fn create_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn create(&self, ctx: &mut PgMigrateCtx) {
+        let mut stmt = Tokens::new();
+        stmt.s("create sequence").s(&self.def.sql_id());
+        if let Some(i) = self.def.increment {
+            stmt.s("increment by").s(&i.to_string());
+        }
+        if let Some(s) = self.def.start {
+            stmt.s("start with").s(&s.to_string());
+        }
+        ctx.statements.push(stmt.to_string());
+    }
+
+    fn delete_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn delete(&self, ctx: &mut PgMigrateCtx) {
+        ctx.statements.push(Tokens::new().s("drop sequence").s(&self.def.sql_id()).to_string());
+    }
+}
@@ -2,8 +2,20 @@ use std::collections::HashSet;
 use crate::{
     pg::{
         schema::{
-            table::Table,
-            field::Field,
+            table::{
+                Table,
+                qualified_id,
+            },
+            field::{
+                Field,
+                SchemaFieldId,
+            },
+            namespace::SchemaNamespaceId,
+            partition::{
+                PartitionBy,
+                PartitionMethod,
+            },
+            policy::RowLevelSecurity,
         },
         types::to_sql_type,
     },
@@ -24,11 +36,15 @@ use super::{
 pub struct NodeTable_ {
     pub def: Table,
     pub fields: Vec<Field>,
+    pub partition_by: Option<PartitionBy>,
+    pub row_level_security: Option<RowLevelSecurity>,
 }
 
 impl NodeTable_ {
     pub fn compare(&self, old: &Self, _created: &HashSet<GraphId>) -> Comparison {
-        if old.def.id != self.def.id {
+        if old.def.id != self.def.id || namespace_schema_id(&old.def) != namespace_schema_id(&self.def) ||
+            partition_key(&old.partition_by) != partition_key(&self.partition_by) ||
+            old.row_level_security != self.row_level_security {
             Comparison::Update
         } else {
             Comparison::DoNothing
@@ -36,13 +52,80 @@ impl NodeTable_ {
     }
 }
 
+fn namespace_schema_id(t: &Table) -> Option<&SchemaNamespaceId> {
+    t.namespace.as_ref().map(|n| &n.schema_id)
+}
+
+fn partition_key(p: &Option<PartitionBy>) -> Option<(PartitionMethod, Vec<&SchemaFieldId>)> {
+    p.as_ref().map(|p| (p.method, p.fields.iter().map(|f| &f.schema_id).collect()))
+}
+
 impl NodeData for NodeTable_ {
     fn update(&self, ctx: &mut PgMigrateCtx, old: &Self) {
+        if partition_key(&old.partition_by) != partition_key(&self.partition_by) {
+            ctx
+                .errs
+                .err(
+                    &rpds::vector![self.def.to_string()],
+                    format!("Table partitioning can't be changed after the table is created"),
+                );
+        }
+
+        // If the table stays in the same schema, the schema may have been renamed
+        // already
+        let moved = namespace_schema_id(&old.def) != namespace_schema_id(&self.def);
+        let old_namespace = if moved {
+            &old.def.namespace
+        } else {
+            &self.def.namespace
+        }.as_ref().map(|n| n.id.as_str());
         if old.def.id != self.def.id {
             let mut stmt = Tokens::new();
-            stmt.s("alter table").id(&old.def.id).s("rename to").id(&self.def.id);
+            stmt.s("alter table").s(&qualified_id(old_namespace, &old.def.id)).s("rename to").id(&self.def.id);
             ctx.statements.push(stmt.to_string());
         }
+        if moved {
+            let mut stmt = Tokens::new();
+            stmt.s("alter table").s(&qualified_id(old_namespace, &self.def.id)).s("set schema");
+            match &self.def.namespace {
+                Some(n) => stmt.id(&n.id),
+                None => stmt.s("public"),
+            };
+            ctx.statements.push(stmt.to_string());
+        }
+        if old.row_level_security.is_some() != self.row_level_security.is_some() {
+            ctx
+                .statements
+                .push(
+                    Tokens::new()
+                        .s("alter table")
+                        .s(&self.def.sql_id())
+                        .s(if self.row_level_security.is_some() {
+                            "enable"
+                        } else {
+                            "disable"
+                        })
+                        .s("row level security")
+                        .to_string(),
+                );
+        }
+        let force = |r: &Option<RowLevelSecurity>| r.map(|r| r.force).unwrap_or(false);
+        if force(&old.row_level_security) != force(&self.row_level_security) {
+            ctx
+                .statements
+                .push(
+                    Tokens::new()
+                        .s("alter table")
+                        .s(&self.def.sql_id())
+                        .s(if force(&self.row_level_security) {
+                            "force"
+                        } else {
+                            "no force"
+                        })
+                        .s("row level security")
+                        .to_string(),
+                );
+        }
     }
 }
 
@@ -62,27 +145,56 @@ impl NodeDataDispatch for NodeTable_ {
             Node::Field(f) if f.def.table == self.def => None,
             Node::Constraint(e) if e.def.table == self.def => None,
             Node::Index(e) if e.def.table == self.def => None,
+            Node::Partition(e) if e.def.table == self.def => None,
+            Node::Policy(e) if e.def.table == self.def => None,
+            Node::Grant(e) if e.def.table == self.def => None,
             other => Some(other),
         }
     }
 
     fn create(&self, ctx: &mut PgMigrateCtx) {
+        ctx.created_tables.insert(self.def.schema_id.clone());
         let mut stmt = Tokens::new();
-        stmt.s("create table").id(&self.def.id).s("(");
+        stmt.s("create table").s(&self.def.sql_id()).s("(");
         for (i, f) in self.fields.iter().enumerate() {
             if i > 0 {
                 stmt.s(",");
             }
             stmt.id(&f.id).s(to_sql_type(&f.0.type_.type_.type_.type_));
+            if let Some(i) = &f.type_.identity {
+                stmt.s("generated").s(i.to_sql()).s("as identity");
+            }
             if !f.type_.type_.opt {
                 stmt.s("not null");
             }
         }
         stmt.s(")");
+        if let Some(p) = &self.partition_by {
+            stmt.s("partition by").s(p.method.to_sql()).s("(");
+            for (i, f) in p.fields.iter().enumerate() {
+                if i > 0 {
+                    stmt.s(",");
+                }
+                stmt.id(&f.id);
+            }
+            stmt.s(")");
+        }
         ctx.statements.push(stmt.to_string());
+        if let Some(r) = &self.row_level_security {
+            ctx
+                .statements
+                .push(Tokens::new().s("alter table").s(&self.def.sql_id()).s("enable row level security").to_string());
+            if r.force {
+                ctx
+                    .statements
+                    .push(
+                        Tokens::new().s("alter table").s(&self.def.sql_id()).s("force row level security").to_string(),
+                    );
+            }
+        }
     }
 
     fn delete(&self, ctx: &mut PgMigrateCtx) {
-        ctx.statements.push(Tokens::new().s("drop table").id(&self.def.id).to_string());
+        ctx.statements.push(Tokens::new().s("drop table").s(&self.def.sql_id()).to_string());
     }
 }
@@ -1,10 +1,37 @@
+use std::collections::HashSet;
 use enum_dispatch::enum_dispatch;
-use crate::utils::Errs;
+use crate::{
+    pg::schema::table::SchemaTableId,
+    utils::Errs,
+};
 use super::Node;
 
 pub(crate) struct PgMigrateCtx {
     pub(crate) errs: Errs,
     pub statements: Vec<String>,
+    /// Statements that can't run in a transaction, run after the migration
+    /// transaction commits.
+    pub concurrent_statements: Vec<ConcurrentStatement>,
+    /// Statements run at the end of the migration transaction, after all other
+    /// changes.
+    pub final_statements: Vec<String>,
+    /// Tables created so far in this migration.
+    pub created_tables: HashSet<SchemaTableId>,
+    /// Indexes (old and new ids) with changes in `concurrent_statements`, so later
+    /// changes to them must also be deferred to keep the order.
+    pub deferred_indexes: HashSet<String>,
+}
+
+pub(crate) struct ConcurrentStatement {
+    pub statement: String,
+    /// The id of an index created concurrently by the statement. A failed
+    /// concurrent create leaves an invalid index behind, which is dropped before
+    /// retrying.
+    pub creates_index: Option<String>,
+    /// The table of an index created by the statement. The statement is skipped if
+    /// the table doesn't exist, since a later version may have dropped it before
+    /// deferred statements run.
+    pub index_table: Option<String>,
 }
 
 impl PgMigrateCtx {
@@ -12,6 +39,10 @@ impl PgMigrateCtx {
         Self {
             errs: errs,
             statements: Default::default(),
+            concurrent_statements: Default::default(),
+            final_statements: Default::default(),
+            created_tables: Default::default(),
+            deferred_indexes: Default::default(),
         }
     }
 }
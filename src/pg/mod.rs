@@ -21,6 +21,7 @@ use crate::{
     pg::{
         types::{
             Type,
+            SimpleSimpleType,
             to_rust_types,
         },
         query::expr::ExprValName,
@@ -28,6 +29,7 @@ use crate::{
     },
     utils::{
         Errs,
+        Tokens,
         sanitize_ident,
     },
 };
@@ -43,12 +45,14 @@ use self::{
         },
         expr::Expr,
         select::{
+            OrderChoice,
             Returning,
             Select,
             NamedSelectSource,
             JoinSource,
             Join,
             Order,
+            Lock,
         },
         update::Update,
         delete::Delete,
@@ -75,6 +79,39 @@ use self::{
             Index_,
             Index,
             SchemaIndexId,
+            IndexMethod,
+        },
+        sequence::{
+            Sequence,
+            Sequence_,
+            SchemaSequenceId,
+        },
+        namespace::{
+            Namespace,
+            Namespace_,
+            SchemaNamespaceId,
+        },
+        extension::Extension,
+        partition::{
+            Partition,
+            Partition_,
+            PartitionBy,
+            PartitionBound,
+            PartitionInterval,
+            PartitionMethod,
+            SchemaPartitionId,
+        },
+        policy::{
+            Policy,
+            Policy_,
+            PolicyCommand,
+            RowLevelSecurity,
+            SchemaPolicyId,
+        },
+        grant::{
+            Grant,
+            NamespaceGrant,
+            Privilege,
         },
     },
     graph::{
@@ -85,6 +122,18 @@ use self::{
         field::NodeField_,
         constraint::NodeConstraint_,
         index::NodeIndex_,
+        sequence::NodeSequence_,
+        namespace::NodeNamespace_,
+        extension::NodeExtension_,
+        partition::NodePartition_,
+        policy::{
+            NodePolicy_,
+            policy_fields,
+        },
+        grant::{
+            NodeGrant_,
+            NodeNamespaceGrant_,
+        },
     },
 };
 
@@ -96,12 +145,22 @@ pub mod graph;
 /// The number of results this query returns. This determines if the return type is
 /// void, `Option`, the value directly, or a `Vec`. It must be a valid value per
 /// the query body (e.g. select can't have `None` res count).
+///
+/// `MaybeOne` and `One` are checked against the schema: a select (or update/delete
+/// with returning) must pin every field of a primary key or unique index with `=`
+/// in its `where` (and join conditions), have an aggregate and no `group by`, or
+/// have `limit 1`. Queries that can't be proven to return at most one row produce a
+/// cargo warning.
 #[derive(Debug, Clone)]
 pub enum QueryResCount {
     None,
     MaybeOne,
     One,
     Many,
+    /// Infer the result count from the query and schema using the rules above. This
+    /// is `None` for queries without outputs, `One` for inserts and aggregates, and
+    /// `MaybeOne` or `Many` otherwise.
+    Auto,
 }
 
 /// See Insert for field descriptions. Call `build()` to get a finished query
@@ -255,6 +314,20 @@ impl SelectBuilder {
         self
     }
 
+    /// Sets `DISTINCT ON`, which returns only the first row (per `order`) of each
+    /// group of rows with equal `clauses`. Any `order` clauses with the same
+    /// expressions must come before other `order` clauses.
+    pub fn distinct_on(mut self, clauses: Vec<Expr>) -> Self {
+        self.q.distinct_on = clauses;
+        self
+    }
+
+    /// Sets `HAVING`. `v` must evaluate to a bool.
+    pub fn having(mut self, v: Expr) -> Self {
+        self.q.having = Some(v);
+        self
+    }
+
     pub fn order(mut self, expr: Expr, order: Order) -> Self {
         self.q.order.push((expr, order));
         self
@@ -265,12 +338,45 @@ impl SelectBuilder {
         self
     }
 
+    /// Make this a keyset (cursor) paginated query, ordered by `order`. The query
+    /// function takes a `page_size` and an optional cursor struct (generated, with
+    /// the types of the order clauses) to start after, and returns the page of rows
+    /// with the cursor for the next page (or `None` if this is the last page). The
+    /// order clauses must be non-null and together uniquely identify a row.
+    ///
+    /// The query must be built with `QueryResCount::Many`.
+    pub fn paginate_by(mut self, order: &[(Expr, Order)]) -> Self {
+        self.q.paginate = Some(order.to_vec());
+        self
+    }
+
+    /// Let the caller choose the order at runtime. See `OrderChoice` for details.
+    pub fn order_choice(mut self, name: impl ToString, variants: Vec<(String, Vec<(Expr, Order)>)>) -> Self {
+        self.q.order_choice = Some(OrderChoice {
+            name: name.to_string(),
+            variants: variants,
+        });
+        self
+    }
+
     /// Sets `LIMIT`. `v` must evaluate to a number.
     pub fn limit(mut self, v: Expr) -> Self {
         self.q.limit = Some(v);
         self
     }
 
+    /// Sets `OFFSET`. `v` must evaluate to a number.
+    pub fn offset(mut self, v: Expr) -> Self {
+        self.q.offset = Some(v);
+        self
+    }
+
+    /// Add a row locking clause like `FOR UPDATE SKIP LOCKED`. See `Lock`.
+    pub fn lock(mut self, lock: Lock) -> Self {
+        self.q.lock.push(lock);
+        self
+    }
+
     /// Produce a migration for use in version pre/post-migration.
     pub fn build_migration(self) -> Select {
         self.q
@@ -503,12 +609,18 @@ pub fn new_select(table: &Table) -> SelectBuilder {
             source: JoinSource::Table(table.clone()),
             alias: None,
         },
+        distinct_on: vec![],
         returning: vec![],
         join: vec![],
         where_: None,
         group: vec![],
+        having: None,
         order: vec![],
         limit: None,
+        offset: None,
+        order_choice: None,
+        paginate: None,
+        lock: vec![],
     } }
 }
 
@@ -517,12 +629,18 @@ pub fn new_select(table: &Table) -> SelectBuilder {
 pub fn new_select_from(source: NamedSelectSource) -> SelectBuilder {
     SelectBuilder { q: Select {
         table: source,
+        distinct_on: vec![],
         returning: vec![],
         join: vec![],
         where_: None,
         group: vec![],
+        having: None,
         order: vec![],
         limit: None,
+        offset: None,
+        order_choice: None,
+        paginate: None,
+        lock: vec![],
     } }
 }
 
@@ -570,19 +688,77 @@ pub struct Version {
 impl Version {
     /// Define a table in this version
     pub fn table(&mut self, schema_id: &str, id: &str) -> Table {
+        self.add_table(None, schema_id, id)
+    }
+
+    fn add_table(&mut self, namespace: Option<&Namespace>, schema_id: &str, id: &str) -> Table {
         let out = Table(Rc::new(Table_ {
             schema_id: SchemaTableId(schema_id.into()),
             id: id.into(),
+            namespace: namespace.cloned(),
         }));
-        if self.schema.insert(GraphId::Table(out.schema_id.clone()), MigrateNode::new(vec![], Node::table(NodeTable_ {
+        let deps = namespace.iter().map(|n| GraphId::Namespace(n.schema_id.clone())).collect();
+        if self.schema.insert(GraphId::Table(out.schema_id.clone()), MigrateNode::new(deps, Node::table(NodeTable_ {
             def: out.clone(),
             fields: vec![],
+            partition_by: None,
+            row_level_security: None,
         }))).is_some() {
             panic!("Table with schema id {} already exists", out.schema_id);
         };
         out
     }
 
+    /// Define a schema (namespace) in this version. Tables (and their indexes) are
+    /// placed in it with `Namespace::table`, otherwise they're in the default schema
+    /// (`public`). Moving a table to a different schema in a later version is
+    /// migrated with `alter table ... set schema`.
+    pub fn namespace(&mut self, schema_id: &str, id: &str) -> Namespace {
+        let out = Namespace(Rc::new(Namespace_ {
+            schema_id: SchemaNamespaceId(schema_id.into()),
+            id: id.into(),
+        }));
+        if self
+            .schema
+            .insert(
+                GraphId::Namespace(out.schema_id.clone()),
+                MigrateNode::new(vec![], Node::namespace(NodeNamespace_ { def: out.clone() })),
+            )
+            .is_some() {
+            panic!("Namespace with schema id {} already exists", out.schema_id);
+        };
+        out
+    }
+
+    /// Require an extension, like `pg_trgm` or `pgcrypto`. It's created (if not
+    /// already installed) before anything that depends on it, see
+    /// `Table::requires`, `FieldBuilder::requires` and `IndexBuilder::requires`.
+    /// Extensions are never dropped, even when no longer required.
+    pub fn extension(&mut self, name: &str) -> Extension {
+        let out = Extension(name.into());
+        if self
+            .schema
+            .insert(
+                GraphId::Extension(out.clone()),
+                MigrateNode::new(vec![], Node::extension(NodeExtension_ { def: out.clone() })),
+            )
+            .is_some() {
+            panic!("Extension {} already required", out);
+        };
+        out
+    }
+
+    /// Define a standalone sequence in this version, for use with `nextval`.
+    pub fn sequence(&self, schema_id: impl ToString, id: impl ToString) -> SequenceBuilder {
+        SequenceBuilder {
+            schema_id: schema_id.to_string(),
+            id: id.to_string(),
+            namespace: None,
+            start: None,
+            increment: None,
+        }
+    }
+
     /// Add a query to execute before before migrating to this schema (applied
     /// immediately before migration).  Note that these may not run on new databases or
     /// if you later delete early migrations, so these should only modify existing data
@@ -599,6 +775,46 @@ impl Version {
     }
 }
 
+impl Namespace {
+    /// Define a table in this schema
+    pub fn table(&self, v: &mut Version, schema_id: &str, id: &str) -> Table {
+        v.add_table(Some(self), schema_id, id)
+    }
+
+    /// Grant usage of this schema to a role, which it needs (on top of the privileges
+    /// granted with `Table::grant`) to access the tables in the schema. The role
+    /// must already exist.
+    pub fn grant_usage(&self, v: &mut Version, role: impl ToString) {
+        let out = NamespaceGrant {
+            namespace: self.clone(),
+            role: role.to_string(),
+        };
+        if v
+            .schema
+            .insert(
+                GraphId::NamespaceGrant(self.schema_id.clone(), out.role.clone()),
+                MigrateNode::new(
+                    vec![GraphId::Namespace(self.schema_id.clone())],
+                    Node::namespace_grant(NodeNamespaceGrant_ { def: out }),
+                ),
+            )
+            .is_some() {
+            panic!("Usage grant to {} on namespace {} already exists", role.to_string(), self);
+        };
+    }
+
+    /// Define a standalone sequence in this schema, see `Version::sequence`.
+    pub fn sequence(&self, schema_id: impl ToString, id: impl ToString) -> SequenceBuilder {
+        SequenceBuilder {
+            schema_id: schema_id.to_string(),
+            id: id.to_string(),
+            namespace: Some(self.clone()),
+            start: None,
+            increment: None,
+        }
+    }
+}
+
 impl Table {
     /// Define a field
     pub fn field(&self, v: &mut Version, schema_id: impl ToString, id: impl ToString, type_: FieldType) -> Field {
@@ -613,7 +829,10 @@ impl Table {
             .insert(
                 GraphId::Field(self.schema_id.clone(), out.schema_id.clone()),
                 MigrateNode::new(
-                    vec![GraphId::Table(self.schema_id.clone())],
+                    [GraphId::Table(self.schema_id.clone())]
+                        .into_iter()
+                        .chain(out.type_.requires.iter().map(|e| GraphId::Extension(e.clone())))
+                        .collect(),
                     Node::field(NodeField_ { def: out.clone() }),
                 ),
             )
@@ -687,6 +906,78 @@ impl Table {
         };
     }
 
+    /// Partition the table by the fields. This must be done when the table is
+    /// created. Primary keys and unique indexes must include all the fields.
+    pub fn partition_by(&self, method: PartitionMethod, fields: &[&Field]) -> PartitionByBuilder {
+        PartitionByBuilder {
+            table: self.clone(),
+            method: method,
+            fields: fields.iter().map(|e| (*e).clone()).collect(),
+            time_function: None,
+        }
+    }
+
+    /// Define a partition of the table, which must be partitioned with
+    /// `partition_by` first. The bound must match the partitioning method.
+    pub fn partition(
+        &self,
+        v: &mut Version,
+        schema_id: impl ToString,
+        id: impl ToString,
+        bound: PartitionBound,
+    ) -> Partition {
+        let partition_by = match v.schema.get(&GraphId::Table(self.schema_id.clone())).map(|n| &n.body) {
+            Some(Node::Table(t)) => t.partition_by.clone(),
+            _ => None,
+        };
+        let Some(partition_by) = partition_by else {
+            panic!(
+                "Partition with schema id {}.{} is of a table that isn't partitioned",
+                self.schema_id,
+                schema_id.to_string()
+            );
+        };
+        let bound_ok = match (&bound, partition_by.method) {
+            (PartitionBound::Range { from, to }, PartitionMethod::Range) => {
+                from.len() == partition_by.fields.len() && to.len() == partition_by.fields.len()
+            },
+            (PartitionBound::List(values), PartitionMethod::List) => !values.is_empty(),
+            (PartitionBound::Hash { modulus, remainder }, PartitionMethod::Hash) => {
+                *modulus > 0 && *remainder >= 0 && remainder < modulus
+            },
+            (PartitionBound::Default, PartitionMethod::Range | PartitionMethod::List) => true,
+            _ => false,
+        };
+        if !bound_ok {
+            panic!(
+                "Partition with schema id {}.{} has a bound that doesn't match the {} partitioning of the table",
+                self.schema_id,
+                schema_id.to_string(),
+                partition_by.method.to_sql()
+            );
+        }
+        let out = Partition(Rc::new(Partition_ {
+            table: self.clone(),
+            partition_by: partition_by,
+            schema_id: SchemaPartitionId(schema_id.to_string()),
+            id: id.to_string(),
+            bound: bound,
+        }));
+        if v
+            .schema
+            .insert(
+                GraphId::Partition(self.schema_id.clone(), out.schema_id.clone()),
+                MigrateNode::new(
+                    vec![GraphId::Table(self.schema_id.clone())],
+                    Node::partition(NodePartition_ { def: out.clone() }),
+                ),
+            )
+            .is_some() {
+            panic!("Partition with schema id {}.{} already exists", self.schema_id, out.schema_id);
+        };
+        out
+    }
+
     /// Define an index
     pub fn index(&self, schema_id: impl ToString, id: impl ToString, fields: &[&Field]) -> IndexBuilder {
         IndexBuilder {
@@ -695,16 +986,152 @@ impl Table {
             id: id.to_string(),
             fields: fields.iter().map(|e| (*e).clone()).collect(),
             unique: false,
+            method: IndexMethod::Btree,
+            opclasses: vec![None; fields.len()],
+            include: vec![],
+            concurrently: false,
+            extensions: vec![],
+        }
+    }
+
+    /// Create the table after the extension. Fields and indexes needing an
+    /// extension can also require it individually, see `FieldBuilder::requires` and
+    /// `IndexBuilder::requires`.
+    pub fn requires(&self, v: &mut Version, extension: &Extension) {
+        match v.schema.get_mut(&GraphId::Table(self.schema_id.clone())) {
+            Some(n) => n.deps.push(GraphId::Extension(extension.clone())),
+            None => panic!("Table {} isn't in this version", self),
+        }
+    }
+
+    /// Enable row-level security on the table. Once enabled, rows are only visible
+    /// to (and modifiable by) roles with a matching policy, except the table owner
+    /// unless `force` is set.
+    pub fn row_level_security(&self, v: &mut Version, force: bool) {
+        match v.schema.get_mut(&GraphId::Table(self.schema_id.clone())).map(|n| &mut n.body) {
+            Some(Node::Table(t)) => {
+                t.row_level_security = Some(RowLevelSecurity { force: force });
+            },
+            _ => panic!("Table {} isn't in this version", self),
+        }
+    }
+
+    /// Define a row-level security policy. By default the policy is permissive,
+    /// applies to all commands and all roles.
+    pub fn policy(&self, schema_id: impl ToString, id: impl ToString) -> PolicyBuilder {
+        PolicyBuilder {
+            table: self.clone(),
+            schema_id: schema_id.to_string(),
+            id: id.to_string(),
+            command: PolicyCommand::All,
+            restrictive: false,
+            roles: vec![],
+            using: None,
+            with_check: None,
+        }
+    }
+
+    /// Grant privileges on the table to a role. The role must already exist, roles
+    /// aren't created by the migrations. Only one grant per role can be defined.
+    pub fn grant(&self, v: &mut Version, role: impl ToString, privileges: &[Privilege]) {
+        let out = Grant {
+            table: self.clone(),
+            role: role.to_string(),
+            privileges: privileges.to_vec(),
+        };
+        if out.privileges.is_empty() {
+            panic!("Grant to {} on table {} has no privileges", out.role, self);
+        }
+        if v
+            .schema
+            .insert(
+                GraphId::Grant(self.schema_id.clone(), out.role.clone()),
+                MigrateNode::new(vec![GraphId::Table(self.schema_id.clone())], Node::grant(NodeGrant_ { def: out })),
+            )
+            .is_some() {
+            panic!("Grant to {} on table {} already exists", role.to_string(), self);
+        };
+    }
+}
+
+pub struct PartitionByBuilder {
+    table: Table,
+    method: PartitionMethod,
+    fields: Vec<Field>,
+    time_function: Option<(String, PartitionInterval)>,
+}
+
+impl PartitionByBuilder {
+    /// Generate a function named `name` that takes a time and creates the partition
+    /// containing it, if it doesn't exist yet. Each partition covers one `interval`
+    /// (in UTC) and is named after the table and the start of the interval, like
+    /// `events_p2024_01`. Only for range partitioning by a single time field.
+    pub fn time_partitions(mut self, interval: PartitionInterval, name: impl ToString) -> Self {
+        self.time_function = Some((name.to_string(), interval));
+        self
+    }
+
+    pub fn build(self, v: &mut Version) {
+        if self.fields.is_empty() {
+            panic!("Partitioning of table {} has no fields", self.table);
+        }
+        for field in &self.fields {
+            if field.table != self.table {
+                panic!("Field {} in partitioning of table {} is in a different table", field, self.table);
+            }
+        }
+        if self.method == PartitionMethod::List && self.fields.len() != 1 {
+            panic!(
+                "Table {} is list partitioned but has {} partitioning fields; list partitioning must have exactly one",
+                self.table,
+                self.fields.len()
+            );
+        }
+        if self.time_function.is_some() {
+            let time_field =
+                self.method == PartitionMethod::Range && self.fields.len() == 1 &&
+                    is_time_type(&self.fields[0].type_.type_.type_.type_);
+            if !time_field {
+                panic!(
+                    "Table {} has time partitions but isn't range partitioned by a single time field",
+                    self.table
+                );
+            }
+        }
+        match v.schema.get_mut(&GraphId::Table(self.table.schema_id.clone())).map(|n| &mut n.body) {
+            Some(Node::Table(t)) => {
+                t.partition_by = Some(PartitionBy {
+                    method: self.method,
+                    fields: self.fields,
+                    time_function: self.time_function,
+                });
+            },
+            _ => panic!("Table {} isn't in this version", self.table),
         }
     }
 }
 
+fn is_time_type(t: &SimpleSimpleType) -> bool {
+    match t {
+        #[cfg(feature = "chrono")]
+        SimpleSimpleType::UtcTimeChrono | SimpleSimpleType::FixedOffsetTimeChrono => true,
+        #[cfg(feature = "jiff")]
+        SimpleSimpleType::UtcTimeJiff => true,
+        _ => false,
+    }
+}
+
 pub struct IndexBuilder {
     table: Table,
     schema_id: String,
     id: String,
     fields: Vec<Field>,
     unique: bool,
+    method: IndexMethod,
+    opclasses: Vec<Option<String>>,
+    include: Vec<Field>,
+    concurrently: bool,
+    extensions: Vec<Extension>,
 }
 
 impl IndexBuilder {
@@ -713,17 +1140,99 @@ impl IndexBuilder {
         self
     }
 
+    /// Use a different index type than the default `btree`.
+    pub fn method(mut self, method: IndexMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Use a non-default operator class for one of the indexed fields, like
+    /// `text_pattern_ops` to support `like` prefix searches.
+    pub fn opclass(mut self, field: &Field, opclass: impl ToString) -> Self {
+        let Some(i) = self.fields.iter().position(|f| f == field) else {
+            panic!(
+                "Operator class for field {} in index with schema id {}.{}, but the field isn't indexed",
+                field,
+                self.table.schema_id,
+                self.schema_id
+            );
+        };
+        self.opclasses[i] = Some(opclass.to_string());
+        self
+    }
+
+    /// Store additional fields in the index (`include (...)`) so queries reading
+    /// them can be answered from the index alone. Only for `btree` and `gist`
+    /// indexes.
+    pub fn include(mut self, fields: &[&Field]) -> Self {
+        self.include.extend(fields.iter().map(|e| (*e).clone()));
+        self
+    }
+
+    /// Create (and later drop) the index with `concurrently`, which doesn't block
+    /// writes to the table. These statements run after the migration transaction
+    /// commits, so nothing else in the migration can rely on the index. If the table
+    /// is created in the same version, the index is created normally.
+    pub fn concurrently(mut self) -> Self {
+        self.concurrently = true;
+        self
+    }
+
+    /// Create the index after the extension, for instance `pg_trgm` for the
+    /// `gin_trgm_ops` operator class.
+    pub fn requires(mut self, extension: &Extension) -> Self {
+        self.extensions.push(extension.clone());
+        self
+    }
+
     pub fn build(self, v: &mut Version) -> Index {
         let mut deps = vec![GraphId::Table(self.table.schema_id.clone())];
-        for field in &self.fields {
+        deps.extend(self.extensions.iter().map(|e| GraphId::Extension(e.clone())));
+        for field in self.fields.iter().chain(self.include.iter()) {
+            if field.table != self.table {
+                panic!(
+                    "Field {} in index with schema id {}.{} is in a different table",
+                    field,
+                    self.table.schema_id,
+                    self.schema_id
+                );
+            }
             deps.push(GraphId::Field(field.table.schema_id.clone(), field.schema_id.clone()));
         }
+        if self.unique && self.method != IndexMethod::Btree {
+            panic!(
+                "Index with schema id {}.{} is unique but {} indexes can't be unique",
+                self.table.schema_id,
+                self.schema_id,
+                self.method.to_sql()
+            );
+        }
+        if self.method == IndexMethod::Hash && self.fields.len() != 1 {
+            panic!(
+                "Index with schema id {}.{} is a hash index but has {} fields; hash indexes must have exactly one",
+                self.table.schema_id,
+                self.schema_id,
+                self.fields.len()
+            );
+        }
+        if !self.include.is_empty() && !matches!(self.method, IndexMethod::Btree | IndexMethod::Gist) {
+            panic!(
+                "Index with schema id {}.{} has include fields but {} indexes don't support include",
+                self.table.schema_id,
+                self.schema_id,
+                self.method.to_sql()
+            );
+        }
         let out = Index(Rc::new(Index_ {
             table: self.table,
             schema_id: SchemaIndexId(self.schema_id),
             id: self.id,
             fields: self.fields,
             unique: self.unique,
+            method: self.method,
+            opclasses: self.opclasses,
+            include: self.include,
+            concurrently: self.concurrently,
         }));
         if v
             .schema
@@ -738,6 +1247,157 @@ impl IndexBuilder {
     }
 }
 
+pub struct PolicyBuilder {
+    table: Table,
+    schema_id: String,
+    id: String,
+    command: PolicyCommand,
+    restrictive: bool,
+    roles: Vec<String>,
+    using: Option<Expr>,
+    with_check: Option<Expr>,
+}
+
+impl PolicyBuilder {
+    /// Limit the policy to one command.
+    pub fn command(mut self, command: PolicyCommand) -> Self {
+        self.command = command;
+        self
+    }
+
+    /// Rows must pass this policy in addition to a permissive one.
+    pub fn restrictive(mut self) -> Self {
+        self.restrictive = true;
+        self
+    }
+
+    /// Apply the policy to this role, can be called multiple times. If not called
+    /// the policy applies to all roles.
+    pub fn to(mut self, role: impl ToString) -> Self {
+        self.roles.push(role.to_string());
+        self
+    }
+
+    /// The condition existing rows must pass to be selected, updated or deleted. This
+    /// can only reference fields of the table, and must not have parameters.
+    pub fn using(mut self, e: Expr) -> Self {
+        self.using = Some(e);
+        self
+    }
+
+    /// The condition new rows must pass to be inserted or be the result of an update.
+    pub fn with_check(mut self, e: Expr) -> Self {
+        self.with_check = Some(e);
+        self
+    }
+
+    pub fn build(self, v: &mut Version) -> Policy {
+        if self.using.is_none() && self.with_check.is_none() {
+            panic!("Policy with schema id {}.{} has no conditions", self.table.schema_id, self.schema_id);
+        }
+        match self.command {
+            PolicyCommand::Select | PolicyCommand::Delete if self.with_check.is_some() => {
+                panic!(
+                    "Policy with schema id {}.{} is for {} but has a with check condition, which only applies to inserts and updates",
+                    self.table.schema_id,
+                    self.schema_id,
+                    self.command.to_sql()
+                );
+            },
+            PolicyCommand::Insert if self.using.is_some() => {
+                panic!(
+                    "Policy with schema id {}.{} is for insert but has a using condition, which doesn't apply to inserts",
+                    self.table.schema_id,
+                    self.schema_id
+                );
+            },
+            _ => { },
+        }
+        let mut deps = vec![GraphId::Table(self.table.schema_id.clone())];
+        let mut fields = vec![];
+        for e in self.using.iter().chain(self.with_check.iter()) {
+            policy_fields(e, &mut fields);
+        }
+        for field in fields {
+            if field.table != self.table {
+                panic!(
+                    "Field {} in policy with schema id {}.{} is in a different table",
+                    field,
+                    self.table.schema_id,
+                    self.schema_id
+                );
+            }
+            deps.push(GraphId::Field(field.table.schema_id.clone(), field.schema_id.clone()));
+        }
+        let out = Policy(Rc::new(Policy_ {
+            table: self.table,
+            schema_id: SchemaPolicyId(self.schema_id),
+            id: self.id,
+            command: self.command,
+            restrictive: self.restrictive,
+            roles: self.roles,
+            using: self.using,
+            with_check: self.with_check,
+        }));
+        if v
+            .schema
+            .insert(
+                GraphId::Policy(out.table.schema_id.clone(), out.schema_id.clone()),
+                MigrateNode::new(deps, Node::policy(NodePolicy_ { def: out.clone() })),
+            )
+            .is_some() {
+            panic!("Policy with schema id {}.{} already exists", out.table.schema_id, out.schema_id);
+        };
+        out
+    }
+}
+
+pub struct SequenceBuilder {
+    schema_id: String,
+    id: String,
+    namespace: Option<Namespace>,
+    start: Option<i64>,
+    increment: Option<i64>,
+}
+
+impl SequenceBuilder {
+    /// The first value, 1 by default.
+    pub fn start(mut self, start: i64) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// The step between values, 1 by default.
+    pub fn increment(mut self, increment: i64) -> Self {
+        if increment == 0 {
+            panic!("Sequence with schema id {} has an increment of 0", self.schema_id);
+        }
+        self.increment = Some(increment);
+        self
+    }
+
+    pub fn build(self, v: &mut Version) -> Sequence {
+        let out = Sequence(Rc::new(Sequence_ {
+            schema_id: SchemaSequenceId(self.schema_id),
+            id: self.id,
+            namespace: self.namespace,
+            start: self.start,
+            increment: self.increment,
+        }));
+        let deps = out.namespace.iter().map(|n| GraphId::Namespace(n.schema_id.clone())).collect();
+        if v
+            .schema
+            .insert(
+                GraphId::Sequence(out.schema_id.clone()),
+                MigrateNode::new(deps, Node::sequence(NodeSequence_ { def: out.clone() })),
+            )
+            .is_some() {
+            panic!("Sequence with schema id {} already exists", out.schema_id);
+        };
+        out
+    }
+}
+
 /// Generate Rust code for migrations and queries.
 ///
 /// # Arguments
@@ -759,22 +1419,34 @@ impl IndexBuilder {
 /// * Error - a list of validation or generation errors that occurred
 pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Query>) -> Result<(), Vec<String>> {
     {
-        let mut prev_relations: HashMap<&String, String> = HashMap::new();
+        let mut prev_relations: HashMap<String, String> = HashMap::new();
         let mut prev_fields = HashMap::new();
         let mut prev_constraints = HashMap::new();
+        let mut prev_policies = HashMap::new();
+        let mut prev_namespaces: HashMap<&String, SchemaNamespaceId> = HashMap::new();
+        let mut prev_namespace_ids: HashMap<SchemaNamespaceId, String> = HashMap::new();
+        let mut prev_relation_namespaces: HashMap<String, Option<SchemaNamespaceId>> = HashMap::new();
         for (v_i, v) in &versions {
             let mut relations = HashMap::new();
             let mut fields = HashMap::new();
             let mut constraints = HashMap::new();
+            let mut policies = HashMap::new();
+            let mut namespaces = HashMap::new();
+            let mut namespace_ids = HashMap::new();
+            let mut relation_namespaces = HashMap::new();
             for n in v.schema.values() {
                 match &n.body {
                     Node::Table(t) => {
-                        let id = &t.def.id;
+                        let id = t.def.sql_id();
                         let comp_id = format!("table {}", t.def.schema_id);
-                        if relations.insert(id, comp_id.clone()).is_some() {
+                        relation_namespaces.insert(
+                            comp_id.clone(),
+                            t.def.namespace.as_ref().map(|n| n.schema_id.clone()),
+                        );
+                        if relations.insert(id.clone(), comp_id.clone()).is_some() {
                             panic!("Duplicate table id {} -- {}", t.def.id, t.def);
                         }
-                        if let Some(schema_id) = prev_relations.get(id) {
+                        if let Some(schema_id) = prev_relations.get(&id) {
                             if schema_id != &comp_id {
                                 panic!(
                                     "Table {} id in version {} swapped with another relation since previous version; unsupported",
@@ -815,9 +1487,9 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                         }
                     },
                     Node::Index(i) => {
-                        let id = &i.def.id;
+                        let id = i.def.sql_id();
                         let comp_id = format!("index {}", i.def.schema_id);
-                        if relations.insert(id, comp_id.clone()).is_some() {
+                        if relations.insert(id.clone(), comp_id.clone()).is_some() {
                             panic!("Duplicate index id {} -- {}", i.def.id, i.def);
                         }
                         if let Some(schema_id) = prev_relations.get(&id) {
@@ -830,18 +1502,113 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                             }
                         }
                     },
-                }
-            }
-            prev_relations = relations;
-            prev_fields = fields;
-            prev_constraints = constraints;
-        }
-    }
-    let mut errs = Errs::new();
-    let mut migrations = vec![];
+                    Node::Sequence(s) => {
+                        let id = s.def.sql_id();
+                        let comp_id = format!("sequence {}", s.def.schema_id);
+                        relation_namespaces.insert(
+                            comp_id.clone(),
+                            s.def.namespace.as_ref().map(|n| n.schema_id.clone()),
+                        );
+                        if relations.insert(id.clone(), comp_id.clone()).is_some() {
+                            panic!("Duplicate sequence id {} -- {}", s.def.id, s.def);
+                        }
+                        if let Some(schema_id) = prev_relations.get(&id) {
+                            if schema_id != &comp_id {
+                                panic!(
+                                    "Sequence {} id in version {} swapped with another relation since previous version; unsupported",
+                                    s.def,
+                                    v_i
+                                );
+                            }
+                        }
+                    },
+                    Node::Namespace(n) => {
+                        let id = &n.def.id;
+                        namespace_ids.insert(n.def.schema_id.clone(), n.def.id.clone());
+                        if namespaces.insert(id, n.def.schema_id.clone()).is_some() {
+                            panic!("Duplicate namespace id {} -- {}", n.def.id, n.def);
+                        }
+                        if let Some(schema_id) = prev_namespaces.get(&id) {
+                            if schema_id != &n.def.schema_id {
+                                panic!(
+                                    "Namespace {} id in version {} swapped with another namespace since previous version; unsupported",
+                                    n.def,
+                                    v_i
+                                );
+                            }
+                        }
+                    },
+                    Node::Partition(p) => {
+                        let id = p.def.sql_id();
+                        let comp_id = format!("partition {}.{}", p.def.table.schema_id, p.def.schema_id);
+                        if relations.insert(id.clone(), comp_id.clone()).is_some() {
+                            panic!("Duplicate partition id {} -- {}", p.def.id, p.def);
+                        }
+                        if let Some(schema_id) = prev_relations.get(&id) {
+                            if schema_id != &comp_id {
+                                panic!(
+                                    "Partition {} id in version {} swapped with another relation since previous version; unsupported",
+                                    p.def,
+                                    v_i
+                                );
+                            }
+                        }
+                    },
+                    Node::Policy(p) => {
+                        let id = (&p.def.table.schema_id, &p.def.id);
+                        if policies.insert(id, p.def.schema_id.clone()).is_some() {
+                            panic!("Duplicate policy id {} -- {}", p.def.id, p.def);
+                        }
+                        if let Some(schema_id) = prev_policies.get(&id) {
+                            if schema_id != &p.def.schema_id {
+                                panic!(
+                                    "Policy {} id in version {} swapped with another policy since previous version; unsupported",
+                                    p.def,
+                                    v_i
+                                );
+                            }
+                        }
+                    },
+                    Node::Extension(_) | Node::Grant(_) | Node::NamespaceGrant(_) => { },
+                }
+            }
+            for (relation, namespace) in &relation_namespaces {
+                let Some(Some(prev_namespace)) = prev_relation_namespaces.get(relation) else {
+                    continue;
+                };
+                if namespace.as_ref() == Some(prev_namespace) {
+                    continue;
+                }
+                let prev_id = prev_namespace_ids.get(prev_namespace);
+                let id = namespace_ids.get(prev_namespace);
+                if let (Some(prev_id), Some(id)) = (prev_id, id) {
+                    if prev_id != id {
+                        panic!(
+                            "The {} in version {} moves out of namespace {} which is renamed in the same version; unsupported, do these in separate versions",
+                            relation,
+                            v_i,
+                            prev_namespace
+                        );
+                    }
+                }
+            }
+            prev_relations = relations;
+            prev_namespaces = namespaces;
+            prev_namespace_ids = namespace_ids;
+            prev_relation_namespaces = relation_namespaces;
+            prev_fields = fields;
+            prev_constraints = constraints;
+            prev_policies = policies;
+        }
+    }
+    let mut errs = Errs::new();
+    let mut migrations = vec![];
+    let mut concurrent_migrations = vec![];
     let mut prev_version: Option<Version> = None;
     let mut prev_version_i: Option<i64> = None;
     let mut field_lookup = HashMap::new();
+    let mut unique_keys: HashMap<Table, Vec<Vec<Field>>> = HashMap::new();
+    let mut primary_keys: HashMap<Table, Vec<Field>> = HashMap::new();
     for (version_i, version) in versions {
         let path = rpds::vector![format!("Migration to {}", version_i)];
         let mut migration = vec![];
@@ -851,9 +1618,11 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
             path: &rpds::Vector<String>,
             migration: &mut Vec<TokenStream>,
             field_lookup: &HashMap<Table, HashMap<Field, Type>>,
+            unique_keys: &HashMap<Table, Vec<Vec<Field>>>,
+            primary_keys: &HashMap<Table, Vec<Field>>,
             q: &dyn QueryBody,
         ) {
-            let mut qctx = PgQueryCtx::new(errs.clone(), &field_lookup);
+            let mut qctx = PgQueryCtx::new(errs.clone(), &field_lookup, &unique_keys, &primary_keys);
             let e_res = q.build(&mut qctx, path, QueryResCount::None);
             if !qctx.rust_args.is_empty() {
                 qctx.errs.err(path, format!("Migration statements can't receive arguments"));
@@ -875,12 +1644,16 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                 &path.push_back(format!("Pre-migration statement {}", i)),
                 &mut migration,
                 &field_lookup,
+                &unique_keys,
+                &primary_keys,
                 q.as_ref(),
             );
         }
 
         // Prep for current version
         field_lookup.clear();
+        unique_keys.clear();
+        primary_keys.clear();
         let version_i = version_i as i64;
         if let Some(i) = prev_version_i {
             if version_i != i as i64 + 1 {
@@ -908,6 +1681,17 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                     let table = field_lookup.get_mut(&f.def.table).unwrap();
                     table.insert(f.def.clone(), f.def.type_.type_.clone());
                 },
+                Node::Constraint(c) => {
+                    if let ConstraintType::PrimaryKey(pk) = &c.def.type_ {
+                        unique_keys.entry(c.def.table.clone()).or_insert_with(Vec::new).push(pk.fields.clone());
+                        primary_keys.insert(c.def.table.clone(), pk.fields.clone());
+                    }
+                },
+                Node::Index(i) => {
+                    if i.def.unique {
+                        unique_keys.entry(i.def.table.clone()).or_insert_with(Vec::new).push(i.def.fields.clone());
+                    }
+                },
                 _ => { },
             };
         }
@@ -916,7 +1700,7 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
         {
             let mut state = PgMigrateCtx::new(errs.clone());
             crate::graphmigrate::migrate(&mut state, prev_version.take().map(|s| s.schema), &version.schema);
-            for statement in &state.statements {
+            for statement in state.statements.iter().chain(state.final_statements.iter()) {
                 migration.push(quote!{
                     {
                         let query = #statement;
@@ -924,6 +1708,60 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                     };
                 });
             }
+            if !state.concurrent_statements.is_empty() {
+                let mut statements = vec![];
+                for statement in &state.concurrent_statements {
+                    let mut steps = vec![];
+                    if let Some(index) = &statement.creates_index {
+                        let drop_query = format!("drop index concurrently if exists {}", index);
+                        steps.push(quote!{
+                            {
+                                let query =
+                                    "select not indisvalid from pg_index where indexrelid = to_regclass($1)";
+                                let invalid =
+                                    db.query_opt(query, &[&#index]).await.to_good_error_query(query)?.map(|r| {
+                                        let invalid: bool = r.get(0usize);
+                                        invalid
+                                    });
+                                if invalid == Some(true) {
+                                    let query = #drop_query;
+                                    db.execute(query, &[]).await.to_good_error_query(query)?;
+                                }
+                            }
+                        });
+                    }
+                    let query = &statement.statement;
+                    steps.push(quote!{
+                        {
+                            let query = #query;
+                            db.execute(query, &[]).await.to_good_error_query(query)?;
+                        }
+                    });
+                    match &statement.index_table {
+                        Some(table) => statements.push(quote!{
+                            {
+                                let query = "select to_regclass($1) is not null";
+                                let exists: bool =
+                                    db.query_one(query, &[&#table]).await.to_good_error_query(query)?.get(0usize);
+                                if exists {
+                                    #(#steps) *
+                                }
+                            }
+                        }),
+                        None => statements.extend(steps),
+                    }
+                }
+                // Tracked separately from the main version so they're retried on the next
+                // migration if they fail after the transaction commits
+                concurrent_migrations.push(quote!{
+                    if concurrent_version < #version_i {
+                        #(#statements) * {
+                            let query = "update __good_version set concurrent_version = $1";
+                            db.execute(query, &[& #version_i]).await.to_good_error_query(query)?;
+                        }
+                    }
+                });
+            }
         }
 
         // Post-migration
@@ -933,6 +1771,8 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                 &path.push_back(format!("Post-migration statement {}", i)),
                 &mut migration,
                 &field_lookup,
+                &unique_keys,
+                &primary_keys,
                 q.as_ref(),
             );
         }
@@ -949,19 +1789,162 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
         prev_version_i = Some(version_i);
     }
 
-    // Generate queries
     let mut db_others = Vec::new();
+
+    // Generate time partition functions
+    if let Some(version) = &prev_version {
+        for n in version.schema.values() {
+            let Node::Table(t) = &n.body else {
+                continue;
+            };
+            let Some(PartitionBy { fields, time_function: Some((name, interval)), .. }) = &t.partition_by else {
+                continue;
+            };
+            let ident = format_ident!("{}", name);
+            let arg_type = to_rust_types(&fields[0].type_.type_.type_.type_).arg_type;
+            let unit = interval.unit();
+            let start = format!("date_trunc('{}', $1, 'UTC')", unit);
+            let bounds_query =
+                format!(
+                    "select to_char({} at time zone 'UTC', '{}'), {}::text, ({} + interval '1 {}')::text",
+                    start,
+                    interval.suffix_format(),
+                    start,
+                    start,
+                    unit
+                );
+            let mut head = Tokens::new();
+            head.s("create table if not exists");
+            if let Some(namespace) = &t.def.namespace {
+                head.id(&namespace.id).s(".");
+            }
+            let head = format!("{} \"{}_p", head.to_string(), t.def.id.replace("\"", "\"\""));
+            let mid = format!("\" partition of {} for values from ('", t.def.sql_id());
+            db_others.push(quote!{
+                pub async fn #ident(db: &mut impl tokio_postgres::GenericClient, time: #arg_type) -> Result <(),
+                GoodError > {
+                    let query = #bounds_query;
+                    let r = db.query_one(query, &[&time]).await.to_good_error_query(query)?;
+                    let suffix: String = r.get(0usize);
+                    let from: String = r.get(1usize);
+                    let to: String = r.get(2usize);
+                    let query =[
+                        #head,
+                        suffix.as_str(),
+                        #mid,
+                        from.as_str(),
+                        "') to ('",
+                        to.as_str(),
+                        "')"
+                    ].concat();
+                    db.execute(query.as_str(), &[]).await.to_good_error_query(&query)?;
+                    Ok(())
+                }
+            });
+        }
+    }
+
+    // Generate queries
     {
         let mut res_type_idents: HashMap<String, Ident> = HashMap::new();
+        let mut cursor_type_idents: HashMap<String, Ident> = HashMap::new();
+        let mut order_choice_defs: HashMap<String, String> = HashMap::new();
         for q in queries {
             let path = rpds::vector![format!("Query {}", q.name)];
-            let mut ctx = PgQueryCtx::new(errs.clone(), &field_lookup);
-            let res = QueryBody::build(q.body.as_ref(), &mut ctx, &path, q.res_count.clone());
+            let mut ctx = PgQueryCtx::new(errs.clone(), &field_lookup, &unique_keys, &primary_keys);
+            let mut res_count_warning = None;
+            let res_count = match (&q.res_count, q.body.infer_res_count(&ctx)) {
+                (QueryResCount::Auto, Some((inferred, _))) => inferred,
+                (QueryResCount::Auto, None) => {
+                    ctx.errs.err(&path, format!("The result count of this query can't be inferred, specify it explicitly"));
+                    QueryResCount::Many
+                },
+                (QueryResCount::MaybeOne | QueryResCount::One, Some((QueryResCount::Many, Some(why)))) => {
+                    res_count_warning =
+                        Some(
+                            format!(
+                                "Query declares {:?} results but may return multiple rows ({}); constrain all fields of a unique key with `=` or use `QueryResCount::Many`",
+                                q.res_count,
+                                why
+                            ),
+                        );
+                    q.res_count.clone()
+                },
+                (res_count, _) => res_count.clone(),
+            };
+            let err_count = ctx.errs.err_count();
+            let res = QueryBody::build(q.body.as_ref(), &mut ctx, &path, res_count.clone());
+
+            // Inference assumes the query is valid, so skip the warning if it isn't (e.g. it
+            // references fields of an aliased table directly)
+            if let Some(w) = res_count_warning {
+                if ctx.errs.err_count() == err_count {
+                    ctx.errs.warn(&path, w);
+                }
+            }
             let ident = format_ident!("{}", q.name);
-            let q_text = res.1.to_string();
+            let mut q_text = res.1.to_string().to_token_stream();
+            let base_arg_count = ctx.rust_args.len();
+            if let Some((enum_name, variants)) = q.body.build_order_choices(&mut ctx, &path, res_count.clone()) {
+                let path = path.push_back(format!("Order choice {}", enum_name));
+                if variants.is_empty() {
+                    ctx.errs.err(&path, format!("Order choice has no variants"));
+                }
+                if ctx.rust_args.len() != base_arg_count {
+                    ctx.errs.err(&path, format!("Order choice variants can't introduce new parameters"));
+                }
+                if ctx.rust_arg_lookup.contains_key("order") || ctx.tuple_arg_lookup.contains_key("order") {
+                    ctx
+                        .errs
+                        .err(&path, format!("Query has a parameter named `order` which conflicts with the order choice"));
+                }
+                let enum_ident = format_ident!("{}", enum_name);
+                let variant_idents = variants.iter().map(|v| format_ident!("{}", v.0)).collect::<Vec<_>>();
+                let variant_texts = variants.iter().map(|v| v.2.to_string()).collect::<Vec<_>>();
+                let enum_def = quote!{
+                    #[derive(Clone, Copy, Debug, PartialEq, Eq)] pub enum #enum_ident {
+                        #(#variant_idents,) *
+                    }
+                };
+                match order_choice_defs.entry(enum_name.clone()) {
+                    std::collections::hash_map::Entry::Occupied(e) => {
+                        if *e.get() != enum_def.to_string() {
+                            ctx
+                                .errs
+                                .err(
+                                    &path,
+                                    format!("Order choice {} was already defined with different variants", enum_name),
+                                );
+                        }
+                    },
+                    std::collections::hash_map::Entry::Vacant(e) => {
+                        e.insert(enum_def.to_string());
+                        db_others.push(enum_def);
+                    },
+                }
+                ctx.rust_args.push(quote!(order: #enum_ident));
+                q_text = quote!(match order {
+                    #(#enum_ident:: #variant_idents => #variant_texts,) *
+                });
+            }
+            let mut res_type = res.0.0;
+            let mut cursor_type = vec![];
+            let paginated = q.body.build_paginated(&mut ctx, &path);
+            if let Some(paginated) = &paginated {
+                cursor_type = paginated.res_type.0.clone();
+                res_type = cursor_type.drain(..cursor_type.len().saturating_sub(paginated.cursor_len)).collect();
+                if ctx.rust_arg_lookup.contains_key("cursor") || ctx.tuple_arg_lookup.contains_key("cursor") {
+                    ctx
+                        .errs
+                        .err(&path, format!("Query has a parameter named `cursor` which conflicts with the pagination cursor"));
+                }
+            }
+            let cursor_start = res_type.len();
             let args = ctx.rust_args.split_off(0);
             let args_forward = ctx.query_args.split_off(0);
             drop(ctx);
+            let mut cursor_fields = vec![];
+            let mut cursor_unforward = vec![];
             let (res_ident, res_def, unforward_res) = {
                 fn convert_one_res(
                     errs: &mut Errs,
@@ -1028,8 +2011,17 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                     })));
                 }
 
-                if res.0.0.len() == 1 {
-                    let e = &res.0.0[0];
+                let cursor_path = path.push_back(format!("Paginate"));
+                for (i, (k, v)) in cursor_type.iter().enumerate() {
+                    let Some((_, type_ident, unforward)) = convert_one_res(&mut errs, &cursor_path, cursor_start + i, k, v) else {
+                        continue;
+                    };
+                    cursor_fields.push(type_ident);
+                    cursor_unforward.push(unforward);
+                }
+
+                if res_type.len() == 1 {
+                    let e = &res_type[0];
                     let (_, type_ident, unforward) = match convert_one_res(&mut errs, &path, 0, &e.0, &e.1) {
                         None => {
                             continue;
@@ -1040,7 +2032,7 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                 } else {
                     let mut fields = vec![];
                     let mut unforward_fields = vec![];
-                    for (i, (k, v)) in res.0.0.into_iter().enumerate() {
+                    for (i, (k, v)) in res_type.into_iter().enumerate() {
                         let (k_ident, type_ident, unforward) = match convert_one_res(&mut errs, &path, i, &k, &v) {
                             Some(x) => x,
                             None => continue,
@@ -1078,7 +2070,82 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                 }
             };
             let db_arg = quote!(db: &mut impl tokio_postgres::GenericClient);
-            match q.res_count {
+            if let Some(paginated) = paginated {
+                let path = path.push_back(format!("Paginate"));
+                match res_count {
+                    QueryResCount::Many => { },
+                    _ => {
+                        errs.err(&path, format!("Paginated queries must return `QueryResCount::Many`"));
+                    },
+                }
+                let cursor_body = quote!((#(pub #cursor_fields,) *));
+                let cursor_type_count = cursor_type_idents.len();
+                let cursor_ident = match cursor_type_idents.entry(cursor_body.to_string()) {
+                    std::collections::hash_map::Entry::Occupied(e) => e.get().clone(),
+                    std::collections::hash_map::Entry::Vacant(e) => {
+                        let ident = format_ident!("DbCursor{}", cursor_type_count);
+                        e.insert(ident.clone());
+                        db_others.push(quote!(#[derive(Clone, Debug)] pub struct #ident #cursor_body;));
+                        ident
+                    },
+                };
+                let cursor_idents = (0 .. cursor_fields.len()).map(|i| format_ident!("cursor_{}", i)).collect::<Vec<_>>();
+                let first_text = paginated.first.to_string();
+                let next_text = paginated.next.to_string();
+                let first_args =
+                    args
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| !paginated.cursor_args.contains(i))
+                        .map(|(_, a)| a)
+                        .collect::<Vec<_>>();
+                let first_args_forward =
+                    args_forward
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| !paginated.cursor_args.contains(i))
+                        .map(|(_, a)| a)
+                        .collect::<Vec<_>>();
+                if let Some(res_def) = res_def {
+                    db_others.push(res_def);
+                }
+                db_others.push(quote!{
+                    pub async fn #ident(
+                        #db_arg,
+                        #(#first_args,) * cursor: Option < #cursor_ident >
+                    ) -> Result <(Vec < #res_ident >, Option < #cursor_ident >),
+                    GoodError > {
+                        let mut out = vec![];
+                        let mut next = None;
+                        let mut more = false;
+                        let query;
+                        let rows = match cursor {
+                            None => {
+                                query = #first_text;
+                                db.query(query, &[#(& #first_args_forward,) *]).await.to_good_error_query(query)?
+                            },
+                            Some(#cursor_ident(#(#cursor_idents,) *)) => {
+                                query = #next_text;
+                                db.query(query, &[#(& #args_forward,) *]).await.to_good_error_query(query)?
+                            },
+                        };
+                        for r in rows {
+                            if (out.len() as i64) >= page_size {
+                                more = true;
+                                break;
+                            }
+                            out.push(#unforward_res);
+                            next = Some(#cursor_ident(#(#cursor_unforward,) *));
+                        }
+                        if !more {
+                            next = None;
+                        }
+                        Ok((out, next))
+                    }
+                });
+                continue;
+            }
+            match res_count {
                 QueryResCount::None => {
                     db_others.push(quote!{
                         pub async fn #ident(#db_arg, #(#args,) *) -> Result <(),
@@ -1134,12 +2201,141 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                         }
                     });
                 },
+                QueryResCount::Auto => unreachable!(),
             }
         }
     }
 
     // Compile, output
     let last_version_i = prev_version_i.unwrap() as i64;
+    // The concurrent version column is only needed (and added) if there are
+    // statements that can't run in the transaction
+    let (migrated_version, version_prelude, version_insert_query, version_lock_query) =
+        if concurrent_migrations.is_empty() {
+            (
+                quote!(_),
+                quote!(),
+                "insert into __good_version (rid, version, lock) values (0, -1, 0) on conflict do nothing;",
+                "update __good_version set lock = 1 where rid = 0 and lock = 0 returning version, version as concurrent_version",
+            )
+        } else {
+            (
+                quote!(concurrent_version),
+                quote!{
+                    {
+                        let query = "alter table __good_version add column if not exists concurrent_version bigint;";
+                        db.execute(query, &[]).await.to_good_error_query(query)?;
+                    }
+                },
+                "insert into __good_version (rid, version, lock, concurrent_version) values (0, -1, 0, -1) on conflict do nothing;",
+                "update __good_version set lock = 1 where rid = 0 and lock = 0 returning version, coalesce(concurrent_version, version) as concurrent_version",
+            )
+        };
+    let migrate_body = quote!{
+        #version_prelude {
+            let query = #version_insert_query;
+            db.execute(query, &[]).await.to_good_error_query(query)?;
+        }
+        loop {
+            let txn = db.transaction().await.to_good_error(|| "Failed to start transaction".to_string())?;
+            match(|| {
+                async {
+                    let query = #version_lock_query;
+                    let (version, concurrent_version) = match txn.query_opt(query, &[]).await.to_good_error_query(query)? {
+                        Some(r) => {
+                            let ver: i64 = r.get("version");
+                            let concurrent_ver: i64 = r.get("concurrent_version");
+                            (ver, concurrent_ver)
+                        },
+                        None => {
+                            return Ok(None);
+                        },
+                    };
+                    if version > #last_version_i {
+                        return Err(
+                            GoodError(
+                                format!(
+                                    "The latest known version is {}, but the schema is at unknown version {}",
+                                    #last_version_i,
+                                    version
+                                ),
+                            ),
+                        );
+                    }
+                    #(#migrations) * {
+                        let query = "update __good_version set version = $1, lock = 0";
+                        txn.execute(query, &[& #last_version_i]).await.to_good_error_query(query) ?;
+                    }
+                    let out: Result < Option < i64 >,
+                    GoodError >= Ok(Some(concurrent_version));
+                    out
+                }
+            })().await {
+                Err(e) => {
+                    match txn.rollback().await {
+                        Err(e1) => {
+                            return Err(
+                                GoodError(
+                                    format!(
+                                        "{}\n\nRolling back the transaction due to the above also failed: {}",
+                                        e,
+                                        e1
+                                    ),
+                                ),
+                            );
+                        },
+                        Ok(_) => {
+                            return Err(GoodError(e.to_string()));
+                        },
+                    };
+                }
+                Ok(migrated) => {
+                    match txn.commit().await {
+                        Err(e) => {
+                            return Err(GoodError(format!("Error committing the migration transaction: {}", e)));
+                        },
+                        Ok(_) => {
+                            match migrated {
+                                Some(#migrated_version) => {
+                                    // Statements that can't run in a transaction
+                                    #(#concurrent_migrations) * return Ok(())
+                                },
+                                None => {
+                                    tokio::time::sleep(tokio::time::Duration::from_millis(5 * 1000)).await;
+                                },
+                            }
+                        },
+                    };
+                }
+            }
+        }
+    };
+    let migrate_body = if concurrent_migrations.is_empty() {
+        migrate_body
+    } else {
+        // The concurrent statements run after the transaction commits, so hold a session
+        // lock until they're done to keep other migrations from racing them. It's polled
+        // (like the version lock) rather than waited on, since a waiting statement would
+        // hold a snapshot that concurrent index builds wait for.
+        quote!{
+            loop {
+                let query = "select pg_try_advisory_lock('__good_version'::regclass::oid::bigint)";
+                let locked: bool = db.query_one(query, &[]).await.to_good_error_query(query)?.get(0usize);
+                if locked {
+                    break;
+                }
+                tokio::time::sleep(tokio::time::Duration::from_millis(5 * 1000)).await;
+            }
+            let res: Result<(), GoodError> = async {
+                #migrate_body
+            }.await;
+            let query = "select pg_advisory_unlock('__good_version'::regclass::oid::bigint)";
+            let unlocked = db.execute(query, &[]).await.to_good_error_query(query);
+            res?;
+            unlocked?;
+            return Ok(());
+        }
+    };
     let tokens = quote!{
         use good_ormning_runtime::GoodError;
         use good_ormning_runtime::ToGoodError;
@@ -1150,80 +2346,7 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                     "create table if not exists __good_version (rid int primary key, version bigint not null, lock int not null);";
                 db.execute(query, &[]).await.to_good_error_query(query)?;
             }
-            {
-                let query =
-                    "insert into __good_version (rid, version, lock) values (0, -1, 0) on conflict do nothing;";
-                db.execute(query, &[]).await.to_good_error_query(query)?;
-            }
-            loop {
-                let txn = db.transaction().await.to_good_error(|| "Failed to start transaction".to_string())?;
-                match(|| {
-                    async {
-                        let query =
-                            "update __good_version set lock = 1 where rid = 0 and lock = 0 returning version";
-                        let version = match txn.query_opt(query, &[]).await.to_good_error_query(query)? {
-                            Some(r) => {
-                                let ver: i64 = r.get("version");
-                                ver
-                            },
-                            None => {
-                                return Ok(false);
-                            },
-                        };
-                        if version > #last_version_i {
-                            return Err(
-                                GoodError(
-                                    format!(
-                                        "The latest known version is {}, but the schema is at unknown version {}",
-                                        #last_version_i,
-                                        version
-                                    ),
-                                ),
-                            );
-                        }
-                        #(#migrations) * {
-                            let query = "update __good_version set version = $1, lock = 0";
-                            txn.execute(query, &[& #last_version_i]).await.to_good_error_query(query) ?;
-                        }
-                        let out: Result < bool,
-                        GoodError >= Ok(true);
-                        out
-                    }
-                })().await {
-                    Err(e) => {
-                        match txn.rollback().await {
-                            Err(e1) => {
-                                return Err(
-                                    GoodError(
-                                        format!(
-                                            "{}\n\nRolling back the transaction due to the above also failed: {}",
-                                            e,
-                                            e1
-                                        ),
-                                    ),
-                                );
-                            },
-                            Ok(_) => {
-                                return Err(GoodError(e.to_string()));
-                            },
-                        };
-                    }
-                    Ok(migrated) => {
-                        match txn.commit().await {
-                            Err(e) => {
-                                return Err(GoodError(format!("Error committing the migration transaction: {}", e)));
-                            },
-                            Ok(_) => {
-                                if migrated {
-                                    return Ok(())
-                                } else {
-                                    tokio::time::sleep(tokio::time::Duration::from_millis(5 * 1000)).await;
-                                }
-                            },
-                        };
-                    }
-                }
-            }
+            #migrate_body
         }
         #(#db_others) *
     };
@@ -1261,18 +2384,50 @@ mod test {
     };
     use crate::pg::{
         new_select,
+        new_select_from,
         QueryResCount,
         new_insert,
     };
     use super::{
-        schema::field::{
-            field_str,
-            field_auto,
-            field_i32,
+        schema::{
+            field::{
+                field_str,
+                field_auto,
+                field_i32,
+                field_i64,
+                field_bytes,
+                Identity,
+            },
+            index::IndexMethod,
+            partition::{
+                PartitionBound,
+                PartitionMethod,
+            },
+            policy::PolicyCommand,
+            constraint::{
+                ConstraintType,
+                PrimaryKeyDef,
+            },
         },
         generate,
         Version,
-        query::expr::Expr,
+        query::{
+            expr::{
+                BinOp,
+                Expr,
+                ExprValName,
+            },
+            functions,
+            select::{
+                JoinSource,
+                NamedSelectSource,
+                Lock,
+                LockStrength,
+                LockWait,
+                Order,
+            },
+        },
+        types::SimpleSimpleType,
     };
 
     #[test]
@@ -1383,4 +2538,397 @@ mod test {
             ).is_err()
         );
     }
+
+    #[test]
+    fn test_select_ungrouped_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zQ7R1J9TE", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_str().build());
+        let hizat2 = bananna.field(&mut v, "z3CRAVV3M", "hizat2", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![
+                    new_select(&bananna)
+                        .return_field(&hizat)
+                        .return_field(&hizat2)
+                        .group(vec![Expr::Field(hizat.clone())])
+                        .build_query("x", QueryResCount::Many)
+                ],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_select_auto_arithmetic() {
+        let mut v = Version::default();
+        let bananna = v.table("zK4D9WM2C", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_auto().build());
+        generate(
+            &PathBuf::from_str("/dev/null").unwrap(),
+            vec![(0usize, v)],
+            vec![
+                new_select(&bananna)
+                    .return_named("x", Expr::BinOp {
+                        left: Box::new(Expr::Field(hizat.clone())),
+                        op: BinOp::Plus,
+                        right: Box::new(Expr::LitI64(1)),
+                    })
+                    .build_query("x", QueryResCount::Many)
+            ],
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_select_grouped_primary_key() {
+        let mut v = Version::default();
+        let bananna = v.table("zK4D9WM2C", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let hizat2 = bananna.field(&mut v, "z3CRAVV3M", "hizat2", field_str().build());
+        bananna.constraint(
+            &mut v,
+            "zU7B3QF1N",
+            "bananna_pk",
+            ConstraintType::PrimaryKey(PrimaryKeyDef { fields: vec![hizat.clone()] }),
+        );
+        generate(
+            &PathBuf::from_str("/dev/null").unwrap(),
+            vec![(0usize, v)],
+            vec![
+                new_select(&bananna)
+                    .return_field(&hizat2)
+                    .return_named("count", functions::count_rows())
+                    .group(vec![Expr::Field(hizat.clone())])
+                    .build_query("x", QueryResCount::Many)
+            ],
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_select_having_not_bool_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zO6XY2LHS", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_str().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![
+                    new_select(&bananna)
+                        .return_field(&hizat)
+                        .group(vec![Expr::Field(hizat.clone())])
+                        .having(Expr::LitI32(1))
+                        .build_query("x", QueryResCount::Many)
+                ],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_case_condition_not_bool_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zA9V2LX4D", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_named("x", Expr::Case {
+                    operand: None,
+                    whens: vec![(Expr::Field(hizat.clone()), Expr::LitI32(1))],
+                    else_: None,
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_case_result_mismatch_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zR6E8NB1F", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_named("x", Expr::Case {
+                    operand: Some(Box::new(Expr::Field(hizat.clone()))),
+                    whens: vec![(Expr::LitI32(1), Expr::LitI32(1))],
+                    else_: Some(Box::new(Expr::LitString("no".into()))),
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_sum_i64_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zW3H7ZC0P", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i64().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![
+                    new_select(&bananna)
+                        .return_named("x", functions::sum(Expr::Field(hizat.clone())))
+                        .build_query("x", QueryResCount::One)
+                ],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_between_operand_count_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zS5C1JX7V", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_field(&hizat).where_(Expr::BinOp {
+                    left: Box::new(Expr::Field(hizat.clone())),
+                    op: BinOp::Between,
+                    right: Box::new(Expr::LitI32(1)),
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_concat_not_string_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zL2M6PK9W", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_named("x", Expr::BinOp {
+                    left: Box::new(Expr::Field(hizat.clone())),
+                    op: BinOp::Concat,
+                    right: Box::new(Expr::LitString("x".into())),
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_convert_bytes_to_i64_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zX3N7GE2V", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_bytes().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_named("x", Expr::Convert {
+                    expr: Box::new(Expr::Field(hizat.clone())),
+                    type_: SimpleSimpleType::I64,
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_tuple_in_length_mismatch_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zM9D4KB7S", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_field(&hizat).where_(Expr::BinOp {
+                    left: Box::new(Expr::Tuple(vec![Expr::Field(hizat.clone()), Expr::Field(hizat.clone())])),
+                    op: BinOp::In,
+                    right: Box::new(Expr::ParamTuples {
+                        name: "keys".into(),
+                        types: vec![hizat.type_.type_.clone()],
+                    }),
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_lock_aggregate_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zS2J7FP4N", "bananna");
+        bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_named("count", functions::count_rows()).lock(Lock {
+                    strength: LockStrength::Update,
+                    of: vec![],
+                    wait: LockWait::Wait,
+                }).build_query("x", QueryResCount::One)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_lock_subselect_aggregate_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zC5R9KT3W", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let sub =
+            new_select(&bananna)
+                .return_field(&hizat)
+                .return_named("count", functions::count_rows())
+                .group(vec![Expr::Field(hizat.clone())])
+                .build_migration();
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select_from(NamedSelectSource {
+                    source: JoinSource::Subsel(Box::new(sub)),
+                    alias: Some("counts".into()),
+                }).return_named("hizat", Expr::Binding(ExprValName {
+                    table_id: "counts".into(),
+                    id: "hizat".into(),
+                })).lock(Lock {
+                    strength: LockStrength::Update,
+                    of: vec![],
+                    wait: LockWait::Wait,
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_distinct_on_order_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zW4E8HD1R", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let zomzom = bananna.field(&mut v, "zPREUVAOD", "zomzom", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![
+                    new_select(&bananna)
+                        .distinct_on(vec![Expr::Field(hizat.clone())])
+                        .return_field(&zomzom)
+                        .order(Expr::Field(zomzom.clone()), Order::Asc)
+                        .order(Expr::Field(hizat.clone()), Order::Asc)
+                        .build_query("x", QueryResCount::Many)
+                ],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_distinct_on_other_order_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zW4E8HD1R", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let zomzom = bananna.field(&mut v, "zPREUVAOD", "zomzom", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![
+                    new_select(&bananna)
+                        .distinct_on(vec![Expr::Field(hizat.clone())])
+                        .return_field(&zomzom)
+                        .order(Expr::Field(zomzom.clone()), Order::Asc)
+                        .build_query("x", QueryResCount::Many)
+                ],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_text_search_match_string_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zK7N3CV9Q", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_str().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_field(&hizat).where_(Expr::BinOp {
+                    left: Box::new(Expr::Field(hizat.clone())),
+                    op: BinOp::TextSearchMatch,
+                    right: Box::new(functions::plainto_tsquery("english", Expr::LitString("x".into()))),
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "indexes don't support include")]
+    fn test_index_include_hash_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zB6W2JT8X", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_str().build());
+        let zomzom = bananna.field(&mut v, "zPREUVAOD", "zomzom", field_str().build());
+        bananna
+            .index("zN4D9KE1S", "bananna_hizat", &[&hizat])
+            .method(IndexMethod::Hash)
+            .include(&[&zomzom])
+            .build(&mut v);
+    }
+
+    #[test]
+    fn test_insert_identity_always_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zG3T8RM5Y", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i64().identity(Identity::Always).build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_insert(&bananna, vec![(hizat.clone(), Expr::LitI64(1))]).build_query("x", QueryResCount::None)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "moves out of namespace")]
+    fn test_move_table_out_of_renamed_namespace_bad() {
+        let mut v0 = Version::default();
+        let v0_ns = v0.namespace("zC5J2WQ7E", "one");
+        v0_ns.table(&mut v0, "zY8D3LK6P", "bananna");
+        let mut v1 = Version::default();
+        v1.namespace("zC5J2WQ7E", "two");
+        v1.table("zY8D3LK6P", "bananna");
+        generate(&PathBuf::from_str("/dev/null").unwrap(), vec![(0usize, v0), (1usize, v1)], vec![]).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "has a bound that doesn't match")]
+    fn test_partition_bound_mismatch_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zV2K7QB4N", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i64().build());
+        bananna.partition_by(PartitionMethod::Range, &[&hizat]).build(&mut v);
+        bananna.partition(&mut v, "zJ8F3WC6T", "bananna_0", PartitionBound::Hash {
+            modulus: 2,
+            remainder: 0,
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "but has a with check condition")]
+    fn test_select_policy_with_check_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zT3N8HV5R", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i64().build());
+        bananna
+            .policy("zG6P1XD9L", "positive")
+            .command(PolicyCommand::Select)
+            .with_check(Expr::BinOp {
+                left: Box::new(Expr::Field(hizat.clone())),
+                op: BinOp::GreaterThan,
+                right: Box::new(Expr::LitI64(0)),
+            })
+            .build(&mut v);
+    }
 }
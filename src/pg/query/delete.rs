@@ -15,6 +15,7 @@ use super::{
     },
     utils::{
         QueryBody,
+        unproven_sources,
         build_returning,
     },
     select::Returning,
@@ -47,7 +48,7 @@ impl QueryBody for Delete {
 
         // Build query
         let mut out = Tokens::new();
-        out.s("delete from").id(&self.table.id);
+        out.s("delete from").s(&self.table.sql_id());
         if let Some(where_) = &self.where_ {
             out.s("where");
             let path = path.push_back("Where".into());
@@ -58,4 +59,19 @@ impl QueryBody for Delete {
         let out_type = build_returning(ctx, path, &scope, &mut out, &self.returning, res_count);
         (out_type, out)
     }
+
+    fn infer_res_count(&self, ctx: &super::utils::PgQueryCtx) -> Option<(QueryResCount, Option<String>)> {
+        if self.returning.is_empty() {
+            return Some((QueryResCount::None, None));
+        }
+        let mut equalities = vec![];
+        if let Some(where_) = &self.where_ {
+            where_.equalities_if_true(&mut equalities);
+        }
+        match unproven_sources(ctx, &[(Some(&self.table), self.table.id.clone(), vec![])], &equalities) {
+            None => return Some((QueryResCount::MaybeOne, None)),
+            Some(unproven) if unproven.is_empty() => return Some((QueryResCount::Many, None)),
+            Some(unproven) => return Some((QueryResCount::Many, Some(unproven.join("; ")))),
+        }
+    }
 }
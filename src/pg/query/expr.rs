@@ -1,5 +1,9 @@
 use {
     chrono::FixedOffset,
+    proc_macro2::{
+        Ident,
+        TokenStream,
+    },
     quote::{
         quote,
         format_ident,
@@ -8,7 +12,10 @@ use {
     samevariant::samevariant,
     syn::Path,
     std::{
-        collections::HashMap,
+        collections::{
+            HashMap,
+            HashSet,
+        },
         fmt::Display,
         rc::Rc,
     },
@@ -19,6 +26,7 @@ use {
                 SimpleSimpleType,
                 SimpleType,
                 to_rust_types,
+                to_sql_type,
             },
             query::utils::QueryBody,
             schema::{
@@ -94,11 +102,25 @@ pub enum Expr {
         name: String,
         type_: Type,
     },
+    /// A query parameter that's a list of tuples, with one element per type in
+    /// `types`. This evaluates to a subquery with a row per tuple, for use on the
+    /// right side of `In` and `NotIn` with a `Tuple`, for instance to look up rows by
+    /// a composite key. Each element is passed as a separate array parameter and
+    /// combined with `unnest`. Unlike SQLite (where the tuples are passed as JSON) any
+    /// type can be an element, since Postgres types here are never arrays themselves.
+    ParamTuples {
+        name: String,
+        types: Vec<Type>,
+    },
     /// This evaluates to the value of a field in the query main or joined tables. If
     /// you've aliased tables or field names, you'll have to instantiate `FieldId`
     /// yourself with the appropriate values. For synthetic values like function
     /// results you may need a `FieldId` with an empty `TableId` (`""`).
     Field(Field),
+    /// This evaluates to a named value in scope that isn't a plain table field, such
+    /// as a column of an aliased source, a lateral subselect, or a table-valued
+    /// function.
+    Binding(ExprValName),
     BinOp {
         left: Box<Expr>,
         op: BinOp,
@@ -114,6 +136,13 @@ pub enum Expr {
         op: PrefixOp,
         right: Box<Expr>,
     },
+    PostfixOp {
+        op: PostfixOp,
+        left: Box<Expr>,
+    },
+    /// A row value `(a, b, ...)`, for comparing multiple values at once. The elements
+    /// must be scalars.
+    Tuple(Vec<Expr>),
     /// Represents a call to an SQL function, like `collate()`. You must provide a
     /// helper to check and determine type of the result since we don't have a table of
     /// functions and their return types at present.
@@ -122,12 +151,30 @@ pub enum Expr {
         args: Vec<Expr>,
         compute_type: ComputeType,
     },
+    /// A `CASE` expression. If `operand` is present, each `when` condition is
+    /// compared to it for equality, otherwise the conditions must be bools. All
+    /// results must have the same type. The result is nullable if any result is
+    /// nullable or there's no `else_`.
+    Case {
+        operand: Option<Box<Expr>>,
+        whens: Vec<(Expr, Expr)>,
+        else_: Option<Box<Expr>>,
+    },
     /// A sub SELECT query.
     Select(Box<Select>),
     /// This is a synthetic expression, saying to treat the result of the expression as
     /// having the specified type. Use this for casting between primitive types and
-    /// Rust new-types for instance.
+    /// Rust new-types for instance. No SQL is generated for it; to convert values
+    /// between SQL types use `Convert`.
     Cast(Box<Expr>, Type),
+    /// A SQL `CAST(expr AS type)` conversion. Only conversions that produce values
+    /// readable as `type_` are allowed: between numeric types, numbers and bools to
+    /// and from strings, `int` to and from bools, and between times or times to
+    /// strings. The result is nullable if `expr` is.
+    Convert {
+        expr: Box<Expr>,
+        type_: SimpleSimpleType,
+    },
 }
 
 #[derive(Clone, Hash, PartialEq, Eq, Debug)]
@@ -137,7 +184,8 @@ pub struct ExprValName {
 }
 
 impl ExprValName {
-    pub(crate) fn local(name: String) -> Self {
+    /// Create an expression field/value name for a select-local (tableless) field.
+    pub fn local(name: String) -> Self {
         ExprValName {
             table_id: "".into(),
             id: name,
@@ -151,14 +199,16 @@ impl ExprValName {
         }
     }
 
-    pub(crate) fn field(f: &Field) -> Self {
+    /// Create an expression field/value name from a table field.
+    pub fn field(f: &Field) -> Self {
         ExprValName {
             table_id: f.table.id.clone(),
             id: f.id.clone(),
         }
     }
 
-    pub(crate) fn with_alias(&self, s: &str) -> ExprValName {
+    /// Derive an expression field/value name from a different name, with a new alias.
+    pub fn with_alias(&self, s: &str) -> ExprValName {
         ExprValName {
             table_id: s.into(),
             id: self.id.clone(),
@@ -193,6 +243,8 @@ pub(crate) enum GeneralType {
     Bool,
     Numeric,
     Blob,
+    TsVector,
+    TsQuery,
 }
 
 pub(crate) fn general_type(t: &Type) -> GeneralType {
@@ -205,6 +257,8 @@ pub(crate) fn general_type(t: &Type) -> GeneralType {
         SimpleSimpleType::Bool => GeneralType::Bool,
         SimpleSimpleType::String => GeneralType::Blob,
         SimpleSimpleType::Bytes => GeneralType::Blob,
+        SimpleSimpleType::TsVector => GeneralType::TsVector,
+        SimpleSimpleType::TsQuery => GeneralType::TsQuery,
         #[cfg(feature = "chrono")]
         SimpleSimpleType::UtcTimeChrono => GeneralType::Numeric,
         #[cfg(feature = "chrono")]
@@ -218,6 +272,17 @@ pub fn check_general_same_type(ctx: &mut PgQueryCtx, path: &rpds::Vector<String>
     if left.opt != right.opt {
         ctx.errs.err(path, format!("Operator arms have differing optionality"));
     }
+    check_general_compatible_type(ctx, path, left, right);
+}
+
+/// Like `check_general_same_type` but allows differing optionality, as in
+/// comparisons.
+pub(crate) fn check_general_compatible_type(
+    ctx: &mut PgQueryCtx,
+    path: &rpds::Vector<String>,
+    left: &Type,
+    right: &Type,
+) {
     match GeneralTypePairs::pairs(&general_type(left), &general_type(right)) {
         GeneralTypePairs::Nonmatching(left, right) => {
             ctx.errs.err(path, format!("Operator arms have incompatible types: {:?} and {:?}", left, right));
@@ -244,10 +309,10 @@ pub(crate) fn check_general_same(
                 ),
             );
     } else if left.0.len() == 1 && right.0.len() == 1 {
-        check_general_same_type(ctx, path, &left.0[0].1, &left.0[0].1);
+        check_general_compatible_type(ctx, path, &left.0[0].1, &right.0[0].1);
     } else {
         for (i, (left, right)) in left.0.iter().zip(right.0.iter()).enumerate() {
-            check_general_same_type(ctx, &path.push_back(format!("Record pair {}", i)), &left.1, &right.1);
+            check_general_compatible_type(ctx, &path.push_back(format!("Record pair {}", i)), &left.1, &right.1);
         }
     }
 }
@@ -318,11 +383,388 @@ pub(crate) fn check_bool(ctx: &mut PgQueryCtx, path: &rpds::Vector<String>, a: &
     }
 }
 
+fn is_numeric(t: &SimpleSimpleType) -> bool {
+    return matches!(
+        t,
+        SimpleSimpleType::Auto | SimpleSimpleType::I32 | SimpleSimpleType::I64 | SimpleSimpleType::F32 |
+            SimpleSimpleType::F64
+    );
+}
+
+fn is_integer(t: &SimpleSimpleType) -> bool {
+    return matches!(t, SimpleSimpleType::Auto | SimpleSimpleType::I32 | SimpleSimpleType::I64);
+}
+
+/// Returns true if `CAST` from `from` to `to` is valid in Postgres and produces a
+/// value that can be read as `to`.
+fn is_convertible(from: &SimpleSimpleType, to: &SimpleSimpleType) -> bool {
+    if from == to {
+        return true;
+    }
+    match (from, to) {
+        (f, t) if is_numeric(f) && is_numeric(t) => return true,
+        (f, SimpleSimpleType::String) if is_numeric(f) => return true,
+        (SimpleSimpleType::String, t) if is_numeric(t) => return true,
+        (SimpleSimpleType::Bool, SimpleSimpleType::I32) | (SimpleSimpleType::I32, SimpleSimpleType::Bool) => {
+            return true;
+        },
+        (SimpleSimpleType::Bool, SimpleSimpleType::String) | (SimpleSimpleType::String, SimpleSimpleType::Bool) => {
+            return true;
+        },
+        (SimpleSimpleType::String, SimpleSimpleType::TsVector | SimpleSimpleType::TsQuery) |
+        (SimpleSimpleType::TsVector | SimpleSimpleType::TsQuery, SimpleSimpleType::String) => {
+            return true;
+        },
+        _ => { },
+    }
+    let is_time = |t: &SimpleSimpleType| match t {
+        #[cfg(feature = "chrono")]
+        SimpleSimpleType::UtcTimeChrono | SimpleSimpleType::FixedOffsetTimeChrono => true,
+        #[cfg(feature = "jiff")]
+        SimpleSimpleType::UtcTimeJiff => true,
+        _ => false,
+    };
+    return is_time(from) && (is_time(to) || *to == SimpleSimpleType::String);
+}
+
+/// Checks that all operands are scalars of the same allowed type, ignoring
+/// nullability. The result is nullable if any operand is.
+fn check_operands_same(
+    ctx: &mut PgQueryCtx,
+    path: &rpds::Vector<String>,
+    res: &[(ExprType, Tokens)],
+    allowed: fn(&SimpleSimpleType) -> bool,
+    desc: &str,
+) -> Option<Type> {
+    let mut out: Option<Type> = None;
+    for (i, res) in res.iter().enumerate() {
+        let path = path.push_back(format!("Operand {}", i));
+        let (_, mut t) = res.0.assert_scalar(&mut ctx.errs, &path)?;
+        if !allowed(&t.type_.type_) {
+            ctx.errs.err(&path, format!("Expected {} operand but got {:?}", desc, t.type_.type_));
+        }
+
+        // `bigserial` values are `bigint` outside of column definitions
+        if t.type_.type_ == SimpleSimpleType::Auto {
+            t.type_.type_ = SimpleSimpleType::I64;
+        }
+        match &mut out {
+            Some(base) => {
+                if base.type_ != t.type_ {
+                    ctx
+                        .errs
+                        .err(
+                            &path,
+                            format!(
+                                "Expected same types, but operand 0 has type {:?} while this operand has type {:?}",
+                                base.type_,
+                                t.type_
+                            ),
+                        );
+                }
+                base.opt = base.opt || t.opt;
+            },
+            None => {
+                out = Some(t);
+            },
+        }
+    }
+    return out;
+}
+
 pub(crate) fn check_assignable(errs: &mut Errs, path: &rpds::Vector<String>, a: &Type, b: &ExprType) {
     check_same(errs, path, &ExprType(vec![(ExprValName::empty(), a.clone())]), b);
 }
 
+/// Built-in Postgres aggregate functions, used to validate grouping.
+const AGGREGATE_FUNCS: &[&str] = &[
+    "array_agg",
+    "avg",
+    "bit_and",
+    "bit_or",
+    "bool_and",
+    "bool_or",
+    "count",
+    "every",
+    "json_agg",
+    "json_object_agg",
+    "jsonb_agg",
+    "jsonb_object_agg",
+    "max",
+    "min",
+    "stddev",
+    "string_agg",
+    "sum",
+    "variance",
+];
+
 impl Expr {
+    /// Returns true if this is a call to an aggregate function.
+    pub(crate) fn is_aggregate(&self) -> bool {
+        match self {
+            Expr::Call { func, .. } => {
+                return AGGREGATE_FUNCS.contains(&func.to_ascii_lowercase().as_str());
+            },
+            _ => return false,
+        }
+    }
+
+    /// Returns true if this expression or any sub-expressions evaluated in the same
+    /// row context (i.e. excluding subqueries) is an aggregate.
+    pub(crate) fn contains_aggregate(&self) -> bool {
+        return self.is_aggregate() || self.row_children().into_iter().any(|e| e.contains_aggregate());
+    }
+
+    /// Collects bindings that must be non-null if this expression evaluates to true,
+    /// from `AND` conjunctions of comparisons and `IS NOT NULL` checks. Used to
+    /// narrow nullable types in `WHERE` and inner `JOIN` conditions.
+    pub(crate) fn non_null_if_true(&self, out: &mut HashSet<ExprValName>) {
+        match self {
+            Expr::BinOp { left, op, right } => {
+                match op {
+                    BinOp::And => {
+                        left.non_null_if_true(out);
+                        right.non_null_if_true(out);
+                    },
+                    BinOp::IsNot if matches!(right.as_ref(), Expr::LitNull(_)) => {
+                        left.strict_bindings(out);
+                    },
+                    BinOp::In | BinOp::NotIn => {
+                        // Elements on the right may be null
+                        left.strict_bindings(out);
+                    },
+                    op if op.is_strict_comparison() => {
+                        left.strict_bindings(out);
+                        right.strict_bindings(out);
+                    },
+                    _ => { },
+                }
+            },
+            Expr::BinOpChain { op, exprs } => {
+                match op {
+                    BinOp::And => {
+                        for e in exprs {
+                            e.non_null_if_true(out);
+                        }
+                    },
+                    BinOp::In | BinOp::NotIn => {
+                        if let Some(e) = exprs.first() {
+                            e.strict_bindings(out);
+                        }
+                    },
+                    op if op.is_strict_comparison() => {
+                        for e in exprs {
+                            e.strict_bindings(out);
+                        }
+                    },
+                    _ => { },
+                }
+            },
+            Expr::PostfixOp { op: PostfixOp::IsNotNull, left } => {
+                left.strict_bindings(out);
+            },
+            _ => { },
+        }
+    }
+
+    /// Collects bindings which, if null, make this expression null.
+    fn strict_bindings(&self, out: &mut HashSet<ExprValName>) {
+        match self {
+            Expr::Field(f) => {
+                out.insert(ExprValName::field(f));
+            },
+            Expr::Binding(b) => {
+                out.insert(b.clone());
+            },
+            Expr::BinOp { left, op, right } if op.is_strict_arithmetic() => {
+                left.strict_bindings(out);
+                right.strict_bindings(out);
+            },
+            Expr::BinOpChain { op, exprs } if op.is_strict_arithmetic() => {
+                for e in exprs {
+                    e.strict_bindings(out);
+                }
+            },
+            Expr::PrefixOp { op: PrefixOp::Negate | PrefixOp::BitNot, right } => {
+                right.strict_bindings(out);
+            },
+            Expr::Cast(e, _) | Expr::Convert { expr: e, .. } => {
+                e.strict_bindings(out);
+            },
+            _ => { },
+        }
+    }
+
+    /// Collects `binding = value` equalities that must hold if this expression
+    /// evaluates to true, from `AND` conjunctions. Comparisons of two bindings are
+    /// collected in both directions. Used to prove a query returns at most one row.
+    pub(crate) fn equalities_if_true<'a>(&'a self, out: &mut Vec<(ExprValName, &'a Expr)>) {
+        fn binding(e: &Expr) -> Option<ExprValName> {
+            match e {
+                Expr::Field(f) => return Some(ExprValName::field(f)),
+                Expr::Binding(b) => return Some(b.clone()),
+                _ => return None,
+            }
+        }
+
+        match self {
+            Expr::BinOp { left, op, right } => {
+                match op {
+                    BinOp::And => {
+                        left.equalities_if_true(out);
+                        right.equalities_if_true(out);
+                    },
+                    BinOp::Equals => {
+                        if let Some(b) = binding(left) {
+                            out.push((b, right.as_ref()));
+                        }
+                        if let Some(b) = binding(right) {
+                            out.push((b, left.as_ref()));
+                        }
+                    },
+                    _ => { },
+                }
+            },
+            Expr::BinOpChain { op: BinOp::And, exprs } => {
+                for e in exprs {
+                    e.equalities_if_true(out);
+                }
+            },
+            _ => { },
+        }
+    }
+
+    /// Returns true if this expression has a single value for the whole query, given
+    /// that the sources named in `fixed` (by table id or alias) have at most one row.
+    /// Function calls and subqueries are conservatively treated as not fixed.
+    pub(crate) fn is_fixed(&self, fixed: &HashSet<String>) -> bool {
+        match self {
+            Expr::Field(f) => return fixed.contains(&f.table.id),
+            Expr::Binding(b) => return fixed.contains(&b.table_id),
+            Expr::Call { .. } | Expr::Select(_) => return false,
+            _ => return self.row_children().into_iter().all(|e| e.is_fixed(fixed)),
+        }
+    }
+
+    /// Returns true if the expressions are structurally the same, for matching
+    /// grouping, distinct and order clauses. Fields are the same as the equivalent
+    /// binding, function calls are compared by name and arguments, and `Cast` is
+    /// ignored since it doesn't change the SQL. Tuple parameters and subqueries are
+    /// never the same.
+    pub(crate) fn same_as(&self, other: &Expr) -> bool {
+        fn name(e: &Expr) -> Option<ExprValName> {
+            match e {
+                Expr::Field(f) => return Some(ExprValName::field(f)),
+                Expr::Binding(b) => return Some(b.clone()),
+                _ => return None,
+            }
+        }
+
+        fn all_same(a: &[Expr], b: &[Expr]) -> bool {
+            return a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.same_as(b));
+        }
+
+        fn opt_same(a: &Option<Box<Expr>>, b: &Option<Box<Expr>>) -> bool {
+            match (a, b) {
+                (None, None) => return true,
+                (Some(a), Some(b)) => return a.same_as(b),
+                _ => return false,
+            }
+        }
+
+        if let (Some(a), Some(b)) = (name(self), name(other)) {
+            return a == b;
+        }
+        match (self, other) {
+            (Expr::Cast(a, _), b) => return a.same_as(b),
+            (a, Expr::Cast(b, _)) => return a.same_as(b),
+            (Expr::LitArray(a), Expr::LitArray(b)) | (Expr::Tuple(a), Expr::Tuple(b)) => return all_same(a, b),
+            (Expr::LitNull(a), Expr::LitNull(b)) => return a == b,
+            (Expr::LitBool(a), Expr::LitBool(b)) => return a == b,
+            (Expr::LitAuto(a), Expr::LitAuto(b)) | (Expr::LitI64(a), Expr::LitI64(b)) => return a == b,
+            (Expr::LitI32(a), Expr::LitI32(b)) => return a == b,
+            (Expr::LitF32(a), Expr::LitF32(b)) => return a.to_bits() == b.to_bits(),
+            (Expr::LitF64(a), Expr::LitF64(b)) => return a.to_bits() == b.to_bits(),
+            (Expr::LitString(a), Expr::LitString(b)) => return a == b,
+            (Expr::LitBytes(a), Expr::LitBytes(b)) => return a == b,
+            #[cfg(feature = "chrono")]
+            (Expr::LitUtcTimeChrono(a), Expr::LitUtcTimeChrono(b)) => return a == b,
+            #[cfg(feature = "chrono")]
+            (Expr::LitFixedOffsetTimeChrono(a), Expr::LitFixedOffsetTimeChrono(b)) => return a == b,
+            #[cfg(feature = "jiff")]
+            (Expr::LitUtcTimeJiff(a), Expr::LitUtcTimeJiff(b)) => return a == b,
+            (Expr::Param { name: a, .. }, Expr::Param { name: b, .. }) => return a == b,
+            (
+                Expr::BinOp { left: a_left, op: a_op, right: a_right },
+                Expr::BinOp { left: b_left, op: b_op, right: b_right },
+            ) => return a_op == b_op && a_left.same_as(b_left) && a_right.same_as(b_right),
+            (Expr::BinOpChain { op: a_op, exprs: a }, Expr::BinOpChain { op: b_op, exprs: b }) => {
+                return a_op == b_op && all_same(a, b);
+            },
+            (Expr::PrefixOp { op: a_op, right: a }, Expr::PrefixOp { op: b_op, right: b }) => {
+                return a_op == b_op && a.same_as(b);
+            },
+            (Expr::PostfixOp { op: a_op, left: a }, Expr::PostfixOp { op: b_op, left: b }) => {
+                return a_op == b_op && a.same_as(b);
+            },
+            (Expr::Call { func: a_func, args: a, .. }, Expr::Call { func: b_func, args: b, .. }) => {
+                return a_func == b_func && all_same(a, b);
+            },
+            (
+                Expr::Case { operand: a_operand, whens: a_whens, else_: a_else },
+                Expr::Case { operand: b_operand, whens: b_whens, else_: b_else },
+            ) => {
+                return opt_same(a_operand, b_operand) && a_whens.len() == b_whens.len() &&
+                    a_whens.iter().zip(b_whens).all(|(a, b)| a.0.same_as(&b.0) && a.1.same_as(&b.1)) &&
+                    opt_same(a_else, b_else);
+            },
+            (Expr::Convert { expr: a, type_: a_type }, Expr::Convert { expr: b, type_: b_type }) => {
+                return a_type == b_type && a.same_as(b);
+            },
+            _ => return false,
+        }
+    }
+
+    /// Sub-expressions evaluated in the same row context as this expression.
+    /// Subqueries are opaque.
+    pub(crate) fn row_children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::LitArray(v) => v.iter().collect(),
+            Expr::LitNull(_) |
+            Expr::LitBool(_) |
+            Expr::LitAuto(_) |
+            Expr::LitI32(_) |
+            Expr::LitI64(_) |
+            Expr::LitF32(_) |
+            Expr::LitF64(_) |
+            Expr::LitString(_) |
+            Expr::LitBytes(_) => vec![],
+            #[cfg(feature = "chrono")]
+            Expr::LitUtcTimeChrono(_) | Expr::LitFixedOffsetTimeChrono(_) => vec![],
+            #[cfg(feature = "jiff")]
+            Expr::LitUtcTimeJiff(_) => vec![],
+            Expr::Param { .. } | Expr::ParamTuples { .. } | Expr::Field(_) | Expr::Binding(_) => vec![],
+            Expr::BinOp { left, right, .. } => vec![left.as_ref(), right.as_ref()],
+            Expr::BinOpChain { exprs, .. } => exprs.iter().collect(),
+            Expr::PrefixOp { right, .. } => vec![right.as_ref()],
+            Expr::PostfixOp { left, .. } => vec![left.as_ref()],
+            Expr::Tuple(v) => v.iter().collect(),
+            Expr::Call { args, .. } => args.iter().collect(),
+            Expr::Case { operand, whens, else_ } => {
+                let mut out = vec![];
+                out.extend(operand.as_deref());
+                for (cond, res) in whens {
+                    out.push(cond);
+                    out.push(res);
+                }
+                out.extend(else_.as_deref());
+                out
+            },
+            Expr::Select(_) => vec![],
+            Expr::Cast(e, _) | Expr::Convert { expr: e, .. } => vec![e.as_ref()],
+        }
+    }
+
     pub(crate) fn build(
         &self,
         ctx: &mut PgQueryCtx,
@@ -348,54 +790,106 @@ impl Expr {
             op: &BinOp,
             exprs: &Vec<Expr>,
         ) -> (ExprType, Tokens) {
-            if exprs.len() < 2 {
-                ctx.errs.err(path, format!("Binary ops must have at least two operands, but got {}", exprs.len()));
+            let (operand_lower_limit, operand_upper_limit) = match op {
+                BinOp::Like | BinOp::NotLike | BinOp::ILike | BinOp::NotILike => (2, Some(3)),
+                BinOp::RegexMatch |
+                BinOp::RegexMatchCaseInsensitive |
+                BinOp::In |
+                BinOp::NotIn |
+                BinOp::TextSearchMatch => (2, Some(2)),
+                BinOp::Between | BinOp::NotBetween => (3, Some(3)),
+                _ => (2, None),
+            };
+            if exprs.len() < operand_lower_limit || operand_upper_limit.map(|u| exprs.len() > u).unwrap_or(false) {
+                ctx
+                    .errs
+                    .err(
+                        path,
+                        match operand_upper_limit {
+                            Some(u) if u == operand_lower_limit => format!(
+                                "{:?} must have exactly {} operand(s), but got {}",
+                                op,
+                                u,
+                                exprs.len()
+                            ),
+                            Some(u) => format!(
+                                "{:?} must have between {} and {} operand(s), but got {}",
+                                op,
+                                operand_lower_limit,
+                                u,
+                                exprs.len()
+                            ),
+                            None => format!(
+                                "Binary ops must have at least {} operands, but got {}",
+                                operand_lower_limit,
+                                exprs.len()
+                            ),
+                        },
+                    );
+                return (ExprType(vec![]), Tokens::new());
             }
             let mut res = vec![];
             for (i, e) in exprs.iter().enumerate() {
                 res.push(e.build(ctx, &path.push_back(format!("Operand {}", i)), scope));
             }
+            let bool_type = Type {
+                type_: SimpleType {
+                    type_: SimpleSimpleType::Bool,
+                    custom: None,
+                },
+                opt: false,
+            };
             let t = match op {
-                BinOp::Plus | BinOp::Minus | BinOp::Multiply | BinOp::Divide => {
-                    let base = res.get(0).unwrap();
-                    let t =
-                        match check_same(
-                            &mut ctx.errs,
-                            &path.push_back(format!("Operands 0, 1")),
-                            &base.0,
-                            &res.get(0).unwrap().0,
-                        ) {
-                            Some(t) => t,
-                            None => {
-                                return (ExprType(vec![]), Tokens::new());
-                            },
-                        };
-                    for (i, res) in res.iter().enumerate().skip(2) {
-                        match check_same(
-                            &mut ctx.errs,
-                            &path.push_back(format!("Operands 0, {}", i)),
-                            &base.0,
-                            &res.0,
-                        ) {
-                            Some(_) => { },
-                            None => {
-                                return (ExprType(vec![]), Tokens::new());
-                            },
+                BinOp::Plus |
+                BinOp::Minus |
+                BinOp::Multiply |
+                BinOp::Divide |
+                BinOp::Mod |
+                BinOp::BitAnd |
+                BinOp::BitOr |
+                BinOp::Concat => {
+                    let (allowed, desc): (fn(&SimpleSimpleType) -> bool, &str) = match op {
+                        BinOp::Plus | BinOp::Minus | BinOp::Multiply | BinOp::Divide => (is_numeric, "numeric"),
+                        BinOp::Mod | BinOp::BitAnd | BinOp::BitOr => (is_integer, "integer"),
+                        _ => (|t| matches!(t, SimpleSimpleType::String), "string"),
+                    };
+                    match check_operands_same(ctx, path, &res, allowed, desc) {
+                        Some(t) => t,
+                        None => {
+                            return (ExprType(vec![]), Tokens::new());
+                        },
+                    }
+                },
+                BinOp::ShiftLeft | BinOp::ShiftRight => {
+                    let mut out = None;
+                    let mut opt = false;
+                    for (i, res) in res.iter().enumerate() {
+                        let path = path.push_back(format!("Operand {}", i));
+                        let Some((_, t)) = res.0.assert_scalar(&mut ctx.errs, &path) else {
+                            return (ExprType(vec![]), Tokens::new());
                         };
+                        if i == 0 {
+                            if !is_integer(&t.type_.type_) {
+                                ctx.errs.err(&path, format!("Expected integer operand but got {:?}", t.type_.type_));
+                            }
+                        } else if !matches!(t.type_.type_, SimpleSimpleType::I32) {
+                            ctx.errs.err(&path, format!("Expected i32 shift amount but got {:?}", t.type_.type_));
+                        }
+                        opt = opt || t.opt;
+                        if out.is_none() {
+                            out = Some(t);
+                        }
+                    }
+                    Type {
+                        opt: opt,
+                        ..out.unwrap()
                     }
-                    t
                 },
                 BinOp::And | BinOp::Or => {
                     for (i, res) in res.iter().enumerate() {
                         check_bool(ctx, &path.push_back(format!("Operand {}", i)), &res.0);
                     }
-                    Type {
-                        type_: SimpleType {
-                            type_: SimpleSimpleType::Bool,
-                            custom: None,
-                        },
-                        opt: false,
-                    }
+                    bool_type
                 },
                 BinOp::Equals |
                 BinOp::NotEquals |
@@ -404,49 +898,121 @@ impl Expr {
                 BinOp::LessThan |
                 BinOp::LessThanEqualTo |
                 BinOp::GreaterThan |
-                BinOp::GreaterThanEqualTo => {
+                BinOp::GreaterThanEqualTo |
+                BinOp::Between |
+                BinOp::NotBetween |
+                BinOp::In |
+                BinOp::NotIn => {
                     let base = res.get(0).unwrap();
-                    check_general_same(
-                        ctx,
-                        &path.push_back(format!("Operands 0, 1")),
-                        &base.0,
-                        &res.get(1).unwrap().0,
-                    );
-                    for (i, res) in res.iter().enumerate().skip(2) {
+                    for (i, res) in res.iter().enumerate().skip(1) {
                         check_general_same(ctx, &path.push_back(format!("Operands 0, {}", i)), &base.0, &res.0);
                     }
-                    Type {
-                        type_: SimpleType {
-                            type_: SimpleSimpleType::Bool,
-                            custom: None,
-                        },
-                        opt: false,
+                    bool_type
+                },
+                BinOp::Like |
+                BinOp::NotLike |
+                BinOp::ILike |
+                BinOp::NotILike |
+                BinOp::RegexMatch |
+                BinOp::RegexMatchCaseInsensitive => {
+                    for (i, res) in res.iter().enumerate() {
+                        let path = path.push_back(format!("Operand {}", i));
+                        let Some((_, t)) = res.0.assert_scalar(&mut ctx.errs, &path) else {
+                            continue;
+                        };
+                        if !matches!(t.type_.type_, SimpleSimpleType::String) {
+                            ctx.errs.err(&path, format!("Expected string operand but got {:?}", t.type_.type_));
+                        }
+                        if i == 2 && t.opt {
+                            ctx.errs.err(&path, format!("The escape character must not be nullable"));
+                        }
                     }
+                    bool_type
+                },
+                BinOp::TextSearchMatch => {
+                    for (i, res) in res.iter().enumerate() {
+                        let path = path.push_back(format!("Operand {}", i));
+                        let Some((_, t)) = res.0.assert_scalar(&mut ctx.errs, &path) else {
+                            continue;
+                        };
+                        let (want, desc) = if i == 0 {
+                            (SimpleSimpleType::TsVector, "tsvector")
+                        } else {
+                            (SimpleSimpleType::TsQuery, "tsquery")
+                        };
+                        if t.type_.type_ != want {
+                            ctx.errs.err(&path, format!("Expected {} operand but got {:?}", desc, t.type_.type_));
+                        }
+                    }
+                    bool_type
                 },
-            };
-            let token = match op {
-                BinOp::Plus => "+",
-                BinOp::Minus => "-",
-                BinOp::Multiply => "*",
-                BinOp::Divide => "/",
-                BinOp::And => "and",
-                BinOp::Or => "or",
-                BinOp::Equals => "=",
-                BinOp::NotEquals => "!=",
-                BinOp::Is => "is",
-                BinOp::IsNot => "is not",
-                BinOp::LessThan => "<",
-                BinOp::LessThanEqualTo => "<=",
-                BinOp::GreaterThan => ">",
-                BinOp::GreaterThanEqualTo => ">=",
             };
             let mut out = Tokens::new();
             out.s("(");
-            for (i, res) in res.iter().enumerate() {
-                if i > 0 {
-                    out.s(token);
-                }
-                out.s(&res.1.to_string());
+            match op {
+                BinOp::Between | BinOp::NotBetween => {
+                    out.s(&res[0].1.to_string());
+                    out.s(if matches!(op, BinOp::Between) {
+                        "between"
+                    } else {
+                        "not between"
+                    });
+                    out.s(&res[1].1.to_string()).s("and").s(&res[2].1.to_string());
+                },
+                BinOp::Like | BinOp::NotLike | BinOp::ILike | BinOp::NotILike => {
+                    out.s(&res[0].1.to_string());
+                    out.s(match op {
+                        BinOp::Like => "like",
+                        BinOp::NotLike => "not like",
+                        BinOp::ILike => "ilike",
+                        _ => "not ilike",
+                    });
+                    out.s(&res[1].1.to_string());
+                    if let Some(escape) = res.get(2) {
+                        out.s("escape").s(&escape.1.to_string());
+                    }
+                },
+                _ => {
+                    let token = match op {
+                        BinOp::Plus => "+",
+                        BinOp::Minus => "-",
+                        BinOp::Multiply => "*",
+                        BinOp::Divide => "/",
+                        BinOp::Mod => "%",
+                        BinOp::BitAnd => "&",
+                        BinOp::BitOr => "|",
+                        BinOp::ShiftLeft => "<<",
+                        BinOp::ShiftRight => ">>",
+                        BinOp::Concat => "||",
+                        BinOp::And => "and",
+                        BinOp::Or => "or",
+                        BinOp::Equals => "=",
+                        BinOp::NotEquals => "!=",
+                        BinOp::Is => "is",
+                        BinOp::IsNot => "is not",
+                        BinOp::LessThan => "<",
+                        BinOp::LessThanEqualTo => "<=",
+                        BinOp::GreaterThan => ">",
+                        BinOp::GreaterThanEqualTo => ">=",
+                        BinOp::RegexMatch => "~",
+                        BinOp::RegexMatchCaseInsensitive => "~*",
+                        BinOp::In => "in",
+                        BinOp::NotIn => "not in",
+                        BinOp::TextSearchMatch => "@@",
+                        BinOp::Like |
+                        BinOp::NotLike |
+                        BinOp::ILike |
+                        BinOp::NotILike |
+                        BinOp::Between |
+                        BinOp::NotBetween => unreachable!(),
+                    };
+                    for (i, res) in res.iter().enumerate() {
+                        if i > 0 {
+                            out.s(token);
+                        }
+                        out.s(&res.1.to_string());
+                    }
+                },
             }
             out.s(")");
             (ExprType(vec![(ExprValName::empty(), t)]), out)
@@ -559,24 +1125,11 @@ impl Expr {
                     std::collections::hash_map::Entry::Vacant(e) => {
                         let i = ctx.query_args.len();
                         e.insert((i, t.clone()));
-                        let rust_types = to_rust_types(&t.type_.type_);
-                        let custom_trait_ident = rust_types.custom_trait;
-                        let rust_type = rust_types.arg_type;
                         let ident = format_ident!("{}", sanitize_ident(x).1);
-                        let (mut rust_type, mut rust_forward) = if let Some(custom) = &t.type_.custom {
-                            let custom_ident = match syn::parse_str::<Path>(custom.as_str()) {
-                                Ok(p) => p,
-                                Err(e) => {
-                                    ctx.errs.err(&path, format!("Couldn't parse custom type {}: {:?}", custom, e));
-                                    return (ExprType(vec![]), Tokens::new());
-                                },
-                            }.to_token_stream();
-                            let forward =
-                                quote!(< #custom_ident as #custom_trait_ident < #custom_ident >>:: to_sql(& #ident));
-                            (quote!(& #custom_ident), forward)
-                        } else {
-                            (rust_type, quote!(#ident))
-                        };
+                        let Some((mut rust_type, mut rust_forward)) =
+                            build_param_forward(ctx, &path, &ident, &t.type_) else {
+                                return (ExprType(vec![]), Tokens::new());
+                            };
                         if t.opt {
                             rust_type = quote!(Option < #rust_type >);
                             rust_forward = quote!(#ident.map(| #ident | #rust_forward));
@@ -586,12 +1139,88 @@ impl Expr {
                         i
                     },
                 };
+                if ctx.tuple_arg_lookup.contains_key(x) {
+                    errs.push(format!("Parameter {} is also used as a tuple list parameter", x));
+                }
                 for e in errs {
                     ctx.errs.err(&path, e);
                 }
                 out.s(&format!("${}", i + 1));
                 return (ExprType(vec![(ExprValName::local(x.clone()), t.clone())]), out);
             },
+            Expr::ParamTuples { name: x, types } => {
+                let path = path.push_back(format!("Param tuples ({})", x));
+                if types.is_empty() {
+                    ctx.errs.err(&path, format!("Tuple list parameters must have at least one element type"));
+                    return (ExprType(vec![]), Tokens::new());
+                }
+                if ctx.rust_arg_lookup.contains_key(x) {
+                    ctx.errs.err(&path, format!("Parameter {} is also used as a scalar parameter", x));
+                }
+                let i = match ctx.tuple_arg_lookup.get(x) {
+                    Some((i, prev_types)) => {
+                        let i = *i;
+                        if types != prev_types {
+                            ctx
+                                .errs
+                                .err(
+                                    &path,
+                                    format!(
+                                        "Parameter {} specified with multiple types: {:?}, {:?}",
+                                        x,
+                                        types,
+                                        prev_types
+                                    ),
+                                );
+                        }
+                        i
+                    },
+                    None => {
+                        let i = ctx.query_args.len();
+                        ctx.tuple_arg_lookup.insert(x.clone(), (i, types.clone()));
+                        let ident_name = sanitize_ident(x).1;
+                        let ident = format_ident!("{}", ident_name);
+                        let mut elem_types = vec![];
+                        let mut elem_forwards = vec![];
+                        for (j, t) in types.iter().enumerate() {
+                            let elem_ident = format_ident!("{}_{}", ident_name, j);
+                            let index = syn::Index::from(j);
+                            let Some((mut rust_type, mut rust_forward)) =
+                                build_param_forward(ctx, &path, &elem_ident, &t.type_) else {
+                                    return (ExprType(vec![]), Tokens::new());
+                                };
+                            if t.opt {
+                                rust_type = quote!(Option < #rust_type >);
+                                rust_forward = quote!(#elem_ident.map(| #elem_ident | #rust_forward));
+                            }
+                            elem_types.push(rust_type);
+                            elem_forwards.push(
+                                quote!(
+                                    #ident.iter().map(| x | x.#index).map(| #elem_ident | #rust_forward).collect::< Vec < _ >>()
+                                ),
+                            );
+                        }
+                        ctx.rust_args.push(quote!(#ident: Vec <(#(#elem_types,) *) >));
+                        ctx.query_args.extend(elem_forwards);
+                        i
+                    },
+                };
+                let mut out = Tokens::new();
+                out.s("( select * from unnest (");
+                for (j, t) in types.iter().enumerate() {
+                    if j > 0 {
+                        out.s(",");
+                    }
+                    out.s(&format!("cast ( ${} as", i + j + 1));
+                    match t.type_.type_ {
+                        // `bigserial` is only valid in column definitions
+                        SimpleSimpleType::Auto => out.s("bigint[] )"),
+                        ref t => out.s(&format!("{}[] )", to_sql_type(t))),
+                    };
+                }
+                out.s(") )");
+                return (ExprType(types.iter().map(|t| (ExprValName::empty(), t.clone())).collect()), out);
+            },
             Expr::Field(x) => {
                 let name = ExprValName::field(x);
                 let t = match scope.get(&name) {
@@ -614,6 +1243,30 @@ impl Expr {
                 out.id(&x.table.id).s(".").id(&x.id);
                 return (ExprType(vec![(name, t.clone())]), out);
             },
+            Expr::Binding(name) => {
+                let t = match scope.get(&name) {
+                    Some(t) => t.clone(),
+                    None => {
+                        ctx
+                            .errs
+                            .err(
+                                path,
+                                format!(
+                                    "Expression references {} but this field isn't available here (available fields: {:?})",
+                                    name,
+                                    scope.iter().map(|e| e.0.to_string()).collect::<Vec<String>>()
+                                ),
+                            );
+                        return (ExprType(vec![]), Tokens::new());
+                    },
+                };
+                let mut out = Tokens::new();
+                if name.table_id != "" {
+                    out.id(&name.table_id).s(".");
+                }
+                out.id(&name.id);
+                return (ExprType(vec![(name.clone(), t.clone())]), out);
+            },
             Expr::BinOp { left, op, right } => {
                 return do_bin_op(
                     ctx,
@@ -630,14 +1283,55 @@ impl Expr {
                 let path = path.push_back(format!("Prefix op {:?}", op));
                 let mut out = Tokens::new();
                 let res = right.build(ctx, &path, scope);
-                let (op_text, op_type) = match op {
+                let (op_text, allowed, desc): (_, fn(&SimpleSimpleType) -> bool, _) = match op {
                     PrefixOp::Not => {
                         check_bool(ctx, &path, &res.0);
-                        ("not", SimpleSimpleType::Bool)
+                        out.s("not").s(&res.1.to_string());
+                        return empty_type!(out, SimpleSimpleType::Bool);
                     },
+                    PrefixOp::Negate => ("-", is_numeric, "numeric"),
+                    PrefixOp::BitNot => ("~", is_integer, "integer"),
                 };
+                let Some((_, t)) = res.0.assert_scalar(&mut ctx.errs, &path) else {
+                    return (ExprType(vec![]), Tokens::new());
+                };
+                if !allowed(&t.type_.type_) {
+                    ctx.errs.err(&path, format!("Expected {} operand but got {:?}", desc, t.type_.type_));
+                }
                 out.s(op_text).s(&res.1.to_string());
-                return empty_type!(out, op_type);
+                return (ExprType(vec![(ExprValName::empty(), t)]), out);
+            },
+            Expr::PostfixOp { op, left } => {
+                let path = path.push_back(format!("Postfix op {:?}", op));
+                let mut out = Tokens::new();
+                let res = left.build(ctx, &path, scope);
+                res.0.assert_scalar(&mut ctx.errs, &path);
+                out.s("(").s(&res.1.to_string()).s(match op {
+                    PostfixOp::IsNull => "is null",
+                    PostfixOp::IsNotNull => "is not null",
+                }).s(")");
+                return empty_type!(out, SimpleSimpleType::Bool);
+            },
+            Expr::Tuple(elements) => {
+                let path = path.push_back(format!("Tuple"));
+                if elements.is_empty() {
+                    ctx.errs.err(&path, format!("Tuples must have at least one element"));
+                    return (ExprType(vec![]), Tokens::new());
+                }
+                let mut out = Tokens::new();
+                let mut types = vec![];
+                out.s("(");
+                for (i, e) in elements.iter().enumerate() {
+                    let path = path.push_back(format!("Element {}", i));
+                    if i > 0 {
+                        out.s(",");
+                    }
+                    let (t, tokens) = e.build(ctx, &path, scope);
+                    types.extend(t.assert_scalar(&mut ctx.errs, &path));
+                    out.s(&tokens.to_string());
+                }
+                out.s(")");
+                return (ExprType(types), out);
             },
             Expr::Call { func, args, compute_type } => {
                 let mut types = vec![];
@@ -662,9 +1356,75 @@ impl Expr {
                 };
                 return (ExprType(vec![(ExprValName::empty(), type_)]), out);
             },
+            Expr::Case { operand, whens, else_ } => {
+                let path = path.push_back(format!("Case"));
+                let mut out = Tokens::new();
+                out.s("case");
+                let operand_t = match operand {
+                    Some(operand) => {
+                        let (operand_t, operand_tokens) = operand.build(ctx, &path.push_back("Operand".into()), scope);
+                        out.s(&operand_tokens.to_string());
+                        Some(operand_t)
+                    },
+                    None => None,
+                };
+                if whens.is_empty() {
+                    ctx.errs.err(&path, format!("Case must have at least one when clause"));
+                }
+                let mut results = vec![];
+                for (i, (cond, res)) in whens.iter().enumerate() {
+                    let path = path.push_back(format!("When {}", i));
+                    let (cond_t, cond_tokens) = cond.build(ctx, &path, scope);
+                    match &operand_t {
+                        Some(operand_t) => check_general_same(ctx, &path, operand_t, &cond_t),
+                        None => check_bool(ctx, &path, &cond_t),
+                    }
+                    let (res_t, res_tokens) = res.build(ctx, &path, scope);
+                    out.s("when").s(&cond_tokens.to_string()).s("then").s(&res_tokens.to_string());
+                    results.push((path, res_t));
+                }
+                if let Some(else_) = else_ {
+                    let path = path.push_back(format!("Else"));
+                    let (else_t, else_tokens) = else_.build(ctx, &path, scope);
+                    out.s("else").s(&else_tokens.to_string());
+                    results.push((path, else_t));
+                }
+                out.s("end");
+
+                // Unify result types, nullability separately
+                let mut opt = else_.is_none();
+                let mut base: Option<ExprType> = None;
+                for (path, res_t) in results {
+                    let Some((_, res_t)) = res_t.assert_scalar(&mut ctx.errs, &path) else {
+                        continue;
+                    };
+                    opt = opt || res_t.opt;
+                    let res_t = ExprType(vec![(ExprValName::empty(), Type {
+                        opt: false,
+                        ..res_t
+                    })]);
+                    match &base {
+                        Some(base) => {
+                            check_same(&mut ctx.errs, &path, base, &res_t);
+                        },
+                        None => {
+                            base = Some(res_t);
+                        },
+                    }
+                }
+                let Some(mut base) = base else {
+                    return (ExprType(vec![]), Tokens::new());
+                };
+                let mut t = base.0.remove(0).1;
+                t.opt = opt;
+                return (ExprType(vec![(ExprValName::empty(), t)]), out);
+            },
             Expr::Select(s) => {
                 let path = path.push_back(format!("Subselect"));
-                return s.build(ctx, &path, QueryResCount::Many);
+                let (t, tokens) = s.build(ctx, &path, QueryResCount::Many);
+                let mut out = Tokens::new();
+                out.s("(").s(&tokens.to_string()).s(")");
+                return (t, out);
             },
             Expr::Cast(e, t) => {
                 let path = path.push_back(format!("Cast"));
@@ -678,16 +1438,80 @@ impl Expr {
                 check_general_same_type(ctx, &path, t, &got_t.1);
                 return (ExprType(vec![(got_t.0, t.clone())]), out.1);
             },
+            Expr::Convert { expr, type_ } => {
+                let path = path.push_back(format!("Convert"));
+                let res = expr.build(ctx, &path, scope);
+                let Some((_, got_t)) = res.0.assert_scalar(&mut ctx.errs, &path) else {
+                    return (ExprType(vec![]), Tokens::new());
+                };
+                if !is_convertible(&got_t.type_.type_, type_) {
+                    ctx.errs.err(&path, format!("Can't convert {:?} to {:?}", got_t.type_.type_, type_));
+                }
+                let mut out = Tokens::new();
+                out.s("cast (").s(&res.1.to_string()).s("as");
+                match type_ {
+                    // `bigserial` is only valid in column definitions
+                    SimpleSimpleType::Auto => out.s("bigint"),
+                    t => out.s(to_sql_type(t)),
+                };
+                out.s(")");
+                return (ExprType(vec![(ExprValName::empty(), Type {
+                    type_: SimpleType {
+                        type_: type_.clone(),
+                        custom: None,
+                    },
+                    opt: got_t.opt,
+                })]), out);
+            },
         };
     }
 }
 
-#[derive(Clone, Debug)]
+/// Builds the Rust argument type of a non-null parameter and the expression
+/// converting the argument (bound to `ident`) to the value passed to Postgres.
+fn build_param_forward(
+    ctx: &mut PgQueryCtx,
+    path: &rpds::Vector<String>,
+    ident: &Ident,
+    t: &SimpleType,
+) -> Option<(TokenStream, TokenStream)> {
+    let rust_types = to_rust_types(&t.type_);
+    let custom_trait_ident = rust_types.custom_trait;
+    if let Some(custom) = &t.custom {
+        let custom_ident = match syn::parse_str::<Path>(custom.as_str()) {
+            Ok(p) => p,
+            Err(e) => {
+                ctx.errs.err(path, format!("Couldn't parse custom type {}: {:?}", custom, e));
+                return None;
+            },
+        }.to_token_stream();
+        let forward = quote!(< #custom_ident as #custom_trait_ident < #custom_ident >>:: to_sql(& #ident));
+        return Some((quote!(& #custom_ident), forward));
+    } else {
+        return Some((rust_types.arg_type, quote!(#ident)));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum BinOp {
     Plus,
     Minus,
     Multiply,
     Divide,
+    /// `%`, integers only.
+    Mod,
+    /// `&`, integers only.
+    BitAnd,
+    /// `|`, integers only.
+    BitOr,
+    /// `<<`, the shift amount must be an `i32`. The result has the type of the left
+    /// operand.
+    ShiftLeft,
+    /// `>>`, the shift amount must be an `i32`. The result has the type of the left
+    /// operand.
+    ShiftRight,
+    /// `||` string concatenation.
+    Concat,
     And,
     Or,
     Equals,
@@ -698,9 +1522,85 @@ pub enum BinOp {
     LessThanEqualTo,
     GreaterThan,
     GreaterThanEqualTo,
+    /// `a BETWEEN b AND c`. Use `BinOpChain` with exactly 3 operands.
+    Between,
+    /// `a NOT BETWEEN b AND c`. Use `BinOpChain` with exactly 3 operands.
+    NotBetween,
+    /// `a LIKE b`. Use `BinOpChain` with a third operand to specify an `ESCAPE`
+    /// character.
+    Like,
+    /// `a NOT LIKE b`. Use `BinOpChain` with a third operand to specify an `ESCAPE`
+    /// character.
+    NotLike,
+    /// Case insensitive `LIKE`.
+    ILike,
+    /// Case insensitive `NOT LIKE`.
+    NotILike,
+    /// `~`, POSIX regular expression match.
+    RegexMatch,
+    /// `~*`, case insensitive POSIX regular expression match.
+    RegexMatchCaseInsensitive,
+    /// `a IN b`, where `b` is a subquery like `Select` or `ParamTuples` with the same
+    /// fields as `a`. Use a `Tuple` to compare multiple values.
+    In,
+    /// `a NOT IN b`, like `In`.
+    NotIn,
+    /// `a @@ b`, full-text search where `a` is a `tsvector` and `b` a `tsquery`.
+    TextSearchMatch,
 }
 
-#[derive(Clone, Debug)]
+impl BinOp {
+    /// Comparisons which are never true if an operand is null.
+    fn is_strict_comparison(&self) -> bool {
+        return matches!(
+            self,
+            BinOp::Equals |
+                BinOp::NotEquals |
+                BinOp::LessThan |
+                BinOp::LessThanEqualTo |
+                BinOp::GreaterThan |
+                BinOp::GreaterThanEqualTo |
+                BinOp::Between |
+                BinOp::NotBetween |
+                BinOp::Like |
+                BinOp::NotLike |
+                BinOp::ILike |
+                BinOp::NotILike |
+                BinOp::RegexMatch |
+                BinOp::RegexMatchCaseInsensitive |
+                BinOp::TextSearchMatch
+        );
+    }
+
+    /// Operators which produce null if any operand is null.
+    fn is_strict_arithmetic(&self) -> bool {
+        return matches!(
+            self,
+            BinOp::Plus |
+                BinOp::Minus |
+                BinOp::Multiply |
+                BinOp::Divide |
+                BinOp::Mod |
+                BinOp::BitAnd |
+                BinOp::BitOr |
+                BinOp::ShiftLeft |
+                BinOp::ShiftRight |
+                BinOp::Concat
+        );
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum PrefixOp {
     Not,
+    /// Unary minus, numbers only.
+    Negate,
+    /// `~`, integers only.
+    BitNot,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PostfixOp {
+    IsNull,
+    IsNotNull,
 }
@@ -0,0 +1,427 @@
+//! Constructors for common built-in SQL functions with result types computed from
+//! their arguments.
+use {
+    super::{
+        expr::{
+            ComputeType,
+            Expr,
+            ExprType,
+        },
+        utils::PgQueryCtx,
+    },
+    crate::pg::{
+        schema::sequence::Sequence,
+        types::{
+            SimpleSimpleType,
+            SimpleType,
+            Type,
+        },
+    },
+};
+
+fn simple_type(t: SimpleSimpleType, opt: bool) -> Type {
+    return Type {
+        type_: SimpleType {
+            type_: t,
+            custom: None,
+        },
+        opt: opt,
+    };
+}
+
+fn arg_type(ctx: &mut PgQueryCtx, path: &rpds::Vector<String>, args: &[ExprType], i: usize) -> Option<Type> {
+    return args.get(i).and_then(|a| a.assert_scalar(&mut ctx.errs, path)).map(|a| a.1);
+}
+
+fn check_numeric(ctx: &mut PgQueryCtx, path: &rpds::Vector<String>, func: &str, t: &Type) {
+    if !matches!(
+        t.type_.type_,
+        SimpleSimpleType::Auto | SimpleSimpleType::I32 | SimpleSimpleType::I64 | SimpleSimpleType::F32 |
+            SimpleSimpleType::F64
+    ) {
+        ctx
+            .errs
+            .err(path, format!("Function {} requires a numeric argument but got {:?}", func, t.type_.type_));
+    }
+}
+
+fn check_float(ctx: &mut PgQueryCtx, path: &rpds::Vector<String>, func: &str, t: &Type) {
+    if !matches!(t.type_.type_, SimpleSimpleType::F32 | SimpleSimpleType::F64) {
+        ctx
+            .errs
+            .err(
+                path,
+                format!(
+                    "Function {} requires a float argument (integer arguments produce `numeric` which isn't supported) but got {:?}",
+                    func,
+                    t.type_.type_
+                ),
+            );
+    }
+}
+
+fn check_i32(ctx: &mut PgQueryCtx, path: &rpds::Vector<String>, func: &str, t: &Type) {
+    if !matches!(t.type_.type_, SimpleSimpleType::I32) {
+        ctx
+            .errs
+            .err(path, format!("Function {} requires an `int` (i32) argument but got {:?}", func, t.type_.type_));
+    }
+}
+
+fn check_string(ctx: &mut PgQueryCtx, path: &rpds::Vector<String>, func: &str, t: &Type) {
+    if !matches!(t.type_.type_, SimpleSimpleType::String) {
+        ctx
+            .errs
+            .err(path, format!("Function {} requires a string argument but got {:?}", func, t.type_.type_));
+    }
+}
+
+fn call(func: &str, args: Vec<Expr>, compute_type: ComputeType) -> Expr {
+    return Expr::Call {
+        func: func.to_string(),
+        args: args,
+        compute_type: compute_type,
+    };
+}
+
+// # Aggregates
+/// `count(expr)` - the number of rows where `expr` isn't null. Never null.
+pub fn count(expr: Expr) -> Expr {
+    return call("count", vec![expr], ComputeType::new(|_ctx, _path, _args| {
+        return Some(simple_type(SimpleSimpleType::I64, false));
+    }));
+}
+
+/// `count(1)` - the number of rows. Never null.
+pub fn count_rows() -> Expr {
+    return count(Expr::LitI32(1));
+}
+
+/// `sum(expr)`. Integer sums are `bigint`, float sums keep the argument's type.
+/// The sum of `bigint` is `numeric` which isn't supported. The result is null if
+/// there are no (non-null) rows.
+pub fn sum(expr: Expr) -> Expr {
+    return call("sum", vec![expr], ComputeType::new(|ctx, path, args| {
+        let t = arg_type(ctx, path, &args, 0)?;
+        match t.type_.type_ {
+            SimpleSimpleType::I32 => return Some(simple_type(SimpleSimpleType::I64, true)),
+            SimpleSimpleType::F32 | SimpleSimpleType::F64 => return Some(simple_type(t.type_.type_, true)),
+            _ => {
+                ctx
+                    .errs
+                    .err(
+                        path,
+                        format!(
+                            "Function sum requires an i32 or float argument (the sum of bigint is `numeric` which isn't supported) but got {:?}",
+                            t.type_.type_
+                        ),
+                    );
+                return None;
+            },
+        }
+    }));
+}
+
+/// `avg(expr)` of a float expression. Null if there are no (non-null) rows.
+pub fn avg(expr: Expr) -> Expr {
+    return call("avg", vec![expr], ComputeType::new(|ctx, path, args| {
+        let t = arg_type(ctx, path, &args, 0)?;
+        check_float(ctx, path, "avg", &t);
+        return Some(simple_type(SimpleSimpleType::F64, true));
+    }));
+}
+
+/// Aggregate `min(expr)`. The result is null if there are no (non-null) rows.
+pub fn min(expr: Expr) -> Expr {
+    return call("min", vec![expr], ComputeType::new(|ctx, path, args| {
+        let mut t = arg_type(ctx, path, &args, 0)?;
+        t.opt = true;
+        return Some(t);
+    }));
+}
+
+/// Aggregate `max(expr)`. The result is null if there are no (non-null) rows.
+pub fn max(expr: Expr) -> Expr {
+    return call("max", vec![expr], ComputeType::new(|ctx, path, args| {
+        let mut t = arg_type(ctx, path, &args, 0)?;
+        t.opt = true;
+        return Some(t);
+    }));
+}
+
+/// `string_agg(expr, separator)` - concatenates string values. Null if there are
+/// no (non-null) rows.
+pub fn string_agg(expr: Expr, separator: impl Into<String>) -> Expr {
+    return call(
+        "string_agg",
+        vec![expr, Expr::LitString(separator.into())],
+        ComputeType::new(|ctx, path, args| {
+            let t = arg_type(ctx, path, &args, 0)?;
+            check_string(ctx, path, "string_agg", &t);
+            return Some(simple_type(SimpleSimpleType::String, true));
+        }),
+    );
+}
+
+// # Scalar
+/// `coalesce(a, b, ...)` - the first non-null argument. All arguments must have
+/// the same type, and the result is only nullable if the last argument is.
+pub fn coalesce(exprs: Vec<Expr>) -> Expr {
+    return call("coalesce", exprs, ComputeType::new(|ctx, path, args| {
+        if args.len() < 2 {
+            ctx.errs.err(path, format!("Coalesce requires at least two arguments but got {}", args.len()));
+            return None;
+        }
+        let mut out: Option<Type> = None;
+        for i in 0 .. args.len() {
+            let t = arg_type(ctx, path, &args, i)?;
+            match &out {
+                Some(first) => {
+                    if first.type_ != t.type_ {
+                        ctx
+                            .errs
+                            .err(
+                                path,
+                                format!(
+                                    "Coalesce argument {} has type {:?} but argument 0 has type {:?}",
+                                    i,
+                                    t.type_,
+                                    first.type_
+                                ),
+                            );
+                    }
+                },
+                None => { },
+            }
+            let opt = t.opt;
+            out = Some(Type {
+                opt: opt,
+                ..out.unwrap_or(t)
+            });
+        }
+        return out;
+    }));
+}
+
+/// `nullif(a, b)` - `a`, or null if `a` equals `b`.
+pub fn nullif(a: Expr, b: Expr) -> Expr {
+    return call("nullif", vec![a, b], ComputeType::new(|ctx, path, args| {
+        let mut t = arg_type(ctx, path, &args, 0)?;
+        let b = arg_type(ctx, path, &args, 1)?;
+        if t.type_ != b.type_ {
+            ctx.errs.err(path, format!("Nullif arguments have differing types: {:?} and {:?}", t.type_, b.type_));
+        }
+        t.opt = true;
+        return Some(t);
+    }));
+}
+
+/// `abs(expr)` of a numeric expression, with the argument's type.
+pub fn abs(expr: Expr) -> Expr {
+    return call("abs", vec![expr], ComputeType::new(|ctx, path, args| {
+        let t = arg_type(ctx, path, &args, 0)?;
+        check_numeric(ctx, path, "abs", &t);
+        if t.type_.type_ == SimpleSimpleType::Auto {
+            return Some(simple_type(SimpleSimpleType::I64, t.opt));
+        }
+        return Some(t);
+    }));
+}
+
+/// `round(expr)` of a float expression, to the nearest integer.
+pub fn round(expr: Expr) -> Expr {
+    return call("round", vec![expr], ComputeType::new(|ctx, path, args| {
+        let t = arg_type(ctx, path, &args, 0)?;
+        check_float(ctx, path, "round", &t);
+        return Some(simple_type(SimpleSimpleType::F64, t.opt));
+    }));
+}
+
+/// A function of a string and further arguments, each checked with `check_rest`.
+fn string_to_string(
+    func: &'static str,
+    args: Vec<Expr>,
+    check_rest: fn(&mut PgQueryCtx, &rpds::Vector<String>, &str, &Type),
+) -> Expr {
+    return call(func, args, ComputeType::new(move |ctx, path, args| {
+        let mut opt = false;
+        for i in 0 .. args.len() {
+            let t = arg_type(ctx, path, &args, i)?;
+            if i == 0 {
+                check_string(ctx, path, func, &t);
+            } else {
+                check_rest(ctx, path, func, &t);
+            }
+            opt = opt || t.opt;
+        }
+        return Some(simple_type(SimpleSimpleType::String, opt));
+    }));
+}
+
+/// `lower(expr)` of a string.
+pub fn lower(expr: Expr) -> Expr {
+    return string_to_string("lower", vec![expr], check_string);
+}
+
+/// `upper(expr)` of a string.
+pub fn upper(expr: Expr) -> Expr {
+    return string_to_string("upper", vec![expr], check_string);
+}
+
+/// `trim(expr)` - removes leading and trailing spaces from a string.
+pub fn trim(expr: Expr) -> Expr {
+    return string_to_string("trim", vec![expr], check_string);
+}
+
+/// `substr(expr, start, len)` - `start` is 1-based, `start` and `len` must be
+/// i32.
+pub fn substr(expr: Expr, start: Expr, len: Expr) -> Expr {
+    return string_to_string("substr", vec![expr, start, len], check_i32);
+}
+
+/// `replace(expr, from, to)` - replaces all occurrences of `from` with `to`.
+pub fn replace(expr: Expr, from: Expr, to: Expr) -> Expr {
+    return string_to_string("replace", vec![expr, from, to], check_string);
+}
+
+/// `length(expr)` - the number of characters in a string or bytes in a `bytea`.
+pub fn length(expr: Expr) -> Expr {
+    return call("length", vec![expr], ComputeType::new(|ctx, path, args| {
+        let t = arg_type(ctx, path, &args, 0)?;
+        if !matches!(t.type_.type_, SimpleSimpleType::String | SimpleSimpleType::Bytes) {
+            ctx
+                .errs
+                .err(
+                    path,
+                    format!("Function length requires a string or bytes argument but got {:?}", t.type_.type_),
+                );
+        }
+        return Some(simple_type(SimpleSimpleType::I32, t.opt));
+    }));
+}
+
+// # Sequences
+/// `nextval('seq')` - advance the sequence and return the new value.
+pub fn nextval(seq: &Sequence) -> Expr {
+    return call(
+        "nextval",
+        vec![Expr::LitString(seq.sql_id())],
+        ComputeType::new(|_ctx, _path, _args| {
+            return Some(simple_type(SimpleSimpleType::I64, false));
+        }),
+    );
+}
+
+// # Settings
+/// `current_setting('name', missing_ok)` - a configuration parameter, like one set
+/// per connection with `set app.tenant = '...'`. Settings are text, convert them
+/// with `Expr::Convert` to compare with other types. If `missing_ok` the result
+/// is null when the setting has never been set in the session, otherwise that's an
+/// error. Once set, a custom setting stays defined - after `reset` (or the end of
+/// the transaction for `set local`) it's the empty string, not null. See
+/// `current_setting_opt`.
+pub fn current_setting(name: impl ToString, missing_ok: bool) -> Expr {
+    return call(
+        "current_setting",
+        vec![Expr::LitString(name.to_string()), Expr::LitBool(missing_ok)],
+        ComputeType::new(move |_ctx, _path, _args| {
+            return Some(simple_type(SimpleSimpleType::String, missing_ok));
+        }),
+    );
+}
+
+/// `nullif(current_setting('name', true), '')` - a configuration parameter, or
+/// null if it was never set or has been reset.
+pub fn current_setting_opt(name: impl ToString) -> Expr {
+    return nullif(current_setting(name, true), Expr::LitString("".into()));
+}
+
+// # Date
+/// `now()` - the start time of the current transaction.
+#[cfg(feature = "chrono")]
+pub fn now() -> Expr {
+    return call("now", vec![], ComputeType::new(|_ctx, _path, _args| {
+        return Some(simple_type(SimpleSimpleType::UtcTimeChrono, false));
+    }));
+}
+
+/// `now()` - the start time of the current transaction.
+#[cfg(feature = "jiff")]
+pub fn now_jiff() -> Expr {
+    return call("now", vec![], ComputeType::new(|_ctx, _path, _args| {
+        return Some(simple_type(SimpleSimpleType::UtcTimeJiff, false));
+    }));
+}
+
+/// `date_trunc(unit, expr)` - truncates a timestamp to `unit` (`"day"`, `"hour"`,
+/// etc).
+pub fn date_trunc(unit: impl Into<String>, expr: Expr) -> Expr {
+    return call(
+        "date_trunc",
+        vec![Expr::LitString(unit.into()), expr],
+        ComputeType::new(|ctx, path, args| {
+            let t = arg_type(ctx, path, &args, 1)?;
+            let is_time = match t.type_.type_ {
+                #[cfg(feature = "chrono")]
+                SimpleSimpleType::UtcTimeChrono | SimpleSimpleType::FixedOffsetTimeChrono => true,
+                #[cfg(feature = "jiff")]
+                SimpleSimpleType::UtcTimeJiff => true,
+                _ => false,
+            };
+            if !is_time {
+                ctx
+                    .errs
+                    .err(path, format!("Function date_trunc requires a timestamp argument but got {:?}", t.type_.type_));
+            }
+            return Some(t);
+        }),
+    );
+}
+
+// # Text search
+fn string_to_text_search(func: &'static str, config: String, expr: Expr, out: SimpleSimpleType) -> Expr {
+    return call(func, vec![Expr::LitString(config), expr], ComputeType::new(move |ctx, path, args| {
+        let t = arg_type(ctx, path, &args, 1)?;
+        check_string(ctx, path, func, &t);
+        return Some(simple_type(out.clone(), t.opt));
+    }));
+}
+
+/// `to_tsvector(config, expr)` - parses and normalizes a string into a `tsvector`
+/// using the text search configuration `config` (like `"english"`).
+pub fn to_tsvector(config: impl Into<String>, expr: Expr) -> Expr {
+    return string_to_text_search("to_tsvector", config.into(), expr, SimpleSimpleType::TsVector);
+}
+
+/// `plainto_tsquery(config, expr)` - a `tsquery` matching all the words in a
+/// string, ignoring punctuation.
+pub fn plainto_tsquery(config: impl Into<String>, expr: Expr) -> Expr {
+    return string_to_text_search("plainto_tsquery", config.into(), expr, SimpleSimpleType::TsQuery);
+}
+
+/// `websearch_to_tsquery(config, expr)` - a `tsquery` from search engine style
+/// input, with `"quoted phrases"`, `or` and `-excluded` words. Never fails on bad
+/// syntax, so it's safe to use with user input.
+pub fn websearch_to_tsquery(config: impl Into<String>, expr: Expr) -> Expr {
+    return string_to_text_search("websearch_to_tsquery", config.into(), expr, SimpleSimpleType::TsQuery);
+}
+
+/// `ts_rank(vector, query)` - how well a `tsvector` matches a `tsquery`, for
+/// ordering search results. Higher is better.
+pub fn ts_rank(vector: Expr, query: Expr) -> Expr {
+    return call("ts_rank", vec![vector, query], ComputeType::new(|ctx, path, args| {
+        let vector = arg_type(ctx, path, &args, 0)?;
+        let query = arg_type(ctx, path, &args, 1)?;
+        if !matches!(vector.type_.type_, SimpleSimpleType::TsVector) {
+            ctx
+                .errs
+                .err(path, format!("Function ts_rank requires a tsvector first argument but got {:?}", vector.type_.type_));
+        }
+        if !matches!(query.type_.type_, SimpleSimpleType::TsQuery) {
+            ctx
+                .errs
+                .err(path, format!("Function ts_rank requires a tsquery second argument but got {:?}", query.type_.type_));
+        }
+        return Some(simple_type(SimpleSimpleType::F32, vector.opt || query.opt));
+    }));
+}
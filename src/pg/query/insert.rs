@@ -8,7 +8,10 @@ use crate::{
     pg::{
         QueryResCount,
         schema::{
-            field::Field,
+            field::{
+                Field,
+                Identity,
+            },
             table::Table,
         },
         types::SimpleSimpleType,
@@ -55,6 +58,9 @@ impl QueryBody for Insert {
         // Prep
         let mut check_inserting_fields = HashSet::new();
         for p in &self.values {
+            if p.0.type_.identity == Some(Identity::Always) {
+                ctx.errs.err(path, format!("Field {} is always generated and can't be inserted", p.0));
+            }
             if p.0.type_.type_.opt {
                 continue;
             }
@@ -72,7 +78,7 @@ impl QueryBody for Insert {
         } {
             scope.insert(ExprValName::field(field), v.clone());
             if !field.type_.type_.opt && field.type_.type_.type_.type_ != SimpleSimpleType::Auto &&
-                !check_inserting_fields.remove(field) {
+                field.type_.identity.is_none() && !check_inserting_fields.remove(field) {
                 ctx.errs.err(path, format!("{} is a non-optional field but is missing in insert", field));
             }
         }
@@ -80,7 +86,7 @@ impl QueryBody for Insert {
 
         // Build query
         let mut out = Tokens::new();
-        out.s("insert into").id(&self.table.id).s("(");
+        out.s("insert into").s(&self.table.sql_id()).s("(");
         for (i, (field, _)) in self.values.iter().enumerate() {
             if i > 0 {
                 out.s(",");
@@ -135,7 +141,10 @@ impl QueryBody for Insert {
             (QueryResCount::Many, _) => {
                 ctx.errs.err(path, format!("Insert can at most return one row, but res count is many"));
             },
-            (QueryResCount::None, _) | (QueryResCount::One, None) | (QueryResCount::MaybeOne, None) => {
+            (QueryResCount::None, _) |
+            (QueryResCount::One, None) |
+            (QueryResCount::MaybeOne, None) |
+            (QueryResCount::Auto, _) => {
                 // handled elsewhere, nop
             },
             (QueryResCount::One, Some(InsertConflict::DoUpdate { .. })) |
@@ -146,4 +155,14 @@ impl QueryBody for Insert {
         let out_type = build_returning(ctx, path, &scope, &mut out, &self.returning, res_count);
         (out_type, out)
     }
+
+    fn infer_res_count(&self, _ctx: &super::utils::PgQueryCtx) -> Option<(QueryResCount, Option<String>)> {
+        if self.returning.is_empty() {
+            return Some((QueryResCount::None, None));
+        }
+        match self.on_conflict {
+            Some(InsertConflict::DoNothing) => return Some((QueryResCount::MaybeOne, None)),
+            _ => return Some((QueryResCount::One, None)),
+        }
+    }
 }
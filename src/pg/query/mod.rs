@@ -5,3 +5,4 @@ pub mod update;
 pub mod expr;
 pub mod insert;
 pub mod helpers;
+pub mod functions;
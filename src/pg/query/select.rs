@@ -1,10 +1,18 @@
-use std::collections::HashMap;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 use crate::{
-    utils::Tokens,
+    utils::{
+        Tokens,
+        Errs,
+    },
     pg::{
         types::{
             Type,
+            SimpleSimpleType,
             type_i64,
+            to_sql_type,
         },
         QueryResCount,
         schema::{
@@ -16,14 +24,18 @@ use super::{
     utils::{
         QueryBody,
         PgQueryCtx,
+        Paginated,
+        unproven_sources,
         build_returning_values,
     },
     expr::{
+        BinOp,
         Expr,
         ExprType,
         check_bool,
         ExprValName,
         check_general_same,
+        check_same,
     },
 };
 
@@ -31,12 +43,49 @@ use super::{
 pub enum Order {
     Asc,
     Desc,
+    AscNullsFirst,
+    AscNullsLast,
+    DescNullsFirst,
+    DescNullsLast,
+}
+
+impl Order {
+    pub(crate) fn to_sql(&self) -> &'static str {
+        match self {
+            Order::Asc => "asc",
+            Order::Desc => "desc",
+            Order::AscNullsFirst => "asc nulls first",
+            Order::AscNullsLast => "asc nulls last",
+            Order::DescNullsFirst => "desc nulls first",
+            Order::DescNullsLast => "desc nulls last",
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum JoinSource {
     Subsel(Box<Select>),
     Table(Table),
+    /// A `LATERAL` subselect, which can refer to fields of sources earlier in the
+    /// `FROM` clause. Use this for things like top-N-per-group queries.
+    Lateral(Box<Select>),
+    /// A table-valued function like `unnest` or `generate_series`. The arguments can
+    /// refer to fields of sources earlier in the `FROM` clause. The output columns
+    /// are named and typed by `columns` and bound to the alias (or the function name
+    /// if there's no alias).
+    Function {
+        func: String,
+        args: Vec<Expr>,
+        columns: Vec<(String, Type)>,
+    },
+    /// An inline table, like `(VALUES (1, 'a'), (2, 'b')) AS alias (id, name)`.
+    /// Every row must have one expression per column, and each column's type must
+    /// be the same across rows (it's nullable if any row's value is nullable). The
+    /// columns are bound to the alias, which is required.
+    Values {
+        columns: Vec<String>,
+        rows: Vec<Vec<Expr>>,
+    },
 }
 
 #[derive(Clone, Debug)]
@@ -46,14 +95,139 @@ pub struct NamedSelectSource {
 }
 
 impl NamedSelectSource {
-    fn build(&self, ctx: &mut PgQueryCtx, path: &rpds::Vector<String>) -> (Vec<(ExprValName, Type)>, Tokens) {
+    fn build(
+        &self,
+        ctx: &mut PgQueryCtx,
+        path: &rpds::Vector<String>,
+        scope: &HashMap<ExprValName, Type>,
+    ) -> (Vec<(ExprValName, Type)>, Tokens) {
         let mut out = Tokens::new();
         let mut new_fields: Vec<(ExprValName, Type)> = match &self.source {
             JoinSource::Subsel(s) => {
-                let res = s.build(ctx, &path.push_back(format!("From subselect")), QueryResCount::Many);
+                let res = s.build_scoped(ctx, &HashMap::new(), &path.push_back(format!("From subselect")), QueryResCount::Many);
                 out.s("(").s(&res.1.to_string()).s(")");
                 res.0.0.clone()
             },
+            JoinSource::Lateral(s) => {
+                let res =
+                    s.build_scoped(ctx, scope, &path.push_back(format!("From lateral subselect")), QueryResCount::Many);
+                out.s("lateral (").s(&res.1.to_string()).s(")");
+                res.0.0.clone()
+            },
+            JoinSource::Function { func, args, columns } => {
+                let path = path.push_back(format!("From function [{}]", func));
+                out.s(func).s("(");
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        out.s(",");
+                    }
+                    let (_, tokens) = arg.build(ctx, &path.push_back(format!("Arg {}", i)), scope);
+                    out.s(&tokens.to_string());
+                }
+                out.s(")");
+
+                // Postgres needs an alias to name the output columns
+                let alias = self.alias.as_ref().unwrap_or(func);
+                out.s("as").id(alias).s("(");
+                for (i, (id, _)) in columns.iter().enumerate() {
+                    if i > 0 {
+                        out.s(",");
+                    }
+                    out.id(id);
+                }
+                out.s(")");
+                return (columns.iter().map(|(id, t)| (ExprValName {
+                    table_id: alias.clone(),
+                    id: id.clone(),
+                }, t.clone())).collect(), out);
+            },
+            JoinSource::Values { columns, rows } => {
+                let path = path.push_back(format!("From values"));
+                let Some(alias) = &self.alias else {
+                    ctx.errs.err(&path, format!("Values sources must have an alias"));
+                    return (vec![], Tokens::new());
+                };
+                if rows.is_empty() {
+                    ctx.errs.err(&path, format!("Values sources must have at least one row"));
+                    return (vec![], Tokens::new());
+                }
+                let mut types: Vec<Option<Type>> = vec![None; columns.len()];
+                out.s("( values");
+                for (i, row) in rows.iter().enumerate() {
+                    let path = path.push_back(format!("Row {}", i));
+                    if row.len() != columns.len() {
+                        ctx
+                            .errs
+                            .err(
+                                &path,
+                                format!("Row has {} values but there are {} columns", row.len(), columns.len()),
+                            );
+                        continue;
+                    }
+                    if i > 0 {
+                        out.s(",");
+                    }
+                    out.s("(");
+                    for (j, (e, t)) in row.iter().zip(types.iter_mut()).enumerate() {
+                        let path = path.push_back(format!("Column {} ({})", j, columns[j]));
+                        if j > 0 {
+                            out.s(",");
+                        }
+                        let (got, tokens) = e.build(ctx, &path, &HashMap::new());
+                        let Some((_, got)) = got.assert_scalar(&mut ctx.errs, &path) else {
+                            continue;
+                        };
+
+                        // Cast everything so parameters and literals get a definite type
+                        out.s("cast (").s(&tokens.to_string()).s("as");
+                        match got.type_.type_ {
+                            // `bigserial` is only valid in column definitions
+                            SimpleSimpleType::Auto => out.s("bigint )"),
+                            ref t => out.s(&format!("{} )", to_sql_type(t))),
+                        };
+                        match t {
+                            Some(t) => {
+                                check_same(
+                                    &mut ctx.errs,
+                                    &path,
+                                    &ExprType(vec![(ExprValName::empty(), Type {
+                                        opt: false,
+                                        ..t.clone()
+                                    })]),
+                                    &ExprType(vec![(ExprValName::empty(), Type {
+                                        opt: false,
+                                        ..got.clone()
+                                    })]),
+                                );
+                                t.opt = t.opt || got.opt;
+                            },
+                            None => {
+                                *t = Some(got);
+                            },
+                        }
+                    }
+                    out.s(")");
+                }
+                out.s(") as").id(alias).s("(");
+                for (i, id) in columns.iter().enumerate() {
+                    if i > 0 {
+                        out.s(",");
+                    }
+                    out.id(id);
+                }
+                out.s(")");
+                return (
+                    columns
+                        .iter()
+                        .zip(types)
+                        .filter_map(|(id, t)| Some((ExprValName {
+                            table_id: alias.clone(),
+                            id: id.clone(),
+                        }, t?)))
+                        .collect(),
+                    out,
+                );
+            },
             JoinSource::Table(s) => {
                 let new_fields = match ctx.tables.get(&s) {
                     Some(f) => f,
@@ -64,7 +238,7 @@ impl NamedSelectSource {
                         return (vec![], Tokens::new());
                     },
                 };
-                out.id(&s.id);
+                out.s(&s.sql_id());
                 new_fields.iter().map(|e| (ExprValName::field(e.0), e.1.clone())).collect()
             },
         };
@@ -99,15 +273,78 @@ pub struct Returning {
     pub rename: Option<String>,
 }
 
+/// Lets the caller choose the `ORDER BY` clause at runtime from a fixed set of
+/// choices. A Rust enum named `name` is generated with one variant per choice, and
+/// the query function takes a value of it as the `order` argument. Each variant is
+/// a separate SQL statement. The clauses of the chosen variant come before any
+/// `order` clauses, which can be used as tie-breakers.
+#[derive(Clone, Debug)]
+pub struct OrderChoice {
+    pub name: String,
+    pub variants: Vec<(String, Vec<(Expr, Order)>)>,
+}
+
+#[derive(Clone, Debug)]
+pub enum LockStrength {
+    Update,
+    NoKeyUpdate,
+    Share,
+    KeyShare,
+}
+
+impl LockStrength {
+    pub(crate) fn to_sql(&self) -> &'static str {
+        match self {
+            LockStrength::Update => "for update",
+            LockStrength::NoKeyUpdate => "for no key update",
+            LockStrength::Share => "for share",
+            LockStrength::KeyShare => "for key share",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum LockWait {
+    /// Wait for conflicting locks to be released (the default).
+    Wait,
+    /// Fail immediately if a row can't be locked.
+    NoWait,
+    /// Skip rows that can't be locked immediately, for example to take jobs from a
+    /// queue table with multiple workers.
+    SkipLocked,
+}
+
+/// A row locking clause like `FOR UPDATE OF jobs SKIP LOCKED`. `of` are the names
+/// of table sources (the alias, or the table id if there's no alias) to lock; if
+/// it's empty every table in the `FROM` clause is locked, including the tables in
+/// subselects, so those subselects can't group or aggregate either. Tables on the
+/// nullable side of a left join can't be locked.
+#[derive(Clone, Debug)]
+pub struct Lock {
+    pub strength: LockStrength,
+    pub of: Vec<String>,
+    pub wait: LockWait,
+}
+
 #[derive(Clone, Debug)]
 pub struct Select {
     pub table: NamedSelectSource,
+    /// `DISTINCT ON` expressions, see `SelectBuilder::distinct_on`.
+    pub distinct_on: Vec<Expr>,
     pub returning: Vec<Returning>,
     pub join: Vec<Join>,
     pub where_: Option<Expr>,
     pub group: Vec<Expr>,
+    pub having: Option<Expr>,
     pub order: Vec<(Expr, Order)>,
     pub(crate) limit: Option<Expr>,
+    pub(crate) offset: Option<Expr>,
+    /// Only used for the top level query.
+    pub order_choice: Option<OrderChoice>,
+    /// Keyset pagination order, see `SelectBuilder::paginate_by`. Only used for the
+    /// top level query.
+    pub paginate: Option<Vec<(Expr, Order)>>,
+    pub lock: Vec<Lock>,
 }
 
 impl QueryBody for Select {
@@ -116,14 +353,170 @@ impl QueryBody for Select {
         ctx: &mut super::utils::PgQueryCtx,
         path: &rpds::Vector<String>,
         res_count: QueryResCount,
+    ) -> (ExprType, Tokens) {
+        return self.build_scoped(ctx, &HashMap::new(), path, res_count);
+    }
+
+    fn build_order_choices(
+        &self,
+        ctx: &mut PgQueryCtx,
+        path: &rpds::Vector<String>,
+        res_count: QueryResCount,
+    ) -> Option<(String, Vec<(String, ExprType, Tokens)>)> {
+        let choice = self.order_choice.as_ref()?;
+        let mut out = vec![];
+        for (name, order) in &choice.variants {
+            let mut variant = self.clone();
+            variant.order_choice = None;
+            variant.order = order.iter().cloned().chain(self.order.iter().cloned()).collect();
+            let (t, tokens) =
+                variant.build_scoped(
+                    ctx,
+                    &HashMap::new(),
+                    &path.push_back(format!("Order choice {}", name)),
+                    res_count.clone(),
+                );
+            out.push((name.clone(), t, tokens));
+        }
+        return Some((choice.name.clone(), out));
+    }
+
+    fn infer_res_count(&self, ctx: &PgQueryCtx) -> Option<(QueryResCount, Option<String>)> {
+        if self.paginate.is_some() {
+            return Some((QueryResCount::Many, None));
+        }
+        if self.group.is_empty() &&
+            (self.having.is_some() || self.returning.iter().any(|r| r.e.contains_aggregate())) {
+            if self.having.is_none() && self.limit.is_none() && self.offset.is_none() {
+                return Some((QueryResCount::One, None));
+            }
+            return Some((QueryResCount::MaybeOne, None));
+        }
+        if matches!(self.limit, Some(Expr::LitI32(0 ..= 1) | Expr::LitI64(0 ..= 1))) {
+            return Some((QueryResCount::MaybeOne, None));
+        }
+        fn source(s: &NamedSelectSource) -> (Option<&Table>, String) {
+            match &s.source {
+                JoinSource::Table(t) => return (Some(t), s.alias.clone().unwrap_or_else(|| t.id.clone())),
+                _ => return (None, s.alias.clone().unwrap_or_default()),
+            }
+        }
+        let mut equalities = vec![];
+        if let Some(where_) = &self.where_ {
+            where_.equalities_if_true(&mut equalities);
+        }
+        let (table, id) = source(&self.table);
+        let mut sources = vec![(table, id, vec![])];
+        for je in &self.join {
+            // Left join conditions only constrain the joined source
+            let mut own_equalities = vec![];
+            match je.type_ {
+                JoinType::Left => je.on.equalities_if_true(&mut own_equalities),
+                JoinType::Inner => je.on.equalities_if_true(&mut equalities),
+            }
+            let (table, id) = source(&je.source);
+            sources.push((table, id, own_equalities));
+        }
+        match unproven_sources(ctx, &sources, &equalities) {
+            None => return Some((QueryResCount::MaybeOne, None)),
+            Some(unproven) if unproven.is_empty() => return Some((QueryResCount::Many, None)),
+            Some(unproven) => return Some((QueryResCount::Many, Some(unproven.join("; ")))),
+        }
+    }
+
+    fn build_paginated(&self, ctx: &mut PgQueryCtx, path: &rpds::Vector<String>) -> Option<Paginated> {
+        let paginate = self.paginate.as_ref()?;
+        let path = path.push_back(format!("Paginate"));
+        if paginate.is_empty() {
+            ctx.errs.err(&path, format!("Pagination needs at least one order clause"));
+        }
+        if !self.order.is_empty() {
+            ctx.errs.err(&path, format!("Paginated queries are ordered by the pagination clauses, but `order` is set"));
+        }
+        if self.limit.is_some() {
+            ctx.errs.err(&path, format!("Paginated queries are limited by the page size, but `limit` is set"));
+        }
+        if self.order_choice.is_some() {
+            ctx.errs.err(&path, format!("Paginated queries can't have an order choice"));
+        }
+        let mut select = self.clone();
+        select.paginate = None;
+        select.order = paginate.clone();
+        // Fetch one extra row to tell whether there's a next page. The cast keeps
+        // postgres from inferring `int4` for the parameter.
+        select.limit = Some(Expr::BinOp {
+            left: Box::new(Expr::Convert {
+                expr: Box::new(Expr::Param {
+                    name: "page_size".into(),
+                    type_: type_i64().build(),
+                }),
+                type_: SimpleSimpleType::I64,
+            }),
+            op: BinOp::Plus,
+            right: Box::new(Expr::LitI64(1)),
+        });
+        for (i, (e, _)) in paginate.iter().enumerate() {
+            select.returning.push(Returning {
+                e: e.clone(),
+                rename: Some(format!("cursor_{}", i)),
+            });
+        }
+
+        // Build the first page query first so the cursor params are numbered last
+        let (res_type, first) = select.build_scoped(ctx, &HashMap::new(), &path, QueryResCount::Many);
+        let (_, next) =
+            select.build_after(
+                ctx,
+                &HashMap::new(),
+                &path.push_back(format!("Next")),
+                QueryResCount::Many,
+                Some(paginate),
+            );
+        let cursor_args =
+            (0 .. paginate.len())
+                .filter_map(|i| ctx.rust_arg_lookup.get(&format!("cursor_{}", i)).map(|a| a.0))
+                .collect::<Vec<_>>();
+        return Some(Paginated {
+            res_type: res_type,
+            cursor_len: paginate.len(),
+            first: first,
+            cursor_args: cursor_args,
+            next: next,
+        });
+    }
+}
+
+impl Select {
+    /// Build the select with fields from an outer query in scope (for lateral
+    /// subselects).
+    pub(crate) fn build_scoped(
+        &self,
+        ctx: &mut PgQueryCtx,
+        inject_scope: &HashMap<ExprValName, Type>,
+        path: &rpds::Vector<String>,
+        res_count: QueryResCount,
+    ) -> (ExprType, Tokens) {
+        return self.build_after(ctx, inject_scope, path, res_count, None);
+    }
+
+    /// Build the select, optionally only returning rows after a keyset pagination
+    /// cursor. `after` is the pagination order, see `build_cursor_condition`.
+    pub(crate) fn build_after(
+        &self,
+        ctx: &mut PgQueryCtx,
+        inject_scope: &HashMap<ExprValName, Type>,
+        path: &rpds::Vector<String>,
+        res_count: QueryResCount,
+        after: Option<&[(Expr, Order)]>,
     ) -> (ExprType, Tokens) {
         // Prep
-        let source = self.table.build(ctx, path);
-        let mut fields = HashMap::new();
+        let source = self.table.build(ctx, path, inject_scope);
+        let mut scope = inject_scope.clone();
+        let mut local_bindings = HashSet::new();
         for (k, v) in source.0 {
-            fields.insert(k, v);
+            local_bindings.insert(k.clone());
+            scope.insert(k, v);
         }
-        let mut scope = fields.clone();
         let mut joins = vec![];
         for (i, je) in self.join.iter().enumerate() {
             let path = path.push_back(format!("Join {}", i));
@@ -133,8 +526,9 @@ impl QueryBody for Select {
                 JoinType::Inner => out.s("inner"),
             };
             out.s("join");
-            let source = je.source.build(ctx, &path);
+            let source = je.source.build(ctx, &path, &scope);
             out.s(&source.1.to_string());
+            local_bindings.extend(source.0.iter().map(|e| e.0.clone()));
             match je.type_ {
                 JoinType::Left => {
                     for (k, mut v) in source.0 {
@@ -157,9 +551,70 @@ impl QueryBody for Select {
             joins.push(out.to_string());
         }
 
+        // Narrow nullable bindings using the where and inner join conditions
+        let mut non_null = HashSet::new();
+        if let Some(where_) = &self.where_ {
+            where_.non_null_if_true(&mut non_null);
+        }
+        for je in &self.join {
+            if let JoinType::Inner = je.type_ {
+                je.on.non_null_if_true(&mut non_null);
+            }
+        }
+        for b in non_null {
+            if let Some(t) = scope.get_mut(&b) {
+                t.opt = false;
+            }
+        }
+
         // Build query
         let mut out = Tokens::new();
         out.s("select");
+        if !self.distinct_on.is_empty() {
+            out.s("distinct on (");
+            for (i, d) in self.distinct_on.iter().enumerate() {
+                let path = path.push_back(format!("Distinct on clause {}", i));
+                if i > 0 {
+                    out.s(",");
+                }
+                let (_, d_tokens) = d.build(ctx, &path, &scope);
+                out.s(&d_tokens.to_string());
+            }
+            out.s(")");
+
+            // Postgres requires the leading order clauses to be the distinct expressions
+            let mut unordered = self.distinct_on.iter().enumerate().collect::<Vec<_>>();
+            for (i, o) in self.order.iter().enumerate() {
+                if unordered.is_empty() {
+                    break;
+                }
+                if !self.distinct_on.iter().any(|d| d.same_as(&o.0)) {
+                    ctx
+                        .errs
+                        .err(
+                            &path.push_back(format!("Order by clause {}", i)),
+                            format!(
+                                "Order clauses must start with the distinct on expressions, but this comes before distinct on clauses {:?}",
+                                unordered.iter().map(|(j, _)| *j).collect::<Vec<_>>()
+                            ),
+                        );
+                    unordered.clear();
+                    break;
+                }
+                unordered.retain(|(_, d)| !d.same_as(&o.0));
+            }
+            if !self.order.is_empty() && !unordered.is_empty() {
+                ctx
+                    .errs
+                    .err(
+                        path,
+                        format!(
+                            "Order clauses must start with the distinct on expressions, but distinct on clauses {:?} aren't ordered",
+                            unordered.iter().map(|(j, _)| *j).collect::<Vec<_>>()
+                        ),
+                    );
+            }
+        }
         if self.returning.is_empty() {
             ctx.errs.err(path, format!("Select must have at least one output, but outputs are empty"));
         }
@@ -169,12 +624,26 @@ impl QueryBody for Select {
         for join in joins {
             out.s(&join);
         }
+        let mut where_parts = vec![];
         if let Some(where_) = &self.where_ {
-            out.s("where");
             let path = path.push_back("Where".into());
             let (where_t, where_tokens) = where_.build(ctx, &path, &scope);
             check_bool(ctx, &path, &where_t);
-            out.s(&where_tokens.to_string());
+            where_parts.push(where_tokens.to_string());
+        }
+        if let Some(after) = after {
+            where_parts.push(build_cursor_condition(ctx, &path.push_back("Cursor".into()), &scope, after).to_string());
+        }
+        if where_parts.len() == 1 {
+            out.s("where").s(&where_parts[0]);
+        } else if where_parts.len() > 1 {
+            out.s("where");
+            for (i, part) in where_parts.iter().enumerate() {
+                if i > 0 {
+                    out.s("and");
+                }
+                out.s("(").s(part).s(")");
+            }
         }
         if self.group.len() > 0 {
             out.s("group by");
@@ -187,6 +656,13 @@ impl QueryBody for Select {
                 out.s(&g_tokens.to_string());
             }
         }
+        if let Some(having) = &self.having {
+            out.s("having");
+            let path = path.push_back("Having".into());
+            let (having_t, having_tokens) = having.build(ctx, &path, &scope);
+            check_bool(ctx, &path, &having_t);
+            out.s(&having_tokens.to_string());
+        }
         if !self.order.is_empty() {
             out.s("order by");
             for (i, o) in self.order.iter().enumerate() {
@@ -196,10 +672,7 @@ impl QueryBody for Select {
                 }
                 let (_, o_tokens) = o.0.build(ctx, &path, &scope);
                 out.s(&o_tokens.to_string());
-                out.s(match o.1 {
-                    Order::Asc => "asc",
-                    Order::Desc => "desc",
-                });
+                out.s(o.1.to_sql());
             }
         }
         if let Some(l) = &self.limit {
@@ -209,6 +682,254 @@ impl QueryBody for Select {
             check_general_same(ctx, &path, &limit_t, &ExprType(vec![(ExprValName::empty(), type_i64().build())]));
             out.s(&limit_tokens.to_string());
         }
+        if let Some(o) = &self.offset {
+            out.s("offset");
+            let path = path.push_back("Offset".into());
+            let (offset_t, offset_tokens) = o.build(ctx, &path, &scope);
+            check_general_same(ctx, &path, &offset_t, &ExprType(vec![(ExprValName::empty(), type_i64().build())]));
+            out.s(&offset_tokens.to_string());
+        }
+        for (i, lock) in self.lock.iter().enumerate() {
+            let path = path.push_back(format!("Lock {}", i));
+            self.check_lock(ctx, &path, lock);
+            out.s(lock.strength.to_sql());
+            if !lock.of.is_empty() {
+                out.s("of");
+                for (j, of) in lock.of.iter().enumerate() {
+                    if j > 0 {
+                        out.s(",");
+                    }
+                    out.id(of);
+                }
+            }
+            match lock.wait {
+                LockWait::Wait => { },
+                LockWait::NoWait => {
+                    out.s("nowait");
+                },
+                LockWait::SkipLocked => {
+                    out.s("skip locked");
+                },
+            }
+        }
+
+        // Check all outputs are grouped or aggregated
+        if !self.group.is_empty() || self.having.is_some() || self.returning.iter().any(|r| r.e.contains_aggregate()) {
+            // Columns of a table depend on its primary key, so they can be used if the whole
+            // key is grouped
+            let mut grouped_sources = HashSet::new();
+            for source in std::iter::once(&self.table).chain(self.join.iter().map(|je| je.source.as_ref())) {
+                let JoinSource::Table(t) = &source.source else {
+                    continue;
+                };
+                let Some(pk) = ctx.primary_keys.get(t) else {
+                    continue;
+                };
+                let id = source.alias.clone().unwrap_or_else(|| t.id.clone());
+                let pk_grouped = pk.iter().all(|f| {
+                    let key = Expr::Binding(ExprValName {
+                        table_id: id.clone(),
+                        id: f.id.clone(),
+                    });
+                    self.group.iter().any(|g| g.same_as(&key))
+                });
+                if pk_grouped {
+                    grouped_sources.insert(id);
+                }
+            }
+            for (i, r) in self.returning.iter().enumerate() {
+                check_grouped(
+                    &mut ctx.errs,
+                    &path.push_back(format!("Return {}", i)),
+                    &local_bindings,
+                    &grouped_sources,
+                    &self.group,
+                    &r.e,
+                );
+            }
+            if let Some(having) = &self.having {
+                check_grouped(
+                    &mut ctx.errs,
+                    &path.push_back("Having".into()),
+                    &local_bindings,
+                    &grouped_sources,
+                    &self.group,
+                    having,
+                );
+            }
+            for (i, o) in self.order.iter().enumerate() {
+                check_grouped(
+                    &mut ctx.errs,
+                    &path.push_back(format!("Order by clause {}", i)),
+                    &local_bindings,
+                    &grouped_sources,
+                    &self.group,
+                    &o.0,
+                );
+            }
+        }
         (out_type, out)
     }
+
+    fn check_lock(&self, ctx: &mut PgQueryCtx, path: &rpds::Vector<String>, lock: &Lock) {
+        // Without `of` the lock also applies to every subselect in the `FROM` clause
+        self.check_lockable(ctx, path, lock.of.is_empty());
+        let mut sources = vec![(&self.table, false)];
+        for je in &self.join {
+            sources.push((&je.source, matches!(je.type_, JoinType::Left)));
+        }
+        if lock.of.is_empty() {
+            if sources.iter().any(|(s, nullable)| *nullable && matches!(s.source, JoinSource::Table(_))) {
+                ctx
+                    .errs
+                    .err(
+                        path,
+                        format!(
+                            "Locking every table would lock the nullable side of a left join, list the tables to lock in `of`"
+                        ),
+                    );
+            }
+            return;
+        }
+        for of in &lock.of {
+            let found = sources.iter().find(|(s, _)| match &s.source {
+                JoinSource::Table(t) => s.alias.as_ref().unwrap_or(&t.id) == of,
+                _ => s.alias.as_ref() == Some(of),
+            });
+            match found {
+                Some((NamedSelectSource { source: JoinSource::Table(_), .. }, nullable)) => {
+                    if *nullable {
+                        ctx.errs.err(path, format!("Can't lock [{}], it's on the nullable side of a left join", of));
+                    }
+                },
+                Some(_) => {
+                    ctx.errs.err(path, format!("Can't lock [{}], only table sources can be locked", of));
+                },
+                None => {
+                    ctx.errs.err(path, format!("No source named [{}] to lock", of));
+                },
+            }
+        }
+    }
+
+    /// Checks that the select doesn't use anything Postgres can't combine with row
+    /// locking, and if `subselects` also checks the subselect sources (which are
+    /// locked along with the select).
+    fn check_lockable(&self, ctx: &mut PgQueryCtx, path: &rpds::Vector<String>, subselects: bool) {
+        if !self.group.is_empty() || self.having.is_some() || self.returning.iter().any(|r| r.e.contains_aggregate()) {
+            ctx.errs.err(path, format!("Row locking can't be used with grouping or aggregates"));
+        }
+        if !self.distinct_on.is_empty() {
+            ctx.errs.err(path, format!("Row locking can't be used with distinct on"));
+        }
+        if !subselects {
+            return;
+        }
+        for source in std::iter::once(&self.table).chain(self.join.iter().map(|je| je.source.as_ref())) {
+            match &source.source {
+                JoinSource::Subsel(s) | JoinSource::Lateral(s) => {
+                    s.check_lockable(ctx, &path.push_back(format!("From subselect")), true);
+                },
+                JoinSource::Table(_) | JoinSource::Function { .. } | JoinSource::Values { .. } => { },
+            }
+        }
+    }
+}
+
+/// Builds the keyset pagination condition selecting rows after the cursor, like
+/// `(a, b) > ($1, $2)`. The cursor values are parameters named `cursor_0`,
+/// `cursor_1`, etc.
+fn build_cursor_condition(
+    ctx: &mut PgQueryCtx,
+    path: &rpds::Vector<String>,
+    scope: &HashMap<ExprValName, Type>,
+    after: &[(Expr, Order)],
+) -> Tokens {
+    let mut items = vec![];
+    for (i, (e, order)) in after.iter().enumerate() {
+        let path = path.push_back(format!("Clause {}", i));
+        let (e_t, e_tokens) = e.build(ctx, &path, scope);
+        let Some((_, t)) = e_t.assert_scalar(&mut ctx.errs, &path) else {
+            return Tokens::new();
+        };
+        if t.opt {
+            ctx.errs.err(&path, format!("Pagination clause is nullable, but cursor comparisons need non-null values"));
+        }
+        let (_, param_tokens) = Expr::Param {
+            name: format!("cursor_{}", i),
+            type_: t,
+        }.build(ctx, &path, scope);
+        let desc = match order {
+            Order::Asc | Order::AscNullsFirst | Order::AscNullsLast => false,
+            Order::Desc | Order::DescNullsFirst | Order::DescNullsLast => true,
+        };
+        items.push((e_tokens.to_string(), param_tokens.to_string(), desc));
+    }
+    let mut out = Tokens::new();
+    let Some(first) = items.first() else {
+        return out;
+    };
+    if items.iter().all(|i| i.2 == first.2) {
+        out.s("(");
+        out.s(&items.iter().map(|i| i.0.as_str()).collect::<Vec<_>>().join(" , "));
+        out.s(")");
+        out.s(if first.2 {
+            "<"
+        } else {
+            ">"
+        });
+        out.s("(");
+        out.s(&items.iter().map(|i| i.1.as_str()).collect::<Vec<_>>().join(" , "));
+        out.s(")");
+    } else {
+        // Row values only compare in one direction, so expand to `a > $1 or (a = $1 and
+        // (b < $2 ...))`
+        let mut cond: Option<String> = None;
+        for (e, param, desc) in items.iter().rev() {
+            let op = if *desc {
+                "<"
+            } else {
+                ">"
+            };
+            cond = Some(match cond {
+                None => format!("{} {} {}", e, op, param),
+                Some(rest) => format!("( {} {} {} or ( {} = {} and {} ) )", e, op, param, e, param, rest),
+            });
+        }
+        out.s(&cond.unwrap());
+    }
+    return out;
+}
+
+/// In an aggregate query, every column must either be in the `GROUP BY` clause,
+/// only used within an aggregate function, or be from a table whose primary key is
+/// grouped (`grouped_sources`, by table id or alias).
+fn check_grouped(
+    errs: &mut Errs,
+    path: &rpds::Vector<String>,
+    local_bindings: &HashSet<ExprValName>,
+    grouped_sources: &HashSet<String>,
+    group: &[Expr],
+    e: &Expr,
+) {
+    if e.is_aggregate() || group.iter().any(|g| g.same_as(e)) {
+        return;
+    }
+    let name = match e {
+        Expr::Field(f) => Some(ExprValName::field(f)),
+        Expr::Binding(b) => Some(b.clone()),
+        _ => None,
+    };
+    if let Some(name) = name {
+        if local_bindings.contains(&name) && !grouped_sources.contains(&name.table_id) {
+            errs.err(
+                path,
+                format!("Column {} must appear in the group by clause or be used in an aggregate function", name),
+            );
+        }
+        return;
+    }
+    for child in e.row_children() {
+        check_grouped(errs, path, local_bindings, grouped_sources, group, child);
+    }
 }
@@ -1,5 +1,8 @@
 use std::{
-    collections::HashMap,
+    collections::{
+        HashMap,
+        HashSet,
+    },
 };
 use proc_macro2::TokenStream;
 use crate::{
@@ -7,7 +10,10 @@ use crate::{
         types::Type,
         QueryResCount,
         schema::{
-            field::Field,
+            field::{
+                Field,
+                Identity,
+            },
             table::Table,
         },
     },
@@ -30,16 +36,33 @@ pub struct PgQueryCtx<'a> {
     pub(crate) tables: &'a HashMap<Table, HashMap<Field, Type>>,
     pub errs: Errs,
     pub(crate) rust_arg_lookup: HashMap<String, (usize, Type)>,
+    /// Tuple list parameters (`Expr::ParamTuples`), with the index of the first
+    /// element array parameter and the element types.
+    pub(crate) tuple_arg_lookup: HashMap<String, (usize, Vec<Type>)>,
     pub(crate) rust_args: Vec<TokenStream>,
     pub(crate) query_args: Vec<TokenStream>,
+    /// The fields of each primary key and unique index, per table. Used to infer
+    /// result counts.
+    pub(crate) unique_keys: &'a HashMap<Table, Vec<Vec<Field>>>,
+    /// The fields of each table's primary key. Columns of a table whose primary key is
+    /// grouped can be used in grouped queries.
+    pub(crate) primary_keys: &'a HashMap<Table, Vec<Field>>,
 }
 
 impl<'a> PgQueryCtx<'a> {
-    pub(crate) fn new(errs: Errs, tables: &'a HashMap<Table, HashMap<Field, Type>>) -> Self {
+    pub(crate) fn new(
+        errs: Errs,
+        tables: &'a HashMap<Table, HashMap<Field, Type>>,
+        unique_keys: &'a HashMap<Table, Vec<Vec<Field>>>,
+        primary_keys: &'a HashMap<Table, Vec<Field>>,
+    ) -> Self {
         Self {
             tables: tables,
+            unique_keys: unique_keys,
+            primary_keys: primary_keys,
             errs: errs,
             rust_arg_lookup: Default::default(),
+            tuple_arg_lookup: Default::default(),
             rust_args: Default::default(),
             query_args: Default::default(),
         }
@@ -53,6 +76,130 @@ pub trait QueryBody {
         path: &rpds::Vector<String>,
         res_count: QueryResCount,
     ) -> (ExprType, Tokens);
+
+    /// If the caller can choose the `ORDER BY` clause at runtime, build the query
+    /// once per choice. Returns the name of the choice enum and the variant names with
+    /// their built queries.
+    fn build_order_choices(
+        &self,
+        _ctx: &mut PgQueryCtx,
+        _path: &rpds::Vector<String>,
+        _res_count: QueryResCount,
+    ) -> Option<(String, Vec<(String, ExprType, Tokens)>)> {
+        return None;
+    }
+
+    /// If the query is keyset paginated, build the queries for the first and
+    /// subsequent pages.
+    fn build_paginated(&self, _ctx: &mut PgQueryCtx, _path: &rpds::Vector<String>) -> Option<Paginated> {
+        return None;
+    }
+
+    /// The most specific result count that can be proven from the query structure and
+    /// the schema's unique keys, used for `QueryResCount::Auto` and to check declared
+    /// counts. `None` if the query doesn't support inference. For `Many`, also
+    /// returns why the query may return multiple rows if it looks like a lookup by
+    /// key, in which case a declared `One` or `MaybeOne` count is warned about.
+    fn infer_res_count(&self, _ctx: &PgQueryCtx) -> Option<(QueryResCount, Option<String>)> {
+        return None;
+    }
+}
+
+/// Checks that every source has at most one row given the `=` equalities, i.e.
+/// every source has a unique key whose fields are all equal to values that are
+/// parameters, literals, or fields of other sources already proven to have at most
+/// one row. Each source is a table (`None` for other sources, which are never
+/// proven) and the id it's bound to, along with equalities that only constrain
+/// that source (like the `ON` condition of a left join). Returns `None` if all
+/// sources were proven, otherwise descriptions of the unproven sources that have a
+/// field compared with `=` to a parameter or literal. Other sources (like a whole
+/// table read or a join) aren't described since they don't look like lookups by
+/// key.
+pub(crate) fn unproven_sources(
+    ctx: &PgQueryCtx,
+    sources: &[(Option<&Table>, String, Vec<(ExprValName, &Expr)>)],
+    equalities: &[(ExprValName, &Expr)],
+) -> Option<Vec<String>> {
+    let mut fixed = HashSet::new();
+    loop {
+        let mut progress = false;
+        for (table, id, own_equalities) in sources {
+            if fixed.contains(id) {
+                continue;
+            }
+            let Some(table) = table else {
+                continue;
+            };
+            let Some(keys) = ctx.unique_keys.get(*table) else {
+                continue;
+            };
+            let pinned = keys.iter().any(|key| key.iter().all(|field| {
+                equalities.iter().chain(own_equalities.iter()).any(|(b, v)| {
+                    &b.table_id == id && b.id == field.id && v.is_fixed(&fixed)
+                })
+            }));
+            if pinned {
+                fixed.insert(id.clone());
+                progress = true;
+            }
+        }
+        if !progress {
+            break;
+        }
+    }
+    if sources.iter().all(|(_, id, _)| fixed.contains(id)) {
+        return None;
+    }
+    let mut out = vec![];
+    for (table, id, own_equalities) in sources {
+        if fixed.contains(id) {
+            continue;
+        }
+        if !equalities
+            .iter()
+            .chain(own_equalities.iter())
+            .any(|(b, v)| &b.table_id == id && v.is_fixed(&HashSet::new())) {
+            continue;
+        }
+        let Some(table) = table else {
+            out.push(format!("`{}` isn't a table", id));
+            continue;
+        };
+        match ctx.unique_keys.get(*table) {
+            Some(keys) => {
+                out.push(
+                    format!(
+                        "not every field of a unique key of `{}` is compared with `=` ({})",
+                        id,
+                        keys
+                            .iter()
+                            .map(|k| k.iter().map(|f| f.id.clone()).collect::<Vec<_>>().join(", "))
+                            .collect::<Vec<_>>()
+                            .join(" or ")
+                    ),
+                );
+            },
+            None => {
+                out.push(format!("`{}` has no primary key or unique index", id));
+            },
+        }
+    }
+    return Some(out);
+}
+
+/// The built queries of a keyset paginated select.
+pub struct Paginated {
+    /// The result type. The last `cursor_len` elements are the cursor values for
+    /// each row.
+    pub(crate) res_type: ExprType,
+    pub(crate) cursor_len: usize,
+    /// The query for the first page, without a cursor.
+    pub(crate) first: Tokens,
+    /// The indices of the query arguments for the cursor values. `first` uses all
+    /// the other arguments.
+    pub(crate) cursor_args: Vec<usize>,
+    /// The query for pages after the cursor.
+    pub(crate) next: Tokens,
 }
 
 pub fn build_set(
@@ -68,6 +215,9 @@ pub fn build_set(
         if i > 0 {
             out.s(",");
         }
+        if field.type_.identity == Some(Identity::Always) {
+            ctx.errs.err(&path, format!("Field {} is always generated and can't be updated", field));
+        }
         out.id(&field.id).s("=");
         let res = val.build(ctx, &path, &scope);
         let field_type = match ctx.tables.get(&field.table).and_then(|t| t.get(&field)) {
@@ -90,7 +240,9 @@ pub fn build_returning_values(
     outputs: &Vec<Returning>,
     res_count: QueryResCount,
 ) -> ExprType {
-    if outputs.is_empty() {
+    if matches!(res_count, QueryResCount::Auto) {
+        ctx.errs.err(path, format!("QueryResCount::Auto can only be used for top level queries"));
+    } else if outputs.is_empty() {
         if !matches!(res_count, QueryResCount::None) {
             ctx.errs.err(path, format!("Query has no outputs but res_count is, {:?}, not None", res_count));
         }
@@ -0,0 +1,12 @@
+use std::fmt::Display;
+
+/// A PostgreSQL extension (like `pg_trgm` or `citext`) required by the schema.
+/// Extensions aren't renamed, the name identifies them across versions.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, PartialOrd, Ord)]
+pub struct Extension(pub String);
+
+impl Display for Extension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
@@ -20,14 +20,38 @@ use crate::{
         },
     },
 };
-use super::table::{
-    Table,
+use super::{
+    extension::Extension,
+    table::{
+        Table,
+    },
 };
 
+/// How values are generated for an identity column.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Identity {
+    /// Values are always generated, inserting explicit values is an error.
+    Always,
+    /// Values are generated unless an explicit value is inserted.
+    ByDefault,
+}
+
+impl Identity {
+    pub(crate) fn to_sql(&self) -> &'static str {
+        match self {
+            Identity::Always => "always",
+            Identity::ByDefault => "by default",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FieldType {
     pub type_: Type,
     pub migration_default: Option<Expr>,
+    pub identity: Option<Identity>,
+    /// Extensions to create before the field.
+    pub requires: Vec<Extension>,
 }
 
 impl FieldType {
@@ -36,6 +60,8 @@ impl FieldType {
         Self {
             type_: t.clone(),
             migration_default: None,
+            identity: None,
+            requires: vec![],
         }
     }
 
@@ -48,6 +74,8 @@ impl FieldType {
         Self {
             type_: t.clone(),
             migration_default: def,
+            identity: None,
+            requires: vec![],
         }
     }
 }
@@ -57,6 +85,8 @@ pub struct FieldBuilder {
     default_: Option<Expr>,
     opt: bool,
     custom: Option<String>,
+    identity: Option<Identity>,
+    requires: Vec<Extension>,
 }
 
 impl FieldBuilder {
@@ -66,6 +96,8 @@ impl FieldBuilder {
             opt: false,
             default_: None,
             custom: None,
+            identity: None,
+            requires: vec![],
         }
     }
 
@@ -74,6 +106,9 @@ impl FieldBuilder {
         if self.default_.is_some() {
             panic!("Optional fields can't have migration fill expressions.");
         }
+        if self.identity.is_some() {
+            panic!("Identity fields can't be optional.");
+        }
         self.opt = true;
         self
     }
@@ -85,10 +120,32 @@ impl FieldBuilder {
         if self.opt {
             panic!("Optional fields can't have migration fill expressions.");
         }
+        if self.identity.is_some() {
+            panic!("Identity fields are filled from their sequence and can't have migration fill expressions.");
+        }
         self.default_ = Some(expr);
         self
     }
 
+    /// Make this a `generated ... as identity` column, with values taken from an
+    /// implicit sequence. Unlike `field_auto` (`bigserial`) this can be added to
+    /// existing tables (existing rows are numbered), and an `auto` field can be
+    /// migrated to an `i64` identity field. Only for non-optional `i32` and `i64`
+    /// fields.
+    pub fn identity(mut self, identity: Identity) -> FieldBuilder {
+        if !matches!(self.t, SimpleSimpleType::I32 | SimpleSimpleType::I64) {
+            panic!("Identity fields must be i32 or i64.");
+        }
+        if self.opt {
+            panic!("Identity fields can't be optional.");
+        }
+        if self.default_.is_some() {
+            panic!("Identity fields are filled from their sequence and can't have migration fill expressions.");
+        }
+        self.identity = Some(identity);
+        self
+    }
+
     /// Use a custom Rust type for this field. This must be the full path to the type,
     /// like `crate::abcdef::MyType`.
     pub fn custom(mut self, type_: impl ToString) -> FieldBuilder {
@@ -96,6 +153,13 @@ impl FieldBuilder {
         self
     }
 
+    /// Create the field after the extension, for instance `pgcrypto` for a
+    /// `migrate_fill` expression using `gen_random_bytes`.
+    pub fn requires(mut self, extension: &Extension) -> FieldBuilder {
+        self.requires.push(extension.clone());
+        self
+    }
+
     pub fn build(self) -> FieldType {
         FieldType {
             type_: Type {
@@ -106,6 +170,8 @@ impl FieldBuilder {
                 opt: self.opt,
             },
             migration_default: self.default_,
+            identity: self.identity,
+            requires: self.requires,
         }
     }
 }
@@ -142,6 +208,14 @@ pub fn field_bytes() -> FieldBuilder {
     FieldBuilder::new(SimpleSimpleType::Bytes)
 }
 
+pub fn field_tsvector() -> FieldBuilder {
+    FieldBuilder::new(SimpleSimpleType::TsVector)
+}
+
+pub fn field_tsquery() -> FieldBuilder {
+    FieldBuilder::new(SimpleSimpleType::TsQuery)
+}
+
 #[cfg(feature = "chrono")]
 pub fn field_utctime_chrono() -> FieldBuilder {
     FieldBuilder::new(SimpleSimpleType::UtcTimeChrono)
@@ -0,0 +1,55 @@
+use crate::utils::Tokens;
+use super::{
+    namespace::Namespace,
+    table::Table,
+};
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, PartialOrd, Ord)]
+pub enum Privilege {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Truncate,
+    References,
+    Trigger,
+}
+
+impl Privilege {
+    pub(crate) fn to_sql(&self) -> &'static str {
+        match self {
+            Privilege::Select => "select",
+            Privilege::Insert => "insert",
+            Privilege::Update => "update",
+            Privilege::Delete => "delete",
+            Privilege::Truncate => "truncate",
+            Privilege::References => "references",
+            Privilege::Trigger => "trigger",
+        }
+    }
+}
+
+/// Privileges on a table granted to a role. Roles aren't part of the schema, they
+/// must be created separately.
+#[derive(Clone, Debug)]
+pub struct Grant {
+    pub table: Table,
+    pub role: String,
+    pub privileges: Vec<Privilege>,
+}
+
+/// Usage of a schema (namespace) granted to a role. Without it the role can't
+/// access anything in the schema, whatever privileges it has on the tables.
+#[derive(Clone, Debug)]
+pub struct NamespaceGrant {
+    pub namespace: Namespace,
+    pub role: String,
+}
+
+/// A role name, leaving the special roles unquoted.
+pub(crate) fn role_sql(role: &str) -> String {
+    match role {
+        "public" | "current_user" | "current_role" | "session_user" => role.to_string(),
+        role => Tokens::new().id(role).to_string(),
+    }
+}
@@ -5,7 +5,10 @@ use std::{
 };
 use super::{
     field::Field,
-    table::Table,
+    table::{
+        Table,
+        qualified_id,
+    },
 };
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug, PartialOrd, Ord)]
@@ -17,12 +20,55 @@ impl Display for SchemaIndexId {
     }
 }
 
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum IndexMethod {
+    /// The default, for equality and range lookups.
+    Btree,
+    /// Equality lookups only, on a single field. Can't be unique.
+    Hash,
+    /// For values containing multiple elements, like `tsvector` full-text search
+    /// documents. Can't be unique.
+    Gin,
+    /// For geometric types, ranges and nearest-neighbor searches. Can't be unique.
+    Gist,
+    /// Small summaries of ranges of table blocks, for very large tables where values
+    /// correlate with physical order (like insertion timestamps). Can't be unique.
+    Brin,
+}
+
+impl IndexMethod {
+    pub(crate) fn to_sql(&self) -> &'static str {
+        match self {
+            IndexMethod::Btree => "btree",
+            IndexMethod::Hash => "hash",
+            IndexMethod::Gin => "gin",
+            IndexMethod::Gist => "gist",
+            IndexMethod::Brin => "brin",
+        }
+    }
+}
+
 pub struct Index_ {
     pub table: Table,
     pub schema_id: SchemaIndexId,
     pub id: String,
     pub fields: Vec<Field>,
     pub unique: bool,
+    pub method: IndexMethod,
+    /// The operator class for each field in `fields`, if not the default.
+    pub opclasses: Vec<Option<String>>,
+    /// Extra fields stored in the index (`include`) so queries can be answered from
+    /// the index alone.
+    pub include: Vec<Field>,
+    /// Create and drop the index without blocking writes to the table.
+    pub concurrently: bool,
+}
+
+impl Index_ {
+    /// The index name, qualified with the schema of the table.
+    pub(crate) fn sql_id(&self) -> String {
+        qualified_id(self.table.namespace.as_ref().map(|n| n.id.as_str()), &self.id)
+    }
 }
 
 #[derive(Clone)]
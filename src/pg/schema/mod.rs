@@ -2,3 +2,9 @@ pub mod table;
 pub mod field;
 pub mod constraint;
 pub mod index;
+pub mod sequence;
+pub mod namespace;
+pub mod extension;
+pub mod partition;
+pub mod policy;
+pub mod grant;
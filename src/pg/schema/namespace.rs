@@ -0,0 +1,47 @@
+use std::{
+    rc::Rc,
+    ops::Deref,
+    fmt::Display,
+};
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, PartialOrd, Ord)]
+pub struct SchemaNamespaceId(pub String);
+
+impl Display for SchemaNamespaceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+/// A PostgreSQL schema (`create schema`), named namespace here to avoid confusion
+/// with the schema being defined.
+#[derive(Debug)]
+pub struct Namespace_ {
+    pub schema_id: SchemaNamespaceId,
+    pub id: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Namespace(pub Rc<Namespace_>);
+
+impl Display for Namespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&format!("{} ({})", self.id, self.schema_id.0), f)
+    }
+}
+
+impl PartialEq for Namespace {
+    fn eq(&self, other: &Self) -> bool {
+        self.schema_id == other.schema_id
+    }
+}
+
+impl Eq for Namespace { }
+
+impl Deref for Namespace {
+    type Target = Namespace_;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    rc::Rc,
+    ops::Deref,
+    fmt::Display,
+};
+use crate::pg::query::expr::Expr;
+use super::{
+    field::Field,
+    table::{
+        Table,
+        qualified_id,
+    },
+};
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum PartitionMethod {
+    /// Each partition holds a range of key values, like a month of timestamps.
+    Range,
+    /// Each partition holds a list of values of a single key field.
+    List,
+    /// Rows are distributed by the hash of the key.
+    Hash,
+}
+
+impl PartitionMethod {
+    pub(crate) fn to_sql(&self) -> &'static str {
+        match self {
+            PartitionMethod::Range => "range",
+            PartitionMethod::List => "list",
+            PartitionMethod::Hash => "hash",
+        }
+    }
+}
+
+/// The period covered by each partition created by a generated time partition
+/// function.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum PartitionInterval {
+    Day,
+    Month,
+    Year,
+}
+
+impl PartitionInterval {
+    pub(crate) fn unit(&self) -> &'static str {
+        match self {
+            PartitionInterval::Day => "day",
+            PartitionInterval::Month => "month",
+            PartitionInterval::Year => "year",
+        }
+    }
+
+    /// The `to_char` format of the partition name suffix.
+    pub(crate) fn suffix_format(&self) -> &'static str {
+        match self {
+            PartitionInterval::Day => "YYYY_MM_DD",
+            PartitionInterval::Month => "YYYY_MM",
+            PartitionInterval::Year => "YYYY",
+        }
+    }
+}
+
+/// The partitioning of a table (`partition by`).
+#[derive(Clone, Debug)]
+pub struct PartitionBy {
+    pub method: PartitionMethod,
+    pub fields: Vec<Field>,
+    /// Generate a function with this name which creates the partition containing a
+    /// given time (if it doesn't exist yet), with partitions covering one interval
+    /// each.
+    pub time_function: Option<(String, PartitionInterval)>,
+}
+
+/// The values stored in a partition (`for values ...`). Expressions must be
+/// constants.
+#[derive(Clone, Debug)]
+pub enum PartitionBound {
+    /// One expression per key field for each end, the end is exclusive.
+    Range {
+        from: Vec<Expr>,
+        to: Vec<Expr>,
+    },
+    List(Vec<Expr>),
+    Hash {
+        modulus: i64,
+        remainder: i64,
+    },
+    /// Rows not matching any other partition.
+    Default,
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, PartialOrd, Ord)]
+pub struct SchemaPartitionId(pub String);
+
+impl Display for SchemaPartitionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+/// A partition of a partitioned table. Partitions are placed in the schema of the
+/// table.
+#[derive(Debug)]
+pub struct Partition_ {
+    pub table: Table,
+    /// The partitioning of the table, for checking the bounds.
+    pub partition_by: PartitionBy,
+    pub schema_id: SchemaPartitionId,
+    pub id: String,
+    pub bound: PartitionBound,
+}
+
+impl Partition_ {
+    pub(crate) fn sql_id(&self) -> String {
+        qualified_id(self.table.namespace.as_ref().map(|n| n.id.as_str()), &self.id)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Partition(pub Rc<Partition_>);
+
+impl Display for Partition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(
+            &format!("{}.{} ({}.{})", self.0.table.id, self.0.id, self.0.table.schema_id, self.0.schema_id),
+            f,
+        )
+    }
+}
+
+impl PartialEq for Partition {
+    fn eq(&self, other: &Self) -> bool {
+        self.table == other.table && self.schema_id == other.schema_id
+    }
+}
+
+impl Eq for Partition { }
+
+impl Deref for Partition {
+    type Target = Partition_;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
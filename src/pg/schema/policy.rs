@@ -0,0 +1,92 @@
+use std::{
+    rc::Rc,
+    ops::Deref,
+    fmt::Display,
+};
+use crate::pg::query::expr::Expr;
+use super::table::Table;
+
+/// Row-level security settings of a table.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct RowLevelSecurity {
+    /// Apply the policies to the table owner too. Without this the owner (often the
+    /// role the application connects as) bypasses them.
+    pub force: bool,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum PolicyCommand {
+    All,
+    Select,
+    Insert,
+    Update,
+    Delete,
+}
+
+impl PolicyCommand {
+    pub(crate) fn to_sql(&self) -> &'static str {
+        match self {
+            PolicyCommand::All => "all",
+            PolicyCommand::Select => "select",
+            PolicyCommand::Insert => "insert",
+            PolicyCommand::Update => "update",
+            PolicyCommand::Delete => "delete",
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, PartialOrd, Ord)]
+pub struct SchemaPolicyId(pub String);
+
+impl Display for SchemaPolicyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+/// A row-level security policy. Policies only take effect when row-level security
+/// is enabled on the table.
+#[derive(Debug)]
+pub struct Policy_ {
+    pub table: Table,
+    pub schema_id: SchemaPolicyId,
+    pub id: String,
+    pub command: PolicyCommand,
+    /// Rows must pass all restrictive policies, in addition to at least one
+    /// permissive (the default) policy.
+    pub restrictive: bool,
+    /// The roles the policy applies to, all roles (`public`) if empty.
+    pub roles: Vec<String>,
+    /// Existing rows are visible (and can be updated or deleted) if this is true.
+    pub using: Option<Expr>,
+    /// New rows (inserted or updated) are rejected unless this is true.
+    pub with_check: Option<Expr>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Policy(pub Rc<Policy_>);
+
+impl Display for Policy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(
+            &format!("{}.{} ({}.{})", self.0.table.id, self.0.id, self.0.table.schema_id, self.0.schema_id),
+            f,
+        )
+    }
+}
+
+impl PartialEq for Policy {
+    fn eq(&self, other: &Self) -> bool {
+        self.table == other.table && self.schema_id == other.schema_id
+    }
+}
+
+impl Eq for Policy { }
+
+impl Deref for Policy {
+    type Target = Policy_;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
@@ -0,0 +1,61 @@
+use std::{
+    rc::Rc,
+    ops::Deref,
+    fmt::Display,
+};
+use super::{
+    namespace::Namespace,
+    table::qualified_id,
+};
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, PartialOrd, Ord)]
+pub struct SchemaSequenceId(pub String);
+
+impl Display for SchemaSequenceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+#[derive(Debug)]
+pub struct Sequence_ {
+    pub schema_id: SchemaSequenceId,
+    pub id: String,
+    pub namespace: Option<Namespace>,
+    /// The first value returned, if not the default (1 for ascending sequences).
+    pub start: Option<i64>,
+    /// The step between values, if not the default (1).
+    pub increment: Option<i64>,
+}
+
+impl Sequence_ {
+    /// The sequence name, qualified with the schema if it's in one.
+    pub(crate) fn sql_id(&self) -> String {
+        qualified_id(self.namespace.as_ref().map(|n| n.id.as_str()), &self.id)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Sequence(pub Rc<Sequence_>);
+
+impl Display for Sequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&format!("{} ({})", self.id, self.schema_id.0), f)
+    }
+}
+
+impl PartialEq for Sequence {
+    fn eq(&self, other: &Self) -> bool {
+        self.schema_id == other.schema_id
+    }
+}
+
+impl Eq for Sequence { }
+
+impl Deref for Sequence {
+    type Target = Sequence_;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
@@ -7,6 +7,8 @@ use std::{
     ops::Deref,
     hash::Hash,
 };
+use crate::utils::Tokens;
+use super::namespace::Namespace;
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug, PartialOrd, Ord)]
 pub struct SchemaTableId(pub String);
@@ -21,6 +23,25 @@ impl Display for SchemaTableId {
 pub struct Table_ {
     pub schema_id: SchemaTableId,
     pub id: String,
+    /// The schema containing the table, or the default (`public`) if `None`.
+    pub namespace: Option<Namespace>,
+}
+
+impl Table_ {
+    /// The table name, qualified with the schema if it's in one.
+    pub(crate) fn sql_id(&self) -> String {
+        qualified_id(self.namespace.as_ref().map(|n| n.id.as_str()), &self.id)
+    }
+}
+
+/// A relation name, qualified with the schema if it's in one.
+pub(crate) fn qualified_id(namespace: Option<&str>, id: &str) -> String {
+    let mut out = Tokens::new();
+    if let Some(n) = namespace {
+        out.id(n).s(".");
+    }
+    out.id(id);
+    out.to_string()
 }
 
 #[derive(Clone, Debug)]
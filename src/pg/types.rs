@@ -13,6 +13,10 @@ pub enum SimpleSimpleType {
     Bool,
     String,
     Bytes,
+    /// Full-text search document, `good_ormning_runtime::pg::TsVector` in Rust.
+    TsVector,
+    /// Full-text search query, `good_ormning_runtime::pg::TsQuery` in Rust.
+    TsQuery,
     #[cfg(feature = "chrono")]
     UtcTimeChrono,
     #[cfg(feature = "chrono")]
@@ -31,6 +35,8 @@ pub fn to_sql_type(t: &SimpleSimpleType) -> &'static str {
         SimpleSimpleType::Bool => "bool",
         SimpleSimpleType::String => "text",
         SimpleSimpleType::Bytes => "bytea",
+        SimpleSimpleType::TsVector => "tsvector",
+        SimpleSimpleType::TsQuery => "tsquery",
         #[cfg(feature = "chrono")]
         SimpleSimpleType::UtcTimeChrono => "timestamp with time zone",
         #[cfg(feature = "chrono")]
@@ -82,6 +88,16 @@ pub fn to_rust_types(t: &SimpleSimpleType) -> RustTypes {
             ret_type: quote!(Vec < u8 >),
             arg_type: quote!(&[u8]),
         },
+        SimpleSimpleType::TsVector => RustTypes {
+            custom_trait: quote!(good_ormning_runtime::pg::GoodOrmningCustomTsVector),
+            ret_type: quote!(good_ormning_runtime::pg::TsVector),
+            arg_type: quote!(&good_ormning_runtime::pg::TsVector),
+        },
+        SimpleSimpleType::TsQuery => RustTypes {
+            custom_trait: quote!(good_ormning_runtime::pg::GoodOrmningCustomTsQuery),
+            ret_type: quote!(good_ormning_runtime::pg::TsQuery),
+            arg_type: quote!(&good_ormning_runtime::pg::TsQuery),
+        },
         #[cfg(feature = "chrono")]
         SimpleSimpleType::UtcTimeChrono => RustTypes {
             custom_trait: quote!(good_ormning_runtime::pg::GoodOrmningCustomUtcTimeChrono),
@@ -186,6 +202,14 @@ pub fn type_bytes() -> TypeBuilder {
     TypeBuilder::new(SimpleSimpleType::Bytes)
 }
 
+pub fn type_tsvector() -> TypeBuilder {
+    TypeBuilder::new(SimpleSimpleType::TsVector)
+}
+
+pub fn type_tsquery() -> TypeBuilder {
+    TypeBuilder::new(SimpleSimpleType::TsQuery)
+}
+
 #[cfg(feature = "chrono")]
 pub fn type_utctime_chrono() -> TypeBuilder {
     TypeBuilder::new(SimpleSimpleType::UtcTimeChrono)
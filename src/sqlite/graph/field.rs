@@ -95,7 +95,7 @@ impl SqliteNodeDataDispatch for NodeField_ {
         if !self.def.type_.type_.opt {
             if let Some(d) = &self.def.type_.migration_default {
                 stmt.s("not null default");
-                let mut qctx = SqliteQueryCtx::new(ctx.errs.clone(), HashMap::new());
+                let mut qctx = SqliteQueryCtx::new(ctx.errs.clone(), HashMap::new(), HashMap::new(), HashMap::new());
                 let e_res = d.build(&mut qctx, &path, &HashMap::new());
                 check_same(&mut qctx.errs, &path, &ExprType(vec![(Binding::empty(), Type {
                     type_: self.def.type_.type_.type_.clone(),
@@ -0,0 +1,248 @@
+use std::collections::HashSet;
+use crate::{
+    graphmigrate::Comparison,
+    sqlite::schema::fts5::Fts5Table,
+    utils::Tokens,
+};
+use super::{
+    utils::{
+        SqliteNodeDataDispatch,
+        SqliteMigrateCtx,
+        SqliteNodeData,
+    },
+    GraphId,
+    Node,
+};
+
+#[derive(Clone)]
+pub(crate) struct NodeFts5_ {
+    pub def: Fts5Table,
+}
+
+impl NodeFts5_ {
+    pub fn compare(&self, old: &Self, created: &HashSet<GraphId>) -> Comparison {
+        let same =
+            self.def.id == old.def.id &&
+                self.def.tokenize == old.def.tokenize &&
+                self.def.columns.len() == old.def.columns.len() &&
+                self
+                    .def
+                    .columns
+                    .iter()
+                    .zip(old.def.columns.iter())
+                    .all(
+                        |(a, b)| a.field.schema_id == b.field.schema_id && a.field.id == b.field.id &&
+                            a.unindexed == b.unindexed,
+                    ) &&
+                match (&self.def.content, &old.def.content) {
+                    (None, None) => true,
+                    (Some(a), Some(b)) => {
+                        a.rowid.table.id == b.rowid.table.id && a.rowid.id == b.rowid.id &&
+                            a.fields.len() == b.fields.len() &&
+                            a
+                                .fields
+                                .iter()
+                                .zip(b.fields.iter())
+                                .all(|(a, b)| a.schema_id == b.schema_id && a.id == b.id)
+                    },
+                    _ => false,
+                };
+        match (&self.def.content, &old.def.content) {
+            (None, None) => {
+                if same {
+                    Comparison::DoNothing
+                } else {
+                    // Virtual tables can't be altered, the update rebuilds the table
+                    // copying the data over
+                    Comparison::Update
+                }
+            },
+            (Some(c), _) => {
+                // The index is rebuilt from the content table (including when the content
+                // field mapping changes), so just drop and recreate
+                if !same || created.contains(&GraphId::Table(c.rowid.table.schema_id.clone())) ||
+                    c.fields.iter().chain([&c.rowid]).any(|f| {
+                        created.contains(&GraphId::Field(f.table.schema_id.clone(), f.schema_id.clone()))
+                    }) {
+                    Comparison::Recreate
+                } else {
+                    Comparison::DoNothing
+                }
+            },
+            (None, Some(_)) => Comparison::Recreate,
+        }
+    }
+
+    fn trigger_ids(&self) -> [String; 3] {
+        return ["ai", "ad", "au"].map(|s| format!("{}_{}", self.def.id, s));
+    }
+
+    fn create_table(&self, ctx: &mut SqliteMigrateCtx, id: &str) {
+        let mut stmt = Tokens::new();
+        stmt.s("create virtual table").id(id).s("using fts5 (");
+        for (i, c) in self.def.columns.iter().enumerate() {
+            if i > 0 {
+                stmt.s(",");
+            }
+            stmt.id(&c.field.id);
+            if c.unindexed {
+                stmt.s("unindexed");
+            }
+        }
+        if let Some(t) = &self.def.tokenize {
+            stmt.s(", tokenize =").s(&sql_str(t));
+        }
+        if let Some(c) = &self.def.content {
+            stmt.s(", content =").s(&sql_str(&c.rowid.table.id));
+            stmt.s(", content_rowid =").s(&sql_str(&c.rowid.id));
+        }
+        stmt.s(")");
+        ctx.statements.push(stmt.to_string());
+    }
+}
+
+fn sql_str(s: &str) -> String {
+    return format!("'{}'", s.replace("'", "''"));
+}
+
+impl SqliteNodeDataDispatch for NodeFts5_ {
+    fn create_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn create(&self, ctx: &mut SqliteMigrateCtx) {
+        self.create_table(ctx, &self.def.id);
+        let Some(content) = &self.def.content else {
+            return;
+        };
+
+        // Keep the index in sync with the content table
+        let [ai, ad, au] = self.trigger_ids();
+        let insert_values = |t: &mut Tokens, row: &str| {
+            t.s("insert into").id(&self.def.id).s("(").id("rowid");
+            for c in &self.def.columns {
+                t.s(",").id(&c.field.id);
+            }
+            t.s(") values (").s(row).s(".").id(&content.rowid.id);
+            for f in &content.fields {
+                t.s(",").s(row).s(".").id(&f.id);
+            }
+            t.s(") ;");
+        };
+        let delete_values = |t: &mut Tokens| {
+            t.s("insert into").id(&self.def.id).s("(").id(&self.def.id).s(",").id("rowid");
+            for c in &self.def.columns {
+                t.s(",").id(&c.field.id);
+            }
+            t.s(") values ( 'delete' , old .").id(&content.rowid.id);
+            for f in &content.fields {
+                t.s(", old .").id(&f.id);
+            }
+            t.s(") ;");
+        };
+        ctx
+            .statements
+            .push(
+                Tokens::new()
+                    .s("create trigger")
+                    .id(&ai)
+                    .s("after insert on")
+                    .id(&content.rowid.table.id)
+                    .s("begin")
+                    .f(|t| insert_values(t, "new"))
+                    .s("end")
+                    .to_string(),
+            );
+        ctx
+            .statements
+            .push(
+                Tokens::new()
+                    .s("create trigger")
+                    .id(&ad)
+                    .s("after delete on")
+                    .id(&content.rowid.table.id)
+                    .s("begin")
+                    .f(delete_values)
+                    .s("end")
+                    .to_string(),
+            );
+        ctx
+            .statements
+            .push(
+                Tokens::new()
+                    .s("create trigger")
+                    .id(&au)
+                    .s("after update on")
+                    .id(&content.rowid.table.id)
+                    .s("begin")
+                    .f(delete_values)
+                    .f(|t| insert_values(t, "new"))
+                    .s("end")
+                    .to_string(),
+            );
+
+        // Index any existing content
+        ctx
+            .statements
+            .push(
+                Tokens::new()
+                    .s("insert into")
+                    .id(&self.def.id)
+                    .s("(")
+                    .id(&self.def.id)
+                    .s(") values ( 'rebuild' )")
+                    .to_string(),
+            );
+    }
+
+    fn delete_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn delete(&self, ctx: &mut SqliteMigrateCtx) {
+        if self.def.content.is_some() {
+            for id in self.trigger_ids() {
+                ctx.statements.push(Tokens::new().s("drop trigger").id(&id).to_string());
+            }
+        }
+        ctx.statements.push(Tokens::new().s("drop table").id(&self.def.id).to_string());
+    }
+}
+
+impl SqliteNodeData for NodeFts5_ {
+    fn update(&self, ctx: &mut SqliteMigrateCtx, old: &Self) {
+        // Only tables storing their own content are updated, by moving the old table
+        // aside and copying the columns that still exist into a new table
+        let temp_id = format!("{}_old", old.def.id);
+        ctx
+            .statements
+            .push(Tokens::new().s("alter table").id(&old.def.id).s("rename to").id(&temp_id).to_string());
+        self.create_table(ctx, &self.def.id);
+        let mut dest = Tokens::new();
+        let mut source = Tokens::new();
+        dest.id("rowid");
+        source.id("rowid");
+        for c in &self.def.columns {
+            let Some(old_c) = old.def.columns.iter().find(|o| o.field.schema_id == c.field.schema_id) else {
+                continue;
+            };
+            dest.s(",").id(&c.field.id);
+            source.s(",").id(&old_c.field.id);
+        }
+        ctx
+            .statements
+            .push(
+                Tokens::new()
+                    .s("insert into")
+                    .id(&self.def.id)
+                    .s("(")
+                    .s(&dest.to_string())
+                    .s(") select")
+                    .s(&source.to_string())
+                    .s("from")
+                    .id(&temp_id)
+                    .to_string(),
+            );
+        ctx.statements.push(Tokens::new().s("drop table").id(&temp_id).to_string());
+    }
+}
@@ -7,6 +7,8 @@ use self::{
     field::NodeField_,
     constraint::NodeConstraint_,
     index::NodeIndex_,
+    fts5::NodeFts5_,
+    rtree::NodeRtree_,
     utils::{
         SqliteMigrateCtx,
         SqliteNodeDataDispatch,
@@ -24,6 +26,8 @@ pub mod table;
 pub mod field;
 pub mod constraint;
 pub mod index;
+pub mod fts5;
+pub mod rtree;
 pub mod utils;
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug, PartialOrd, Ord)]
@@ -42,6 +46,8 @@ pub(crate) enum Node {
     Field(NodeField_),
     Constraint(NodeConstraint_),
     Index(NodeIndex_),
+    Fts5(NodeFts5_),
+    Rtree(NodeRtree_),
 }
 
 impl Node {
@@ -60,6 +66,14 @@ impl Node {
     pub(crate) fn table_index(t: NodeIndex_) -> Self {
         Node::Index(t)
     }
+
+    pub(crate) fn fts5(t: NodeFts5_) -> Self {
+        Node::Fts5(t)
+    }
+
+    pub(crate) fn rtree(t: NodeRtree_) -> Self {
+        Node::Rtree(t)
+    }
 }
 
 impl<'a> crate::graphmigrate::NodeData for Node {
@@ -72,6 +86,8 @@ impl<'a> crate::graphmigrate::NodeData for Node {
             PairwiseNode::Field(current, old) => current.compare(old, created),
             PairwiseNode::Constraint(current, old) => current.compare(old, created),
             PairwiseNode::Index(current, old) => current.compare(old, created),
+            PairwiseNode::Fts5(current, old) => current.compare(old, created),
+            PairwiseNode::Rtree(current, old) => current.compare(old, created),
             PairwiseNode::Nonmatching(_, _) => unreachable!(),
         }
     }
@@ -90,6 +106,8 @@ impl<'a> crate::graphmigrate::NodeData for Node {
             PairwiseNode::Field(current, old) => current.update(ctx, &old),
             PairwiseNode::Constraint(current, old) => current.update(ctx, &old),
             PairwiseNode::Index(current, old) => current.update(ctx, &old),
+            PairwiseNode::Fts5(current, old) => current.update(ctx, &old),
+            PairwiseNode::Rtree(current, old) => current.update(ctx, &old),
             PairwiseNode::Nonmatching(_, _) => unreachable!(),
         }
     }
@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+use crate::{
+    graphmigrate::Comparison,
+    sqlite::{
+        schema::{
+            field::Field,
+            rtree::RtreeTable,
+        },
+        types::to_sql_type,
+    },
+    utils::{
+        quote_id,
+        Tokens,
+    },
+};
+use super::{
+    utils::{
+        SqliteNodeDataDispatch,
+        SqliteMigrateCtx,
+        SqliteNodeData,
+    },
+    GraphId,
+    Node,
+};
+
+#[derive(Clone)]
+pub(crate) struct NodeRtree_ {
+    pub def: RtreeTable,
+}
+
+impl NodeRtree_ {
+    fn fields(&self) -> Vec<&Field> {
+        let mut out = vec![&self.def.id_field];
+        for (min, max) in &self.def.dimensions {
+            out.push(min);
+            out.push(max);
+        }
+        out.extend(self.def.aux.iter());
+        return out;
+    }
+
+    pub fn compare(&self, old: &Self, _created: &HashSet<GraphId>) -> Comparison {
+        // Virtual tables can't be altered, so any change recreates the table
+        let fields = self.fields();
+        let old_fields = old.fields();
+        if self.def.id != old.def.id || self.def.i32_coordinates != old.def.i32_coordinates ||
+            self.def.dimensions.len() != old.def.dimensions.len() ||
+            self.def.aux.len() != old.def.aux.len() ||
+            fields.iter().zip(old_fields.iter()).any(|(a, b)| {
+                a.schema_id != b.schema_id || a.id != b.id || a.type_.type_.type_.type_ != b.type_.type_.type_.type_
+            }) {
+            Comparison::Recreate
+        } else {
+            Comparison::DoNothing
+        }
+    }
+}
+
+impl SqliteNodeDataDispatch for NodeRtree_ {
+    fn create_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn create(&self, ctx: &mut SqliteMigrateCtx) {
+        let mut stmt = Tokens::new();
+        stmt.s("create virtual table").id(&self.def.id).s("using").s(if self.def.i32_coordinates {
+            "rtree_i32"
+        } else {
+            "rtree"
+        }).s("(").id(&self.def.id_field.id);
+        for (min, max) in &self.def.dimensions {
+            stmt.s(",").id(&min.id).s(",").id(&max.id);
+        }
+        for f in &self.def.aux {
+            // The `+` marking auxiliary columns must be directly before the name
+            stmt.s(",").s(&format!("+{}", quote_id(&f.id))).s(to_sql_type(&f.type_.type_.type_.type_));
+        }
+        stmt.s(")");
+        ctx.statements.push(stmt.to_string());
+    }
+
+    fn delete_coalesce(&mut self, other: Node) -> Option<Node> {
+        Some(other)
+    }
+
+    fn delete(&self, ctx: &mut SqliteMigrateCtx) {
+        ctx.statements.push(Tokens::new().s("drop table").id(&self.def.id).to_string());
+    }
+}
+
+impl SqliteNodeData for NodeRtree_ {
+    fn update(&self, _ctx: &mut SqliteMigrateCtx, _old: &Self) {
+        unreachable!()
+    }
+}
@@ -3,6 +3,8 @@ use {
         graph::{
             constraint::NodeConstraint_,
             field::NodeField_,
+            fts5::NodeFts5_,
+            rtree::NodeRtree_,
             index::NodeIndex_,
             table::NodeTable_,
             utils::MigrateNode,
@@ -16,7 +18,10 @@ use {
                 Insert,
                 InsertConflict,
             },
-            select::Select,
+            select::{
+                OrderChoice,
+                Select,
+            },
             select_body::{
                 Join,
                 JoinSource,
@@ -43,11 +48,17 @@ use {
                 Field_,
                 SchemaFieldId,
             },
+            fts5::{
+                Fts5Column,
+                Fts5Content,
+                Fts5Table,
+            },
             index::{
                 Index,
                 Index_,
                 SchemaIndexId,
             },
+            rtree::RtreeTable,
             table::{
                 SchemaTableId,
                 Table,
@@ -78,6 +89,7 @@ use {
         TokenStream,
     },
     query::{
+        expr::WindowSpec,
         select_body::{
             SelectBody,
             SelectJunction,
@@ -109,12 +121,22 @@ pub mod graph;
 /// The number of results this query returns. This determines if the return type is
 /// void, `Option`, the value directly, or a `Vec`. It must be a valid value per
 /// the query body (e.g. select can't have `None` res count).
+///
+/// `MaybeOne` and `One` are checked against the schema: a select (or update/delete
+/// with returning) must pin every field of a primary key or unique index with `=`
+/// in its `where` (and join conditions), have an aggregate and no `group by`, or
+/// have `limit 1`. Queries that can't be proven to return at most one row produce a
+/// cargo warning.
 #[derive(Debug, Clone)]
 pub enum QueryResCount {
     None,
     MaybeOne,
     One,
     Many,
+    /// Infer the result count from the query and schema using the rules above. This
+    /// is `None` for queries without outputs, `One` for inserts and aggregates, and
+    /// `MaybeOne` or `Many` otherwise.
+    Auto,
 }
 
 /// See Insert for field descriptions. Call `build()` to get a finished query
@@ -277,6 +299,19 @@ impl SelectBuilder {
         self
     }
 
+    /// Sets `HAVING`. `v` must evaluate to a bool.
+    pub fn having(mut self, v: Expr) -> Self {
+        self.q.body.having = Some(v);
+        self
+    }
+
+    /// Adds a named window definition (`WINDOW name AS (...)`) which can be used as
+    /// the `base` of windows in this select.
+    pub fn window(mut self, name: impl Into<String>, spec: WindowSpec) -> Self {
+        self.q.body.windows.push((name.into(), spec));
+        self
+    }
+
     pub fn order(mut self, expr: Expr, order: Order) -> Self {
         self.q.body.order.push((expr, order));
         self
@@ -287,12 +322,39 @@ impl SelectBuilder {
         self
     }
 
+    /// Make this a keyset (cursor) paginated query, ordered by `order`. The query
+    /// function takes a `page_size` and an optional cursor struct (generated, with
+    /// the types of the order clauses) to start after, and returns the page of rows
+    /// with the cursor for the next page (or `None` if this is the last page). The
+    /// order clauses must be non-null and together uniquely identify a row.
+    ///
+    /// The query must be built with `QueryResCount::Many`.
+    pub fn paginate_by(mut self, order: &[(Expr, Order)]) -> Self {
+        self.q.paginate = Some(order.to_vec());
+        self
+    }
+
+    /// Let the caller choose the order at runtime. See `OrderChoice` for details.
+    pub fn order_choice(mut self, name: impl ToString, variants: Vec<(String, Vec<(Expr, Order)>)>) -> Self {
+        self.q.order_choice = Some(OrderChoice {
+            name: name.to_string(),
+            variants: variants,
+        });
+        self
+    }
+
     /// Sets `LIMIT`. `v` must evaluate to a number.
     pub fn limit(mut self, v: Expr) -> Self {
         self.q.body.limit = Some(v);
         self
     }
 
+    /// Sets `OFFSET`. `v` must evaluate to a number.
+    pub fn offset(mut self, v: Expr) -> Self {
+        self.q.body.offset = Some(v);
+        self
+    }
+
     /// Add a UNION/INTERSECT/EXCEPT junction to the query.
     pub fn junction(mut self, j: SelectJunction) -> Self {
         self.q.body_junctions.push(j);
@@ -304,6 +366,13 @@ impl SelectBuilder {
         self.q
     }
 
+    /// Produce a select for use as a subquery, in `Expr::Select`, `Expr::Exists` or
+    /// `JoinSource::Subsel`. The subquery can have CTEs and junctions, but not an
+    /// order choice or pagination.
+    pub fn build_subquery(self) -> Select {
+        self.q
+    }
+
     /// Produce a query object.
     ///
     /// # Arguments
@@ -396,6 +465,19 @@ impl SelectBodyBuilder {
         self
     }
 
+    /// Sets `HAVING`. `v` must evaluate to a bool.
+    pub fn having(mut self, v: Expr) -> Self {
+        self.q.having = Some(v);
+        self
+    }
+
+    /// Adds a named window definition (`WINDOW name AS (...)`) which can be used as
+    /// the `base` of windows in this select.
+    pub fn window(mut self, name: impl Into<String>, spec: WindowSpec) -> Self {
+        self.q.windows.push((name.into(), spec));
+        self
+    }
+
     pub fn order(mut self, expr: Expr, order: Order) -> Self {
         self.q.order.push((expr, order));
         self
@@ -412,6 +494,12 @@ impl SelectBodyBuilder {
         self
     }
 
+    /// Sets `OFFSET`. `v` must evaluate to a number.
+    pub fn offset(mut self, v: Expr) -> Self {
+        self.q.offset = Some(v);
+        self
+    }
+
     /// Produce a select body object.
     pub fn build(self) -> SelectBody {
         return self.q;
@@ -639,10 +727,15 @@ pub fn new_select(table: &Table) -> SelectBuilder {
             join: vec![],
             where_: None,
             group: vec![],
+            having: None,
+            windows: vec![],
             order: vec![],
             limit: None,
+            offset: None,
         },
         body_junctions: vec![],
+        order_choice: None,
+        paginate: None,
     } }
 }
 
@@ -658,14 +751,19 @@ pub fn new_select_from(source: NamedSelectSource) -> SelectBuilder {
             join: vec![],
             where_: None,
             group: vec![],
+            having: None,
+            windows: vec![],
             order: vec![],
             limit: None,
+            offset: None,
         },
         body_junctions: vec![],
+        order_choice: None,
+        paginate: None,
     } }
 }
 
-/// Get a builder for an inner SELECT, such as in a CTE, subquery, JOIN, etc.
+/// Get a builder for an inner SELECT, such as in a CTE or junction.
 pub fn new_select_body(table: &Table) -> SelectBodyBuilder {
     SelectBodyBuilder { q: SelectBody {
         distinct: false,
@@ -677,8 +775,11 @@ pub fn new_select_body(table: &Table) -> SelectBodyBuilder {
         join: vec![],
         where_: None,
         group: vec![],
+        having: None,
+        windows: vec![],
         order: vec![],
         limit: None,
+        offset: None,
     } }
 }
 
@@ -742,6 +843,50 @@ impl Version {
         out
     }
 
+    /// Define an FTS5 full text search virtual table in this version. Virtual tables
+    /// can't be altered, so any changes to the definition rebuild the table.
+    pub fn fts5_table(&self, schema_id: &str, id: &str) -> Fts5TableBuilder {
+        let table = Table(Rc::new(Table_ {
+            schema_id: SchemaTableId(schema_id.into()),
+            id: id.into(),
+        }));
+        Fts5TableBuilder {
+            rowid: Field(Rc::new(Field_ {
+                table: table.clone(),
+                schema_id: SchemaFieldId("rowid".into()),
+                id: "rowid".into(),
+                type_: FieldType::with(&Type {
+                    type_: SimpleType {
+                        type_: SimpleSimpleType::I64,
+                        custom: None,
+                    },
+                    opt: false,
+                    array: false,
+                }),
+            })),
+            table: table,
+            columns: vec![],
+            tokenize: None,
+            content: None,
+        }
+    }
+
+    /// Define an R*Tree spatial index virtual table in this version. Virtual tables
+    /// can't be altered, so any changes to the definition drop and recreate the
+    /// table, losing its data (repopulate it in a post-migration query if needed).
+    pub fn rtree_table(&self, schema_id: &str, id: &str) -> RtreeTableBuilder {
+        RtreeTableBuilder {
+            table: Table(Rc::new(Table_ {
+                schema_id: SchemaTableId(schema_id.into()),
+                id: id.into(),
+            })),
+            id_field: None,
+            dimensions: vec![],
+            aux: vec![],
+            i32_coordinates: false,
+        }
+    }
+
     /// Add a query to execute before before migrating to this schema (applied
     /// immediately before migration).  Note that these may not run on new databases or
     /// if you later delete early migrations, so these should only modify existing data
@@ -932,6 +1077,285 @@ impl IndexBuilder {
     }
 }
 
+pub struct Fts5TableBuilder {
+    table: Table,
+    rowid: Field,
+    columns: Vec<Fts5Column>,
+    tokenize: Option<String>,
+    content: Option<Fts5Content>,
+}
+
+impl Fts5TableBuilder {
+    fn add_column(&mut self, schema_id: String, id: String, type_: Type, unindexed: bool) -> Field {
+        if id == "rowid" || id == self.table.id {
+            panic!("FTS5 column id {} in {} conflicts with a hidden column", id, self.table);
+        }
+        let out = Field(Rc::new(Field_ {
+            table: self.table.clone(),
+            schema_id: SchemaFieldId(schema_id),
+            id: id,
+            type_: FieldType::with(&type_),
+        }));
+        if self.columns.iter().any(|c| c.field.schema_id == out.schema_id || c.field.id == out.id) {
+            panic!("Column {} already exists", out);
+        }
+        self.columns.push(Fts5Column {
+            field: out.clone(),
+            unindexed: unindexed,
+        });
+        out
+    }
+
+    /// Define a column, which must be a string. The table must not use external
+    /// content.
+    pub fn column(&mut self, schema_id: impl ToString, id: impl ToString, type_: FieldType) -> Field {
+        self.add_own_column(schema_id.to_string(), id.to_string(), type_, false)
+    }
+
+    /// Like `column`, but the column is only stored, not indexed for matching.
+    pub fn unindexed_column(&mut self, schema_id: impl ToString, id: impl ToString, type_: FieldType) -> Field {
+        self.add_own_column(schema_id.to_string(), id.to_string(), type_, true)
+    }
+
+    fn add_own_column(&mut self, schema_id: String, id: String, type_: FieldType, unindexed: bool) -> Field {
+        if self.content.is_some() {
+            panic!("Columns of external content FTS5 table {} must be defined with content_column", self.table);
+        }
+        if !matches!(type_.type_.type_.type_, SimpleSimpleType::String) || type_.type_.array {
+            panic!("FTS5 column {} in {} must be a string", id, self.table);
+        }
+        self.add_column(schema_id, id, type_.type_, unindexed)
+    }
+
+    /// Set the tokenizer, like `porter unicode61`.
+    pub fn tokenize(&mut self, tokenize: impl ToString) {
+        self.tokenize = Some(tokenize.to_string());
+    }
+
+    /// Index the rows of another table rather than storing the text in the FTS5
+    /// table. `rowid` is the content table's rowid field or an integer primary key.
+    /// Triggers are created to keep the index up to date with changes to the
+    /// content table. This must be called before defining any columns.
+    pub fn external_content(&mut self, rowid: &Field) {
+        if !self.columns.is_empty() {
+            panic!("External content for FTS5 table {} must be set before defining columns", self.table);
+        }
+        if !matches!(rowid.type_.type_.type_.type_, SimpleSimpleType::I64) || rowid.type_.type_.opt {
+            panic!("External content rowid {} for FTS5 table {} must be a non-optional i64", rowid, self.table);
+        }
+        self.rowid = Field(Rc::new(Field_ {
+            table: self.table.clone(),
+            schema_id: SchemaFieldId("rowid".into()),
+            id: "rowid".into(),
+            type_: FieldType::with(&rowid.type_.type_),
+        }));
+        self.content = Some(Fts5Content {
+            rowid: rowid.clone(),
+            fields: vec![],
+        });
+    }
+
+    /// Define a column mirroring a string field in the external content table. The
+    /// column has the same name and type as the field.
+    pub fn content_column(&mut self, schema_id: impl ToString, field: &Field) -> Field {
+        self.add_content_column(schema_id.to_string(), field, false)
+    }
+
+    /// Like `content_column`, but the column isn't indexed for matching.
+    pub fn unindexed_content_column(&mut self, schema_id: impl ToString, field: &Field) -> Field {
+        self.add_content_column(schema_id.to_string(), field, true)
+    }
+
+    fn add_content_column(&mut self, schema_id: String, field: &Field, unindexed: bool) -> Field {
+        let Some(content) = &self.content else {
+            panic!("FTS5 table {} has no external content, use column to define columns", self.table);
+        };
+        if field.table != content.rowid.table {
+            panic!(
+                "Content column {} for FTS5 table {} isn't in the content table {}",
+                field,
+                self.table,
+                content.rowid.table
+            );
+        }
+        if !matches!(field.type_.type_.type_.type_, SimpleSimpleType::String) {
+            panic!("Content column {} for FTS5 table {} must be a string", field, self.table);
+        }
+        let out = self.add_column(schema_id, field.id.clone(), field.type_.type_.clone(), unindexed);
+        self.content.as_mut().unwrap().fields.push(field.clone());
+        out
+    }
+
+    pub fn build(self, v: &mut Version) -> Fts5Table {
+        if self.columns.is_empty() {
+            panic!("FTS5 table {} has no columns", self.table);
+        }
+        let mut deps = vec![];
+        if let Some(content) = &self.content {
+            deps.push(GraphId::Table(content.rowid.table.schema_id.clone()));
+            for f in content.fields.iter().chain([&content.rowid]) {
+                deps.push(GraphId::Field(f.table.schema_id.clone(), f.schema_id.clone()));
+            }
+        }
+        let out = Fts5Table {
+            table_field: Field(Rc::new(Field_ {
+                table: self.table.clone(),
+                schema_id: SchemaFieldId(self.table.id.clone()),
+                id: self.table.id.clone(),
+                type_: FieldType::with(&Type {
+                    type_: SimpleType {
+                        type_: SimpleSimpleType::String,
+                        custom: None,
+                    },
+                    opt: true,
+                    array: false,
+                }),
+            })),
+            table: self.table,
+            rowid: self.rowid,
+            columns: self.columns,
+            tokenize: self.tokenize,
+            content: self.content,
+        };
+        if v
+            .schema
+            .insert(
+                GraphId::Table(out.table.schema_id.clone()),
+                MigrateNode::new(deps, Node::fts5(NodeFts5_ { def: out.clone() })),
+            )
+            .is_some() {
+            panic!("Table with schema id {} already exists", out.table.schema_id);
+        };
+        out
+    }
+}
+
+pub struct RtreeTableBuilder {
+    table: Table,
+    id_field: Option<Field>,
+    dimensions: Vec<(Field, Field)>,
+    aux: Vec<Field>,
+    i32_coordinates: bool,
+}
+
+impl RtreeTableBuilder {
+    fn new_field(&self, schema_id: String, id: String, type_: Type) -> Field {
+        let out = Field(Rc::new(Field_ {
+            table: self.table.clone(),
+            schema_id: SchemaFieldId(schema_id),
+            id: id,
+            type_: FieldType::with(&type_),
+        }));
+        for f in self
+            .id_field
+            .iter()
+            .chain(self.dimensions.iter().flat_map(|(min, max)| [min, max]))
+            .chain(self.aux.iter()) {
+            if f.schema_id == out.schema_id || f.id == out.id {
+                panic!("Column {} already exists", out);
+            }
+        }
+        out
+    }
+
+    fn coordinate_type(&self) -> Type {
+        Type {
+            type_: SimpleType {
+                type_: if self.i32_coordinates {
+                    SimpleSimpleType::I32
+                } else {
+                    SimpleSimpleType::F64
+                },
+                custom: None,
+            },
+            opt: false,
+            array: false,
+        }
+    }
+
+    /// Define the integer primary key column. This is required.
+    pub fn id_field(&mut self, schema_id: impl ToString, id: impl ToString, custom_type: Option<String>) -> Field {
+        if self.id_field.is_some() {
+            panic!("R*Tree table {} already has an id field", self.table);
+        }
+        let out = self.new_field(schema_id.to_string(), id.to_string(), Type {
+            type_: SimpleType {
+                type_: SimpleSimpleType::I64,
+                custom: custom_type,
+            },
+            opt: false,
+            array: false,
+        });
+        self.id_field = Some(out.clone());
+        out
+    }
+
+    /// Use 32 bit integer coordinates (`rtree_i32`) rather than floating point. This
+    /// must be called before defining any dimensions.
+    pub fn i32_coordinates(&mut self) {
+        if !self.dimensions.is_empty() {
+            panic!("R*Tree table {} coordinate type must be set before defining dimensions", self.table);
+        }
+        self.i32_coordinates = true;
+    }
+
+    /// Define a dimension with min and max coordinate fields. There can be 1 to 5
+    /// dimensions. Floating point coordinates are `f64` but are stored as 32 bit
+    /// floats, rounded so the stored box contains the original.
+    pub fn dimension(
+        &mut self,
+        min_schema_id: impl ToString,
+        min_id: impl ToString,
+        max_schema_id: impl ToString,
+        max_id: impl ToString,
+    ) -> (Field, Field) {
+        if self.dimensions.len() == 5 {
+            panic!("R*Tree table {} can't have more than 5 dimensions", self.table);
+        }
+        let min = self.new_field(min_schema_id.to_string(), min_id.to_string(), self.coordinate_type());
+        let max = self.new_field(max_schema_id.to_string(), max_id.to_string(), self.coordinate_type());
+        if min.schema_id == max.schema_id || min.id == max.id {
+            panic!("Column {} already exists", max);
+        }
+        self.dimensions.push((min.clone(), max.clone()));
+        (min, max)
+    }
+
+    /// Define an auxiliary column, which is stored with each entry but can't be used
+    /// to search the index.
+    pub fn aux_column(&mut self, schema_id: impl ToString, id: impl ToString, type_: FieldType) -> Field {
+        let out = self.new_field(schema_id.to_string(), id.to_string(), type_.type_);
+        self.aux.push(out.clone());
+        out
+    }
+
+    pub fn build(self, v: &mut Version) -> RtreeTable {
+        let Some(id_field) = self.id_field else {
+            panic!("R*Tree table {} has no id field", self.table);
+        };
+        if self.dimensions.is_empty() {
+            panic!("R*Tree table {} has no dimensions", self.table);
+        }
+        let out = RtreeTable {
+            table: self.table,
+            id_field: id_field,
+            dimensions: self.dimensions,
+            aux: self.aux,
+            i32_coordinates: self.i32_coordinates,
+        };
+        if v
+            .schema
+            .insert(
+                GraphId::Table(out.table.schema_id.clone()),
+                MigrateNode::new(vec![], Node::rtree(NodeRtree_ { def: out.clone() })),
+            )
+            .is_some() {
+            panic!("Table with schema id {} already exists", out.table.schema_id);
+        };
+        out
+    }
+}
+
 /// Generate Rust code for migrations and queries.
 ///
 /// # Arguments
@@ -1008,6 +1432,38 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                             }
                         }
                     },
+                    Node::Fts5(t) => {
+                        let id = &t.def.id;
+                        let comp_id = format!("table {}", t.def.schema_id);
+                        if relations.insert(id, comp_id.clone()).is_some() {
+                            panic!("Duplicate table id {} -- {}", t.def.id, t.def.table);
+                        }
+                        if let Some(schema_id) = prev_relations.get(id) {
+                            if schema_id != &comp_id {
+                                panic!(
+                                    "Table {} id in version {} swapped with another relation since previous version; unsupported",
+                                    t.def.table,
+                                    v_i
+                                );
+                            }
+                        }
+                    },
+                    Node::Rtree(t) => {
+                        let id = &t.def.id;
+                        let comp_id = format!("table {}", t.def.schema_id);
+                        if relations.insert(id, comp_id.clone()).is_some() {
+                            panic!("Duplicate table id {} -- {}", t.def.id, t.def.table);
+                        }
+                        if let Some(schema_id) = prev_relations.get(id) {
+                            if schema_id != &comp_id {
+                                panic!(
+                                    "Table {} id in version {} swapped with another relation since previous version; unsupported",
+                                    t.def.table,
+                                    v_i
+                                );
+                            }
+                        }
+                    },
                     Node::Index(i) => {
                         let id = &i.def.id;
                         let comp_id = format!("index {}", i.def.schema_id);
@@ -1036,6 +1492,8 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
     let mut prev_version: Option<Version> = None;
     let mut prev_version_i: Option<i64> = None;
     let mut field_lookup = HashMap::new();
+    let mut unique_keys: HashMap<Table, Vec<Vec<Field>>> = HashMap::new();
+    let mut primary_keys: HashMap<Table, Vec<Field>> = HashMap::new();
     for (version_i, version) in versions {
         let path = rpds::vector![format!("Migration to {}", version_i)];
         let mut migration = vec![];
@@ -1045,9 +1503,12 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
             path: &rpds::Vector<String>,
             migration: &mut Vec<TokenStream>,
             field_lookup: &HashMap<Table, HashSet<Field>>,
+            unique_keys: &HashMap<Table, Vec<Vec<Field>>>,
+            primary_keys: &HashMap<Table, Vec<Field>>,
             q: &dyn QueryBody,
         ) {
-            let mut qctx = SqliteQueryCtx::new(errs.clone(), field_lookup.clone());
+            let mut qctx =
+                SqliteQueryCtx::new(errs.clone(), field_lookup.clone(), unique_keys.clone(), primary_keys.clone());
             let e_res = q.build(&mut qctx, path, QueryResCount::None);
             if !qctx.rust_args.is_empty() {
                 qctx.errs.err(path, format!("Migration statements can't receive arguments"));
@@ -1069,12 +1530,16 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                 &path.push_back(format!("Pre-migration statement {}", i)),
                 &mut migration,
                 &field_lookup,
+                &unique_keys,
+                &primary_keys,
                 q.as_ref(),
             );
         }
 
         // Prep for current version
         field_lookup.clear();
+        unique_keys.clear();
+        primary_keys.clear();
         let version_i = version_i as i64;
         if let Some(i) = prev_version_i {
             if version_i != i as i64 + 1 {
@@ -1101,6 +1566,44 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                     };
                     let table = field_lookup.get_mut(&f.def.table).unwrap();
                     table.insert(f.def.clone());
+                    if f.def.schema_id.0 == "rowid" {
+                        unique_keys.entry(f.def.table.clone()).or_insert_with(Vec::new).push(vec![f.def.clone()]);
+                        primary_keys.entry(f.def.table.clone()).or_insert_with(|| vec![f.def.clone()]);
+                    }
+                },
+                Node::Constraint(c) => {
+                    if let ConstraintType::PrimaryKey(pk) = &c.def.type_ {
+                        unique_keys.entry(c.def.table.clone()).or_insert_with(Vec::new).push(pk.fields.clone());
+                        primary_keys.insert(c.def.table.clone(), pk.fields.clone());
+                    }
+                },
+                Node::Fts5(t) => {
+                    let fields =
+                        t
+                            .def
+                            .columns
+                            .iter()
+                            .map(|c| c.field.clone())
+                            .chain([t.def.rowid.clone(), t.def.table_field.clone()])
+                            .collect();
+                    field_lookup.insert(t.def.table.clone(), fields);
+                    unique_keys.entry(t.def.table.clone()).or_insert_with(Vec::new).push(vec![t.def.rowid.clone()]);
+                },
+                Node::Rtree(t) => {
+                    let mut fields = HashSet::new();
+                    fields.insert(t.def.id_field.clone());
+                    for (min, max) in &t.def.dimensions {
+                        fields.insert(min.clone());
+                        fields.insert(max.clone());
+                    }
+                    fields.extend(t.def.aux.iter().cloned());
+                    field_lookup.insert(t.def.table.clone(), fields);
+                    unique_keys.entry(t.def.table.clone()).or_insert_with(Vec::new).push(vec![t.def.id_field.clone()]);
+                },
+                Node::Index(i) => {
+                    if i.def.unique {
+                        unique_keys.entry(i.def.table.clone()).or_insert_with(Vec::new).push(i.def.fields.clone());
+                    }
                 },
                 _ => { },
             };
@@ -1127,6 +1630,8 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                 &path.push_back(format!("Post-migration statement {}", i)),
                 &mut migration,
                 &field_lookup,
+                &unique_keys,
+                &primary_keys,
                 q.as_ref(),
             );
         }
@@ -1147,15 +1652,105 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
     let mut db_others = Vec::new();
     {
         let mut res_type_idents: HashMap<String, Ident> = HashMap::new();
+        let mut cursor_type_idents: HashMap<String, Ident> = HashMap::new();
+        let mut order_choice_defs: HashMap<String, String> = HashMap::new();
         for q in queries {
             let path = rpds::vector![format!("Query {}", q.name)];
-            let mut ctx = SqliteQueryCtx::new(errs.clone(), field_lookup.clone());
-            let res = QueryBody::build(q.body.as_ref(), &mut ctx, &path, q.res_count.clone());
+            let mut ctx =
+                SqliteQueryCtx::new(errs.clone(), field_lookup.clone(), unique_keys.clone(), primary_keys.clone());
+            let mut res_count_warning = None;
+            let res_count = match (&q.res_count, q.body.infer_res_count(&ctx)) {
+                (QueryResCount::Auto, Some((inferred, _))) => inferred,
+                (QueryResCount::Auto, None) => {
+                    ctx.errs.err(&path, format!("The result count of this query can't be inferred, specify it explicitly"));
+                    QueryResCount::Many
+                },
+                (QueryResCount::MaybeOne | QueryResCount::One, Some((QueryResCount::Many, Some(why)))) => {
+                    res_count_warning =
+                        Some(
+                            format!(
+                                "Query declares {:?} results but may return multiple rows ({}); constrain all fields of a unique key with `=` or use `QueryResCount::Many`",
+                                q.res_count,
+                                why
+                            ),
+                        );
+                    q.res_count.clone()
+                },
+                (res_count, _) => res_count.clone(),
+            };
+            let err_count = ctx.errs.err_count();
+            let res = QueryBody::build(q.body.as_ref(), &mut ctx, &path, res_count.clone());
+
+            // Inference assumes the query is valid, so skip the warning if it isn't (e.g. it
+            // references fields of an aliased table directly)
+            if let Some(w) = res_count_warning {
+                if ctx.errs.err_count() == err_count {
+                    ctx.errs.warn(&path, w);
+                }
+            }
             let ident = format_ident!("{}", q.name);
-            let q_text = res.1.to_string();
+            let mut q_text = res.1.to_string().to_token_stream();
+            let base_arg_count = ctx.rust_args.len();
+            if let Some((enum_name, variants)) = q.body.build_order_choices(&mut ctx, &path, res_count.clone()) {
+                let path = path.push_back(format!("Order choice {}", enum_name));
+                if variants.is_empty() {
+                    ctx.errs.err(&path, format!("Order choice has no variants"));
+                }
+                if ctx.rust_args.len() != base_arg_count {
+                    ctx.errs.err(&path, format!("Order choice variants can't introduce new parameters"));
+                }
+                if ctx.rust_arg_lookup.contains_key("order") || ctx.tuple_arg_lookup.contains_key("order") {
+                    ctx
+                        .errs
+                        .err(&path, format!("Query has a parameter named `order` which conflicts with the order choice"));
+                }
+                let enum_ident = format_ident!("{}", enum_name);
+                let variant_idents = variants.iter().map(|v| format_ident!("{}", v.0)).collect::<Vec<_>>();
+                let variant_texts = variants.iter().map(|v| v.2.to_string()).collect::<Vec<_>>();
+                let enum_def = quote!{
+                    #[derive(Clone, Copy, Debug, PartialEq, Eq)] pub enum #enum_ident {
+                        #(#variant_idents,) *
+                    }
+                };
+                match order_choice_defs.entry(enum_name.clone()) {
+                    std::collections::hash_map::Entry::Occupied(e) => {
+                        if *e.get() != enum_def.to_string() {
+                            ctx
+                                .errs
+                                .err(
+                                    &path,
+                                    format!("Order choice {} was already defined with different variants", enum_name),
+                                );
+                        }
+                    },
+                    std::collections::hash_map::Entry::Vacant(e) => {
+                        e.insert(enum_def.to_string());
+                        db_others.push(enum_def);
+                    },
+                }
+                ctx.rust_args.push(quote!(order: #enum_ident));
+                q_text = quote!(match order {
+                    #(#enum_ident:: #variant_idents => #variant_texts,) *
+                });
+            }
+            let mut res_type = res.0.0;
+            let mut cursor_type = vec![];
+            let paginated = q.body.build_paginated(&mut ctx, &path);
+            if let Some(paginated) = &paginated {
+                cursor_type = paginated.res_type.0.clone();
+                res_type = cursor_type.drain(..cursor_type.len().saturating_sub(paginated.cursor_len)).collect();
+                if ctx.rust_arg_lookup.contains_key("cursor") || ctx.tuple_arg_lookup.contains_key("cursor") {
+                    ctx
+                        .errs
+                        .err(&path, format!("Query has a parameter named `cursor` which conflicts with the pagination cursor"));
+                }
+            }
+            let cursor_start = res_type.len();
             let args = ctx.rust_args.split_off(0);
             let args_forward = ctx.query_args.split_off(0);
             drop(ctx);
+            let mut cursor_fields = vec![];
+            let mut cursor_unforward = vec![];
             let (res_ident, res_def, unforward_res) = {
                 fn convert_one_res(
                     errs: &mut Errs,
@@ -1281,8 +1876,17 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                     })));
                 }
 
-                if res.0.0.len() == 1 {
-                    let e = &res.0.0[0];
+                let cursor_path = path.push_back(format!("Paginate"));
+                for (i, (k, v)) in cursor_type.iter().enumerate() {
+                    let Some((_, type_ident, unforward)) = convert_one_res(&mut errs, &cursor_path, cursor_start + i, k, v) else {
+                        continue;
+                    };
+                    cursor_fields.push(type_ident);
+                    cursor_unforward.push(unforward);
+                }
+
+                if res_type.len() == 1 {
+                    let e = &res_type[0];
                     let (_, type_ident, unforward) = match convert_one_res(&mut errs, &path, 0, &e.0, &e.1) {
                         None => {
                             continue;
@@ -1293,7 +1897,7 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                 } else {
                     let mut fields = vec![];
                     let mut unforward_fields = vec![];
-                    for (i, (k, v)) in res.0.0.into_iter().enumerate() {
+                    for (i, (k, v)) in res_type.into_iter().enumerate() {
                         let (k_ident, type_ident, unforward) = match convert_one_res(&mut errs, &path, i, &k, &v) {
                             Some(x) => x,
                             None => continue,
@@ -1331,7 +1935,85 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                 }
             };
             let db_arg = quote!(db:& rusqlite:: Connection);
-            match q.res_count {
+            if let Some(paginated) = paginated {
+                let path = path.push_back(format!("Paginate"));
+                match res_count {
+                    QueryResCount::Many => { },
+                    _ => {
+                        errs.err(&path, format!("Paginated queries must return `QueryResCount::Many`"));
+                    },
+                }
+                let cursor_body = quote!((#(pub #cursor_fields,) *));
+                let cursor_type_count = cursor_type_idents.len();
+                let cursor_ident = match cursor_type_idents.entry(cursor_body.to_string()) {
+                    std::collections::hash_map::Entry::Occupied(e) => e.get().clone(),
+                    std::collections::hash_map::Entry::Vacant(e) => {
+                        let ident = format_ident!("DbCursor{}", cursor_type_count);
+                        e.insert(ident.clone());
+                        db_others.push(quote!(#[derive(Clone, Debug)] pub struct #ident #cursor_body;));
+                        ident
+                    },
+                };
+                let cursor_idents = (0 .. cursor_fields.len()).map(|i| format_ident!("cursor_{}", i)).collect::<Vec<_>>();
+                let first_text = paginated.first.to_string();
+                let next_text = paginated.next.to_string();
+                let first_args =
+                    args
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| !paginated.cursor_args.contains(i))
+                        .map(|(_, a)| a)
+                        .collect::<Vec<_>>();
+                let first_args_forward =
+                    args_forward
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| !paginated.cursor_args.contains(i))
+                        .map(|(_, a)| a)
+                        .collect::<Vec<_>>();
+                if let Some(res_def) = res_def {
+                    db_others.push(res_def);
+                }
+                db_others.push(quote!{
+                    pub fn #ident(
+                        #db_arg,
+                        #(#first_args,) * cursor: Option < #cursor_ident >
+                    ) -> Result <(Vec < #res_ident >, Option < #cursor_ident >),
+                    GoodError > {
+                        let mut out = vec![];
+                        let mut next = None;
+                        let mut more = false;
+                        let query;
+                        let mut stmt;
+                        let mut rows = match cursor {
+                            None => {
+                                query = #first_text;
+                                stmt = db.prepare(query).to_good_error_query(query)?;
+                                stmt.query(rusqlite::params![#(#first_args_forward,) *]).to_good_error_query(query)?
+                            },
+                            Some(#cursor_ident(#(#cursor_idents,) *)) => {
+                                query = #next_text;
+                                stmt = db.prepare(query).to_good_error_query(query)?;
+                                stmt.query(rusqlite::params![#(#args_forward,) *]).to_good_error_query(query)?
+                            },
+                        };
+                        while let Some(r) = rows.next().to_good_error(|| format!("Getting row in query [{}]", query)) ? {
+                            if (out.len() as i64) >= page_size {
+                                more = true;
+                                break;
+                            }
+                            out.push(#unforward_res);
+                            next = Some(#cursor_ident(#(#cursor_unforward,) *));
+                        }
+                        if !more {
+                            next = None;
+                        }
+                        Ok((out, next))
+                    }
+                });
+                continue;
+            }
+            match res_count {
                 QueryResCount::None => {
                     db_others.push(quote!{
                         pub fn #ident(#db_arg, #(#args,) *) -> Result <(),
@@ -1409,6 +2091,7 @@ pub fn generate(output: &Path, versions: Vec<(usize, Version)>, queries: Vec<Que
                         }
                     });
                 },
+                QueryResCount::Auto => unreachable!(),
             }
         }
     }
@@ -1559,17 +2242,49 @@ mod test {
     };
     use crate::sqlite::{
         new_select,
+        new_select_body,
+        new_select_from,
         QueryResCount,
         new_insert,
+        query::{
+            functions,
+            select_body::{
+                JoinSource,
+                NamedSelectSource,
+                Order,
+                SelectJunction,
+                SelectJunctionOperator,
+            },
+            utils::{
+                CteBuilder,
+                With,
+            },
+        },
     };
     use super::{
-        schema::field::{
-            field_str,
-            field_i32,
+        schema::{
+            constraint::{
+                ConstraintType,
+                PrimaryKeyDef,
+            },
+            field::{
+                field_bytes,
+                field_str,
+                field_i32,
+            },
         },
         generate,
         Version,
-        query::expr::Expr,
+        query::expr::{
+            BinOp,
+            Binding,
+            Expr,
+            WindowFrame,
+            WindowFrameBound,
+            WindowFrameUnits,
+            WindowSpec,
+        },
+        types::SimpleSimpleType,
     };
 
     #[test]
@@ -1660,4 +2375,439 @@ mod test {
             ).is_err()
         );
     }
+
+    #[test]
+    fn test_select_ungrouped_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zVL2C0E1Z", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_str().build());
+        let hizat2 = bananna.field(&mut v, "z3CRAVV3M", "hizat2", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![
+                    new_select(&bananna)
+                        .return_field(&hizat)
+                        .return_field(&hizat2)
+                        .group(vec![Expr::Binding(Binding::field(&hizat))])
+                        .build_query("x", QueryResCount::Many)
+                ],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_select_grouped_primary_key() {
+        let mut v = Version::default();
+        let bananna = v.table("zR8N2KD5W", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let hizat2 = bananna.field(&mut v, "z3CRAVV3M", "hizat2", field_str().build());
+        bananna.constraint(
+            &mut v,
+            "zU7B3QF1N",
+            "bananna_pk",
+            ConstraintType::PrimaryKey(PrimaryKeyDef { fields: vec![hizat.clone()] }),
+        );
+        generate(
+            &PathBuf::from_str("/dev/null").unwrap(),
+            vec![(0usize, v)],
+            vec![
+                new_select(&bananna)
+                    .return_field(&hizat2)
+                    .return_named("count", functions::count_rows())
+                    .group(vec![Expr::Binding(Binding::field(&hizat))])
+                    .build_query("x", QueryResCount::Many)
+            ],
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_select_having_not_bool_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zB3W9XRTC", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_str().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![
+                    new_select(&bananna)
+                        .return_field(&hizat)
+                        .group(vec![Expr::Binding(Binding::field(&hizat))])
+                        .having(Expr::LitI32(1))
+                        .build_query("x", QueryResCount::Many)
+                ],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_select_paginate_nullable_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zH1T5WQ6M", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().opt().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![
+                    new_select(&bananna)
+                        .return_field(&hizat)
+                        .paginate_by(&[(Expr::Binding(Binding::field(&hizat)), Order::Asc)])
+                        .build_query("x", QueryResCount::Many)
+                ],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_case_condition_not_bool_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zC4K8QJ2N", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_named("x", Expr::Case {
+                    operand: None,
+                    whens: vec![(Expr::Binding(Binding::field(&hizat)), Expr::LitI32(1))],
+                    else_: None,
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_case_result_mismatch_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zT0M3WZ8R", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_named("x", Expr::Case {
+                    operand: Some(Box::new(Expr::Binding(Binding::field(&hizat)))),
+                    whens: vec![(Expr::LitI32(1), Expr::LitI32(1))],
+                    else_: Some(Box::new(Expr::LitString("no".into()))),
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_coalesce_mismatch_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zJ5Q2MV8T", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().opt().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![
+                    new_select(&bananna)
+                        .return_named(
+                            "x",
+                            functions::coalesce(
+                                vec![Expr::Binding(Binding::field(&hizat)), Expr::LitString("no".into())],
+                            ),
+                        )
+                        .build_query("x", QueryResCount::Many)
+                ],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_window_undefined_base_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zN2X8CQ5L", "bananna");
+        bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_named("x", Expr::Window {
+                    expr: Box::new(functions::row_number()),
+                    filter: None,
+                    over: WindowSpec {
+                        base: Some("nope".to_string()),
+                        ..Default::default()
+                    },
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_window_filter_not_aggregate_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zH6T1RD9K", "bananna");
+        bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_named("x", Expr::Window {
+                    expr: Box::new(functions::row_number()),
+                    filter: Some(Box::new(Expr::LitBool(true))),
+                    over: WindowSpec::default(),
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_window_frame_reversed_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zP4G7WS2E", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_named("x", Expr::Window {
+                    expr: Box::new(functions::sum(Expr::Binding(Binding::field(&hizat)))),
+                    filter: None,
+                    over: WindowSpec {
+                        order_by: vec![(Expr::Binding(Binding::field(&hizat)), Order::Asc)],
+                        frame: Some(WindowFrame {
+                            units: WindowFrameUnits::Rows,
+                            start: WindowFrameBound::CurrentRow,
+                            end: Some(WindowFrameBound::Preceding(Box::new(Expr::LitI64(1)))),
+                        }),
+                        ..Default::default()
+                    },
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_between_operand_count_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zK3B9TQ6M", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_field(&hizat).where_(Expr::BinOp {
+                    left: Box::new(Expr::Binding(Binding::field(&hizat))),
+                    op: BinOp::Between,
+                    right: Box::new(Expr::LitI32(1)),
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_concat_not_string_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zD8F2YH5N", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_named("x", Expr::BinOp {
+                    left: Box::new(Expr::Binding(Binding::field(&hizat))),
+                    op: BinOp::Concat,
+                    right: Box::new(Expr::LitString("x".into())),
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_convert_string_to_bool_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zT6R1QW8C", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_str().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_named("x", Expr::Convert {
+                    expr: Box::new(Expr::Binding(Binding::field(&hizat))),
+                    type_: SimpleSimpleType::Bool,
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_tuple_param_bytes_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zG2H8TN4L", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_bytes().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_field(&hizat).where_(Expr::BinOp {
+                    left: Box::new(Expr::Tuple(vec![Expr::Binding(Binding::field(&hizat))])),
+                    op: BinOp::In,
+                    right: Box::new(Expr::ParamTuples {
+                        name: "keys".into(),
+                        types: vec![hizat.type_.type_.clone()],
+                    }),
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cte_self_reference_not_recursive_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zB7Q3WN5J", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let mut hibbo = CteBuilder::new("hibbo", new_select_body(&bananna).return_field(&hizat).build());
+        let zathi = hibbo.field("zathi", hizat.type_.type_.clone());
+        hibbo.body_junction(SelectJunction {
+            op: SelectJunctionOperator::UnionAll,
+            body: new_select_body(&hibbo.table()).return_field(&zathi).build(),
+        });
+        let (hibbo, hibbo_cte) = hibbo.build();
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&hibbo).with(With {
+                    recursive: false,
+                    ctes: vec![hibbo_cte],
+                }).return_field(&zathi).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cte_self_reference_in_subquery_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zP4V8RM2C", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let mut hibbo = CteBuilder::new("hibbo", new_select_body(&bananna).return_field(&hizat).build());
+        let zathi = hibbo.field("zathi", hizat.type_.type_.clone());
+        hibbo.body_junction(SelectJunction {
+            op: SelectJunctionOperator::UnionAll,
+            body: new_select_body(&hibbo.table()).return_field(&zathi).where_(Expr::Exists {
+                not: true,
+                body: Box::new(new_select(&hibbo.table()).return_field(&zathi).build_subquery()),
+            }).build(),
+        });
+        let (hibbo, hibbo_cte) = hibbo.build();
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&hibbo).with(With {
+                    recursive: true,
+                    ctes: vec![hibbo_cte],
+                }).return_field(&zathi).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_subquery_paginate_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zH3S6YC9F", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_i32().build());
+        let sub =
+            new_select(&bananna)
+                .return_field(&hizat)
+                .paginate_by(&[(Expr::Binding(Binding::field(&hizat)), Order::Asc)])
+                .build_subquery();
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select(&bananna).return_field(&hizat).where_(Expr::BinOp {
+                    left: Box::new(Expr::Binding(Binding::field(&hizat))),
+                    op: BinOp::In,
+                    right: Box::new(Expr::Select(Box::new(sub))),
+                }).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_values_column_type_mismatch_bad() {
+        let mut v = Version::default();
+        v.table("zT8D2WK5P", "bananna");
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![new_select_from(NamedSelectSource {
+                    source: JoinSource::Values {
+                        columns: vec!["x".into()],
+                        rows: vec![vec![Expr::LitI32(1)], vec![Expr::LitString("two".into())]],
+                    },
+                    alias: Some("vals".into()),
+                }).return_named("x", Expr::Binding(Binding {
+                    table_id: "vals".into(),
+                    id: "x".into(),
+                })).build_query("x", QueryResCount::Many)],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_fts5_match_non_string_bad() {
+        let mut v = Version::default();
+        let mut search = v.fts5_table("zW5N1RJ7C", "banan_search");
+        search.column("zE2K8TB4M", "hizat", field_str().build());
+        let search = search.build(&mut v);
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![
+                    new_select(&search)
+                        .return_field(&search.rowid)
+                        .where_(functions::fts5_match(&search, Expr::LitI32(4)))
+                        .build_query("x", QueryResCount::Many)
+                ],
+            ).is_err()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "can't have more than 5 dimensions")]
+    fn test_rtree_too_many_dimensions_bad() {
+        let v = Version::default();
+        let mut places = v.rtree_table("zC7H4XP1M", "places");
+        places.id_field("zJ2T9FW6L", "id", None);
+        for i in 0 .. 6 {
+            places.dimension(format!("zmin{}", i), format!("min_{}", i), format!("zmax{}", i), format!("max_{}", i));
+        }
+    }
+
+    #[test]
+    fn test_substr_string_start_bad() {
+        let mut v = Version::default();
+        let bananna = v.table("zT3G8NQ5D", "bananna");
+        let hizat = bananna.field(&mut v, "z437INV6D", "hizat", field_str().build());
+        assert!(
+            generate(
+                &PathBuf::from_str("/dev/null").unwrap(),
+                vec![(0usize, v)],
+                vec![
+                    new_select(&bananna)
+                        .return_named(
+                            "x",
+                            functions::substr(
+                                Expr::Binding(Binding::field(&hizat)),
+                                Expr::LitString("1".into()),
+                                Expr::LitI32(2),
+                            ),
+                        )
+                        .build_query("x", QueryResCount::Many)
+                ],
+            ).is_err()
+        );
+    }
 }
@@ -1,23 +1,19 @@
 use {
     super::{
-        select_body::{
-            Order,
-            SelectBody,
-            SelectJunction,
-        },
+        select::Select,
+        select_body::Order,
         utils::SqliteQueryCtx,
     },
     crate::{
         sqlite::{
-            query::select_body::build_select_junction,
             schema::field::Field,
             types::{
                 to_rust_types,
+                to_sql_type,
                 SimpleSimpleType,
                 SimpleType,
                 Type,
             },
-            QueryResCount,
         },
         utils::{
             sanitize_ident,
@@ -25,6 +21,10 @@ use {
             Tokens,
         },
     },
+    proc_macro2::{
+        Ident,
+        TokenStream,
+    },
     quote::{
         format_ident,
         quote,
@@ -32,7 +32,10 @@ use {
     },
     samevariant::samevariant,
     std::{
-        collections::HashMap,
+        collections::{
+            HashMap,
+            HashSet,
+        },
         fmt::Display,
         rc::Rc,
     },
@@ -102,6 +105,15 @@ pub enum Expr {
         name: String,
         type_: Type,
     },
+    /// A query parameter that's a list of tuples, with one element per type in
+    /// `types`. This evaluates to a subquery with a row per tuple, for use on the
+    /// right side of `In` and `NotIn` with a `Tuple`, for instance to look up rows by
+    /// a composite key. The values are passed as JSON, so the types can't be arrays or
+    /// bytes.
+    ParamTuples {
+        name: String,
+        types: Vec<Type>,
+    },
     /// This evaluates to the value of a field in the query main or joined tables. If
     /// you've aliased tables or field names, you'll have to instantiate `FieldId`
     /// yourself with the appropriate values. For synthetic values like function
@@ -122,6 +134,13 @@ pub enum Expr {
         op: PrefixOp,
         right: Box<Expr>,
     },
+    PostfixOp {
+        op: PostfixOp,
+        left: Box<Expr>,
+    },
+    /// A row value `(a, b, ...)`, for comparing multiple values at once. The elements
+    /// must be scalars.
+    Tuple(Vec<Expr>),
     /// Represents a call to an SQL function, like `collate()`. You must provide the
     /// type of the result since we don't have a table of functions and their return
     /// types at present.
@@ -131,33 +150,575 @@ pub enum Expr {
         /// Checks the input types and computes the resulting type
         compute_type: ComputeType,
     },
-    // This is an `OVER` windowing function. If neither `partition_by` nor `order_by`
-    // have elements it'll be rendered as `OVER()` (all rows).
+    // This is an `OVER` windowing function. If `over` is empty it'll be rendered as
+    // `OVER()` (all rows). `filter` (`FILTER (WHERE ...)`) can only be used with
+    // aggregate functions.
     Window {
         expr: Box<Expr>,
-        partition_by: Vec<Expr>,
-        order_by: Vec<(Expr, Order)>,
+        filter: Option<Box<Expr>>,
+        over: WindowSpec,
     },
-    /// A sub SELECT query.
-    Select {
-        body: Box<SelectBody>,
-        body_junctions: Vec<SelectJunction>,
+    /// A `CASE` expression. If `operand` is present, each `when` condition is
+    /// compared to it for equality, otherwise the conditions must be bools. All
+    /// results must have the same type. The result is nullable if any result is
+    /// nullable or there's no `else_`.
+    Case {
+        operand: Option<Box<Expr>>,
+        whens: Vec<(Expr, Expr)>,
+        else_: Option<Box<Expr>>,
     },
+    /// A sub SELECT query, with optional CTEs and junctions. See
+    /// `SelectBuilder::build_subquery`.
+    Select(Box<Select>),
     Exists {
         not: bool,
-        body: Box<SelectBody>,
-        body_junctions: Vec<SelectJunction>,
+        body: Box<Select>,
     },
     /// This is a synthetic expression, saying to treat the result of the expression as
     /// having the specified type. Use this for casting between primitive types and
-    /// Rust new-types for instance.
+    /// Rust new-types for instance. No SQL is generated for it; to convert values
+    /// between SQL types use `Convert`.
     Cast(Box<Expr>, Type),
+    /// A SQL `CAST(expr AS type)` conversion. Only conversions that produce values
+    /// readable as `type_` are allowed: between numeric types, numbers to and from
+    /// strings, integers to and from bools, strings to and from bytes, and times to
+    /// the type they're stored as. The result is nullable if `expr` is.
+    Convert {
+        expr: Box<Expr>,
+        type_: SimpleSimpleType,
+    },
+}
+
+/// The unit of window frame bounds.
+#[derive(Clone, Debug)]
+pub enum WindowFrameUnits {
+    Rows,
+    /// Bounds are distances in the (single) `order_by` value.
+    Range,
+    /// Bounds count groups of rows with equal `order_by` values.
+    Groups,
+}
+
+/// One end of a window frame. Offsets must be non-null integers, or for `Range`
+/// numbers.
+#[derive(Clone, Debug)]
+pub enum WindowFrameBound {
+    UnboundedPreceding,
+    Preceding(Box<Expr>),
+    CurrentRow,
+    Following(Box<Expr>),
+    UnboundedFollowing,
+}
+
+impl WindowFrameBound {
+    fn rank(&self) -> usize {
+        match self {
+            WindowFrameBound::UnboundedPreceding => 0,
+            WindowFrameBound::Preceding(_) => 1,
+            WindowFrameBound::CurrentRow => 2,
+            WindowFrameBound::Following(_) => 3,
+            WindowFrameBound::UnboundedFollowing => 4,
+        }
+    }
+}
+
+/// A window frame clause, like `rows between 6 preceding and current row`.
+#[derive(Clone, Debug)]
+pub struct WindowFrame {
+    pub units: WindowFrameUnits,
+    pub start: WindowFrameBound,
+    /// If `None`, the frame ends at the current row.
+    pub end: Option<WindowFrameBound>,
+}
+
+/// The window specification for `OVER` and named `WINDOW` definitions. `base` is
+/// the name of a window defined with `window()` on the select to extend - a
+/// window with a `base` can't have `partition_by`.
+#[derive(Clone, Debug, Default)]
+pub struct WindowSpec {
+    pub base: Option<String>,
+    pub partition_by: Vec<Expr>,
+    pub order_by: Vec<(Expr, Order)>,
+    pub frame: Option<WindowFrame>,
 }
 
+impl WindowSpec {
+    pub(crate) fn is_bare_name(&self) -> bool {
+        return self.base.is_some() && self.partition_by.is_empty() && self.order_by.is_empty() &&
+            self.frame.is_none();
+    }
+
+    /// Collects the subqueries in the window's expressions, see `Expr::subqueries`.
+    pub(crate) fn subqueries<'a>(&'a self, out: &mut Vec<&'a Select>) {
+        for e in &self.partition_by {
+            e.subqueries(out);
+        }
+        for (e, _) in &self.order_by {
+            e.subqueries(out);
+        }
+        if let Some(frame) = &self.frame {
+            for bound in [Some(&frame.start), frame.end.as_ref()].into_iter().flatten() {
+                if let WindowFrameBound::Preceding(e) | WindowFrameBound::Following(e) = bound {
+                    e.subqueries(out);
+                }
+            }
+        }
+    }
+
+    /// Builds the contents of the window parentheses.
+    pub(crate) fn build(
+        &self,
+        ctx: &mut SqliteQueryCtx,
+        path: &rpds::Vector<String>,
+        scope: &HashMap<Binding, Type>,
+    ) -> Tokens {
+        let mut out = Tokens::new();
+        if let Some(base) = &self.base {
+            if !ctx.windows.contains(base) {
+                ctx.errs.err(path, format!("Window [{}] isn't defined (before this point) in this select", base));
+            }
+            if !self.partition_by.is_empty() {
+                ctx.errs.err(path, format!("A window based on another window can't have a partition by clause"));
+            }
+            out.id(base);
+        }
+        if !self.partition_by.is_empty() {
+            out.s("partition by");
+            for (i, e) in self.partition_by.iter().enumerate() {
+                let path = path.push_back(format!("Partition by {}", i));
+                if i > 0 {
+                    out.s(",");
+                }
+                let (_, p) = e.build(ctx, &path, &scope);
+                out.s(&p.to_string());
+            }
+        }
+        if !self.order_by.is_empty() {
+            out.s("order by");
+            for (i, o) in self.order_by.iter().enumerate() {
+                let path = path.push_back(format!("Order by clause {}", i));
+                if i > 0 {
+                    out.s(",");
+                }
+                let (_, o_tokens) = o.0.build(ctx, &path, &scope);
+                out.s(&o_tokens.to_string());
+                out.s(o.1.to_sql());
+            }
+        }
+        if let Some(frame) = &self.frame {
+            let path = path.push_back("Frame".into());
+            match frame.units {
+                WindowFrameUnits::Rows => out.s("rows"),
+                WindowFrameUnits::Range => {
+                    let has_offset =
+                        [Some(&frame.start), frame.end.as_ref()]
+                            .into_iter()
+                            .flatten()
+                            .any(|b| matches!(b, WindowFrameBound::Preceding(_) | WindowFrameBound::Following(_)));
+                    if has_offset && self.base.is_none() && self.order_by.len() != 1 {
+                        ctx
+                            .errs
+                            .err(
+                                &path,
+                                format!(
+                                    "Range frames with offsets require exactly one order by clause, but got {}",
+                                    self.order_by.len()
+                                ),
+                            );
+                    }
+                    out.s("range")
+                },
+                WindowFrameUnits::Groups => {
+                    if self.base.is_none() && self.order_by.is_empty() {
+                        ctx.errs.err(&path, format!("Groups frames require an order by clause"));
+                    }
+                    out.s("groups")
+                },
+            };
+            if matches!(frame.start, WindowFrameBound::UnboundedFollowing) {
+                ctx.errs.err(&path, format!("A frame can't start at unbounded following"));
+            }
+            match &frame.end {
+                Some(end) => {
+                    if matches!(end, WindowFrameBound::UnboundedPreceding) {
+                        ctx.errs.err(&path, format!("A frame can't end at unbounded preceding"));
+                    }
+                    if frame.start.rank() > end.rank() {
+                        ctx.errs.err(&path, format!("The frame start is after the frame end"));
+                    }
+                    out.s("between");
+                    frame.build_bound(ctx, &path.push_back("Start".into()), scope, &mut out, &frame.start);
+                    out.s("and");
+                    frame.build_bound(ctx, &path.push_back("End".into()), scope, &mut out, end);
+                },
+                None => {
+                    if frame.start.rank() > WindowFrameBound::CurrentRow.rank() {
+                        ctx.errs.err(&path, format!("A frame without an end can't start after the current row"));
+                    }
+                    frame.build_bound(ctx, &path.push_back("Start".into()), scope, &mut out, &frame.start);
+                },
+            }
+        }
+        return out;
+    }
+}
+
+impl WindowFrame {
+    fn build_bound(
+        &self,
+        ctx: &mut SqliteQueryCtx,
+        path: &rpds::Vector<String>,
+        scope: &HashMap<Binding, Type>,
+        out: &mut Tokens,
+        bound: &WindowFrameBound,
+    ) {
+        let offset = match bound {
+            WindowFrameBound::UnboundedPreceding => {
+                out.s("unbounded preceding");
+                return;
+            },
+            WindowFrameBound::CurrentRow => {
+                out.s("current row");
+                return;
+            },
+            WindowFrameBound::UnboundedFollowing => {
+                out.s("unbounded following");
+                return;
+            },
+            WindowFrameBound::Preceding(e) => (e, "preceding"),
+            WindowFrameBound::Following(e) => (e, "following"),
+        };
+        let (offset_t, offset_tokens) = offset.0.build(ctx, path, scope);
+        if let Some((_, t)) = offset_t.assert_scalar(&mut ctx.errs, path) {
+            let ok = match self.units {
+                WindowFrameUnits::Rows | WindowFrameUnits::Groups => matches!(
+                    t.type_.type_,
+                    SimpleSimpleType::U32 | SimpleSimpleType::I32 | SimpleSimpleType::I64
+                ),
+                WindowFrameUnits::Range => matches!(
+                    t.type_.type_,
+                    SimpleSimpleType::U32 | SimpleSimpleType::I32 | SimpleSimpleType::I64 | SimpleSimpleType::F32 |
+                        SimpleSimpleType::F64
+                ),
+            };
+            if !ok || t.opt || t.array {
+                ctx
+                    .errs
+                    .err(path, format!("Frame offsets must be non-null integers (or numbers for range frames), but got {:?}", t));
+            }
+        }
+        out.s(&offset_tokens.to_string());
+        out.s(offset.1);
+    }
+}
+
+/// Built-in SQLite aggregate functions, used to validate grouping. `min` and `max`
+/// are only aggregates when called with a single argument.
+const AGGREGATE_FUNCS: &[&str] = &[
+    "avg",
+    "count",
+    "group_concat",
+    "json_group_array",
+    "json_group_object",
+    "jsonb_group_array",
+    "jsonb_group_object",
+    "max",
+    "min",
+    "string_agg",
+    "sum",
+    "total",
+];
+
 impl Expr {
     pub fn field(f: &Field) -> Expr {
         return Expr::Binding(Binding::field(f));
     }
+
+    /// A window function over a window with only partitioning and ordering, like
+    /// `Expr::Window` took before it had `filter` and `over`.
+    pub fn window(expr: Expr, partition_by: Vec<Expr>, order_by: Vec<(Expr, Order)>) -> Expr {
+        return Expr::Window {
+            expr: Box::new(expr),
+            filter: None,
+            over: WindowSpec {
+                base: None,
+                partition_by: partition_by,
+                order_by: order_by,
+                frame: None,
+            },
+        };
+    }
+
+    /// Returns true if this is a call to an aggregate function.
+    pub(crate) fn is_aggregate(&self) -> bool {
+        match self {
+            Expr::Call { func, args, .. } => {
+                let func = func.to_ascii_lowercase();
+                if (func == "min" || func == "max") && args.len() != 1 {
+                    return false;
+                }
+                return AGGREGATE_FUNCS.contains(&func.as_str());
+            },
+            _ => return false,
+        }
+    }
+
+    /// Returns true if this expression or any sub-expressions evaluated in the same
+    /// row context (i.e. excluding windows and subqueries) is an aggregate.
+    pub(crate) fn contains_aggregate(&self) -> bool {
+        return self.is_aggregate() || self.row_children().into_iter().any(|e| e.contains_aggregate());
+    }
+
+    /// Collects bindings that must be non-null if this expression evaluates to true,
+    /// from `AND` conjunctions of comparisons and `IS NOT NULL` checks. Used to
+    /// narrow nullable types in `WHERE` and inner `JOIN` conditions.
+    pub(crate) fn non_null_if_true(&self, out: &mut HashSet<Binding>) {
+        match self {
+            Expr::BinOp { left, op, right } => {
+                match op {
+                    BinOp::And => {
+                        left.non_null_if_true(out);
+                        right.non_null_if_true(out);
+                    },
+                    BinOp::IsNot | BinOp::TzIsNot if matches!(right.as_ref(), Expr::LitNull(_)) => {
+                        left.strict_bindings(out);
+                    },
+                    BinOp::In | BinOp::NotIn => {
+                        // Elements on the right may be null
+                        left.strict_bindings(out);
+                    },
+                    op if op.is_strict_comparison() => {
+                        left.strict_bindings(out);
+                        right.strict_bindings(out);
+                    },
+                    _ => { },
+                }
+            },
+            Expr::BinOpChain { op, exprs } => {
+                match op {
+                    BinOp::And => {
+                        for e in exprs {
+                            e.non_null_if_true(out);
+                        }
+                    },
+                    BinOp::In | BinOp::NotIn => {
+                        if let Some(e) = exprs.first() {
+                            e.strict_bindings(out);
+                        }
+                    },
+                    op if op.is_strict_comparison() => {
+                        for e in exprs {
+                            e.strict_bindings(out);
+                        }
+                    },
+                    _ => { },
+                }
+            },
+            Expr::PostfixOp { op: PostfixOp::IsNotNull, left } => {
+                left.strict_bindings(out);
+            },
+            _ => { },
+        }
+    }
+
+    /// Collects bindings which, if null, make this expression null.
+    fn strict_bindings(&self, out: &mut HashSet<Binding>) {
+        match self {
+            Expr::Binding(b) => {
+                out.insert(b.clone());
+            },
+            Expr::BinOp { left, op, right } if op.is_strict_arithmetic() => {
+                left.strict_bindings(out);
+                right.strict_bindings(out);
+            },
+            Expr::BinOpChain { op, exprs } if op.is_strict_arithmetic() => {
+                for e in exprs {
+                    e.strict_bindings(out);
+                }
+            },
+            Expr::PrefixOp { op: PrefixOp::Negate | PrefixOp::BitNot, right } => {
+                right.strict_bindings(out);
+            },
+            Expr::Cast(e, _) | Expr::Convert { expr: e, .. } => {
+                e.strict_bindings(out);
+            },
+            _ => { },
+        }
+    }
+
+    /// Collects `binding = value` equalities that must hold if this expression
+    /// evaluates to true, from `AND` conjunctions. Comparisons of two bindings are
+    /// collected in both directions. Used to prove a query returns at most one row.
+    pub(crate) fn equalities_if_true<'a>(&'a self, out: &mut Vec<(&'a Binding, &'a Expr)>) {
+        match self {
+            Expr::BinOp { left, op, right } => {
+                match op {
+                    BinOp::And => {
+                        left.equalities_if_true(out);
+                        right.equalities_if_true(out);
+                    },
+                    BinOp::Equals => {
+                        if let Expr::Binding(b) = left.as_ref() {
+                            out.push((b, right.as_ref()));
+                        }
+                        if let Expr::Binding(b) = right.as_ref() {
+                            out.push((b, left.as_ref()));
+                        }
+                    },
+                    _ => { },
+                }
+            },
+            Expr::BinOpChain { op: BinOp::And, exprs } => {
+                for e in exprs {
+                    e.equalities_if_true(out);
+                }
+            },
+            _ => { },
+        }
+    }
+
+    /// Returns true if this expression has a single value for the whole query, given
+    /// that the sources named in `fixed` (by table id or alias) have at most one row.
+    /// Function calls and subqueries are conservatively treated as not fixed.
+    pub(crate) fn is_fixed(&self, fixed: &HashSet<String>) -> bool {
+        match self {
+            Expr::Binding(b) => return fixed.contains(&b.table_id),
+            Expr::Call { .. } | Expr::Window { .. } | Expr::Select { .. } | Expr::Exists { .. } => return false,
+            _ => return self.row_children().into_iter().all(|e| e.is_fixed(fixed)),
+        }
+    }
+
+    /// Returns true if the expressions are structurally the same, for matching
+    /// grouping clauses. Function calls are compared by name and arguments, and
+    /// `Cast` is ignored since it doesn't change the SQL. Tuple parameters, windows
+    /// and subqueries are never the same.
+    pub(crate) fn same_as(&self, other: &Expr) -> bool {
+        fn all_same(a: &[Expr], b: &[Expr]) -> bool {
+            return a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.same_as(b));
+        }
+
+        fn opt_same(a: &Option<Box<Expr>>, b: &Option<Box<Expr>>) -> bool {
+            match (a, b) {
+                (None, None) => return true,
+                (Some(a), Some(b)) => return a.same_as(b),
+                _ => return false,
+            }
+        }
+
+        match (self, other) {
+            (Expr::Cast(a, _), b) => return a.same_as(b),
+            (a, Expr::Cast(b, _)) => return a.same_as(b),
+            (Expr::LitArray(a), Expr::LitArray(b)) | (Expr::Tuple(a), Expr::Tuple(b)) => return all_same(a, b),
+            (Expr::LitNull(a), Expr::LitNull(b)) => return a == b,
+            (Expr::LitBool(a), Expr::LitBool(b)) => return a == b,
+            (Expr::LitI32(a), Expr::LitI32(b)) => return a == b,
+            (Expr::LitI64(a), Expr::LitI64(b)) => return a == b,
+            (Expr::LitU32(a), Expr::LitU32(b)) => return a == b,
+            (Expr::LitF32(a), Expr::LitF32(b)) => return a.to_bits() == b.to_bits(),
+            (Expr::LitF64(a), Expr::LitF64(b)) => return a.to_bits() == b.to_bits(),
+            (Expr::LitString(a), Expr::LitString(b)) => return a == b,
+            (Expr::LitBytes(a), Expr::LitBytes(b)) => return a == b,
+            #[cfg(feature = "chrono")]
+            (Expr::LitUtcTimeSChrono(a), Expr::LitUtcTimeSChrono(b)) |
+            (Expr::LitUtcTimeMsChrono(a), Expr::LitUtcTimeMsChrono(b)) => return a == b,
+            #[cfg(feature = "chrono")]
+            (Expr::LitFixedOffsetTimeMsChrono(a), Expr::LitFixedOffsetTimeMsChrono(b)) => return a == b,
+            #[cfg(feature = "jiff")]
+            (Expr::LitUtcTimeSJiff(a), Expr::LitUtcTimeSJiff(b)) |
+            (Expr::LitUtcTimeMsJiff(a), Expr::LitUtcTimeMsJiff(b)) => return a == b,
+            (Expr::Param { name: a, .. }, Expr::Param { name: b, .. }) => return a == b,
+            (Expr::Binding(a), Expr::Binding(b)) => return a == b,
+            (
+                Expr::BinOp { left: a_left, op: a_op, right: a_right },
+                Expr::BinOp { left: b_left, op: b_op, right: b_right },
+            ) => return a_op == b_op && a_left.same_as(b_left) && a_right.same_as(b_right),
+            (Expr::BinOpChain { op: a_op, exprs: a }, Expr::BinOpChain { op: b_op, exprs: b }) => {
+                return a_op == b_op && all_same(a, b);
+            },
+            (Expr::PrefixOp { op: a_op, right: a }, Expr::PrefixOp { op: b_op, right: b }) => {
+                return a_op == b_op && a.same_as(b);
+            },
+            (Expr::PostfixOp { op: a_op, left: a }, Expr::PostfixOp { op: b_op, left: b }) => {
+                return a_op == b_op && a.same_as(b);
+            },
+            (Expr::Call { func: a_func, args: a, .. }, Expr::Call { func: b_func, args: b, .. }) => {
+                return a_func == b_func && all_same(a, b);
+            },
+            (
+                Expr::Case { operand: a_operand, whens: a_whens, else_: a_else },
+                Expr::Case { operand: b_operand, whens: b_whens, else_: b_else },
+            ) => {
+                return opt_same(a_operand, b_operand) && a_whens.len() == b_whens.len() &&
+                    a_whens.iter().zip(b_whens).all(|(a, b)| a.0.same_as(&b.0) && a.1.same_as(&b.1)) &&
+                    opt_same(a_else, b_else);
+            },
+            (Expr::Convert { expr: a, type_: a_type }, Expr::Convert { expr: b, type_: b_type }) => {
+                return a_type == b_type && a.same_as(b);
+            },
+            _ => return false,
+        }
+    }
+
+    /// Sub-expressions evaluated in the same row context as this expression. Windows
+    /// and subqueries are opaque.
+    pub(crate) fn row_children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::LitArray(v) => v.iter().collect(),
+            Expr::LitNull(_) |
+            Expr::LitBool(_) |
+            Expr::LitI32(_) |
+            Expr::LitI64(_) |
+            Expr::LitU32(_) |
+            Expr::LitF32(_) |
+            Expr::LitF64(_) |
+            Expr::LitString(_) |
+            Expr::LitBytes(_) => vec![],
+            #[cfg(feature = "chrono")]
+            Expr::LitUtcTimeSChrono(_) | Expr::LitUtcTimeMsChrono(_) | Expr::LitFixedOffsetTimeMsChrono(_) => vec![],
+            #[cfg(feature = "jiff")]
+            Expr::LitUtcTimeSJiff(_) | Expr::LitUtcTimeMsJiff(_) => vec![],
+            Expr::Param { .. } | Expr::ParamTuples { .. } | Expr::Binding(_) => vec![],
+            Expr::BinOp { left, right, .. } => vec![left.as_ref(), right.as_ref()],
+            Expr::BinOpChain { exprs, .. } => exprs.iter().collect(),
+            Expr::PrefixOp { right, .. } => vec![right.as_ref()],
+            Expr::PostfixOp { left, .. } => vec![left.as_ref()],
+            Expr::Tuple(v) => v.iter().collect(),
+            Expr::Call { args, .. } => args.iter().collect(),
+            Expr::Case { operand, whens, else_ } => {
+                let mut out = vec![];
+                out.extend(operand.as_deref());
+                for (cond, res) in whens {
+                    out.push(cond);
+                    out.push(res);
+                }
+                out.extend(else_.as_deref());
+                out
+            },
+            Expr::Window { .. } | Expr::Select { .. } | Expr::Exists { .. } => vec![],
+            Expr::Cast(e, _) | Expr::Convert { expr: e, .. } => vec![e.as_ref()],
+        }
+    }
+
+    /// Collects the subqueries in this expression, including ones in windows but not
+    /// ones nested within other subqueries.
+    pub(crate) fn subqueries<'a>(&'a self, out: &mut Vec<&'a Select>) {
+        match self {
+            Expr::Select(s) => out.push(s),
+            Expr::Exists { body, .. } => out.push(body),
+            Expr::Window { expr, filter, over } => {
+                expr.subqueries(out);
+                if let Some(filter) = filter {
+                    filter.subqueries(out);
+                }
+                over.subqueries(out);
+            },
+            _ => {
+                for e in self.row_children() {
+                    e.subqueries(out);
+                }
+            },
+        }
+    }
 }
 
 #[derive(Clone, Hash, PartialEq, Eq, Debug)]
@@ -222,6 +783,17 @@ pub fn check_general_same_type(ctx: &mut SqliteQueryCtx, path: &rpds::Vector<Str
     if left.opt != right.opt {
         ctx.errs.err(path, format!("Operator arms have differing optionality"));
     }
+    check_general_compatible_type(ctx, path, left, right);
+}
+
+/// Like `check_general_same_type` but allows differing optionality, as in
+/// comparisons.
+pub(crate) fn check_general_compatible_type(
+    ctx: &mut SqliteQueryCtx,
+    path: &rpds::Vector<String>,
+    left: &Type,
+    right: &Type,
+) {
     if left.array != right.array {
         ctx.errs.err(path, format!("Operator arms are either not both arrays or not both scalars"));
     }
@@ -283,10 +855,10 @@ pub(crate) fn check_general_same(
                 ),
             );
     } else if left.0.len() == 1 && right.0.len() == 1 {
-        check_general_same_type(ctx, path, &left.0[0].1, &left.0[0].1);
+        check_general_compatible_type(ctx, path, &left.0[0].1, &right.0[0].1);
     } else {
         for (i, (left, right)) in left.0.iter().zip(right.0.iter()).enumerate() {
-            check_general_same_type(ctx, &path.push_back(format!("Record pair {}", i)), &left.1, &right.1);
+            check_general_compatible_type(ctx, &path.push_back(format!("Record pair {}", i)), &left.1, &right.1);
         }
     }
 }
@@ -375,10 +947,138 @@ pub(crate) fn check_utc_if_time(ctx: &mut SqliteQueryCtx, path: &rpds::Vector<St
     }
 }
 
+fn is_numeric(t: &SimpleSimpleType) -> bool {
+    return matches!(
+        t,
+        SimpleSimpleType::U32 | SimpleSimpleType::I32 | SimpleSimpleType::I64 | SimpleSimpleType::F32 |
+            SimpleSimpleType::F64
+    );
+}
+
+fn is_integer(t: &SimpleSimpleType) -> bool {
+    return matches!(t, SimpleSimpleType::U32 | SimpleSimpleType::I32 | SimpleSimpleType::I64);
+}
+
+/// Returns true if `CAST` from `from` to `to` produces a value that can be read as
+/// `to`. Times can't be parsed from other types, and are only converted to the type
+/// they're stored as.
+fn is_convertible(from: &SimpleSimpleType, to: &SimpleSimpleType) -> bool {
+    if from == to {
+        return true;
+    }
+    match (from, to) {
+        (f, t) if is_numeric(f) && is_numeric(t) => return true,
+        (f, SimpleSimpleType::String) if is_numeric(f) => return true,
+        (SimpleSimpleType::String, t) if is_numeric(t) => return true,
+        (SimpleSimpleType::Bool, t) if is_integer(t) => return true,
+        (f, SimpleSimpleType::Bool) if is_integer(f) => return true,
+        (SimpleSimpleType::String, SimpleSimpleType::Bytes) | (SimpleSimpleType::Bytes, SimpleSimpleType::String) => {
+            return true;
+        },
+        #[cfg(feature = "chrono")]
+        (SimpleSimpleType::UtcTimeSChrono, SimpleSimpleType::I64) |
+        (SimpleSimpleType::UtcTimeMsChrono | SimpleSimpleType::FixedOffsetTimeMsChrono, SimpleSimpleType::String) => {
+            return true;
+        },
+        #[cfg(feature = "jiff")]
+        (SimpleSimpleType::UtcTimeSJiff, SimpleSimpleType::I64) |
+        (SimpleSimpleType::UtcTimeMsJiff, SimpleSimpleType::String) => {
+            return true;
+        },
+        _ => return false,
+    }
+}
+
+/// Checks that all operands are scalars of the same allowed type, ignoring
+/// nullability. The result is nullable if any operand is.
+fn check_operands_same(
+    ctx: &mut SqliteQueryCtx,
+    path: &rpds::Vector<String>,
+    res: &[(ExprType, Tokens)],
+    allowed: fn(&SimpleSimpleType) -> bool,
+    desc: &str,
+) -> Option<Type> {
+    let mut out: Option<Type> = None;
+    for (i, res) in res.iter().enumerate() {
+        let path = path.push_back(format!("Operand {}", i));
+        let (_, t) = res.0.assert_scalar(&mut ctx.errs, &path)?;
+        if !allowed(&t.type_.type_) || t.array {
+            ctx.errs.err(&path, format!("Expected {} operand but got {:?}", desc, t.type_.type_));
+        }
+        match &mut out {
+            Some(base) => {
+                if base.type_ != t.type_ {
+                    ctx
+                        .errs
+                        .err(
+                            &path,
+                            format!(
+                                "Expected same types, but operand 0 has type {:?} while this operand has type {:?}",
+                                base.type_,
+                                t.type_
+                            ),
+                        );
+                }
+                base.opt = base.opt || t.opt;
+            },
+            None => {
+                out = Some(t);
+            },
+        }
+    }
+    return out;
+}
+
 pub(crate) fn check_assignable(errs: &mut Errs, path: &rpds::Vector<String>, a: &Type, b: &ExprType) {
     check_same(errs, path, &ExprType(vec![(Binding::empty(), a.clone())]), b);
 }
 
+/// Builds the Rust argument type of a non-null scalar parameter and the expression
+/// converting the argument (bound to `ident`) to the value passed to SQLite.
+fn build_param_forward(
+    ctx: &mut SqliteQueryCtx,
+    path: &rpds::Vector<String>,
+    ident: &Ident,
+    t: &SimpleType,
+) -> Option<(TokenStream, TokenStream)> {
+    let rust_types = to_rust_types(&t.type_);
+    let custom_trait_ident = rust_types.custom_trait;
+    let (rust_type, rust_forward) = if let Some(custom) = &t.custom {
+        let custom_ident = match syn::parse_str::<Path>(custom.as_str()) {
+            Ok(p) => p,
+            Err(e) => {
+                ctx.errs.err(path, format!("Couldn't parse custom type {}: {:?}", custom, e));
+                return None;
+            },
+        }.to_token_stream();
+        let forward = quote!(< #custom_ident as #custom_trait_ident < #custom_ident >>:: to_sql(& #ident));
+        (quote!(& #custom_ident), forward)
+    } else {
+        (rust_types.arg_type, quote!(#ident))
+    };
+    let rust_forward = match t.type_ {
+        SimpleSimpleType::U32 => rust_forward,
+        SimpleSimpleType::I32 => rust_forward,
+        SimpleSimpleType::I64 => rust_forward,
+        SimpleSimpleType::F32 => rust_forward,
+        SimpleSimpleType::F64 => rust_forward,
+        SimpleSimpleType::Bool => rust_forward,
+        SimpleSimpleType::String => rust_forward,
+        SimpleSimpleType::Bytes => rust_forward,
+        #[cfg(feature = "chrono")]
+        SimpleSimpleType::UtcTimeSChrono => quote!(#rust_forward.timestamp()),
+        #[cfg(feature = "chrono")]
+        SimpleSimpleType::UtcTimeMsChrono => quote!(#rust_forward.to_rfc3339()),
+        #[cfg(feature = "chrono")]
+        SimpleSimpleType::FixedOffsetTimeMsChrono => quote!(#rust_forward.to_rfc3339()),
+        #[cfg(feature = "jiff")]
+        SimpleSimpleType::UtcTimeSJiff => quote!(#rust_forward.as_second()),
+        #[cfg(feature = "jiff")]
+        SimpleSimpleType::UtcTimeMsJiff => quote!(#rust_forward.to_string()),
+    };
+    return Some((rust_type, rust_forward));
+}
+
 impl Expr {
     pub(crate) fn build(
         &self,
@@ -406,11 +1106,19 @@ impl Expr {
             op: &BinOp,
             exprs: &Vec<Expr>,
         ) -> (ExprType, Tokens) {
-            let operand_lower_limit;
-            match op {
-                BinOp::Plus | BinOp::Minus | BinOp::Multiply | BinOp::Divide | BinOp::And | BinOp::Or => {
-                    operand_lower_limit = 1;
-                },
+            let (operand_lower_limit, operand_upper_limit) = match op {
+                BinOp::Plus |
+                BinOp::Minus |
+                BinOp::Multiply |
+                BinOp::Divide |
+                BinOp::Mod |
+                BinOp::BitAnd |
+                BinOp::BitOr |
+                BinOp::Concat |
+                BinOp::And |
+                BinOp::Or => (1, None),
+                BinOp::ShiftLeft |
+                BinOp::ShiftRight |
                 BinOp::Equals |
                 BinOp::NotEquals |
                 BinOp::Is |
@@ -423,71 +1131,100 @@ impl Expr {
                 BinOp::LessThanEqualTo |
                 BinOp::GreaterThan |
                 BinOp::GreaterThanEqualTo |
-                BinOp::Like |
                 BinOp::In |
-                BinOp::NotIn => {
-                    operand_lower_limit = 2;
-                },
+                BinOp::NotIn => (2, None),
+                BinOp::Glob | BinOp::Match => (2, Some(2)),
+                BinOp::Like | BinOp::NotLike => (2, Some(3)),
+                BinOp::Between | BinOp::NotBetween => (3, Some(3)),
             };
-            if exprs.len() < operand_lower_limit {
+            if exprs.len() < operand_lower_limit || operand_upper_limit.map(|u| exprs.len() > u).unwrap_or(false) {
                 ctx
                     .errs
                     .err(
                         path,
-                        format!(
-                            "{:?} must have at least {} operand(s), but got {}",
-                            op,
-                            operand_lower_limit,
-                            exprs.len()
-                        ),
+                        match operand_upper_limit {
+                            Some(u) if u == operand_lower_limit => format!(
+                                "{:?} must have exactly {} operand(s), but got {}",
+                                op,
+                                u,
+                                exprs.len()
+                            ),
+                            Some(u) => format!(
+                                "{:?} must have between {} and {} operand(s), but got {}",
+                                op,
+                                operand_lower_limit,
+                                u,
+                                exprs.len()
+                            ),
+                            None => format!(
+                                "{:?} must have at least {} operand(s), but got {}",
+                                op,
+                                operand_lower_limit,
+                                exprs.len()
+                            ),
+                        },
                     );
+                return (ExprType(vec![]), Tokens::new());
             }
             let mut res = vec![];
             for (i, e) in exprs.iter().enumerate() {
                 res.push(e.build(ctx, &path.push_back(format!("Operand {}", i)), scope));
             }
+            let bool_type = Type {
+                type_: SimpleType {
+                    type_: SimpleSimpleType::Bool,
+                    custom: None,
+                },
+                opt: false,
+                array: false,
+            };
             let t = match op {
-                BinOp::Plus | BinOp::Minus | BinOp::Multiply | BinOp::Divide => {
-                    let base = res.get(0).unwrap();
-                    let t =
-                        match check_same(
-                            &mut ctx.errs,
-                            &path.push_back(format!("Operands 0, 1")),
-                            &base.0,
-                            &res.get(0).unwrap().0,
-                        ) {
-                            Some(t) => t,
-                            None => {
-                                return (ExprType(vec![]), Tokens::new());
-                            },
-                        };
-                    for (i, res) in res.iter().enumerate().skip(2) {
-                        match check_same(
-                            &mut ctx.errs,
-                            &path.push_back(format!("Operands 0, {}", i)),
-                            &base.0,
-                            &res.0,
-                        ) {
-                            Some(_) => { },
-                            None => {
-                                return (ExprType(vec![]), Tokens::new());
-                            },
+                BinOp::Plus |
+                BinOp::Minus |
+                BinOp::Multiply |
+                BinOp::Divide |
+                BinOp::Mod |
+                BinOp::BitAnd |
+                BinOp::BitOr |
+                BinOp::Concat => {
+                    let (allowed, desc): (fn(&SimpleSimpleType) -> bool, &str) = match op {
+                        BinOp::Plus | BinOp::Minus | BinOp::Multiply | BinOp::Divide => (is_numeric, "numeric"),
+                        BinOp::Mod | BinOp::BitAnd | BinOp::BitOr => (is_integer, "integer"),
+                        _ => (|t| matches!(t, SimpleSimpleType::String), "string"),
+                    };
+                    match check_operands_same(ctx, path, &res, allowed, desc) {
+                        Some(t) => t,
+                        None => {
+                            return (ExprType(vec![]), Tokens::new());
+                        },
+                    }
+                },
+                BinOp::ShiftLeft | BinOp::ShiftRight => {
+                    let mut out = None;
+                    let mut opt = false;
+                    for (i, res) in res.iter().enumerate() {
+                        let path = path.push_back(format!("Operand {}", i));
+                        let Some((_, t)) = res.0.assert_scalar(&mut ctx.errs, &path) else {
+                            return (ExprType(vec![]), Tokens::new());
                         };
+                        if !is_integer(&t.type_.type_) || t.array {
+                            ctx.errs.err(&path, format!("Expected integer operand but got {:?}", t.type_.type_));
+                        }
+                        opt = opt || t.opt;
+                        if out.is_none() {
+                            out = Some(t);
+                        }
+                    }
+                    Type {
+                        opt: opt,
+                        ..out.unwrap()
                     }
-                    t
                 },
                 BinOp::And | BinOp::Or => {
                     for (i, res) in res.iter().enumerate() {
                         check_bool(ctx, &path.push_back(format!("Operand {}", i)), &res.0);
                     }
-                    Type {
-                        type_: SimpleType {
-                            type_: SimpleSimpleType::Bool,
-                            custom: None,
-                        },
-                        array: false,
-                        opt: false,
-                    }
+                    bool_type
                 },
                 BinOp::Equals |
                 BinOp::NotEquals |
@@ -501,7 +1238,10 @@ impl Expr {
                 BinOp::LessThanEqualTo |
                 BinOp::GreaterThan |
                 BinOp::GreaterThanEqualTo |
-                BinOp::Like => {
+                BinOp::Between |
+                BinOp::NotBetween |
+                BinOp::In |
+                BinOp::NotIn => {
                     #[cfg(feature = "chrono")]
                     if match op {
                         BinOp::TzEquals | BinOp::TzNotEquals | BinOp::TzIs | BinOp::TzIsNot => false,
@@ -512,84 +1252,103 @@ impl Expr {
                         }
                     }
                     let base = res.get(0).unwrap();
-                    check_general_same(
-                        ctx,
-                        &path.push_back(format!("Operands 0, 1")),
-                        &base.0,
-                        &res.get(1).unwrap().0,
-                    );
-                    for (i, res) in res.iter().enumerate().skip(2) {
-                        check_general_same(ctx, &path.push_back(format!("Operands 0, {}", i)), &base.0, &res.0);
-                    }
-                    Type {
-                        type_: SimpleType {
-                            type_: SimpleSimpleType::Bool,
-                            custom: None,
-                        },
-                        opt: false,
-                        array: false,
+                    for (i, res) in res.iter().enumerate().skip(1) {
+                        let path = path.push_back(format!("Operands 0, {}", i));
+                        if matches!(op, BinOp::In | BinOp::NotIn) {
+                            // Compare with the elements of array params
+                            let elements =
+                                ExprType(
+                                    res.0.0.iter().map(|(b, t)| (b.clone(), Type {
+                                        array: false,
+                                        ..t.clone()
+                                    })).collect(),
+                                );
+                            check_general_same(ctx, &path, &base.0, &elements);
+                        } else {
+                            check_general_same(ctx, &path, &base.0, &res.0);
+                        }
                     }
+                    bool_type
                 },
-                BinOp::In | BinOp::NotIn => {
-                    #[cfg(feature = "chrono")]
-                    if match op {
-                        BinOp::TzEquals | BinOp::TzNotEquals | BinOp::TzIs | BinOp::TzIsNot => false,
-                        _ => true,
-                    } {
-                        for (i, el) in res.iter().enumerate() {
-                            check_utc_if_time(ctx, &path.push_back(format!("Operand {}", i)), &el.0);
+                BinOp::Like | BinOp::NotLike | BinOp::Glob | BinOp::Match => {
+                    for (i, res) in res.iter().enumerate() {
+                        let path = path.push_back(format!("Operand {}", i));
+                        let Some((_, t)) = res.0.assert_scalar(&mut ctx.errs, &path) else {
+                            continue;
+                        };
+                        if !matches!(t.type_.type_, SimpleSimpleType::String) || t.array {
+                            ctx.errs.err(&path, format!("Expected string operand but got {:?}", t.type_.type_));
+                        }
+                        if i == 2 && t.opt {
+                            ctx.errs.err(&path, format!("The escape character must not be nullable"));
                         }
                     }
-                    let base = res.get(0).unwrap();
-                    check_general_same(
-                        ctx,
-                        &path.push_back(format!("Operands 0, 1")),
-                        &base.0,
-                        &res.get(1).unwrap().0,
-                    );
-                    for (i, res) in res.iter().enumerate().skip(2) {
-                        check_general_same(ctx, &path.push_back(format!("Operands 0, {}", i)), &base.0, &res.0);
-                    }
-                    Type {
-                        type_: SimpleType {
-                            type_: SimpleSimpleType::Bool,
-                            custom: None,
-                        },
-                        opt: false,
-                        array: false,
-                    }
+                    bool_type
                 },
             };
-            let token = match op {
-                BinOp::Plus => "+",
-                BinOp::Minus => "-",
-                BinOp::Multiply => "*",
-                BinOp::Divide => "/",
-                BinOp::And => "and",
-                BinOp::Or => "or",
-                BinOp::Equals => "=",
-                BinOp::NotEquals => "!=",
-                BinOp::Is => "is",
-                BinOp::IsNot => "is not",
-                BinOp::TzEquals => "=",
-                BinOp::TzNotEquals => "!=",
-                BinOp::TzIs => "is",
-                BinOp::TzIsNot => "is not",
-                BinOp::LessThan => "<",
-                BinOp::LessThanEqualTo => "<=",
-                BinOp::GreaterThan => ">",
-                BinOp::GreaterThanEqualTo => ">=",
-                BinOp::Like => "like",
-                BinOp::In => "in",
-                BinOp::NotIn => "not in",
-            };
             let mut out = Tokens::new();
             out.s("(");
-            for (i, res) in res.iter().enumerate() {
-                if i > 0 {
-                    out.s(token);
-                }
-                out.s(&res.1.to_string());
+            match op {
+                BinOp::Between | BinOp::NotBetween => {
+                    out.s(&res[0].1.to_string());
+                    out.s(if matches!(op, BinOp::Between) {
+                        "between"
+                    } else {
+                        "not between"
+                    });
+                    out.s(&res[1].1.to_string()).s("and").s(&res[2].1.to_string());
+                },
+                BinOp::Like | BinOp::NotLike => {
+                    out.s(&res[0].1.to_string());
+                    out.s(if matches!(op, BinOp::Like) {
+                        "like"
+                    } else {
+                        "not like"
+                    });
+                    out.s(&res[1].1.to_string());
+                    if let Some(escape) = res.get(2) {
+                        out.s("escape").s(&escape.1.to_string());
+                    }
+                },
+                _ => {
+                    let token = match op {
+                        BinOp::Plus => "+",
+                        BinOp::Minus => "-",
+                        BinOp::Multiply => "*",
+                        BinOp::Divide => "/",
+                        BinOp::Mod => "%",
+                        BinOp::BitAnd => "&",
+                        BinOp::BitOr => "|",
+                        BinOp::ShiftLeft => "<<",
+                        BinOp::ShiftRight => ">>",
+                        BinOp::Concat => "||",
+                        BinOp::And => "and",
+                        BinOp::Or => "or",
+                        BinOp::Equals => "=",
+                        BinOp::NotEquals => "!=",
+                        BinOp::Is => "is",
+                        BinOp::IsNot => "is not",
+                        BinOp::TzEquals => "=",
+                        BinOp::TzNotEquals => "!=",
+                        BinOp::TzIs => "is",
+                        BinOp::TzIsNot => "is not",
+                        BinOp::LessThan => "<",
+                        BinOp::LessThanEqualTo => "<=",
+                        BinOp::GreaterThan => ">",
+                        BinOp::GreaterThanEqualTo => ">=",
+                        BinOp::Glob => "glob",
+                        BinOp::Match => "match",
+                        BinOp::In => "in",
+                        BinOp::NotIn => "not in",
+                        BinOp::Like | BinOp::NotLike | BinOp::Between | BinOp::NotBetween => unreachable!(),
+                    };
+                    for (i, res) in res.iter().enumerate() {
+                        if i > 0 {
+                            out.s(token);
+                        }
+                        out.s(&res.1.to_string());
+                    }
+                },
             }
             out.s(")");
             (ExprType(vec![(Binding::empty(), t)]), out)
@@ -715,44 +1474,11 @@ impl Expr {
                     std::collections::hash_map::Entry::Vacant(e) => {
                         let i = ctx.query_args.len();
                         e.insert((i, t.clone()));
-                        let rust_types = to_rust_types(&t.type_.type_);
-                        let custom_trait_ident = rust_types.custom_trait;
-                        let rust_type = rust_types.arg_type;
                         let ident = format_ident!("{}", sanitize_ident(x).1);
-                        let (mut rust_type, mut rust_forward) = if let Some(custom) = &t.type_.custom {
-                            let custom_ident = match syn::parse_str::<Path>(custom.as_str()) {
-                                Ok(p) => p,
-                                Err(e) => {
-                                    ctx.errs.err(&path, format!("Couldn't parse custom type {}: {:?}", custom, e));
-                                    return (ExprType(vec![]), Tokens::new());
-                                },
-                            }.to_token_stream();
-                            let forward =
-                                quote!(< #custom_ident as #custom_trait_ident < #custom_ident >>:: to_sql(& #ident));
-                            (quote!(& #custom_ident), forward)
-                        } else {
-                            (rust_type, quote!(#ident))
-                        };
-                        rust_forward = match t.type_.type_ {
-                            SimpleSimpleType::U32 => rust_forward,
-                            SimpleSimpleType::I32 => rust_forward,
-                            SimpleSimpleType::I64 => rust_forward,
-                            SimpleSimpleType::F32 => rust_forward,
-                            SimpleSimpleType::F64 => rust_forward,
-                            SimpleSimpleType::Bool => rust_forward,
-                            SimpleSimpleType::String => rust_forward,
-                            SimpleSimpleType::Bytes => rust_forward,
-                            #[cfg(feature = "chrono")]
-                            SimpleSimpleType::UtcTimeSChrono => quote!(#rust_forward.timestamp()),
-                            #[cfg(feature = "chrono")]
-                            SimpleSimpleType::UtcTimeMsChrono => quote!(#rust_forward.to_rfc3339()),
-                            #[cfg(feature = "chrono")]
-                            SimpleSimpleType::FixedOffsetTimeMsChrono => quote!(#rust_forward.to_rfc3339()),
-                            #[cfg(feature = "jiff")]
-                            SimpleSimpleType::UtcTimeSJiff => quote!(#rust_forward.as_second()),
-                            #[cfg(feature = "jiff")]
-                            SimpleSimpleType::UtcTimeMsJiff => quote!(#rust_forward.to_string()),
-                        };
+                        let Some((mut rust_type, mut rust_forward)) =
+                            build_param_forward(ctx, &path, &ident, &t.type_) else {
+                                return (ExprType(vec![]), Tokens::new());
+                            };
                         if t.array {
                             rust_type = quote!(Vec < #rust_type >);
                             rust_forward =
@@ -774,6 +1500,9 @@ impl Expr {
                         i
                     },
                 };
+                if ctx.tuple_arg_lookup.contains_key(x) {
+                    errs.push(format!("Parameter {} is also used as a tuple list parameter", x));
+                }
                 for e in errs {
                     ctx.errs.err(&path, e);
                 }
@@ -784,6 +1513,104 @@ impl Expr {
                 }
                 return (ExprType(vec![(Binding::local(x.clone()), t.clone())]), out);
             },
+            Expr::ParamTuples { name: x, types } => {
+                let path = path.push_back(format!("Param tuples ({})", x));
+                if types.is_empty() {
+                    ctx.errs.err(&path, format!("Tuple list parameters must have at least one element type"));
+                    return (ExprType(vec![]), Tokens::new());
+                }
+                for (j, t) in types.iter().enumerate() {
+                    if t.array || matches!(t.type_.type_, SimpleSimpleType::Bytes) {
+                        ctx
+                            .errs
+                            .err(
+                                &path.push_back(format!("Element {}", j)),
+                                format!("Tuple list parameter elements can't be arrays or bytes, but got {:?}", t),
+                            );
+                    }
+                }
+                if ctx.rust_arg_lookup.contains_key(x) {
+                    ctx.errs.err(&path, format!("Parameter {} is also used as a scalar parameter", x));
+                }
+                let i = match ctx.tuple_arg_lookup.get(x) {
+                    Some((i, prev_types)) => {
+                        let i = *i;
+                        if types != prev_types {
+                            ctx
+                                .errs
+                                .err(
+                                    &path,
+                                    format!(
+                                        "Parameter {} specified with multiple types: {:?}, {:?}",
+                                        x,
+                                        types,
+                                        prev_types
+                                    ),
+                                );
+                        }
+                        i
+                    },
+                    None => {
+                        let i = ctx.query_args.len();
+                        ctx.tuple_arg_lookup.insert(x.clone(), (i, types.clone()));
+                        let ident_name = sanitize_ident(x).1;
+                        let ident = format_ident!("{}", ident_name);
+                        let mut elem_types = vec![];
+                        let mut elem_forwards = vec![];
+                        for (j, t) in types.iter().enumerate() {
+                            let elem_ident = format_ident!("{}_{}", ident_name, j);
+                            let index = syn::Index::from(j);
+                            let Some((mut rust_type, mut rust_forward)) =
+                                build_param_forward(ctx, &path, &elem_ident, &t.type_) else {
+                                    return (ExprType(vec![]), Tokens::new());
+                                };
+                            if t.opt {
+                                rust_type = quote!(Option < #rust_type >);
+                                rust_forward = quote!(#elem_ident.map(| #elem_ident | #rust_forward));
+                            }
+                            let sep = if j > 0 {
+                                quote!(out.push(',');)
+                            } else {
+                                quote!()
+                            };
+                            elem_types.push(rust_type);
+                            elem_forwards.push(quote!({
+                                #sep
+                                let #elem_ident = x.#index;
+                                good_ormning_runtime:: sqlite:: ToJson:: to_json(&#rust_forward, &mut out);
+                            }));
+                        }
+                        ctx.rust_args.push(quote!(#ident: Vec <(#(#elem_types,) *) >));
+                        ctx.query_args.push(quote!({
+                            let mut out = String::new();
+                            out.push('[');
+                            for (i, x) in #ident.iter().enumerate() {
+                                if i > 0 {
+                                    out.push(',');
+                                }
+                                out.push('[');
+                                #(#elem_forwards) * out.push(']');
+                            }
+                            out.push(']');
+                            out
+                        }));
+                        i
+                    },
+                };
+                let mut out = Tokens::new();
+                out.s("( select");
+                for j in 0 .. types.len() {
+                    if j > 0 {
+                        out.s(",");
+                    }
+                    out.s(&format!("value ->> {}", j));
+                }
+                out.s(&format!("from json_each(${}) )", i + 1));
+                return (ExprType(types.iter().map(|t| (Binding::empty(), Type {
+                    array: false,
+                    ..t.clone()
+                })).collect()), out);
+            },
             Expr::Binding(name) => {
                 let t = match scope.get(&name) {
                     Some(t) => t.clone(),
@@ -824,14 +1651,55 @@ impl Expr {
                 let path = path.push_back(format!("Prefix op {:?}", op));
                 let mut out = Tokens::new();
                 let res = right.build(ctx, &path, scope);
-                let (op_text, op_type) = match op {
+                let (op_text, allowed, desc): (_, fn(&SimpleSimpleType) -> bool, _) = match op {
                     PrefixOp::Not => {
                         check_bool(ctx, &path, &res.0);
-                        ("not", SimpleSimpleType::Bool)
+                        out.s("not").s(&res.1.to_string());
+                        return empty_type!(out, SimpleSimpleType::Bool);
                     },
+                    PrefixOp::Negate => ("-", is_numeric, "numeric"),
+                    PrefixOp::BitNot => ("~", is_integer, "integer"),
+                };
+                let Some((_, t)) = res.0.assert_scalar(&mut ctx.errs, &path) else {
+                    return (ExprType(vec![]), Tokens::new());
                 };
+                if !allowed(&t.type_.type_) || t.array {
+                    ctx.errs.err(&path, format!("Expected {} operand but got {:?}", desc, t.type_.type_));
+                }
                 out.s(op_text).s(&res.1.to_string());
-                return empty_type!(out, op_type);
+                return (ExprType(vec![(Binding::empty(), t)]), out);
+            },
+            Expr::PostfixOp { op, left } => {
+                let path = path.push_back(format!("Postfix op {:?}", op));
+                let mut out = Tokens::new();
+                let res = left.build(ctx, &path, scope);
+                res.0.assert_scalar(&mut ctx.errs, &path);
+                out.s("(").s(&res.1.to_string()).s(match op {
+                    PostfixOp::IsNull => "is null",
+                    PostfixOp::IsNotNull => "is not null",
+                }).s(")");
+                return empty_type!(out, SimpleSimpleType::Bool);
+            },
+            Expr::Tuple(elements) => {
+                let path = path.push_back(format!("Tuple"));
+                if elements.is_empty() {
+                    ctx.errs.err(&path, format!("Tuples must have at least one element"));
+                    return (ExprType(vec![]), Tokens::new());
+                }
+                let mut out = Tokens::new();
+                let mut types = vec![];
+                out.s("(");
+                for (i, e) in elements.iter().enumerate() {
+                    let path = path.push_back(format!("Element {}", i));
+                    if i > 0 {
+                        out.s(",");
+                    }
+                    let (t, tokens) = e.build(ctx, &path, scope);
+                    types.extend(t.assert_scalar(&mut ctx.errs, &path));
+                    out.s(&tokens.to_string());
+                }
+                out.s(")");
+                return (ExprType(types), out);
             },
             Expr::Call { func, args, compute_type } => {
                 let mut types = vec![];
@@ -856,50 +1724,101 @@ impl Expr {
                 };
                 return (ExprType(vec![(Binding::empty(), type_)]), out);
             },
-            Expr::Window { expr, partition_by, order_by } => {
+            Expr::Window { expr, filter, over } => {
+                let path = path.push_back(format!("Window"));
                 let mut out = Tokens::new();
-                let expr = expr.build(ctx, &path, &scope);
-                out.s(&expr.1.to_string());
+                let expr_built = expr.build(ctx, &path, &scope);
+                out.s(&expr_built.1.to_string());
+                if let Some(filter) = filter {
+                    let path = path.push_back("Filter".into());
+                    if !expr.is_aggregate() {
+                        ctx.errs.err(&path, format!("Filter can only be used with aggregate functions"));
+                    }
+                    let (filter_t, filter_tokens) = filter.build(ctx, &path, &scope);
+                    check_bool(ctx, &path, &filter_t);
+                    out.s("filter ( where").s(&filter_tokens.to_string()).s(")");
+                }
                 out.s("over");
-                out.s("(");
-                if !partition_by.is_empty() {
-                    out.s("partition by");
-                    for (i, e) in partition_by.iter().enumerate() {
-                        let path = path.push_back(format!("Partition by {}", i));
-                        if i > 0 {
-                            out.s(",");
-                        }
-                        let (_, p) = e.build(ctx, &path, &scope);
-                        out.s(&p.to_string());
+                if over.is_bare_name() {
+                    out.s(&over.build(ctx, &path, &scope).to_string());
+                } else {
+                    out.s("(");
+                    out.s(&over.build(ctx, &path, &scope).to_string());
+                    out.s(")");
+                }
+                return (expr_built.0, out);
+            },
+            Expr::Case { operand, whens, else_ } => {
+                let path = path.push_back(format!("Case"));
+                let mut out = Tokens::new();
+                out.s("case");
+                let operand_t = match operand {
+                    Some(operand) => {
+                        let (operand_t, operand_tokens) = operand.build(ctx, &path.push_back("Operand".into()), scope);
+                        out.s(&operand_tokens.to_string());
+                        Some(operand_t)
+                    },
+                    None => None,
+                };
+                if whens.is_empty() {
+                    ctx.errs.err(&path, format!("Case must have at least one when clause"));
+                }
+                let mut results = vec![];
+                for (i, (cond, res)) in whens.iter().enumerate() {
+                    let path = path.push_back(format!("When {}", i));
+                    let (cond_t, cond_tokens) = cond.build(ctx, &path, scope);
+                    match &operand_t {
+                        Some(operand_t) => check_general_same(ctx, &path, operand_t, &cond_t),
+                        None => check_bool(ctx, &path, &cond_t),
                     }
+                    let (res_t, res_tokens) = res.build(ctx, &path, scope);
+                    out.s("when").s(&cond_tokens.to_string()).s("then").s(&res_tokens.to_string());
+                    results.push((path, res_t));
                 }
-                if !order_by.is_empty() {
-                    out.s("order by");
-                    for (i, o) in order_by.iter().enumerate() {
-                        let path = path.push_back(format!("Order by clause {}", i));
-                        if i > 0 {
-                            out.s(",");
-                        }
-                        let (_, o_tokens) = o.0.build(ctx, &path, &scope);
-                        out.s(&o_tokens.to_string());
-                        out.s(match o.1 {
-                            Order::Asc => "asc",
-                            Order::Desc => "desc",
-                        });
+                if let Some(else_) = else_ {
+                    let path = path.push_back(format!("Else"));
+                    let (else_t, else_tokens) = else_.build(ctx, &path, scope);
+                    out.s("else").s(&else_tokens.to_string());
+                    results.push((path, else_t));
+                }
+                out.s("end");
+
+                // Unify result types, nullability separately
+                let mut opt = else_.is_none();
+                let mut base: Option<ExprType> = None;
+                for (path, res_t) in results {
+                    let Some((_, res_t)) = res_t.assert_scalar(&mut ctx.errs, &path) else {
+                        continue;
+                    };
+                    opt = opt || res_t.opt;
+                    let res_t = ExprType(vec![(Binding::empty(), Type {
+                        opt: false,
+                        ..res_t
+                    })]);
+                    match &base {
+                        Some(base) => {
+                            check_same(&mut ctx.errs, &path, base, &res_t);
+                        },
+                        None => {
+                            base = Some(res_t);
+                        },
                     }
                 }
-                out.s(")");
-                return (expr.0, out);
+                let Some(mut base) = base else {
+                    return (ExprType(vec![]), Tokens::new());
+                };
+                let mut t = base.0.remove(0).1;
+                t.opt = opt;
+                return (ExprType(vec![(Binding::empty(), t)]), out);
             },
-            Expr::Select { body, body_junctions } => {
+            Expr::Select(body) => {
                 let path = path.push_back(format!("Subselect"));
                 let mut out = Tokens::new();
-                let base = body.build(ctx, scope, &path, QueryResCount::Many);
-                out.s(&base.1.to_string());
-                out.s(&build_select_junction(ctx, &path, &base.0, &body_junctions).to_string());
+                let base = body.build_subquery(ctx, scope, &path);
+                out.s("(").s(&base.1.to_string()).s(")");
                 return (base.0, out);
             },
-            Expr::Exists { not, body, body_junctions } => {
+            Expr::Exists { not, body } => {
                 let path = path.push_back(format!("(Not)Exists"));
                 let mut out = Tokens::new();
                 if *not {
@@ -907,9 +1826,8 @@ impl Expr {
                 }
                 out.s("exists");
                 out.s("(");
-                let base = body.build(ctx, scope, &path, QueryResCount::Many);
+                let base = body.build_subquery(ctx, scope, &path);
                 out.s(&base.1.to_string());
-                out.s(&build_select_junction(ctx, &path, &base.0, &body_junctions).to_string());
                 out.s(")");
                 return (ExprType(vec![(Binding::empty(), Type {
                     type_: SimpleType {
@@ -932,6 +1850,28 @@ impl Expr {
                 check_general_same_type(ctx, &path, t, &got_t.1);
                 return (ExprType(vec![(got_t.0, t.clone())]), out.1);
             },
+            Expr::Convert { expr, type_ } => {
+                let path = path.push_back(format!("Convert"));
+                let res = expr.build(ctx, &path, scope);
+                let Some((_, got_t)) = res.0.assert_scalar(&mut ctx.errs, &path) else {
+                    return (ExprType(vec![]), Tokens::new());
+                };
+                if got_t.array {
+                    ctx.errs.err(&path, format!("Can't convert an array"));
+                } else if !is_convertible(&got_t.type_.type_, type_) {
+                    ctx.errs.err(&path, format!("Can't convert {:?} to {:?}", got_t.type_.type_, type_));
+                }
+                let mut out = Tokens::new();
+                out.s("cast (").s(&res.1.to_string()).s("as").s(to_sql_type(type_)).s(")");
+                return (ExprType(vec![(Binding::empty(), Type {
+                    type_: SimpleType {
+                        type_: type_.clone(),
+                        custom: None,
+                    },
+                    opt: got_t.opt,
+                    array: false,
+                })]), out);
+            },
         };
     }
 }
@@ -942,12 +1882,24 @@ impl Expr {
 /// _want_ to not consider datetimes referring to the same instant but with
 /// different timezones equal (that is, to be equal both the time and timezone must
 /// match). I think this is probably a rare use case.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum BinOp {
     Plus,
     Minus,
     Multiply,
     Divide,
+    /// `%`, integers only.
+    Mod,
+    /// `&`, integers only.
+    BitAnd,
+    /// `|`, integers only.
+    BitOr,
+    /// `<<`, integers only. The result has the type of the left operand.
+    ShiftLeft,
+    /// `>>`, integers only. The result has the type of the left operand.
+    ShiftRight,
+    /// `||` string concatenation.
+    Concat,
     And,
     Or,
     Equals,
@@ -962,12 +1914,76 @@ pub enum BinOp {
     LessThanEqualTo,
     GreaterThan,
     GreaterThanEqualTo,
+    /// `a BETWEEN b AND c`. Use `BinOpChain` with exactly 3 operands.
+    Between,
+    /// `a NOT BETWEEN b AND c`. Use `BinOpChain` with exactly 3 operands.
+    NotBetween,
+    /// `a LIKE b`. Use `BinOpChain` with a third operand to specify an `ESCAPE`
+    /// character.
     Like,
+    /// `a NOT LIKE b`. Use `BinOpChain` with a third operand to specify an `ESCAPE`
+    /// character.
+    NotLike,
+    /// Case sensitive matching with unix file glob syntax.
+    Glob,
+    /// FTS5 full text query, the left operand is a column of an FTS5 table or the
+    /// table itself (see `functions::fts5_match`).
+    Match,
     In,
     NotIn,
 }
 
-#[derive(Clone, Debug)]
+impl BinOp {
+    /// Comparisons which are never true if an operand is null.
+    fn is_strict_comparison(&self) -> bool {
+        return matches!(
+            self,
+            BinOp::Equals |
+                BinOp::NotEquals |
+                BinOp::TzEquals |
+                BinOp::TzNotEquals |
+                BinOp::LessThan |
+                BinOp::LessThanEqualTo |
+                BinOp::GreaterThan |
+                BinOp::GreaterThanEqualTo |
+                BinOp::Between |
+                BinOp::NotBetween |
+                BinOp::Like |
+                BinOp::NotLike |
+                BinOp::Glob |
+                BinOp::Match
+        );
+    }
+
+    /// Operators which produce null if any operand is null.
+    fn is_strict_arithmetic(&self) -> bool {
+        return matches!(
+            self,
+            BinOp::Plus |
+                BinOp::Minus |
+                BinOp::Multiply |
+                BinOp::Divide |
+                BinOp::Mod |
+                BinOp::BitAnd |
+                BinOp::BitOr |
+                BinOp::ShiftLeft |
+                BinOp::ShiftRight |
+                BinOp::Concat
+        );
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum PrefixOp {
     Not,
+    /// Unary minus, numbers only.
+    Negate,
+    /// `~`, integers only.
+    BitNot,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PostfixOp {
+    IsNull,
+    IsNotNull,
 }
@@ -0,0 +1,487 @@
+//! Constructors for common built-in SQL functions with result types computed from
+//! their arguments.
+use {
+    super::{
+        expr::{
+            BinOp,
+            ComputeType,
+            Expr,
+            ExprType,
+        },
+        utils::SqliteQueryCtx,
+    },
+    crate::sqlite::{
+        schema::{
+            field::Field,
+            fts5::Fts5Table,
+        },
+        types::{
+            SimpleSimpleType,
+            SimpleType,
+            Type,
+        },
+    },
+};
+
+fn simple_type(t: SimpleSimpleType, opt: bool) -> Type {
+    return Type {
+        type_: SimpleType {
+            type_: t,
+            custom: None,
+        },
+        opt: opt,
+        array: false,
+    };
+}
+
+fn arg_type(
+    ctx: &mut SqliteQueryCtx,
+    path: &rpds::Vector<String>,
+    args: &[ExprType],
+    i: usize,
+) -> Option<Type> {
+    return args.get(i).and_then(|a| a.assert_scalar(&mut ctx.errs, path)).map(|a| a.1);
+}
+
+fn is_numeric(t: &Type) -> bool {
+    return matches!(
+        t.type_.type_,
+        SimpleSimpleType::U32 | SimpleSimpleType::I32 | SimpleSimpleType::I64 | SimpleSimpleType::F32 |
+            SimpleSimpleType::F64
+    ) && !t.array;
+}
+
+fn check_numeric(ctx: &mut SqliteQueryCtx, path: &rpds::Vector<String>, func: &str, t: &Type) {
+    if !is_numeric(t) {
+        ctx
+            .errs
+            .err(path, format!("Function {} requires a numeric argument but got {:?}", func, t.type_.type_));
+    }
+}
+
+fn check_integer(ctx: &mut SqliteQueryCtx, path: &rpds::Vector<String>, func: &str, t: &Type) {
+    if !matches!(t.type_.type_, SimpleSimpleType::U32 | SimpleSimpleType::I32 | SimpleSimpleType::I64) || t.array {
+        ctx
+            .errs
+            .err(path, format!("Function {} requires an integer argument but got {:?}", func, t.type_.type_));
+    }
+}
+
+fn check_string(ctx: &mut SqliteQueryCtx, path: &rpds::Vector<String>, func: &str, t: &Type) {
+    if !matches!(t.type_.type_, SimpleSimpleType::String) || t.array {
+        ctx
+            .errs
+            .err(path, format!("Function {} requires a string argument but got {:?}", func, t.type_.type_));
+    }
+}
+
+fn call(func: &str, args: Vec<Expr>, compute_type: ComputeType) -> Expr {
+    return Expr::Call {
+        func: func.to_string(),
+        args: args,
+        compute_type: compute_type,
+    };
+}
+
+// # Aggregates
+/// `count(expr)` - the number of rows where `expr` isn't null. Never null.
+pub fn count(expr: Expr) -> Expr {
+    return call("count", vec![expr], ComputeType::new(|_ctx, _path, _args| {
+        return Some(simple_type(SimpleSimpleType::I64, false));
+    }));
+}
+
+/// `count(1)` - the number of rows. Never null.
+pub fn count_rows() -> Expr {
+    return count(Expr::LitI32(1));
+}
+
+/// `sum(expr)` over a numeric expression. Integer sums are `i64` and float sums
+/// `f64`. The result is null if there are no (non-null) rows.
+pub fn sum(expr: Expr) -> Expr {
+    return call("sum", vec![expr], ComputeType::new(|ctx, path, args| {
+        let t = arg_type(ctx, path, &args, 0)?;
+        check_numeric(ctx, path, "sum", &t);
+        match t.type_.type_ {
+            SimpleSimpleType::F32 | SimpleSimpleType::F64 => return Some(simple_type(SimpleSimpleType::F64, true)),
+            _ => return Some(simple_type(SimpleSimpleType::I64, true)),
+        }
+    }));
+}
+
+/// `total(expr)` - like `sum` but always a float, and `0.0` rather than null when
+/// there are no rows.
+pub fn total(expr: Expr) -> Expr {
+    return call("total", vec![expr], ComputeType::new(|ctx, path, args| {
+        let t = arg_type(ctx, path, &args, 0)?;
+        check_numeric(ctx, path, "total", &t);
+        return Some(simple_type(SimpleSimpleType::F64, false));
+    }));
+}
+
+/// `avg(expr)` over a numeric expression. Always a float, null if there are no
+/// (non-null) rows.
+pub fn avg(expr: Expr) -> Expr {
+    return call("avg", vec![expr], ComputeType::new(|ctx, path, args| {
+        let t = arg_type(ctx, path, &args, 0)?;
+        check_numeric(ctx, path, "avg", &t);
+        return Some(simple_type(SimpleSimpleType::F64, true));
+    }));
+}
+
+/// Aggregate `min(expr)`. The result is null if there are no (non-null) rows.
+pub fn min(expr: Expr) -> Expr {
+    return call("min", vec![expr], ComputeType::new(|ctx, path, args| {
+        let mut t = arg_type(ctx, path, &args, 0)?;
+        t.opt = true;
+        return Some(t);
+    }));
+}
+
+/// Aggregate `max(expr)`. The result is null if there are no (non-null) rows.
+pub fn max(expr: Expr) -> Expr {
+    return call("max", vec![expr], ComputeType::new(|ctx, path, args| {
+        let mut t = arg_type(ctx, path, &args, 0)?;
+        t.opt = true;
+        return Some(t);
+    }));
+}
+
+/// `group_concat(expr, separator)` - concatenates string values. Null if there
+/// are no (non-null) rows.
+pub fn group_concat(expr: Expr, separator: impl Into<String>) -> Expr {
+    return call(
+        "group_concat",
+        vec![expr, Expr::LitString(separator.into())],
+        ComputeType::new(|ctx, path, args| {
+            let t = arg_type(ctx, path, &args, 0)?;
+            check_string(ctx, path, "group_concat", &t);
+            return Some(simple_type(SimpleSimpleType::String, true));
+        }),
+    );
+}
+
+// # Scalar
+/// `coalesce(a, b, ...)` - the first non-null argument. All arguments must have
+/// the same type, and the result is only nullable if the last argument is.
+pub fn coalesce(exprs: Vec<Expr>) -> Expr {
+    return call("coalesce", exprs, ComputeType::new(|ctx, path, args| {
+        if args.len() < 2 {
+            ctx.errs.err(path, format!("Coalesce requires at least two arguments but got {}", args.len()));
+            return None;
+        }
+        let mut out: Option<Type> = None;
+        for i in 0 .. args.len() {
+            let t = arg_type(ctx, path, &args, i)?;
+            match &out {
+                Some(first) => {
+                    if first.type_ != t.type_ || first.array != t.array {
+                        ctx
+                            .errs
+                            .err(
+                                path,
+                                format!(
+                                    "Coalesce argument {} has type {:?} but argument 0 has type {:?}",
+                                    i,
+                                    t.type_,
+                                    first.type_
+                                ),
+                            );
+                    }
+                },
+                None => { },
+            }
+            let opt = t.opt;
+            out = Some(Type {
+                opt: opt,
+                ..out.unwrap_or(t)
+            });
+        }
+        return out;
+    }));
+}
+
+/// `nullif(a, b)` - `a`, or null if `a` equals `b`.
+pub fn nullif(a: Expr, b: Expr) -> Expr {
+    return call("nullif", vec![a, b], ComputeType::new(|ctx, path, args| {
+        let mut t = arg_type(ctx, path, &args, 0)?;
+        let b = arg_type(ctx, path, &args, 1)?;
+        if t.type_ != b.type_ {
+            ctx.errs.err(path, format!("Nullif arguments have differing types: {:?} and {:?}", t.type_, b.type_));
+        }
+        t.opt = true;
+        return Some(t);
+    }));
+}
+
+/// `abs(expr)` of a numeric expression, with the argument's type.
+pub fn abs(expr: Expr) -> Expr {
+    return call("abs", vec![expr], ComputeType::new(|ctx, path, args| {
+        let t = arg_type(ctx, path, &args, 0)?;
+        check_numeric(ctx, path, "abs", &t);
+        return Some(t);
+    }));
+}
+
+/// `round(expr, digits)` of a numeric expression. Always a float.
+pub fn round(expr: Expr, digits: i32) -> Expr {
+    return call("round", vec![expr, Expr::LitI32(digits)], ComputeType::new(|ctx, path, args| {
+        let t = arg_type(ctx, path, &args, 0)?;
+        check_numeric(ctx, path, "round", &t);
+        return Some(simple_type(SimpleSimpleType::F64, t.opt));
+    }));
+}
+
+/// A function of a string and further arguments, each checked with `check_rest`.
+fn string_to_string(
+    func: &'static str,
+    args: Vec<Expr>,
+    check_rest: fn(&mut SqliteQueryCtx, &rpds::Vector<String>, &str, &Type),
+) -> Expr {
+    return call(func, args, ComputeType::new(move |ctx, path, args| {
+        let mut opt = false;
+        for i in 0 .. args.len() {
+            let t = arg_type(ctx, path, &args, i)?;
+            if i == 0 {
+                check_string(ctx, path, func, &t);
+            } else {
+                check_rest(ctx, path, func, &t);
+            }
+            opt = opt || t.opt;
+        }
+        return Some(simple_type(SimpleSimpleType::String, opt));
+    }));
+}
+
+/// `lower(expr)` of a string.
+pub fn lower(expr: Expr) -> Expr {
+    return string_to_string("lower", vec![expr], check_string);
+}
+
+/// `upper(expr)` of a string.
+pub fn upper(expr: Expr) -> Expr {
+    return string_to_string("upper", vec![expr], check_string);
+}
+
+/// `trim(expr)` - removes leading and trailing spaces from a string.
+pub fn trim(expr: Expr) -> Expr {
+    return string_to_string("trim", vec![expr], check_string);
+}
+
+/// `substr(expr, start, len)` - `start` is 1-based. `start` and `len` are
+/// integers.
+pub fn substr(expr: Expr, start: Expr, len: Expr) -> Expr {
+    return string_to_string("substr", vec![expr, start, len], check_integer);
+}
+
+/// `replace(expr, from, to)` - replaces all occurrences of `from` with `to`.
+pub fn replace(expr: Expr, from: Expr, to: Expr) -> Expr {
+    return string_to_string("replace", vec![expr, from, to], check_string);
+}
+
+/// `instr(haystack, needle)` - the 1-based position of the first occurrence of
+/// `needle` in `haystack`, or 0 if it doesn't occur.
+pub fn instr(haystack: Expr, needle: Expr) -> Expr {
+    return call("instr", vec![haystack, needle], ComputeType::new(|ctx, path, args| {
+        let mut opt = false;
+        for i in 0 .. 2 {
+            let t = arg_type(ctx, path, &args, i)?;
+            check_string(ctx, path, "instr", &t);
+            opt = opt || t.opt;
+        }
+        return Some(simple_type(SimpleSimpleType::I64, opt));
+    }));
+}
+
+/// `length(expr)` - the number of characters in a string or bytes in a blob.
+pub fn length(expr: Expr) -> Expr {
+    return call("length", vec![expr], ComputeType::new(|ctx, path, args| {
+        let t = arg_type(ctx, path, &args, 0)?;
+        if !matches!(t.type_.type_, SimpleSimpleType::String | SimpleSimpleType::Bytes) || t.array {
+            ctx
+                .errs
+                .err(
+                    path,
+                    format!("Function length requires a string or bytes argument but got {:?}", t.type_.type_),
+                );
+        }
+        return Some(simple_type(SimpleSimpleType::I64, t.opt));
+    }));
+}
+
+// # Date
+/// `unixepoch()` - the current time in seconds since the unix epoch.
+pub fn unixepoch() -> Expr {
+    return call("unixepoch", vec![], ComputeType::new(|_ctx, _path, _args| {
+        return Some(simple_type(SimpleSimpleType::I64, false));
+    }));
+}
+
+/// The current time, for comparison with or storing in `type_utctime_s` fields.
+#[cfg(feature = "chrono")]
+pub fn now_utc_s() -> Expr {
+    return call("unixepoch", vec![], ComputeType::new(|_ctx, _path, _args| {
+        return Some(simple_type(SimpleSimpleType::UtcTimeSChrono, false));
+    }));
+}
+
+/// The current time, for comparison with or storing in `type_utctime_s_jiff`
+/// fields.
+#[cfg(feature = "jiff")]
+pub fn now_utc_s_jiff() -> Expr {
+    return call("unixepoch", vec![], ComputeType::new(|_ctx, _path, _args| {
+        return Some(simple_type(SimpleSimpleType::UtcTimeSJiff, false));
+    }));
+}
+
+// # Full text search
+// The FTS5 table must not be aliased in the select.
+
+/// `table MATCH query` - filters the rows of an FTS5 table with a full text query,
+/// matching against all indexed columns. Use `BinOp::Match` with a column to match
+/// against a single column.
+pub fn fts5_match(table: &Fts5Table, query: Expr) -> Expr {
+    return Expr::BinOp {
+        left: Box::new(Expr::field(&table.table_field)),
+        op: BinOp::Match,
+        right: Box::new(query),
+    };
+}
+
+/// `bm25(table)` - the relevance of the matched row, lower (more negative) is a
+/// better match. Order ascending by this to get the best matches first.
+pub fn bm25(table: &Fts5Table) -> Expr {
+    return call("bm25", vec![Expr::field(&table.table_field)], ComputeType::new(|_ctx, _path, _args| {
+        return Some(simple_type(SimpleSimpleType::F64, false));
+    }));
+}
+
+/// `highlight(table, column, open, close)` - the value of `column` in the matched
+/// row with each matched phrase surrounded by `open` and `close`.
+pub fn highlight(table: &Fts5Table, column: &Field, open: Expr, close: Expr) -> Expr {
+    let opt = column.type_.type_.opt;
+    return call(
+        "highlight",
+        vec![Expr::field(&table.table_field), Expr::LitI64(table.column_index(column) as i64), open, close],
+        ComputeType::new(move |ctx, path, args| {
+            for i in 2 .. 4 {
+                let t = arg_type(ctx, path, &args, i)?;
+                check_string(ctx, path, "highlight", &t);
+            }
+            return Some(simple_type(SimpleSimpleType::String, opt));
+        }),
+    );
+}
+
+/// `snippet(table, column, open, close, ellipsis, max_tokens)` - a short fragment
+/// of `column` (or the best matching column if `None`) in the matched row, with
+/// matched phrases surrounded by `open` and `close` and `ellipsis` where text was
+/// cut. `max_tokens` must be between 1 and 64.
+pub fn snippet(
+    table: &Fts5Table,
+    column: Option<&Field>,
+    open: Expr,
+    close: Expr,
+    ellipsis: Expr,
+    max_tokens: i64,
+) -> Expr {
+    let (column_index, opt) = match column {
+        Some(column) => (table.column_index(column) as i64, column.type_.type_.opt),
+        None => (-1, table.columns.iter().any(|c| c.field.type_.type_.opt)),
+    };
+    return call(
+        "snippet",
+        vec![
+            Expr::field(&table.table_field),
+            Expr::LitI64(column_index),
+            open,
+            close,
+            ellipsis,
+            Expr::LitI64(max_tokens)
+        ],
+        ComputeType::new(move |ctx, path, args| {
+            for i in 2 .. 5 {
+                let t = arg_type(ctx, path, &args, i)?;
+                check_string(ctx, path, "snippet", &t);
+            }
+            if !(1 ..= 64).contains(&max_tokens) {
+                ctx.errs.err(path, format!("Function snippet requires max_tokens between 1 and 64 but got {}", max_tokens));
+            }
+            return Some(simple_type(SimpleSimpleType::String, opt));
+        }),
+    );
+}
+
+// # Window
+// These can only be used as the `expr` of `Expr::Window`.
+
+/// `row_number()` - the 1-based number of the row within the partition.
+pub fn row_number() -> Expr {
+    return call("row_number", vec![], ComputeType::new(|_ctx, _path, _args| {
+        return Some(simple_type(SimpleSimpleType::I64, false));
+    }));
+}
+
+/// `rank()` - the 1-based rank of the row within the partition, with gaps for
+/// ties.
+pub fn rank() -> Expr {
+    return call("rank", vec![], ComputeType::new(|_ctx, _path, _args| {
+        return Some(simple_type(SimpleSimpleType::I64, false));
+    }));
+}
+
+/// `dense_rank()` - the 1-based rank of the row within the partition, without
+/// gaps.
+pub fn dense_rank() -> Expr {
+    return call("dense_rank", vec![], ComputeType::new(|_ctx, _path, _args| {
+        return Some(simple_type(SimpleSimpleType::I64, false));
+    }));
+}
+
+/// `ntile(buckets)` - the 1-based bucket number of the row when the partition is
+/// split into `buckets` groups.
+pub fn ntile(buckets: i64) -> Expr {
+    return call("ntile", vec![Expr::LitI64(buckets)], ComputeType::new(|_ctx, _path, _args| {
+        return Some(simple_type(SimpleSimpleType::I64, false));
+    }));
+}
+
+/// `lag(expr, offset)` - the value `offset` rows before the current row. Always
+/// nullable since there may be no such row.
+pub fn lag(expr: Expr, offset: i64) -> Expr {
+    return call("lag", vec![expr, Expr::LitI64(offset)], ComputeType::new(|ctx, path, args| {
+        let mut t = arg_type(ctx, path, &args, 0)?;
+        t.opt = true;
+        return Some(t);
+    }));
+}
+
+/// `lead(expr, offset)` - the value `offset` rows after the current row. Always
+/// nullable since there may be no such row.
+pub fn lead(expr: Expr, offset: i64) -> Expr {
+    return call("lead", vec![expr, Expr::LitI64(offset)], ComputeType::new(|ctx, path, args| {
+        let mut t = arg_type(ctx, path, &args, 0)?;
+        t.opt = true;
+        return Some(t);
+    }));
+}
+
+/// `first_value(expr)` - the value at the first row of the frame. Always nullable
+/// since the frame may be empty (e.g. `rows between 2 preceding and 1 preceding`).
+pub fn first_value(expr: Expr) -> Expr {
+    return call("first_value", vec![expr], ComputeType::new(|ctx, path, args| {
+        let mut t = arg_type(ctx, path, &args, 0)?;
+        t.opt = true;
+        return Some(t);
+    }));
+}
+
+/// `last_value(expr)` - the value at the last row of the frame. Always nullable
+/// since the frame may be empty.
+pub fn last_value(expr: Expr) -> Expr {
+    return call("last_value", vec![expr], ComputeType::new(|ctx, path, args| {
+        let mut t = arg_type(ctx, path, &args, 0)?;
+        t.opt = true;
+        return Some(t);
+    }));
+}
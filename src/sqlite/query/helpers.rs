@@ -1,9 +1,12 @@
 use {
-    super::expr::{
-        BinOp,
-        ComputeType,
-        Expr,
-        Binding,
+    super::{
+        expr::{
+            BinOp,
+            ComputeType,
+            Expr,
+            Binding,
+        },
+        functions::instr,
     },
     crate::sqlite::{
         schema::field::Field,
@@ -201,3 +204,60 @@ pub fn fn_count(expr: Expr) -> Expr {
         }),
     }
 }
+
+/// For recursive CTEs, the depth column value in a recursive select: `depth + 1`.
+/// The depth column must be an `i64`, starting with `Expr::LitI64(0)` in the
+/// initial select.
+pub fn expr_cte_depth_next(depth: Expr) -> Expr {
+    Expr::BinOp {
+        left: Box::new(depth),
+        op: BinOp::Plus,
+        right: Box::new(Expr::LitI64(1)),
+    }
+}
+
+/// For recursive CTEs, a recursive select condition that stops recursing once
+/// `depth` reaches `max` (an `i64`).
+pub fn expr_cte_depth_below(depth: Expr, max: Expr) -> Expr {
+    Expr::BinOp {
+        left: Box::new(depth),
+        op: BinOp::LessThan,
+        right: Box::new(max),
+    }
+}
+
+fn cte_path_segment(id: Expr) -> Expr {
+    Expr::Convert {
+        expr: Box::new(id),
+        type_: SimpleSimpleType::String,
+    }
+}
+
+/// For recursive CTEs, the initial value of a string column tracking the ids
+/// visited so far, to guard against cycles with `expr_cte_path_excludes`. Ids are
+/// converted to strings and must not contain `/`.
+pub fn expr_cte_path_start(id: Expr) -> Expr {
+    Expr::BinOpChain {
+        op: BinOp::Concat,
+        exprs: vec![Expr::LitString("/".into()), cte_path_segment(id), Expr::LitString("/".into())],
+    }
+}
+
+/// For recursive CTEs, the path column value in a recursive select, adding `id` to
+/// the ids visited so far. See `expr_cte_path_start`.
+pub fn expr_cte_path_append(path: Expr, id: Expr) -> Expr {
+    Expr::BinOpChain {
+        op: BinOp::Concat,
+        exprs: vec![path, cte_path_segment(id), Expr::LitString("/".into())],
+    }
+}
+
+/// For recursive CTEs, a recursive select condition that's true if `id` hasn't
+/// been visited yet. See `expr_cte_path_start`.
+pub fn expr_cte_path_excludes(path: Expr, id: Expr) -> Expr {
+    Expr::BinOp {
+        left: Box::new(instr(path, expr_cte_path_start(id))),
+        op: BinOp::Equals,
+        right: Box::new(Expr::LitI64(0)),
+    }
+}
@@ -129,7 +129,10 @@ impl QueryBody for Insert {
             (QueryResCount::Many, _) => {
                 ctx.errs.err(path, format!("Insert can at most return one row, but res count is many"));
             },
-            (QueryResCount::None, _) | (QueryResCount::One, None) | (QueryResCount::MaybeOne, None) => {
+            (QueryResCount::None, _) |
+            (QueryResCount::One, None) |
+            (QueryResCount::MaybeOne, None) |
+            (QueryResCount::Auto, _) => {
                 // handled elsewhere, nop
             },
             (QueryResCount::One, Some(InsertConflict::DoUpdate(_))) |
@@ -140,4 +143,14 @@ impl QueryBody for Insert {
         let out_type = build_returning(ctx, path, &scope, &mut out, &self.returning, res_count);
         (out_type, out)
     }
+
+    fn infer_res_count(&self, _ctx: &super::utils::SqliteQueryCtx) -> Option<(QueryResCount, Option<String>)> {
+        if self.returning.is_empty() {
+            return Some((QueryResCount::None, None));
+        }
+        match self.on_conflict {
+            Some(InsertConflict::DoNothing) => return Some((QueryResCount::MaybeOne, None)),
+            _ => return Some((QueryResCount::One, None)),
+        }
+    }
 }
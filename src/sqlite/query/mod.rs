@@ -6,3 +6,4 @@ pub mod update;
 pub mod expr;
 pub mod insert;
 pub mod helpers;
+pub mod functions;
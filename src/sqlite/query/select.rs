@@ -1,21 +1,31 @@
 use {
     super::{
         expr::{
+            BinOp,
+            Expr,
             ExprType,
         },
+        expr::Binding,
         select_body::{
-            build_select_junction,
+            build_compound,
+            Order,
+            Returning,
             SelectBody,
             SelectJunction,
         },
         utils::{
             build_with,
+            Paginated,
             QueryBody,
             With,
         },
     },
     crate::{
         sqlite::{
+            types::{
+                type_i64,
+                Type,
+            },
             QueryResCount,
         },
         utils::Tokens,
@@ -23,27 +33,169 @@ use {
     std::collections::HashMap,
 };
 
+/// Lets the caller choose the `ORDER BY` clause at runtime from a fixed set of
+/// choices. A Rust enum named `name` is generated with one variant per choice, and
+/// the query function takes a value of it as the `order` argument. Each variant is
+/// a separate SQL statement. The clauses of the chosen variant come before any
+/// `order` clauses, which can be used as tie-breakers.
+#[derive(Clone, Debug)]
+pub struct OrderChoice {
+    pub name: String,
+    pub variants: Vec<(String, Vec<(Expr, Order)>)>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Select {
     pub with: Option<With>,
     pub body: SelectBody,
     pub body_junctions: Vec<SelectJunction>,
+    pub order_choice: Option<OrderChoice>,
+    /// Keyset pagination order, see `SelectBuilder::paginate_by`.
+    pub paginate: Option<Vec<(Expr, Order)>>,
 }
 
-impl QueryBody for Select {
-    fn build(
+impl Select {
+    /// Builds the select with CTEs and junctions. CTEs are only visible within the
+    /// select.
+    pub(crate) fn build_scoped(
         &self,
         ctx: &mut super::utils::SqliteQueryCtx,
+        scope: &HashMap<Binding, Type>,
         path: &rpds::Vector<String>,
         res_count: QueryResCount,
     ) -> (ExprType, Tokens) {
         let mut out = Tokens::new();
+        let outer_tables = self.with.as_ref().map(|_| ctx.tables.clone());
         if let Some(w) = &self.with {
             out.s(&build_with(ctx, path, w).to_string());
         }
-        let body: (ExprType, Tokens) = self.body.build(ctx, &HashMap::new(), path, res_count);
+        let body = build_compound(ctx, scope, path, &self.body, &self.body_junctions, res_count);
         out.s(&body.1.to_string());
-        out.s(&build_select_junction(ctx, path, &body.0, &self.body_junctions).to_string());
+        if let Some(outer_tables) = outer_tables {
+            ctx.tables = outer_tables;
+        }
         return (body.0, out);
     }
+
+    /// Builds the select for use as a subquery, without parentheses. `scope` has the
+    /// fields of the enclosing query available for correlated subqueries.
+    pub(crate) fn build_subquery(
+        &self,
+        ctx: &mut super::utils::SqliteQueryCtx,
+        scope: &HashMap<Binding, Type>,
+        path: &rpds::Vector<String>,
+    ) -> (ExprType, Tokens) {
+        if self.order_choice.is_some() {
+            ctx.errs.err(path, format!("Order choices can only be used in top level queries"));
+        }
+        if self.paginate.is_some() {
+            ctx.errs.err(path, format!("Pagination can only be used in top level queries"));
+        }
+        return self.build_scoped(ctx, scope, path, QueryResCount::Many);
+    }
+}
+
+impl QueryBody for Select {
+    fn build(
+        &self,
+        ctx: &mut super::utils::SqliteQueryCtx,
+        path: &rpds::Vector<String>,
+        res_count: QueryResCount,
+    ) -> (ExprType, Tokens) {
+        return self.build_scoped(ctx, &HashMap::new(), path, res_count);
+    }
+
+    fn build_order_choices(
+        &self,
+        ctx: &mut super::utils::SqliteQueryCtx,
+        path: &rpds::Vector<String>,
+        res_count: QueryResCount,
+    ) -> Option<(String, Vec<(String, ExprType, Tokens)>)> {
+        let choice = self.order_choice.as_ref()?;
+        let mut out = vec![];
+        for (name, order) in &choice.variants {
+            let mut variant = self.clone();
+            variant.order_choice = None;
+            variant.body.order = order.iter().cloned().chain(self.body.order.iter().cloned()).collect();
+            let (t, tokens) = variant.build(ctx, &path.push_back(format!("Order choice {}", name)), res_count.clone());
+            out.push((name.clone(), t, tokens));
+        }
+        return Some((choice.name.clone(), out));
+    }
+
+    fn infer_res_count(&self, ctx: &super::utils::SqliteQueryCtx) -> Option<(QueryResCount, Option<String>)> {
+        if !self.body_junctions.is_empty() || self.paginate.is_some() {
+            return Some((QueryResCount::Many, None));
+        }
+        return Some(self.body.infer_res_count(ctx));
+    }
+
+    fn build_paginated(
+        &self,
+        ctx: &mut super::utils::SqliteQueryCtx,
+        path: &rpds::Vector<String>,
+    ) -> Option<Paginated> {
+        let paginate = self.paginate.as_ref()?;
+        let path = path.push_back(format!("Paginate"));
+        if paginate.is_empty() {
+            ctx.errs.err(&path, format!("Pagination needs at least one order clause"));
+        }
+        if !self.body.order.is_empty() {
+            ctx.errs.err(&path, format!("Paginated queries are ordered by the pagination clauses, but `order` is set"));
+        }
+        if self.body.limit.is_some() {
+            ctx.errs.err(&path, format!("Paginated queries are limited by the page size, but `limit` is set"));
+        }
+        if self.order_choice.is_some() {
+            ctx.errs.err(&path, format!("Paginated queries can't have an order choice"));
+        }
+        if !self.body_junctions.is_empty() {
+            ctx.errs.err(&path, format!("Paginated queries can't have junctions"));
+        }
+        let mut body = self.body.clone();
+        body.order = paginate.clone();
+        // Fetch one extra row to tell whether there's a next page
+        body.limit = Some(Expr::BinOp {
+            left: Box::new(Expr::Param {
+                name: "page_size".into(),
+                type_: type_i64().build(),
+            }),
+            op: BinOp::Plus,
+            right: Box::new(Expr::LitI64(1)),
+        });
+        for (i, (e, _)) in paginate.iter().enumerate() {
+            body.returning.push(Returning {
+                e: e.clone(),
+                rename: Some(format!("cursor_{}", i)),
+            });
+        }
+        let with = self.with.as_ref().map(|w| build_with(ctx, &path, w).to_string());
+
+        // Build the next page query first so params are numbered in the order they
+        // appear
+        let mut next = Tokens::new();
+        if let Some(w) = &with {
+            next.s(w);
+        }
+        let (res_type, next_body) =
+            body.build_after(ctx, &HashMap::new(), &path.push_back(format!("Next")), QueryResCount::Many, Some(paginate));
+        next.s(&next_body.to_string());
+        let cursor_args =
+            (0 .. paginate.len())
+                .filter_map(|i| ctx.rust_arg_lookup.get(&format!("cursor_{}", i)).map(|a| a.0))
+                .collect::<Vec<_>>();
+        let mut first = Tokens::new();
+        if let Some(w) = &with {
+            first.s(w);
+        }
+        let (_, first_body) = body.build(ctx, &HashMap::new(), &path, QueryResCount::Many);
+        first.s(&first_body.to_string());
+        return Some(Paginated {
+            res_type: res_type,
+            cursor_len: paginate.len(),
+            first: first,
+            cursor_args: cursor_args,
+            next: next,
+        });
+    }
 }
@@ -4,16 +4,21 @@ use {
             check_assignable,
             check_bool,
             check_general_same,
+            check_same,
             Expr,
             ExprType,
             Binding,
+            WindowSpec,
         },
+        select::Select,
         utils::{
+            unproven_sources,
             build_returning_values,
             SqliteQueryCtx,
         },
     },
     crate::{
+        utils::Errs,
         sqlite::{
             schema::table::Table,
             types::{
@@ -24,19 +29,57 @@ use {
         },
         utils::Tokens,
     },
-    std::collections::HashMap,
+    std::collections::{
+        HashMap,
+        HashSet,
+    },
 };
 
 #[derive(Clone, Debug)]
 pub enum Order {
     Asc,
     Desc,
+    AscNullsFirst,
+    AscNullsLast,
+    DescNullsFirst,
+    DescNullsLast,
+}
+
+impl Order {
+    pub(crate) fn to_sql(&self) -> &'static str {
+        match self {
+            Order::Asc => "asc",
+            Order::Desc => "desc",
+            Order::AscNullsFirst => "asc nulls first",
+            Order::AscNullsLast => "asc nulls last",
+            Order::DescNullsFirst => "desc nulls first",
+            Order::DescNullsLast => "desc nulls last",
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum JoinSource {
-    Subsel(Box<SelectBody>),
+    /// A subselect, see `SelectBuilder::build_subquery`.
+    Subsel(Box<Select>),
     Table(Table),
+    /// A table-valued function like `json_each` or `generate_series`. The arguments
+    /// can refer to fields of sources earlier in the `FROM` clause. Only the
+    /// `columns` you declare are made available, bound to the alias (or the function
+    /// name if there's no alias).
+    Function {
+        func: String,
+        args: Vec<Expr>,
+        columns: Vec<(String, Type)>,
+    },
+    /// An inline table, like `(VALUES (1, 'a'), (2, 'b'))` with named columns. Every
+    /// row must have one expression per column, and each column's type must be the
+    /// same across rows (it's nullable if any row's value is nullable). The columns
+    /// are bound to the alias, which is required.
+    Values {
+        columns: Vec<String>,
+        rows: Vec<Vec<Expr>>,
+    },
 }
 
 #[derive(Clone, Debug)]
@@ -46,12 +89,16 @@ pub struct NamedSelectSource {
 }
 
 impl NamedSelectSource {
-    fn build(&self, ctx: &mut SqliteQueryCtx, path: &rpds::Vector<String>) -> (Vec<(Binding, Type)>, Tokens) {
+    fn build(
+        &self,
+        ctx: &mut SqliteQueryCtx,
+        path: &rpds::Vector<String>,
+        scope: &HashMap<Binding, Type>,
+    ) -> (Vec<(Binding, Type)>, Tokens) {
         let mut out = Tokens::new();
         let mut new_fields: Vec<(Binding, Type)> = match &self.source {
             JoinSource::Subsel(s) => {
-                let res =
-                    s.build(ctx, &HashMap::new(), &path.push_back(format!("From subselect")), QueryResCount::Many);
+                let res = s.build_subquery(ctx, &HashMap::new(), &path.push_back(format!("From subselect")));
                 out.s("(").s(&res.1.to_string()).s(")");
                 res.0.0.clone()
             },
@@ -66,6 +113,107 @@ impl NamedSelectSource {
                 out.id(&s.id);
                 new_fields.iter().map(|e| (Binding::field(e), e.type_.type_.clone())).collect()
             },
+            JoinSource::Function { func, args, columns } => {
+                let path = path.push_back(format!("From function [{}]", func));
+                out.s(func).s("(");
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        out.s(",");
+                    }
+                    let (_, tokens) = arg.build(ctx, &path.push_back(format!("Arg {}", i)), scope);
+                    out.s(&tokens.to_string());
+                }
+                out.s(")");
+                columns.iter().map(|(id, t)| (Binding {
+                    table_id: func.clone(),
+                    id: id.clone(),
+                }, t.clone())).collect()
+            },
+            JoinSource::Values { columns, rows } => {
+                let path = path.push_back(format!("From values"));
+                let Some(alias) = &self.alias else {
+                    ctx.errs.err(&path, format!("Values sources must have an alias"));
+                    return (vec![], Tokens::new());
+                };
+                if rows.is_empty() {
+                    ctx.errs.err(&path, format!("Values sources must have at least one row"));
+                    return (vec![], Tokens::new());
+                }
+                let mut types: Vec<Option<Type>> = vec![None; columns.len()];
+                let mut rows_out = Tokens::new();
+                for (i, row) in rows.iter().enumerate() {
+                    let path = path.push_back(format!("Row {}", i));
+                    if row.len() != columns.len() {
+                        ctx
+                            .errs
+                            .err(
+                                &path,
+                                format!("Row has {} values but there are {} columns", row.len(), columns.len()),
+                            );
+                        continue;
+                    }
+                    if i > 0 {
+                        rows_out.s(",");
+                    }
+                    rows_out.s("(");
+                    for (j, (e, t)) in row.iter().zip(types.iter_mut()).enumerate() {
+                        let path = path.push_back(format!("Column {} ({})", j, columns[j]));
+                        if j > 0 {
+                            rows_out.s(",");
+                        }
+                        let (got, tokens) = e.build(ctx, &path, &HashMap::new());
+                        rows_out.s(&tokens.to_string());
+                        let Some((_, got)) = got.assert_scalar(&mut ctx.errs, &path) else {
+                            continue;
+                        };
+                        if got.array {
+                            ctx.errs.err(&path, format!("Values can't be arrays"));
+                        }
+                        match t {
+                            Some(t) => {
+                                check_same(
+                                    &mut ctx.errs,
+                                    &path,
+                                    &ExprType(vec![(Binding::empty(), Type {
+                                        opt: false,
+                                        ..t.clone()
+                                    })]),
+                                    &ExprType(vec![(Binding::empty(), Type {
+                                        opt: false,
+                                        ..got.clone()
+                                    })]),
+                                );
+                                t.opt = t.opt || got.opt;
+                            },
+                            None => {
+                                *t = Some(got);
+                            },
+                        }
+                    }
+                    rows_out.s(")");
+                }
+
+                // Sqlite names values columns `column1`, `column2`, ... so rename them
+                out.s("( select");
+                for (i, id) in columns.iter().enumerate() {
+                    if i > 0 {
+                        out.s(",");
+                    }
+                    out.id(&format!("column{}", i + 1)).s("as").id(id);
+                }
+                out.s("from ( values").s(&rows_out.to_string()).s(") ) as").id(alias);
+                return (
+                    columns
+                        .iter()
+                        .zip(types)
+                        .filter_map(|(id, t)| Some((Binding {
+                            table_id: alias.clone(),
+                            id: id.clone(),
+                        }, t?)))
+                        .collect(),
+                    out,
+                );
+            },
         };
         if let Some(s) = &self.alias {
             out.s("as").id(s);
@@ -106,8 +254,11 @@ pub struct SelectBody {
     pub join: Vec<Join>,
     pub where_: Option<Expr>,
     pub group: Vec<Expr>,
+    pub having: Option<Expr>,
+    pub windows: Vec<(String, WindowSpec)>,
     pub order: Vec<(Expr, Order)>,
     pub limit: Option<Expr>,
+    pub offset: Option<Expr>,
 }
 
 impl SelectBody {
@@ -117,11 +268,26 @@ impl SelectBody {
         inject_scope: &HashMap<Binding, Type>,
         path: &rpds::Vector<String>,
         res_count: QueryResCount,
+    ) -> (ExprType, Tokens) {
+        return self.build_after(ctx, inject_scope, path, res_count, None);
+    }
+
+    /// Build the select, optionally only returning rows after a keyset pagination
+    /// cursor. `after` is the pagination order, see `build_cursor_condition`.
+    pub(crate) fn build_after(
+        &self,
+        ctx: &mut super::utils::SqliteQueryCtx,
+        inject_scope: &HashMap<Binding, Type>,
+        path: &rpds::Vector<String>,
+        res_count: QueryResCount,
+        after: Option<&[(Expr, Order)]>,
     ) -> (ExprType, Tokens) {
         // Prep
-        let source = self.table.build(ctx, path);
+        let source = self.table.build(ctx, path, inject_scope);
         let mut scope = inject_scope.clone();
+        let mut local_bindings = HashSet::new();
         for (k, v) in source.0 {
+            local_bindings.insert(k.clone());
             scope.insert(k, v);
         }
         let mut joins = vec![];
@@ -133,8 +299,9 @@ impl SelectBody {
                 JoinType::Inner => out.s("inner"),
             };
             out.s("join");
-            let source = je.source.build(ctx, &path);
+            let source = je.source.build(ctx, &path, &scope);
             out.s(&source.1.to_string());
+            local_bindings.extend(source.0.iter().map(|e| e.0.clone()));
             match je.type_ {
                 JoinType::Left => {
                     for (k, mut v) in source.0 {
@@ -158,7 +325,28 @@ impl SelectBody {
             joins.push(out.to_string());
         }
 
+        // Narrow nullable bindings using the where and inner join conditions
+        let mut non_null = HashSet::new();
+        if let Some(where_) = &self.where_ {
+            where_.non_null_if_true(&mut non_null);
+        }
+        for je in &self.join {
+            if let JoinType::Inner = je.type_ {
+                je.on.non_null_if_true(&mut non_null);
+            }
+        }
+        for b in non_null {
+            if let Some(t) = scope.get_mut(&b) {
+                t.opt = false;
+            }
+        }
+
         // Build query
+        let window_names = self.windows.iter().map(|w| w.0.clone()).collect::<HashSet<_>>();
+        if window_names.len() != self.windows.len() {
+            ctx.errs.err(path, format!("Select has duplicate window names"));
+        }
+        let outer_windows = std::mem::replace(&mut ctx.windows, window_names.clone());
         let mut out = Tokens::new();
         out.s("select");
         if self.distinct {
@@ -173,12 +361,26 @@ impl SelectBody {
         for join in joins {
             out.s(&join);
         }
+        let mut where_parts = vec![];
         if let Some(where_) = &self.where_ {
-            out.s("where");
             let path = path.push_back("Where".into());
             let (where_t, where_tokens) = where_.build(ctx, &path, &scope);
             check_bool(ctx, &path, &where_t);
-            out.s(&where_tokens.to_string());
+            where_parts.push(where_tokens.to_string());
+        }
+        if let Some(after) = after {
+            where_parts.push(build_cursor_condition(ctx, &path.push_back("Cursor".into()), &scope, after).to_string());
+        }
+        if where_parts.len() == 1 {
+            out.s("where").s(&where_parts[0]);
+        } else if where_parts.len() > 1 {
+            out.s("where");
+            for (i, part) in where_parts.iter().enumerate() {
+                if i > 0 {
+                    out.s("and");
+                }
+                out.s("(").s(part).s(")");
+            }
         }
         if self.group.len() > 0 {
             out.s("group by");
@@ -191,30 +393,307 @@ impl SelectBody {
                 out.s(&g_tokens.to_string());
             }
         }
-        if !self.order.is_empty() {
-            out.s("order by");
-            for (i, o) in self.order.iter().enumerate() {
-                let path = path.push_back(format!("Order by clause {}", i));
+        if let Some(having) = &self.having {
+            out.s("having");
+            let path = path.push_back("Having".into());
+            let (having_t, having_tokens) = having.build(ctx, &path, &scope);
+            check_bool(ctx, &path, &having_t);
+            out.s(&having_tokens.to_string());
+        }
+        if !self.windows.is_empty() {
+            out.s("window");
+            for (i, (name, spec)) in self.windows.iter().enumerate() {
+                let path = path.push_back(format!("Window [{}]", name));
                 if i > 0 {
                     out.s(",");
                 }
-                let (_, o_tokens) = o.0.build(ctx, &path, &scope);
-                out.s(&o_tokens.to_string());
-                out.s(match o.1 {
-                    Order::Asc => "asc",
-                    Order::Desc => "desc",
-                });
+
+                // Only windows defined earlier can be used as the base
+                ctx.windows = self.windows[.. i].iter().map(|w| w.0.clone()).collect();
+                out.id(name).s("as (").s(&spec.build(ctx, &path, &scope).to_string()).s(")");
             }
+            ctx.windows = window_names;
         }
-        if let Some(l) = &self.limit {
-            out.s("limit");
-            let path = path.push_back("Limit".into());
-            let (limit_t, limit_tokens) = l.build(ctx, &path, &scope);
-            check_general_same(ctx, &path, &limit_t, &ExprType(vec![(Binding::empty(), type_i64().build())]));
-            out.s(&limit_tokens.to_string());
+        build_order_limit(ctx, path, &scope, &mut out, &self.order, &self.limit, &self.offset);
+
+        ctx.windows = outer_windows;
+
+        // Check all outputs are grouped or aggregated
+        if !self.group.is_empty() || self.having.is_some() || self.returning.iter().any(|r| r.e.contains_aggregate()) {
+            // Columns of a table depend on its primary key, so they can be used if the whole
+            // key is grouped
+            let mut grouped_sources = HashSet::new();
+            for source in std::iter::once(&self.table).chain(self.join.iter().map(|je| je.source.as_ref())) {
+                let JoinSource::Table(t) = &source.source else {
+                    continue;
+                };
+                let Some(pk) = ctx.primary_keys.get(t) else {
+                    continue;
+                };
+                let id = source.alias.clone().unwrap_or_else(|| t.id.clone());
+                let pk_grouped = pk.iter().all(|f| {
+                    let key = Expr::Binding(Binding {
+                        table_id: id.clone(),
+                        id: f.id.clone(),
+                    });
+                    self.group.iter().any(|g| g.same_as(&key))
+                });
+                if pk_grouped {
+                    grouped_sources.insert(id);
+                }
+            }
+            for (i, r) in self.returning.iter().enumerate() {
+                check_grouped(
+                    &mut ctx.errs,
+                    &path.push_back(format!("Return {}", i)),
+                    &local_bindings,
+                    &grouped_sources,
+                    &self.group,
+                    &r.e,
+                );
+            }
+            if let Some(having) = &self.having {
+                check_grouped(
+                    &mut ctx.errs,
+                    &path.push_back("Having".into()),
+                    &local_bindings,
+                    &grouped_sources,
+                    &self.group,
+                    having,
+                );
+            }
+            for (i, o) in self.order.iter().enumerate() {
+                check_grouped(
+                    &mut ctx.errs,
+                    &path.push_back(format!("Order by clause {}", i)),
+                    &local_bindings,
+                    &grouped_sources,
+                    &self.group,
+                    &o.0,
+                );
+            }
         }
         (out_type, out)
     }
+
+    /// Counts the `FROM` and `JOIN` sources of this select that are `table`, and
+    /// separately the references within subselect sources and subquery expressions.
+    pub(crate) fn table_refs(&self, table: &Table) -> (usize, usize) {
+        let mut direct = 0;
+        let mut subqueries = vec![];
+        for source in std::iter::once(&self.table).chain(self.join.iter().map(|j| j.source.as_ref())) {
+            match &source.source {
+                JoinSource::Table(t) => {
+                    if t == table {
+                        direct += 1;
+                    }
+                },
+                JoinSource::Subsel(s) => subqueries.push(s.as_ref()),
+                JoinSource::Function { .. } | JoinSource::Values { .. } => { },
+            }
+        }
+        for e in self
+            .returning
+            .iter()
+            .map(|r| &r.e)
+            .chain(self.join.iter().map(|j| &j.on))
+            .chain(self.where_.iter())
+            .chain(self.group.iter())
+            .chain(self.having.iter())
+            .chain(self.order.iter().map(|o| &o.0))
+            .chain(self.limit.iter())
+            .chain(self.offset.iter()) {
+            e.subqueries(&mut subqueries);
+        }
+        for (_, w) in &self.windows {
+            w.subqueries(&mut subqueries);
+        }
+        let mut nested = 0;
+        for s in subqueries {
+            for body in std::iter::once(&s.body).chain(s.body_junctions.iter().map(|j| &j.body)) {
+                let (sub_direct, sub_nested) = body.table_refs(table);
+                nested += sub_direct + sub_nested;
+            }
+        }
+        return (direct, nested);
+    }
+
+    /// The most specific result count that can be proven for this select, see
+    /// `QueryResCount`.
+    pub(crate) fn infer_res_count(&self, ctx: &SqliteQueryCtx) -> (QueryResCount, Option<String>) {
+        if self.group.is_empty() &&
+            (self.having.is_some() || self.returning.iter().any(|r| r.e.contains_aggregate())) {
+            if self.having.is_none() && self.limit.is_none() && self.offset.is_none() {
+                return (QueryResCount::One, None);
+            }
+            return (QueryResCount::MaybeOne, None);
+        }
+        if matches!(self.limit, Some(Expr::LitI32(0 ..= 1) | Expr::LitI64(0 ..= 1))) {
+            return (QueryResCount::MaybeOne, None);
+        }
+        fn source(s: &NamedSelectSource) -> (Option<&Table>, String) {
+            match &s.source {
+                JoinSource::Table(t) => return (Some(t), s.alias.clone().unwrap_or_else(|| t.id.clone())),
+                _ => return (None, s.alias.clone().unwrap_or_default()),
+            }
+        }
+        let mut equalities = vec![];
+        if let Some(where_) = &self.where_ {
+            where_.equalities_if_true(&mut equalities);
+        }
+        let (table, id) = source(&self.table);
+        let mut sources = vec![(table, id, vec![])];
+        for je in &self.join {
+            // Left join conditions only constrain the joined source
+            let mut own_equalities = vec![];
+            match je.type_ {
+                JoinType::Left => je.on.equalities_if_true(&mut own_equalities),
+                JoinType::Inner => je.on.equalities_if_true(&mut equalities),
+            }
+            let (table, id) = source(&je.source);
+            sources.push((table, id, own_equalities));
+        }
+        match unproven_sources(ctx, &sources, &equalities) {
+            None => return (QueryResCount::MaybeOne, None),
+            Some(unproven) if unproven.is_empty() => return (QueryResCount::Many, None),
+            Some(unproven) => return (QueryResCount::Many, Some(unproven.join("; "))),
+        }
+    }
+}
+
+/// Builds the keyset pagination condition selecting rows after the cursor, like
+/// `(a, b) > ($1, $2)`. The cursor values are parameters named `cursor_0`,
+/// `cursor_1`, etc.
+fn build_cursor_condition(
+    ctx: &mut SqliteQueryCtx,
+    path: &rpds::Vector<String>,
+    scope: &HashMap<Binding, Type>,
+    after: &[(Expr, Order)],
+) -> Tokens {
+    let mut items = vec![];
+    for (i, (e, order)) in after.iter().enumerate() {
+        let path = path.push_back(format!("Clause {}", i));
+        let (e_t, e_tokens) = e.build(ctx, &path, scope);
+        let Some((_, t)) = e_t.assert_scalar(&mut ctx.errs, &path) else {
+            return Tokens::new();
+        };
+        if t.opt {
+            ctx.errs.err(&path, format!("Pagination clause is nullable, but cursor comparisons need non-null values"));
+        }
+        let (_, param_tokens) = Expr::Param {
+            name: format!("cursor_{}", i),
+            type_: t,
+        }.build(ctx, &path, scope);
+        let desc = match order {
+            Order::Asc | Order::AscNullsFirst | Order::AscNullsLast => false,
+            Order::Desc | Order::DescNullsFirst | Order::DescNullsLast => true,
+        };
+        items.push((e_tokens.to_string(), param_tokens.to_string(), desc));
+    }
+    let mut out = Tokens::new();
+    let Some(first) = items.first() else {
+        return out;
+    };
+    if items.iter().all(|i| i.2 == first.2) {
+        out.s("(");
+        out.s(&items.iter().map(|i| i.0.as_str()).collect::<Vec<_>>().join(" , "));
+        out.s(")");
+        out.s(if first.2 {
+            "<"
+        } else {
+            ">"
+        });
+        out.s("(");
+        out.s(&items.iter().map(|i| i.1.as_str()).collect::<Vec<_>>().join(" , "));
+        out.s(")");
+    } else {
+        // Row values only compare in one direction, so expand to `a > $1 or (a = $1 and
+        // (b < $2 ...))`
+        let mut cond: Option<String> = None;
+        for (e, param, desc) in items.iter().rev() {
+            let op = if *desc {
+                "<"
+            } else {
+                ">"
+            };
+            cond = Some(match cond {
+                None => format!("{} {} {}", e, op, param),
+                Some(rest) => format!("( {} {} {} or ( {} = {} and {} ) )", e, op, param, e, param, rest),
+            });
+        }
+        out.s(&cond.unwrap());
+    }
+    return out;
+}
+
+/// In an aggregate query, every column must either be in the `GROUP BY` clause,
+/// only used within an aggregate function, or be from a table whose primary key is
+/// grouped (`grouped_sources`, by table id or alias).
+fn check_grouped(
+    errs: &mut Errs,
+    path: &rpds::Vector<String>,
+    local_bindings: &HashSet<Binding>,
+    grouped_sources: &HashSet<String>,
+    group: &[Expr],
+    e: &Expr,
+) {
+    if e.is_aggregate() || group.iter().any(|g| g.same_as(e)) {
+        return;
+    }
+    if let Expr::Binding(b) = e {
+        if local_bindings.contains(b) && !grouped_sources.contains(&b.table_id) {
+            errs.err(
+                path,
+                format!("Column {} must appear in the group by clause or be used in an aggregate function", b),
+            );
+        }
+        return;
+    }
+    for child in e.row_children() {
+        check_grouped(errs, path, local_bindings, grouped_sources, group, child);
+    }
+}
+
+/// Builds the `ORDER BY`, `LIMIT` and `OFFSET` clauses.
+fn build_order_limit(
+    ctx: &mut SqliteQueryCtx,
+    path: &rpds::Vector<String>,
+    scope: &HashMap<Binding, Type>,
+    out: &mut Tokens,
+    order: &[(Expr, Order)],
+    limit: &Option<Expr>,
+    offset: &Option<Expr>,
+) {
+    if !order.is_empty() {
+        out.s("order by");
+        for (i, o) in order.iter().enumerate() {
+            let path = path.push_back(format!("Order by clause {}", i));
+            if i > 0 {
+                out.s(",");
+            }
+            let (_, o_tokens) = o.0.build(ctx, &path, scope);
+            out.s(&o_tokens.to_string());
+            out.s(o.1.to_sql());
+        }
+    }
+    if let Some(l) = limit {
+        out.s("limit");
+        let path = path.push_back("Limit".into());
+        let (limit_t, limit_tokens) = l.build(ctx, &path, scope);
+        check_general_same(ctx, &path, &limit_t, &ExprType(vec![(Binding::empty(), type_i64().build())]));
+        out.s(&limit_tokens.to_string());
+    }
+    if let Some(o) = offset {
+        if limit.is_none() {
+            // Sqlite only allows offset after limit
+            out.s("limit -1");
+        }
+        out.s("offset");
+        let path = path.push_back("Offset".into());
+        let (offset_t, offset_tokens) = o.build(ctx, &path, scope);
+        check_general_same(ctx, &path, &offset_t, &ExprType(vec![(Binding::empty(), type_i64().build())]));
+        out.s(&offset_tokens.to_string());
+    }
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -234,6 +713,7 @@ pub struct SelectJunction {
 pub fn build_select_junction(
     ctx: &mut super::utils::SqliteQueryCtx,
     path: &rpds::Vector<String>,
+    scope: &HashMap<Binding, Type>,
     base_type: &ExprType,
     body_junctions: &[SelectJunction],
 ) -> Tokens {
@@ -254,7 +734,7 @@ pub fn build_select_junction(
                 out.s("except");
             },
         }
-        let j_body = j.body.build(ctx, &HashMap::new(), &path, QueryResCount::Many);
+        let j_body = j.body.build(ctx, scope, &path, QueryResCount::Many);
         if j_body.0.0.len() != base_type.0.len() {
             ctx
                 .errs
@@ -276,3 +756,38 @@ pub fn build_select_junction(
     }
     return out;
 }
+
+/// Builds a select followed by its junctions. With junctions the body's `order`,
+/// `limit` and `offset` apply to the combined rows, so they're placed after the
+/// junctions and `order` can only refer to the body's outputs.
+pub(crate) fn build_compound(
+    ctx: &mut super::utils::SqliteQueryCtx,
+    scope: &HashMap<Binding, Type>,
+    path: &rpds::Vector<String>,
+    body: &SelectBody,
+    body_junctions: &[SelectJunction],
+    res_count: QueryResCount,
+) -> (ExprType, Tokens) {
+    if body_junctions.is_empty() {
+        return body.build(ctx, scope, path, res_count);
+    }
+    let mut head = body.clone();
+    head.order = vec![];
+    head.limit = None;
+    head.offset = None;
+    let (out_type, head_tokens) = head.build(ctx, scope, path, res_count);
+    let mut out = Tokens::new();
+    out.s(&head_tokens.to_string());
+    out.s(&build_select_junction(ctx, path, scope, &out_type, body_junctions).to_string());
+    let compound_scope = out_type.0.iter().cloned().collect::<HashMap<_, _>>();
+    build_order_limit(
+        ctx,
+        &path.push_back("Compound".into()),
+        &compound_scope,
+        &mut out,
+        &body.order,
+        &body.limit,
+        &body.offset,
+    );
+    return (out_type, out);
+}
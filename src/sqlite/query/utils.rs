@@ -50,18 +50,38 @@ pub struct SqliteQueryCtx {
     pub(crate) tables: HashMap<Table, HashSet<Field>>,
     pub errs: Errs,
     pub(crate) rust_arg_lookup: HashMap<String, (usize, Type)>,
+    /// Tuple list parameters (`Expr::ParamTuples`), with their element types.
+    pub(crate) tuple_arg_lookup: HashMap<String, (usize, Vec<Type>)>,
     pub(crate) rust_args: Vec<TokenStream>,
     pub(crate) query_args: Vec<TokenStream>,
+    /// Named windows (`WINDOW` definitions) usable in the select currently being
+    /// built.
+    pub(crate) windows: HashSet<String>,
+    /// The fields of each primary key and unique index, per table. Used to infer
+    /// result counts.
+    pub(crate) unique_keys: HashMap<Table, Vec<Vec<Field>>>,
+    /// The fields of each table's primary key. Columns of a table whose primary key is
+    /// grouped can be used in grouped queries.
+    pub(crate) primary_keys: HashMap<Table, Vec<Field>>,
 }
 
 impl<'a> SqliteQueryCtx {
-    pub(crate) fn new(errs: Errs, tables: HashMap<Table, HashSet<Field>>) -> Self {
+    pub(crate) fn new(
+        errs: Errs,
+        tables: HashMap<Table, HashSet<Field>>,
+        unique_keys: HashMap<Table, Vec<Vec<Field>>>,
+        primary_keys: HashMap<Table, Vec<Field>>,
+    ) -> Self {
         Self {
             tables: tables,
+            unique_keys: unique_keys,
+            primary_keys: primary_keys,
             errs: errs,
             rust_arg_lookup: Default::default(),
+            tuple_arg_lookup: Default::default(),
             rust_args: Default::default(),
             query_args: Default::default(),
+            windows: Default::default(),
         }
     }
 }
@@ -73,6 +93,130 @@ pub trait QueryBody {
         path: &rpds::Vector<String>,
         res_count: QueryResCount,
     ) -> (ExprType, Tokens);
+
+    /// If the caller can choose the `ORDER BY` clause at runtime, build the query
+    /// once per choice. Returns the name of the choice enum and the variant names with
+    /// their built queries.
+    fn build_order_choices(
+        &self,
+        _ctx: &mut SqliteQueryCtx,
+        _path: &rpds::Vector<String>,
+        _res_count: QueryResCount,
+    ) -> Option<(String, Vec<(String, ExprType, Tokens)>)> {
+        return None;
+    }
+
+    /// If the query is keyset paginated, build the queries for the first and
+    /// subsequent pages.
+    fn build_paginated(&self, _ctx: &mut SqliteQueryCtx, _path: &rpds::Vector<String>) -> Option<Paginated> {
+        return None;
+    }
+
+    /// The most specific result count that can be proven from the query structure and
+    /// the schema's unique keys, used for `QueryResCount::Auto` and to check declared
+    /// counts. `None` if the query doesn't support inference. For `Many`, also
+    /// returns why the query may return multiple rows if it looks like a lookup by
+    /// key, in which case a declared `One` or `MaybeOne` count is warned about.
+    fn infer_res_count(&self, _ctx: &SqliteQueryCtx) -> Option<(QueryResCount, Option<String>)> {
+        return None;
+    }
+}
+
+/// Checks that every source has at most one row given the `=` equalities, i.e.
+/// every source has a unique key whose fields are all equal to values that are
+/// parameters, literals, or fields of other sources already proven to have at most
+/// one row. Each source is a table (`None` for other sources, which are never
+/// proven) and the id it's bound to, along with equalities that only constrain
+/// that source (like the `ON` condition of a left join). Returns `None` if all
+/// sources were proven, otherwise descriptions of the unproven sources that have a
+/// field compared with `=` to a parameter or literal. Other sources (like a whole
+/// table read or a join) aren't described since they don't look like lookups by
+/// key.
+pub(crate) fn unproven_sources(
+    ctx: &SqliteQueryCtx,
+    sources: &[(Option<&Table>, String, Vec<(&Binding, &Expr)>)],
+    equalities: &[(&Binding, &Expr)],
+) -> Option<Vec<String>> {
+    let mut fixed = HashSet::new();
+    loop {
+        let mut progress = false;
+        for (table, id, own_equalities) in sources {
+            if fixed.contains(id) {
+                continue;
+            }
+            let Some(table) = table else {
+                continue;
+            };
+            let Some(keys) = ctx.unique_keys.get(*table) else {
+                continue;
+            };
+            let pinned = keys.iter().any(|key| key.iter().all(|field| {
+                equalities.iter().chain(own_equalities.iter()).any(|(b, v)| {
+                    &b.table_id == id && b.id == field.id && v.is_fixed(&fixed)
+                })
+            }));
+            if pinned {
+                fixed.insert(id.clone());
+                progress = true;
+            }
+        }
+        if !progress {
+            break;
+        }
+    }
+    if sources.iter().all(|(_, id, _)| fixed.contains(id)) {
+        return None;
+    }
+    let mut out = vec![];
+    for (table, id, own_equalities) in sources {
+        if fixed.contains(id) {
+            continue;
+        }
+        if !equalities
+            .iter()
+            .chain(own_equalities.iter())
+            .any(|(b, v)| &b.table_id == id && v.is_fixed(&HashSet::new())) {
+            continue;
+        }
+        let Some(table) = table else {
+            out.push(format!("`{}` isn't a table", id));
+            continue;
+        };
+        match ctx.unique_keys.get(*table) {
+            Some(keys) => {
+                out.push(
+                    format!(
+                        "not every field of a unique key of `{}` is compared with `=` ({})",
+                        id,
+                        keys
+                            .iter()
+                            .map(|k| k.iter().map(|f| f.id.clone()).collect::<Vec<_>>().join(", "))
+                            .collect::<Vec<_>>()
+                            .join(" or ")
+                    ),
+                );
+            },
+            None => {
+                out.push(format!("`{}` has no primary key or unique index", id));
+            },
+        }
+    }
+    return Some(out);
+}
+
+/// The built queries of a keyset paginated select.
+pub struct Paginated {
+    /// The result type. The last `cursor_len` elements are the cursor values for
+    /// each row.
+    pub(crate) res_type: ExprType,
+    pub(crate) cursor_len: usize,
+    /// The query for the first page, without a cursor.
+    pub(crate) first: Tokens,
+    /// The indices of the query arguments for the cursor values. `first` uses all
+    /// the other arguments.
+    pub(crate) cursor_args: Vec<usize>,
+    /// The query for pages after the cursor.
+    pub(crate) next: Tokens,
 }
 
 pub fn build_set(
@@ -110,7 +254,9 @@ pub fn build_returning_values(
     outputs: &Vec<Returning>,
     res_count: QueryResCount,
 ) -> ExprType {
-    if outputs.is_empty() {
+    if matches!(res_count, QueryResCount::Auto) {
+        ctx.errs.err(path, format!("QueryResCount::Auto can only be used for top level queries"));
+    } else if outputs.is_empty() {
         if !matches!(res_count, QueryResCount::None) {
             ctx.errs.err(path, format!("Query has no outputs but res_count is, {:?}, not None", res_count));
         }
@@ -157,6 +303,11 @@ pub fn build_returning(
 
 #[derive(Clone, Debug)]
 pub struct With {
+    /// Allow CTEs to reference themselves. In a recursive CTE the `body` is the
+    /// initial select and can't reference the CTE, and each `body_junctions` select
+    /// that references the CTE (exactly once, in its from or join clauses) is a
+    /// recursive select. Recursive selects must come after any other junctions, be
+    /// combined with `union` or `union all`, and can't aggregate.
     pub recursive: bool,
     pub ctes: Vec<Cte>,
 }
@@ -191,6 +342,12 @@ impl CteBuilder {
         };
     }
 
+    /// The CTE table, for referencing the CTE in recursive selects before the CTE is
+    /// built.
+    pub fn table(&self) -> Table {
+        return self.table.clone();
+    }
+
     pub fn body_junction(&mut self, j: SelectJunction) {
         self.cte.body_junctions.push(j);
     }
@@ -220,49 +377,70 @@ impl CteBuilder {
 pub fn build_with(ctx: &mut SqliteQueryCtx, path: &rpds::Vector<String>, with: &With) -> Tokens {
     let mut out = Tokens::new();
     out.s("with");
+    if with.recursive {
+        out.s("recursive");
+    }
     for (i, cte) in with.ctes.iter().enumerate() {
         if i > 0 {
             out.s(",");
         }
         let path = path.push_back(format!("CTE {}", i));
-        out.s(&cte.table.id);
+        out.id(&cte.table.id);
         out.s("(");
         for (i, c) in cte.columns.iter().enumerate() {
             if i > 0 {
                 out.s(",");
             }
-            out.s(&c.id);
+            out.id(&c.id);
         }
         out.s(")");
         out.s("as");
         out.s("(");
-        let body = cte.body.build(ctx, &HashMap::new(), &path, QueryResCount::Many);
-        for (i, ((_, got), want)) in Iterator::zip(body.0.0.iter(), cte.columns.iter()).enumerate() {
-            let path = path.push_back(format!("Select return {}", i));
-            check_assignable(
-                &mut ctx.errs,
-                &path,
-                &want.type_.type_,
-                &ExprType(vec![(Binding::empty(), got.clone())]),
-            );
+        if cte.body.table_refs(&cte.table) != (0, 0) {
+            ctx.errs.err(&path, format!("The initial select of a CTE can't reference the CTE itself"));
         }
+        let body = cte.body.build(ctx, &HashMap::new(), &path, QueryResCount::Many);
+        check_cte_columns(ctx, &path, cte, &body.0);
         out.s(&body.1.to_string());
-        if body.0.0.len() != cte.columns.len() {
-            ctx
-                .errs
-                .err(
-                    &path,
-                    format!(
-                        "Select returns {} columns but the CTE needs exactly {} columns",
-                        body.0.0.len(),
-                        cte.columns.len()
-                    ),
-                );
-            continue;
-        }
-        ctx.tables.insert(cte.table.clone(), cte.columns.iter().cloned().collect());
+
+        // Recursive selects can see the CTE, following the initial selects
+        let mut recursing = false;
         for (i, j) in cte.body_junctions.iter().enumerate() {
             let path = path.push_back(format!("Junction clause {} - {:?}", i, j.op));
+            let (direct, nested) = j.body.table_refs(&cte.table);
+            if direct + nested > 0 {
+                if !with.recursive {
+                    ctx.errs.err(&path, format!("Select references its CTE but the `With` isn't recursive"));
+                }
+                if direct != 1 || nested > 0 {
+                    ctx
+                        .errs
+                        .err(
+                            &path,
+                            format!(
+                                "Recursive selects must reference the CTE exactly once, directly in the from or join clauses"
+                            ),
+                        );
+                }
+                if !matches!(j.op, SelectJunctionOperator::Union | SelectJunctionOperator::UnionAll) {
+                    ctx
+                        .errs
+                        .err(&path, format!("Recursive selects must be combined with `union` or `union all`, not {:?}", j.op));
+                }
+                if !j.body.group.is_empty() || j.body.having.is_some() ||
+                    j.body.returning.iter().any(|r| r.e.contains_aggregate()) {
+                    ctx.errs.err(&path, format!("Recursive selects can't use aggregates or grouping"));
+                }
+                if j.body.returning.iter().any(|r| matches!(r.e, Expr::Window { .. })) {
+                    ctx.errs.err(&path, format!("Recursive selects can't use window functions"));
+                }
+                if !recursing {
+                    recursing = true;
+                    ctx.tables.insert(cte.table.clone(), cte.columns.iter().cloned().collect());
+                }
+            } else if recursing {
+                ctx.errs.err(&path, format!("Non-recursive selects must come before all recursive selects in a CTE"));
+            }
             match j.op {
                 SelectJunctionOperator::Union => {
                     out.s("union");
@@ -278,30 +456,28 @@ pub fn build_with(ctx: &mut SqliteQueryCtx, path: &rpds::Vector<String>, with: &
                 },
             }
             let j_body = j.body.build(ctx, &HashMap::new(), &path, QueryResCount::Many);
-            if j_body.0.0.len() != cte.columns.len() {
-                ctx
-                    .errs
-                    .err(
-                        &path,
-                        format!(
-                            "Select returns {} columns but the CTE needs exactly {} columns",
-                            j_body.0.0.len(),
-                            cte.columns.len()
-                        ),
-                    );
-                continue;
-            }
-            for (i, ((_, got), want)) in Iterator::zip(j_body.0.0.iter(), cte.columns.iter()).enumerate() {
-                let path = path.push_back(format!("Select return {}", i));
-                check_assignable(
-                    &mut ctx.errs,
-                    &path,
-                    &want.type_.type_,
-                    &ExprType(vec![(Binding::empty(), got.clone())]),
-                );
-            }
+            check_cte_columns(ctx, &path, cte, &j_body.0);
+            out.s(&j_body.1.to_string());
         }
         out.s(")");
+        ctx.tables.insert(cte.table.clone(), cte.columns.iter().cloned().collect());
     }
     return out;
 }
+
+/// Checks that a select in a CTE produces values for the CTE columns.
+fn check_cte_columns(ctx: &mut SqliteQueryCtx, path: &rpds::Vector<String>, cte: &Cte, got: &ExprType) {
+    if got.0.len() != cte.columns.len() {
+        ctx
+            .errs
+            .err(
+                path,
+                format!("Select returns {} columns but the CTE needs exactly {} columns", got.0.len(), cte.columns.len()),
+            );
+        return;
+    }
+    for (i, ((_, got), want)) in Iterator::zip(got.0.iter(), cte.columns.iter()).enumerate() {
+        let path = path.push_back(format!("Select return {}", i));
+        check_assignable(&mut ctx.errs, &path, &want.type_.type_, &ExprType(vec![(Binding::empty(), got.clone())]));
+    }
+}
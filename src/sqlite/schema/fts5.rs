@@ -0,0 +1,54 @@
+use std::ops::Deref;
+use super::{
+    field::Field,
+    table::Table,
+};
+
+#[derive(Clone, Debug)]
+pub struct Fts5Column {
+    pub field: Field,
+    /// The column is stored and returned but not indexed for matching.
+    pub unindexed: bool,
+}
+
+/// The source of an external content FTS5 table.
+#[derive(Clone, Debug)]
+pub struct Fts5Content {
+    /// The field used as the rowid of the index entries - either `rowid` or an
+    /// integer primary key.
+    pub rowid: Field,
+    /// The content table fields, one per column in the FTS5 table (in the same
+    /// order).
+    pub fields: Vec<Field>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Fts5Table {
+    pub table: Table,
+    pub rowid: Field,
+    /// The hidden column with the same name as the table. Matching against it
+    /// matches all columns, and it's the first argument to the auxiliary functions
+    /// like `bm25`.
+    pub table_field: Field,
+    pub columns: Vec<Fts5Column>,
+    pub tokenize: Option<String>,
+    pub content: Option<Fts5Content>,
+}
+
+impl Fts5Table {
+    /// The index of the column, for auxiliary functions like `highlight`.
+    pub(crate) fn column_index(&self, field: &Field) -> usize {
+        match self.columns.iter().position(|c| &c.field == field) {
+            Some(i) => i,
+            None => panic!("Field {} is not a column of FTS5 table {}", field, self.table),
+        }
+    }
+}
+
+impl Deref for Fts5Table {
+    type Target = Table;
+
+    fn deref(&self) -> &Self::Target {
+        &self.table
+    }
+}
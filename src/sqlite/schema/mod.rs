@@ -2,3 +2,5 @@ pub mod table;
 pub mod field;
 pub mod constraint;
 pub mod index;
+pub mod fts5;
+pub mod rtree;
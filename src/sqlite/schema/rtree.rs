@@ -0,0 +1,26 @@
+use std::ops::Deref;
+use super::{
+    field::Field,
+    table::Table,
+};
+
+#[derive(Clone, Debug)]
+pub struct RtreeTable {
+    pub table: Table,
+    /// The integer primary key.
+    pub id_field: Field,
+    /// The min and max coordinate fields of each dimension.
+    pub dimensions: Vec<(Field, Field)>,
+    /// Auxiliary columns, stored with each entry but not indexed.
+    pub aux: Vec<Field>,
+    /// Use 32 bit integer coordinates (`rtree_i32`) rather than floats.
+    pub i32_coordinates: bool,
+}
+
+impl Deref for RtreeTable {
+    type Target = Table;
+
+    fn deref(&self) -> &Self::Target {
+        &self.table
+    }
+}
@@ -6,6 +6,11 @@ use proc_macro2::{
     TokenStream,
 };
 
+/// Quote an identifier for SQL, doubling any quotes within it.
+pub fn quote_id(i: &str) -> String {
+    return format!("\"{}\"", i.replace("\"", "\"\""));
+}
+
 pub struct Tokens(String);
 
 impl ToString for Tokens {
@@ -31,7 +36,7 @@ impl Tokens {
         if !self.0.is_empty() {
             self.0.push(' ');
         }
-        self.0.push_str(&format!("\"{}\"", i));
+        self.0.push_str(&quote_id(i));
         self
     }
 
@@ -43,6 +48,7 @@ impl Tokens {
 
 pub struct Errs_ {
     errs: Vec<String>,
+    warnings: Vec<String>,
 }
 
 #[derive(Clone)]
@@ -50,11 +56,13 @@ pub struct Errs(Rc<RefCell<Errs_>>);
 
 impl Errs {
     pub(crate) fn new() -> Self {
-        Self(Rc::new(RefCell::new(Errs_ { errs: vec![] })))
+        Self(Rc::new(RefCell::new(Errs_ {
+            errs: vec![],
+            warnings: vec![],
+        })))
     }
 
-    pub fn err(&self, path: &rpds::Vector<String>, t: String) {
-        let mut s = self.0.as_ref().borrow_mut();
+    fn format(path: &rpds::Vector<String>, t: String) -> String {
         let mut out = String::new();
         for (i, k) in path.iter().enumerate() {
             if i > 0 {
@@ -64,10 +72,27 @@ impl Errs {
         }
         out.push_str(" -- ");
         out.push_str(&t);
-        s.errs.push(out);
+        return out;
+    }
+
+    pub fn err(&self, path: &rpds::Vector<String>, t: String) {
+        self.0.as_ref().borrow_mut().errs.push(Self::format(path, t));
+    }
+
+    /// Record a problem that doesn't prevent generation. Warnings are printed as
+    /// cargo build script warnings.
+    pub fn warn(&self, path: &rpds::Vector<String>, t: String) {
+        self.0.as_ref().borrow_mut().warnings.push(Self::format(path, t));
+    }
+
+    pub(crate) fn err_count(&self) -> usize {
+        return self.0.borrow().errs.len();
     }
 
     pub fn raise(self) -> Result<(), Vec<String>> {
+        for w in self.0.borrow_mut().warnings.split_off(0) {
+            println!("cargo:warning={}", w);
+        }
         let errs = self.0.borrow_mut().errs.split_off(0);
         if !errs.is_empty() {
             return Err(errs);